@@ -0,0 +1,201 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Batch verification of transaction signatures.
+//!
+//! Ed25519 signatures are checked together in one multi-scalar multiplication,
+//! and only checked one by one to find the bad ones when the batch fails.
+//! A signature whose public key or `R` is not in the prime order subgroup, or
+//! whose `S` is not reduced, is never batched: the batch equation with random
+//! coefficients could accept it while `verify_tx_sig` rejects it, so the nodes
+//! would not agree on the transaction. Such a signature is checked alone.
+//! Secp256k1 and SM2 signatures are checked one by one, as their signers are
+//! recovered from each signature.
+
+use chain_core::transaction::CryptoType;
+use curve25519_dalek::constants::BASEPOINT_ORDER;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use ed25519_dalek::{verify_batch, PublicKey, Signature};
+use handler::verify_tx_sig;
+use libproto::{Crypto, VerifyTxReq};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use sha2::Sha512;
+
+// An ed25519 signature of CITA is followed by the public key of the signer.
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// Verify the signatures of the requests, the result of each request is the
+/// public key of its signer, in the same order as the requests.
+pub fn verify_tx_sigs(reqs: &[VerifyTxReq]) -> Vec<Result<Vec<u8>, ()>> {
+    let batch: Vec<&VerifyTxReq> = reqs.iter().filter(|req| is_batchable(req)).collect();
+    let batch_passed = !batch.is_empty() && verify_ed25519_batch(&batch);
+    reqs.par_iter()
+        .map(|req| {
+            if batch_passed && is_batchable(req) {
+                Ok(req.get_signature()[ED25519_SIGNATURE_LEN..].to_vec())
+            } else {
+                verify_tx_sig(req)
+            }
+        })
+        .collect()
+}
+
+fn is_batchable(req: &VerifyTxReq) -> bool {
+    req.get_crypto() == Crypto::ED25519
+        && req.get_signature().len() == CryptoType::ED25519.signature_len()
+        && is_strict(req.get_signature())
+}
+
+// The checks `verify_tx_sig` makes beyond the signature equation.
+fn is_strict(signature: &[u8]) -> bool {
+    let (r, rest) = signature.split_at(32);
+    let (s, public_key) = rest.split_at(32);
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(s);
+    Scalar::from_canonical_bytes(s_bytes).is_some()
+        && is_prime_order(r)
+        && is_prime_order(public_key)
+}
+
+// Neither of small order nor of mixed order, which is a point of the prime
+// order subgroup plus a torsion component.
+fn is_prime_order(point: &[u8]) -> bool {
+    CompressedEdwardsY::from_slice(point)
+        .decompress()
+        .map_or(false, |point| {
+            !point.is_small_order() && (point * BASEPOINT_ORDER).is_identity()
+        })
+}
+
+fn verify_ed25519_batch(reqs: &[&VerifyTxReq]) -> bool {
+    let mut messages = Vec::with_capacity(reqs.len());
+    let mut signatures = Vec::with_capacity(reqs.len());
+    let mut public_keys = Vec::with_capacity(reqs.len());
+    for req in reqs {
+        let (signature, public_key) = req.get_signature().split_at(ED25519_SIGNATURE_LEN);
+        match (Signature::from_bytes(signature), PublicKey::from_bytes(public_key)) {
+            (Ok(signature), Ok(public_key)) => {
+                signatures.push(signature);
+                public_keys.push(public_key);
+            }
+            _ => return false,
+        }
+        messages.push(req.get_hash());
+    }
+    verify_batch::<Sha512>(&messages, &signatures, &public_keys).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_ed25519::{KeyPair, Signature as EdSignature};
+    use cita_types::H256;
+    use curve25519_dalek::constants::EIGHT_TORSION;
+    use test::Bencher;
+    use util::crypto::{CreateKey, Sign};
+
+    fn ed25519_reqs(count: usize) -> Vec<VerifyTxReq> {
+        let keypair = KeyPair::gen_keypair();
+        (0..count)
+            .map(|i| {
+                let hash = H256::from(i as u64 + 1);
+                let signature = EdSignature::sign(keypair.privkey(), &hash.into()).unwrap();
+                let mut req = VerifyTxReq::new();
+                req.set_hash(hash.to_vec());
+                req.set_tx_hash(hash.to_vec());
+                req.set_signature(signature.to_vec());
+                req.set_crypto(Crypto::ED25519);
+                req
+            })
+            .collect()
+    }
+
+    #[test]
+    fn batch_results() {
+        let reqs = ed25519_reqs(8);
+        let expected: Vec<_> = reqs.iter().map(verify_tx_sig).collect();
+        assert!(expected.iter().all(|result| result.is_ok()));
+        assert_eq!(verify_tx_sigs(&reqs), expected);
+    }
+
+    #[test]
+    fn bad_signature_in_batch() {
+        let mut reqs = ed25519_reqs(8);
+        let hash = H256::from(100);
+        reqs[3].set_hash(hash.to_vec());
+
+        let results = verify_tx_sigs(&reqs);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_ok(), i != 3);
+        }
+    }
+
+    #[test]
+    fn small_order_signature_in_batch() {
+        let mut reqs = ed25519_reqs(7);
+        // R and the public key are the identity and S is zero, which meets the
+        // signature equation for any message.
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let mut signature = identity.to_vec();
+        signature.extend_from_slice(&[0u8; 32]);
+        signature.extend_from_slice(&identity);
+        let hash = H256::from(100);
+        let mut req = VerifyTxReq::new();
+        req.set_hash(hash.to_vec());
+        req.set_tx_hash(hash.to_vec());
+        req.set_signature(signature);
+        req.set_crypto(Crypto::ED25519);
+        reqs.push(req);
+
+        let expected: Vec<_> = reqs.iter().map(verify_tx_sig).collect();
+        assert!(expected[7].is_err());
+        assert_eq!(verify_tx_sigs(&reqs), expected);
+    }
+
+    #[test]
+    fn mixed_order_signature_in_batch() {
+        let mut reqs = ed25519_reqs(8);
+        // R of a valid signature plus a point of order 8
+        let mut signature = reqs[3].get_signature().to_vec();
+        let r = CompressedEdwardsY::from_slice(&signature[..32])
+            .decompress()
+            .unwrap();
+        let mixed = (r + EIGHT_TORSION[1]).compress();
+        signature[..32].copy_from_slice(mixed.as_bytes());
+        reqs[3].set_signature(signature);
+        assert!(!is_batchable(&reqs[3]));
+
+        let expected: Vec<_> = reqs.iter().map(verify_tx_sig).collect();
+        assert!(expected[3].is_err());
+        assert_eq!(verify_tx_sigs(&reqs), expected);
+    }
+
+    #[bench]
+    fn verify_one_by_one(b: &mut Bencher) {
+        let reqs = ed25519_reqs(1000);
+        b.iter(|| reqs.par_iter().map(verify_tx_sig).collect::<Vec<_>>());
+    }
+
+    #[bench]
+    fn verify_in_batch(b: &mut Bencher) {
+        let reqs = ed25519_reqs(1000);
+        b.iter(|| verify_tx_sigs(&reqs));
+    }
+}
@@ -1,3 +1,6 @@
+use cita_types::Address;
+use pool::Strategy;
+
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Config {
     pub count_per_batch: usize,
@@ -7,6 +10,10 @@ pub struct Config {
     pub tx_pool_limit: usize,
     pub prof_start: u64,
     pub prof_duration: u64,
+    #[serde(default)]
+    pub package_strategy: Strategy,
+    #[serde(default)]
+    pub priority_senders: Vec<Address>,
 }
 
 impl Config {
@@ -44,5 +51,31 @@ mod tests {
         assert_eq!(50000, value.tx_pool_limit);
         assert_eq!(0, value.prof_start);
         assert_eq!(0, value.prof_duration);
+        assert_eq!(Strategy::Fifo, value.package_strategy);
+        assert!(value.priority_senders.is_empty());
+    }
+
+    #[test]
+    fn read_package_policy() {
+        let toml_str = r#"
+        count_per_batch = 30
+        buffer_duration = 30
+        tx_verify_thread_num = 4
+        tx_verify_cache_size = 100000
+        tx_pool_limit = 50000
+        prof_start = 0
+        prof_duration = 0
+        package_strategy = "round_robin"
+        priority_senders = ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"]
+        "#;
+
+        let mut tmpfile: NamedTempFile = NamedTempFile::new().unwrap();
+        tmpfile.write_all(toml_str.as_bytes()).unwrap();
+        let path = tmpfile.path().to_str().unwrap();
+        let value: Config = parse_config!(Config, path);
+
+        assert_eq!(Strategy::RoundRobin, value.package_strategy);
+        let sender: Address = "5b073e9233944b5e729e46d618f0d8edf3d9c34a".parse().unwrap();
+        assert_eq!(vec![sender], value.priority_senders);
     }
 }
@@ -16,30 +16,33 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use cita_types::traits::LowerHex;
-use cita_types::H256;
+use cita_types::{Address, H256, U256};
+use jsonrpc_types::rpctypes::{PoolSender, PoolStatus, PoolTransaction};
 use libproto::blockchain::{AccountGasLimit, BlockBody, BlockTxs, SignedTransaction};
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::Message;
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::{Into, TryInto};
 use std::sync::mpsc::Sender;
 use std::thread;
-use tx_pool;
+use pool::{self, Enqueue, PackagePolicy, Pool};
 use txwal::TxWal;
 
 pub struct Dispatcher {
-    txs_pool: RefCell<tx_pool::Pool>,
+    txs_pool: RefCell<Pool>,
     wal: TxWal,
     wal_enable: bool,
+    package_policy: PackagePolicy,
 }
 
 impl Dispatcher {
-    pub fn new(wal_enable: bool) -> Self {
+    pub fn new(wal_enable: bool, package_policy: PackagePolicy) -> Self {
         let mut dispatch = Dispatcher {
-            txs_pool: RefCell::new(tx_pool::Pool::new(0)),
+            txs_pool: RefCell::new(Pool::new(0, package_policy.clone())),
             wal: TxWal::new("/txwal"),
             wal_enable: wal_enable,
+            package_policy: package_policy,
         };
 
         // restore tx data from wal to txs_pool
@@ -52,7 +55,7 @@ impl Dispatcher {
 
     /// Clean transaction pool and regenerate an pool cache db
     pub fn clear_txs_pool(&mut self, package_limit: usize) {
-        self.txs_pool = RefCell::new(tx_pool::Pool::new(package_limit));
+        self.txs_pool = RefCell::new(Pool::new(package_limit, self.package_policy.clone()));
         self.wal.regenerate("/txwal");
     }
 
@@ -60,6 +63,46 @@ impl Dispatcher {
         self.txs_pool.borrow().len()
     }
 
+    pub fn pool_status(&self) -> PoolStatus {
+        let txs_pool = self.txs_pool.borrow();
+        let mut senders: BTreeMap<Address, (u64, u64)> = BTreeMap::new();
+        for tx in txs_pool.transactions() {
+            let quota = tx.get_transaction_with_sig().get_transaction().get_quota();
+            let sender = senders.entry(pool::signer(tx)).or_insert((0, 0));
+            sender.0 += 1;
+            sender.1 += quota;
+        }
+        let quota = senders.values().map(|&(_, quota)| quota).sum::<u64>();
+        PoolStatus {
+            pending: U256::from(txs_pool.len()),
+            quota: U256::from(quota),
+            senders: senders
+                .into_iter()
+                .map(|(address, (pending, quota))| PoolSender {
+                    address: address,
+                    pending: U256::from(pending),
+                    quota: U256::from(quota),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn pool_transactions(&self) -> Vec<PoolTransaction> {
+        self.txs_pool
+            .borrow()
+            .transactions()
+            .into_iter()
+            .map(|tx| PoolTransaction::new(tx, pool::signer(tx)))
+            .collect()
+    }
+
+    pub fn pool_transaction(&self, hash: &H256) -> Option<PoolTransaction> {
+        self.txs_pool
+            .borrow()
+            .get(hash)
+            .map(|tx| PoolTransaction::new(tx, pool::signer(tx)))
+    }
+
     /// package a block with new transactions,
     /// send to cita-bft
     pub fn proposal_tx_list(
@@ -100,23 +143,47 @@ impl Dispatcher {
             .unwrap();
     }
 
-    pub fn add_tx_to_pool(&self, tx: &SignedTransaction) -> bool {
+    pub fn add_tx_to_pool(&self, tx: &SignedTransaction) -> Enqueue {
         // 交易放入pool，
         // 放入pool完成后，持久化
         trace!("add tx {} to pool", tx.get_tx_hash().lower_hex());
         let txs_pool = &mut self.txs_pool.borrow_mut();
-        let success = txs_pool.enqueue(tx.clone());
-        if self.wal_enable {
-            if success {
-                self.wal.write(tx);
-            } else {
+        let result = txs_pool.enqueue(tx.clone());
+        match result {
+            Enqueue::Added => {
+                if self.wal_enable {
+                    self.wal.write(tx);
+                }
+            }
+            Enqueue::Replaced(ref pending) => {
+                info!(
+                    "the transaction {} is replaced by {}",
+                    pending.get_tx_hash().lower_hex(),
+                    tx.get_tx_hash().lower_hex()
+                );
+                if self.wal_enable {
+                    self.wal.delete(pending);
+                    self.wal.write(tx);
+                }
+            }
+            Enqueue::Canceled(ref pending) => {
+                info!(
+                    "the transaction {} is canceled by {}",
+                    pending.get_tx_hash().lower_hex(),
+                    tx.get_tx_hash().lower_hex()
+                );
+                if self.wal_enable {
+                    self.wal.delete(pending);
+                }
+            }
+            Enqueue::Dup => {
                 warn!(
                     "the transaction {} is already exist",
                     tx.get_tx_hash().lower_hex()
                 );
             }
         }
-        success
+        result
     }
 
     pub fn get_txs_from_pool(
@@ -137,7 +204,7 @@ impl Dispatcher {
         }
         //改成多线程删除数据
         if self.wal_enable {
-            let mut wal = self.wal.clone();
+            let wal = self.wal.clone();
             let txs = txs.clone();
             thread::spawn(move || {
                 for tx in txs {
@@ -15,9 +15,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use batch_verify::verify_tx_sigs;
+use chain_core::transaction::{pubkey_to_address, CryptoType};
 use cita_types::traits::LowerHex;
 use cita_types::{Address, H256};
-use crypto::{pubkey_to_address, PubKey, Public, Sign, Signature, SIGNATURE_BYTES_LEN};
 use dispatcher::Dispatcher;
 use error::ErrorCode;
 use jsonrpc_types::rpctypes::TxResponse;
@@ -26,11 +27,11 @@ use libproto::blockchain::{AccountGasLimit, SignedTransaction};
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::snapshot::{Cmd, Resp, SnapshotResp};
 use libproto::{
-    BlockTxHashesReq, Crypto, Message, Request, Response, Ret, VerifyBlockReq, VerifyBlockResp,
-    VerifyTxReq,
+    BlockTxHashesReq, Message, PendingTxHashes, Request, Request_oneof_req, Response, Ret,
+    VerifyBlockReq, VerifyBlockResp, VerifyTxReq,
 };
 use lru::LruCache;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use pool::Enqueue;
 use rayon::ThreadPoolBuilder;
 use serde_json;
 use std::collections::{HashMap, HashSet};
@@ -40,6 +41,10 @@ use std::time::Duration;
 use util::instrument::{unix_now, AsMillis};
 use util::BLOCKLIMIT;
 
+/// The status of a cancellation, which removed the pending transaction from this
+/// node's pool only. Other nodes may still package the pending transaction.
+const CANCELED_LOCALLY: &str = "CanceledLocally";
+
 #[derive(Debug)]
 struct HistoryHeights {
     heights: HashSet<u64>,
@@ -184,25 +189,12 @@ mod history_heights_tests {
     }
 }
 
-// verify chain id and signature
+// verify signature with the crypto of the transaction
 pub fn verify_tx_sig(req: &VerifyTxReq) -> Result<Vec<u8>, ()> {
     let hash = H256::from(req.get_hash());
-    let sig_bytes = req.get_signature();
-    if sig_bytes.len() != SIGNATURE_BYTES_LEN {
-        return Err(());
-    }
-
-    let sig = Signature::from(sig_bytes);
-    match req.get_crypto() {
-        Crypto::SECP => sig
-            .recover(&hash)
-            .map(|pubkey| pubkey.to_vec())
-            .map_err(|_| ()),
-        _ => {
-            warn!("Unexpected crypto");
-            Err(())
-        }
-    }
+    CryptoType::from(req.get_crypto())
+        .recover(req.get_signature(), &hash)
+        .map_err(|_| ())
 }
 
 pub struct MsgHandler {
@@ -315,7 +307,7 @@ impl MsgHandler {
         let mut n = self.block_gas_limit;
         for req in reqs {
             let quota = req.get_quota();
-            let signer = pubkey_to_address(&PubKey::from(req.get_signer()));
+            let signer = pubkey_to_address(req.get_signer());
 
             if n < quota {
                 return false;
@@ -353,7 +345,7 @@ impl MsgHandler {
             return false;
         }
         if self.check_quota {
-            let addr = pubkey_to_address(&PubKey::from(signer));
+            let addr = pubkey_to_address(signer);
             let mut gas_limit = self.account_gas_limit.get_common_gas_limit();
             let mut specific_gas_limit = self.account_gas_limit.get_specific_gas_limit().clone();
             if let Some(value) = specific_gas_limit.remove(&addr.lower_hex()) {
@@ -412,7 +404,7 @@ impl MsgHandler {
                     return;
                 }
             } else {
-                reqs_no_cache.push(req);
+                reqs_no_cache.push(req.clone());
             }
         }
 
@@ -422,34 +414,20 @@ impl MsgHandler {
             reqs_no_cache.len()
         );
 
-        // parallel verify tx and collect results
-        let reqs_no_cache_count = reqs_no_cache.len();
-        let results: Vec<(H256, Vec<u8>)> = reqs_no_cache
-            .into_par_iter()
-            .map(|req| {
-                let tx_hash = H256::from_slice(req.get_tx_hash());
-                let result = verify_tx_sig(&req);
-                match result {
-                    Ok(pubkey) => {
-                        let req_signer = req.get_signer();
-                        if req_signer != pubkey.as_slice() {
-                            None
-                        } else {
-                            Some((tx_hash, pubkey))
-                        }
-                    }
-                    Err(_) => None,
+        // verify tx in batch and collect results
+        let results = verify_tx_sigs(&reqs_no_cache);
+        let mut is_all_valid = true;
+        for (req, result) in reqs_no_cache.iter().zip(results) {
+            match result {
+                Ok(ref pubkey) if req.get_signer() == pubkey.as_slice() => {
+                    let tx_hash = H256::from_slice(req.get_tx_hash());
+                    self.save_ret_to_cache(tx_hash, Some(pubkey.clone()));
                 }
-            })
-            .while_some()
-            .collect();
-
-        let results_len = results.len();
-        for (tx_hash, pubkey) in results {
-            self.save_ret_to_cache(tx_hash, Some(pubkey));
+                _ => is_all_valid = false,
+            }
         }
 
-        if results_len != reqs_no_cache_count {
+        if !is_all_valid {
             self.publish_block_verification_result(request_id, Ret::BadSig);
             return;
         }
@@ -475,7 +453,7 @@ impl MsgHandler {
     fn verify_black_list(&self, req: &VerifyTxReq) -> Ret {
         match self
             .black_list_cache
-            .get(&pubkey_to_address(&Public::from_slice(req.get_signer())))
+            .get(&pubkey_to_address(req.get_signer()))
         {
             Some(credit) => {
                 if credit < &0 {
@@ -559,11 +537,14 @@ impl MsgHandler {
     }
 
     fn publish_tx_success_result(&self, request_id: Vec<u8>, ret: Ret, tx_hash: H256) {
+        self.publish_tx_state_result(request_id, format!("{:?}", ret), tx_hash);
+    }
+
+    fn publish_tx_state_result(&self, request_id: Vec<u8>, status: String, tx_hash: H256) {
         let mut response = Response::new();
         response.set_request_id(request_id);
 
-        let result = format!("{:?}", ret);
-        let tx_response = TxResponse::new(tx_hash, result.clone());
+        let tx_response = TxResponse::new(tx_hash, status);
         let tx_state = serde_json::to_string(&tx_response).unwrap();
         response.set_tx_state(tx_state);
 
@@ -576,6 +557,54 @@ impl MsgHandler {
             .unwrap();
     }
 
+    /// Tell subscribers about transactions which just entered the pool.
+    fn publish_pending_tx_hashes(&self, tx_hashes: &[H256]) {
+        if tx_hashes.is_empty() {
+            return;
+        }
+        let mut pending_tx_hashes = PendingTxHashes::new();
+        let tx_hashes: Vec<Vec<u8>> = tx_hashes.iter().map(|hash| hash.to_vec()).collect();
+        pending_tx_hashes.set_tx_hashes(tx_hashes.into());
+        let msg: Message = pending_tx_hashes.into();
+        self.tx_pub
+            .send((
+                routing_key!(Auth >> PendingTxHashes).into(),
+                msg.try_into().unwrap(),
+            ))
+            .unwrap();
+    }
+
+    /// Reply the transaction pool requests from Jsonrpc.
+    fn reply_pool_request(&self, mut req: Request) {
+        let mut response = Response::new();
+        response.set_request_id(req.take_request_id());
+        match req.req {
+            Some(Request_oneof_req::pool_status(_)) => {
+                let status = self.dispatcher.pool_status();
+                response.set_pool_status(serde_json::to_string(&status).unwrap());
+            }
+            Some(Request_oneof_req::pool_transactions(_)) => {
+                let txs = self.dispatcher.pool_transactions();
+                response.set_pool_transactions(serde_json::to_string(&txs).unwrap());
+            }
+            Some(Request_oneof_req::pool_transaction(hash)) => {
+                match self.dispatcher.pool_transaction(&H256::from_slice(&hash)) {
+                    Some(tx) => response.set_pool_transaction(serde_json::to_string(&tx).unwrap()),
+                    None => response.set_none(true),
+                }
+            }
+            _ => return,
+        }
+
+        let msg: Message = response.into();
+        self.tx_pub
+            .send((
+                routing_key!(Auth >> Response).into(),
+                msg.try_into().unwrap(),
+            ))
+            .unwrap();
+    }
+
     fn forward_request(&self, tx_req: Request) {
         let _ = self.tx_request.send(tx_req);
     }
@@ -757,6 +786,10 @@ impl MsgHandler {
 
                             self.process_block_verify(blk_req);
                         }
+                        routing_key!(Jsonrpc >> RequestPool) => {
+                            let req = msg.take_request().unwrap();
+                            self.reply_pool_request(req);
+                        }
                         routing_key!(Net >> Request)
                         | routing_key!(Jsonrpc >> RequestNewTxBatch) => {
                             let is_local = rounting_key.is_sub_module(SubModules::Jsonrpc);
@@ -822,21 +855,12 @@ impl MsgHandler {
                                     }
                                 }
 
-                                let results: Vec<(
-                                    H256,
-                                    Option<Vec<u8>>,
-                                )> = requests_no_cached
-                                    .into_par_iter()
-                                    .map(|(tx_hash, ref req)| {
-                                        let result = verify_tx_sig(req);
-                                        match result {
-                                            Ok(pubkey) => (tx_hash, Some(pubkey)),
-                                            Err(_) => (tx_hash, None),
-                                        }
-                                    })
-                                    .collect();
+                                let (tx_hashes, reqs): (Vec<H256>, Vec<VerifyTxReq>) =
+                                    requests_no_cached.into_iter().unzip();
+                                let results = verify_tx_sigs(&reqs);
 
-                                for (tx_hash, option_pubkey) in results {
+                                for (tx_hash, result) in tx_hashes.into_iter().zip(results) {
+                                    let option_pubkey = result.ok();
                                     self.save_ret_to_cache(tx_hash.clone(), option_pubkey.clone());
                                     if let Some(pubkey) = option_pubkey {
                                         if let Some(ref mut v) = requests.get_mut(&tx_hash) {
@@ -857,6 +881,7 @@ impl MsgHandler {
                                 }
 
                                 // other verify
+                                let mut pending_tx_hashes = Vec::new();
                                 requests
                                     .into_iter()
                                     .filter(|(_tx_hash, (_req, _tx_req, flag))| *flag)
@@ -891,27 +916,43 @@ impl MsgHandler {
                                         signed_tx.set_signer(req.get_signer().to_vec());
                                         signed_tx.set_tx_hash(tx_hash.to_vec());
                                         let request_id = tx_req.get_request_id().to_vec();
-                                        if self.dispatcher.add_tx_to_pool(&signed_tx) {
-                                            if is_local {
-                                                self.publish_tx_success_result(
-                                                    request_id,
-                                                    Ret::OK,
-                                                    tx_hash.clone(),
-                                                );
+                                        let enqueued = self.dispatcher.add_tx_to_pool(&signed_tx);
+                                        match enqueued {
+                                            Enqueue::Added | Enqueue::Replaced(_) => {
+                                                if is_local {
+                                                    self.publish_tx_success_result(
+                                                        request_id,
+                                                        Ret::OK,
+                                                        tx_hash.clone(),
+                                                    );
+                                                }
+                                                // new tx need forward to other nodes
+                                                pending_tx_hashes.push(tx_hash);
+                                                self.forward_request(tx_req.clone());
                                             }
-                                            // new tx need forward to other nodes
-                                            self.forward_request(tx_req.clone());
-                                        } else {
-                                            // dup with transaction in tx pool
-                                            if is_local {
-                                                self.publish_tx_success_result(
-                                                    request_id,
-                                                    Ret::Dup,
-                                                    tx_hash.clone(),
-                                                );
+                                            Enqueue::Canceled(_) => {
+                                                // a cancellation only works locally
+                                                if is_local {
+                                                    self.publish_tx_state_result(
+                                                        request_id,
+                                                        CANCELED_LOCALLY.to_owned(),
+                                                        tx_hash.clone(),
+                                                    );
+                                                }
+                                            }
+                                            Enqueue::Dup => {
+                                                // dup with transaction in tx pool
+                                                if is_local {
+                                                    self.publish_tx_success_result(
+                                                        request_id,
+                                                        Ret::Dup,
+                                                        tx_hash.clone(),
+                                                    );
+                                                }
                                             }
                                         }
                                     });
+                                self.publish_pending_tx_hashes(&pending_tx_hashes);
                             } else if newtx_req.has_un_tx() {
                                 trace!("get single new tx request from Jsonrpc");
                                 let request_id = newtx_req.get_request_id().to_vec();
@@ -980,24 +1021,39 @@ impl MsgHandler {
                                 signed_tx.set_transaction_with_sig(newtx_req.get_un_tx().clone());
                                 signed_tx.set_signer(req.get_signer().to_vec());
                                 signed_tx.set_tx_hash(tx_hash.to_vec());
-                                if self.dispatcher.add_tx_to_pool(&signed_tx) {
-                                    if is_local {
-                                        self.publish_tx_success_result(
-                                            request_id,
-                                            Ret::OK,
-                                            tx_hash,
-                                        );
+                                let enqueued = self.dispatcher.add_tx_to_pool(&signed_tx);
+                                match enqueued {
+                                    Enqueue::Added | Enqueue::Replaced(_) => {
+                                        if is_local {
+                                            self.publish_tx_success_result(
+                                                request_id,
+                                                Ret::OK,
+                                                tx_hash,
+                                            );
+                                        }
+                                        // new tx need forward to other nodes
+                                        self.publish_pending_tx_hashes(&[tx_hash]);
+                                        self.forward_request(newtx_req);
                                     }
-                                    // new tx need forward to other nodes
-                                    self.forward_request(newtx_req);
-                                } else {
-                                    // dup with transaction in tx pool
-                                    if is_local {
-                                        self.publish_tx_success_result(
-                                            request_id,
-                                            Ret::Dup,
-                                            tx_hash,
-                                        );
+                                    Enqueue::Canceled(_) => {
+                                        // a cancellation only works locally
+                                        if is_local {
+                                            self.publish_tx_state_result(
+                                                request_id,
+                                                CANCELED_LOCALLY.to_owned(),
+                                                tx_hash,
+                                            );
+                                        }
+                                    }
+                                    Enqueue::Dup => {
+                                        // dup with transaction in tx pool
+                                        if is_local {
+                                            self.publish_tx_success_result(
+                                                request_id,
+                                                Ret::Dup,
+                                                tx_hash,
+                                            );
+                                        }
                                     }
                                 }
                             }
@@ -0,0 +1,166 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The auth service, see the binary of the same name for its messages.
+//!
+//! [`run`] is also used to host the service in a single process with others.
+//!
+//! [`run`]: fn.run.html
+
+#![cfg_attr(feature = "clippy", feature(plugin))]
+#![cfg_attr(feature = "clippy", plugin(clippy))]
+#![feature(custom_attribute)]
+#![feature(integer_atomics)]
+#![feature(try_from)]
+#![cfg_attr(test, feature(test))]
+
+extern crate cita_crypto as crypto;
+#[cfg(test)]
+extern crate cita_ed25519;
+extern crate cita_types;
+extern crate core as chain_core;
+extern crate cpuprofiler;
+extern crate curve25519_dalek;
+extern crate ed25519_dalek;
+extern crate error;
+extern crate jsonrpc_types;
+#[macro_use]
+extern crate libproto;
+#[macro_use]
+extern crate logger;
+extern crate lru;
+#[cfg(not(feature = "channel"))]
+extern crate pubsub;
+#[cfg(feature = "channel")]
+extern crate pubsub_channel as pubsub;
+extern crate rayon;
+extern crate rustc_serialize;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate sha2;
+#[cfg(test)]
+extern crate tempfile;
+#[cfg(test)]
+extern crate test;
+extern crate threadpool;
+extern crate tx_pool;
+#[macro_use]
+extern crate util;
+extern crate uuid;
+
+pub mod batch_forward;
+pub mod batch_verify;
+pub mod config;
+pub mod dispatcher;
+pub mod handler;
+pub mod pool;
+pub mod txwal;
+use batch_forward::BatchForward;
+use config::Config;
+use cpuprofiler::PROFILER;
+use dispatcher::Dispatcher;
+use handler::MsgHandler;
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use pubsub::start_pubsub;
+use std::sync::mpsc::channel;
+use std::thread;
+use pool::PackagePolicy;
+
+fn profiler(flag_prof_start: u64, flag_prof_duration: u64) {
+    //start profiling
+    if flag_prof_duration != 0 {
+        let start = flag_prof_start;
+        let duration = flag_prof_duration;
+        thread::spawn(move || {
+            thread::sleep(std::time::Duration::new(start, 0));
+            PROFILER
+                .lock()
+                .unwrap()
+                .start("./auth.profiler")
+                .expect("Couldn't start");
+            thread::sleep(std::time::Duration::new(duration, 0));
+            PROFILER.lock().unwrap().stop().unwrap();
+        });
+    }
+}
+
+/// Start the service with the given config, never returns.
+pub fn run(config_path: &str, wal_enable: bool) {
+    let config = Config::new(config_path);
+
+    let count_per_batch = config.count_per_batch;
+    let buffer_duration = config.buffer_duration;
+    let tx_verify_thread_num = config.tx_verify_thread_num;
+    let tx_verify_cache_size = config.tx_verify_cache_size;
+    let tx_pool_limit = config.tx_pool_limit;
+
+    // start profiler
+    let flag_prof_start = config.prof_start;
+    let flag_prof_duration = config.prof_duration;
+    profiler(flag_prof_start, flag_prof_duration);
+
+    // Start publish and subcribe message from MQ.
+    // The CITA system runs in a logic nodes, and it contains some components
+    // which we called micro-service at their running time.
+    // All micro-services connect to a MQ, as this design can keep them loose
+    // coupling with each other.
+    let (tx_sub, rx_sub) = channel();
+    let (tx_pub, rx_pub) = channel();
+    start_pubsub(
+        "auth",
+        routing_key!([
+            Consensus >> VerifyBlockReq,
+            Chain >> BlockTxHashes,
+            Executor >> BlackList,
+            Jsonrpc >> RequestNewTxBatch,
+            Jsonrpc >> RequestPool,
+            Net >> Request,
+            Snapshot >> SnapshotReq,
+            Executor >> Miscellaneous,
+        ]),
+        tx_sub,
+        rx_pub,
+    );
+
+    // a single thread to batch forward transactions
+    let tx_pub_forward = tx_pub.clone();
+    let (tx_request, rx_request) = channel();
+    thread::spawn(move || {
+        let mut batch_forward =
+            BatchForward::new(count_per_batch, buffer_duration, rx_request, tx_pub_forward);
+        batch_forward.run();
+    });
+
+    let package_policy = PackagePolicy {
+        strategy: config.package_strategy,
+        priority_senders: config.priority_senders.into_iter().collect(),
+    };
+    let dispatcher = Dispatcher::new(wal_enable, package_policy);
+
+    // handle message from MQ
+    let mut msg_handler = MsgHandler::new(
+        rx_sub,
+        tx_pub,
+        dispatcher,
+        tx_request,
+        tx_pool_limit,
+        tx_verify_thread_num,
+        tx_verify_cache_size,
+    );
+    msg_handler.handle_remote_msg();
+}
@@ -0,0 +1,488 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replacement, cancellation and package policies on top of `tx_pool::Pool`.
+
+use chain_core::transaction::pubkey_to_address;
+use cita_types::traits::LowerHex;
+use cita_types::{clean_0x, Address, H256};
+use libproto::blockchain::{AccountGasLimit, SignedTransaction};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use tx_pool;
+use util::BLOCKLIMIT;
+
+pub fn signer(tx: &SignedTransaction) -> Address {
+    pubkey_to_address(tx.get_signer())
+}
+
+fn hash_of(tx: &SignedTransaction) -> H256 {
+    H256::from_slice(tx.get_tx_hash())
+}
+
+fn quota_of(tx: &SignedTransaction) -> u64 {
+    tx.get_transaction_with_sig().get_transaction().get_quota()
+}
+
+fn nonce_key(tx: &SignedTransaction) -> (Address, String) {
+    (
+        signer(tx),
+        tx.get_transaction_with_sig()
+            .get_transaction()
+            .get_nonce()
+            .to_owned(),
+    )
+}
+
+/// A cancellation is a transfer of nothing from the signer to itself.
+fn is_cancellation(tx: &SignedTransaction, signer: &Address) -> bool {
+    let transaction = tx.get_transaction_with_sig().get_transaction();
+    transaction.get_data().is_empty()
+        && transaction.get_value().iter().all(|byte| *byte == 0)
+        && Address::from_str(clean_0x(transaction.get_to())).ok() == Some(*signer)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Enqueue {
+    Added,
+    /// Replaced the pending transaction with the same signer and nonce.
+    Replaced(SignedTransaction),
+    /// Removed the pending transaction with the same signer and nonce,
+    /// the cancellation itself is neither kept nor forwarded to other nodes.
+    Canceled(SignedTransaction),
+    /// The same transaction, or the same signer and nonce without a higher quota,
+    /// is already in the pool.
+    Dup,
+}
+
+/// The order in which transactions are packaged, after the priority senders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// In arrival order, stop at the first transaction which does not fit in the block.
+    Fifo,
+    /// Higher quota first, transactions with the same quota in arrival order.
+    QuotaDesc,
+    /// One transaction of each sender in turn, senders in order of their first transaction.
+    RoundRobin,
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy::Fifo
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackagePolicy {
+    pub strategy: Strategy,
+    /// Transactions of these senders are packaged first, in arrival order.
+    pub priority_senders: HashSet<Address>,
+}
+
+impl PackagePolicy {
+    /// Plain arrival order, which `tx_pool::Pool::package` implements itself.
+    fn is_fifo(&self) -> bool {
+        self.strategy == Strategy::Fifo && self.priority_senders.is_empty()
+    }
+}
+
+fn round_robin(txs: Vec<&SignedTransaction>) -> Vec<&SignedTransaction> {
+    let mut ordered = Vec::with_capacity(txs.len());
+    let mut senders = Vec::new();
+    let mut queues: HashMap<Address, VecDeque<&SignedTransaction>> = HashMap::new();
+    for tx in txs {
+        let sender = signer(tx);
+        queues
+            .entry(sender)
+            .or_insert_with(|| {
+                senders.push(sender);
+                VecDeque::new()
+            })
+            .push_back(tx);
+    }
+    while !senders.is_empty() {
+        senders.retain(|sender| match queues.get_mut(sender).unwrap().pop_front() {
+            Some(tx) => {
+                ordered.push(tx);
+                true
+            }
+            None => false,
+        });
+    }
+    ordered
+}
+
+pub struct Pool {
+    txs: tx_pool::Pool,
+    package_limit: usize,
+    policy: PackagePolicy,
+    // (signer, nonce) => hash, entries of transactions dropped by
+    // `tx_pool::Pool::package` are removed after packaging.
+    nonces: HashMap<(Address, String), H256>,
+}
+
+impl Pool {
+    pub fn new(package_limit: usize, policy: PackagePolicy) -> Self {
+        Pool {
+            txs: tx_pool::Pool::new(package_limit),
+            package_limit,
+            policy,
+            nonces: HashMap::new(),
+        }
+    }
+
+    fn forget(nonces: &mut HashMap<(Address, String), H256>, tx: &SignedTransaction) {
+        let key = nonce_key(tx);
+        if nonces.get(&key) == Some(&hash_of(tx)) {
+            nonces.remove(&key);
+        }
+    }
+
+    fn remove(&mut self, tx: &SignedTransaction) {
+        Self::forget(&mut self.nonces, tx);
+        self.txs.update(&[tx.clone()]);
+    }
+
+    /// A transaction with the same signer and nonce as a pending one replaces it
+    /// if its quota is strictly higher, and queues up as a new transaction.
+    ///
+    /// Replacement and cancellation only apply to this pool, other nodes may still
+    /// package the pending transaction.
+    pub fn enqueue(&mut self, tx: SignedTransaction) -> Enqueue {
+        let hash = hash_of(&tx);
+        if self.txs.get(&hash).is_some() {
+            return Enqueue::Dup;
+        }
+
+        let key = nonce_key(&tx);
+        let pending = self
+            .nonces
+            .get(&key)
+            .and_then(|pending_hash| self.txs.get(pending_hash))
+            .cloned();
+        let result = match pending {
+            Some(pending) => {
+                if is_cancellation(&tx, &key.0) {
+                    self.remove(&pending);
+                    return Enqueue::Canceled(pending);
+                }
+                if quota_of(&tx) <= quota_of(&pending) {
+                    return Enqueue::Dup;
+                }
+                self.remove(&pending);
+                Enqueue::Replaced(pending)
+            }
+            None => Enqueue::Added,
+        };
+        self.nonces.insert(key, hash);
+        self.txs.enqueue(tx);
+        result
+    }
+
+    pub fn update(&mut self, txs: &[SignedTransaction]) {
+        for tx in txs {
+            Self::forget(&mut self.nonces, tx);
+        }
+        self.txs.update(txs);
+    }
+
+    pub fn update_with_hash(&mut self, txs: &HashSet<H256>) {
+        for hash in txs {
+            if let Some(tx) = self.txs.get(hash) {
+                Self::forget(&mut self.nonces, tx);
+            }
+        }
+        self.txs.update_with_hash(txs);
+    }
+
+    /// Transactions in the order they are considered for packaging.
+    fn package_order(&self) -> Vec<&SignedTransaction> {
+        let priority_senders = &self.policy.priority_senders;
+        let (mut ordered, mut others): (Vec<_>, Vec<_>) = self
+            .txs
+            .transactions()
+            .into_iter()
+            .partition(|tx| priority_senders.contains(&signer(tx)));
+        match self.policy.strategy {
+            Strategy::Fifo => {}
+            Strategy::QuotaDesc => others.sort_by(|a, b| quota_of(b).cmp(&quota_of(a))),
+            Strategy::RoundRobin => others = round_robin(others),
+        }
+        ordered.append(&mut others);
+        ordered
+    }
+
+    /// Take transactions in the order of the package policy until the block quota is used up.
+    ///
+    /// Expired transactions are dropped from the pool on the way.
+    pub fn package(
+        &mut self,
+        height: u64,
+        block_gas_limit: u64,
+        account_gas_limit: AccountGasLimit,
+        check_quota: bool,
+    ) -> Vec<SignedTransaction> {
+        if self.policy.is_fifo() {
+            let tx_list = self
+                .txs
+                .package(height, block_gas_limit, account_gas_limit, check_quota);
+            let txs = &self.txs;
+            self.nonces.retain(|_, hash| txs.get(hash).is_some());
+            return tx_list;
+        }
+
+        let mut tx_list = Vec::new();
+        let mut invalid_tx_list = Vec::new();
+        {
+            let mut block_quota_left = block_gas_limit;
+            let mut account_quota_left: HashMap<Address, u64> = HashMap::new();
+            let common_gas_limit = account_gas_limit.get_common_gas_limit();
+            let specific_gas_limit = account_gas_limit.get_specific_gas_limit();
+
+            for tx in self.package_order() {
+                let transaction = tx.get_transaction_with_sig().get_transaction();
+                let valid_until_block = transaction.get_valid_until_block();
+                if valid_until_block <= height || valid_until_block > height + BLOCKLIMIT {
+                    invalid_tx_list.push(tx.clone());
+                    continue;
+                }
+
+                let quota = transaction.get_quota();
+                if block_quota_left == 0 {
+                    break;
+                }
+                if quota > block_quota_left {
+                    if self.policy.strategy == Strategy::Fifo {
+                        break;
+                    }
+                    continue;
+                }
+                if check_quota {
+                    let signer = signer(tx);
+                    let quota_left = account_quota_left.entry(signer).or_insert_with(|| {
+                        specific_gas_limit
+                            .get(&signer.lower_hex())
+                            .cloned()
+                            .unwrap_or(common_gas_limit)
+                    });
+                    if quota > *quota_left {
+                        continue;
+                    }
+                    *quota_left -= quota;
+                }
+                block_quota_left -= quota;
+
+                tx_list.push(tx.clone());
+                if self.package_limit > 0 && tx_list.len() >= self.package_limit {
+                    break;
+                }
+            }
+        }
+
+        self.update(&invalid_tx_list);
+        tx_list
+    }
+
+    pub fn get(&self, hash: &H256) -> Option<&SignedTransaction> {
+        self.txs.get(hash)
+    }
+
+    /// All transactions in the pool, in arrival order.
+    pub fn transactions(&self) -> Vec<&SignedTransaction> {
+        self.txs.transactions()
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::{CreateKey, KeyPair, PrivKey};
+    use libproto::Transaction;
+
+    fn generate_tx(
+        privkey: &PrivKey,
+        nonce: u32,
+        quota: u64,
+        valid_until_block: u64,
+    ) -> SignedTransaction {
+        let mut tx = Transaction::new();
+        tx.set_data(vec![]);
+        tx.set_nonce(format!("{}", nonce));
+        tx.set_quota(quota);
+        tx.set_valid_until_block(valid_until_block);
+        tx.sign(*privkey)
+    }
+
+    #[test]
+    fn enqueue_and_update() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pool = Pool::new(0, PackagePolicy::default());
+        let tx1 = generate_tx(keypair.privkey(), 1, 100, 10);
+        let tx2 = generate_tx(keypair.privkey(), 2, 100, 10);
+
+        assert_eq!(pool.enqueue(tx1.clone()), Enqueue::Added);
+        assert_eq!(pool.enqueue(tx1.clone()), Enqueue::Dup);
+        assert_eq!(pool.enqueue(tx2.clone()), Enqueue::Added);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.transactions(), vec![&tx1, &tx2]);
+
+        pool.update(&[tx1.clone()]);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.get(&H256::from_slice(tx1.get_tx_hash())).is_none());
+        assert_eq!(pool.transactions(), vec![&tx2]);
+    }
+
+    #[test]
+    fn package_drops_expired() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pool = Pool::new(0, PackagePolicy::default());
+        let expired = generate_tx(keypair.privkey(), 1, 100, 5);
+        let valid = generate_tx(keypair.privkey(), 2, 100, 10);
+        pool.enqueue(expired);
+        pool.enqueue(valid.clone());
+
+        let txs = pool.package(5, 1000, AccountGasLimit::new(), false);
+        assert_eq!(txs, vec![valid]);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn package_with_quota_limits() {
+        let keypair = KeyPair::gen_keypair();
+        let other = KeyPair::gen_keypair();
+        let mut pool = Pool::new(0, PackagePolicy::default());
+        let tx1 = generate_tx(keypair.privkey(), 1, 300, 10);
+        let tx2 = generate_tx(keypair.privkey(), 2, 300, 10);
+        let tx3 = generate_tx(other.privkey(), 1, 300, 10);
+        let tx4 = generate_tx(other.privkey(), 2, 300, 10);
+        for tx in &[&tx1, &tx2, &tx3, &tx4] {
+            pool.enqueue((*tx).clone());
+        }
+
+        let mut account_gas_limit = AccountGasLimit::new();
+        account_gas_limit.set_common_gas_limit(500);
+        let txs = pool.package(1, 1000, account_gas_limit, true);
+        assert_eq!(txs, vec![tx1, tx3]);
+    }
+
+    #[test]
+    fn package_limit() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pool = Pool::new(1, PackagePolicy::default());
+        pool.enqueue(generate_tx(keypair.privkey(), 1, 100, 10));
+        pool.enqueue(generate_tx(keypair.privkey(), 2, 100, 10));
+        assert_eq!(pool.package(1, 1000, AccountGasLimit::new(), false).len(), 1);
+    }
+
+    #[test]
+    fn replace_with_higher_quota() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pool = Pool::new(0, PackagePolicy::default());
+        let tx1 = generate_tx(keypair.privkey(), 1, 100, 10);
+        let tx2 = generate_tx(keypair.privkey(), 2, 100, 10);
+        let same_quota = generate_tx(keypair.privkey(), 1, 100, 11);
+        let higher_quota = generate_tx(keypair.privkey(), 1, 200, 10);
+        pool.enqueue(tx1.clone());
+        pool.enqueue(tx2.clone());
+
+        assert_eq!(pool.enqueue(same_quota), Enqueue::Dup);
+        assert_eq!(pool.enqueue(higher_quota.clone()), Enqueue::Replaced(tx1));
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.transactions(), vec![&tx2, &higher_quota]);
+
+        // Another signer may use the same nonce.
+        let other = KeyPair::gen_keypair();
+        let tx3 = generate_tx(other.privkey(), 1, 100, 10);
+        assert_eq!(pool.enqueue(tx3), Enqueue::Added);
+    }
+
+    #[test]
+    fn cancel() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pool = Pool::new(0, PackagePolicy::default());
+        let tx = generate_tx(keypair.privkey(), 1, 100, 10);
+        pool.enqueue(tx.clone());
+
+        let mut cancellation = Transaction::new();
+        cancellation.set_nonce("1".to_owned());
+        cancellation.set_quota(100);
+        cancellation.set_valid_until_block(10);
+        cancellation.set_to(keypair.address().lower_hex());
+        let cancellation = cancellation.sign(*keypair.privkey());
+
+        assert_eq!(pool.enqueue(cancellation), Enqueue::Canceled(tx));
+        assert!(pool.is_empty());
+        assert!(pool.transactions().is_empty());
+    }
+
+    fn package_with_policy(
+        policy: PackagePolicy,
+        txs: &[SignedTransaction],
+    ) -> Vec<SignedTransaction> {
+        let mut pool = Pool::new(0, policy);
+        for tx in txs {
+            pool.enqueue(tx.clone());
+        }
+        pool.package(1, 1000, AccountGasLimit::new(), false)
+    }
+
+    #[test]
+    fn package_strategies() {
+        let heavy = KeyPair::gen_keypair();
+        let light = KeyPair::gen_keypair();
+        let heavy1 = generate_tx(heavy.privkey(), 1, 400, 10);
+        let heavy2 = generate_tx(heavy.privkey(), 2, 400, 10);
+        let heavy3 = generate_tx(heavy.privkey(), 3, 400, 10);
+        let light1 = generate_tx(light.privkey(), 1, 100, 10);
+        let txs = vec![heavy1.clone(), heavy2.clone(), heavy3.clone(), light1.clone()];
+
+        let fifo = package_with_policy(PackagePolicy::default(), &txs);
+        assert_eq!(fifo, vec![heavy1.clone(), heavy2.clone()]);
+
+        let quota_desc = PackagePolicy {
+            strategy: Strategy::QuotaDesc,
+            priority_senders: HashSet::new(),
+        };
+        let txs_desc = package_with_policy(quota_desc, &txs);
+        assert_eq!(txs_desc, vec![heavy1.clone(), heavy2.clone(), light1.clone()]);
+
+        let round_robin = PackagePolicy {
+            strategy: Strategy::RoundRobin,
+            priority_senders: HashSet::new(),
+        };
+        let txs_round_robin = package_with_policy(round_robin, &txs);
+        assert_eq!(txs_round_robin, vec![heavy1.clone(), light1.clone(), heavy2.clone()]);
+
+        let mut priority_senders = HashSet::new();
+        priority_senders.insert(light.address().clone());
+        let priority = PackagePolicy {
+            strategy: Strategy::Fifo,
+            priority_senders: priority_senders,
+        };
+        let txs_priority = package_with_policy(priority, &txs);
+        assert_eq!(txs_priority, vec![light1, heavy1, heavy2]);
+    }
+}
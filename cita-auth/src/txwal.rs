@@ -20,7 +20,7 @@ use cita_types::H256;
 use libproto::blockchain::SignedTransaction;
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
-use tx_pool::Pool;
+use pool::Pool;
 use util::datapath::DataPath;
 use util::kvdb::{Database, DatabaseConfig, KeyValueDB};
 
@@ -51,14 +51,14 @@ impl TxWal {
         let _ = self.db.write(batch);
     }
 
-    pub fn delete(&mut self, tx: &SignedTransaction) {
+    pub fn delete(&self, tx: &SignedTransaction) {
         let tx = tx.clone();
         let mut batch = self.db.transaction();
         batch.delete(None, tx.get_tx_hash());
         let _ = self.db.write(batch);
     }
 
-    pub fn delete_with_hash(&mut self, txhash: &H256) {
+    pub fn delete_with_hash(&self, txhash: &H256) {
         let mut batch = self.db.transaction();
         batch.delete(None, txhash);
         let _ = self.db.write(batch);
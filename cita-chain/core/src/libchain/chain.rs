@@ -30,6 +30,7 @@ use filters::{PollFilter, PollManager};
 use header::*;
 pub use libchain::block::*;
 use libchain::cache::CacheSize;
+use libchain::cross_chain::{CrossChainAck, CrossChainMessage};
 use libchain::extras::*;
 use libchain::status::Status;
 pub use libchain::transaction::*;
@@ -42,11 +43,12 @@ use libproto::blockchain::{
 use cita_types::traits::LowerHex;
 use cita_types::{Address, H256, U256};
 use header::Header;
+use jsonrpc_types::rpctypes::{Data, MerkleProofNode, ReceiptProof, TransactionInclusionProof};
 use libproto::executor::ExecutedResult;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::{BlockTxHashes, FullTransaction, Message};
 use proof::TendermintProof;
-use receipt::{LocalizedReceipt, Receipt};
+use receipt::{LocalizedReceipt, Receipt, ReceiptError};
 use rlp::{self, Encodable};
 use state::State;
 use state_db::StateDB;
@@ -95,6 +97,10 @@ impl TxProof {
         rlp::decode(bytes)
     }
 
+    pub fn block_header(&self) -> &Header {
+        &self.block_header
+    }
+
     pub fn verify(&self, authorities: &[Address]) -> bool {
         // Calculate transaction hash, and it should be same as the transaction hash in receipt.
         if self.receipt.transaction_hash == self.tx.calc_transaction_hash() {
@@ -137,6 +143,22 @@ impl TxProof {
         true
     }
 
+    /// Get the cross-chain message of the log at `index` in the receipt.
+    pub fn cross_chain_message(&self, index: usize) -> Option<CrossChainMessage> {
+        self.receipt
+            .logs
+            .get(index)
+            .and_then(CrossChainMessage::from_log)
+    }
+
+    /// Get the acknowledgement of the log at `index` in the receipt.
+    pub fn cross_chain_ack(&self, index: usize) -> Option<CrossChainAck> {
+        self.receipt
+            .logs
+            .get(index)
+            .and_then(CrossChainAck::from_log)
+    }
+
     // extract info which relayer needed
     pub fn extract_relay_info(&self) -> Option<RelayInfo> {
         if self.receipt.logs.len() == 0 {
@@ -518,6 +540,16 @@ impl Chain {
                 })
                 .collect();
 
+            for receipt in receipts.iter().filter_map(|receipt| receipt.as_ref()) {
+                if let Some(ReceiptError::Reverted(Some(ref reason))) = receipt.error {
+                    batch.write(
+                        db::COL_EXTRA,
+                        &receipt.transaction_hash,
+                        &RevertReason(reason.clone()),
+                    );
+                }
+            }
+
             let block_receipts = BlockReceipts::new(receipts.clone());
             let mut write_receipts = self.block_receipts.write();
             batch.write_with_cache(
@@ -917,6 +949,52 @@ impl Chain {
             )
     }
 
+    /// Get the proof that the receipt of the transaction is in the `receipts_root`.
+    pub fn get_receipt_proof(&self, hash: TransactionId) -> Option<ReceiptProof> {
+        let address = self.transaction_address(hash)?;
+        let header = self.block_header_by_hash(address.block_hash)?;
+        let receipts = self.block_receipts(address.block_hash)?.receipts;
+        let receipt = match receipts.get(address.index) {
+            Some(&Some(ref receipt)) if receipt.transaction_hash == hash => receipt.clone(),
+            _ => return None,
+        };
+        let proof = merklehash::MerkleTree::from_bytes(
+            receipts.iter().map(|r| r.rlp_bytes().into_vec()),
+        ).get_proof_by_input_index(address.index)?;
+        let localized_receipt = self.localized_receipt(hash)?;
+        Some(ReceiptProof {
+            transaction_hash: hash,
+            transaction_index: address.index.into(),
+            transactions_count: receipts.len().into(),
+            block_hash: address.block_hash,
+            block_number: header.number().into(),
+            receipts_root: *header.receipts_root(),
+            receipt_rlp: Data::new(Some(receipt).rlp_bytes().into_vec()),
+            receipt: localized_receipt.into(),
+            proof: merkle_proof_nodes(proof),
+        })
+    }
+
+    /// Get the proof that the transaction is in the `transactions_root`.
+    pub fn get_transaction_inclusion_proof(
+        &self,
+        hash: TransactionId,
+    ) -> Option<TransactionInclusionProof> {
+        let address = self.transaction_address(hash)?;
+        let block = self.block_by_hash(address.block_hash)?;
+        let proof = merklehash::MerkleTree::from_hashes(block.body().transaction_hashes())
+            .get_proof_by_input_index(address.index)?;
+        Some(TransactionInclusionProof {
+            transaction_hash: hash,
+            transaction_index: address.index.into(),
+            transactions_count: block.body().transactions().len().into(),
+            block_hash: address.block_hash,
+            block_number: block.number().into(),
+            transactions_root: *block.header().transactions_root(),
+            proof: merkle_proof_nodes(proof),
+        })
+    }
+
     pub fn localized_receipt(&self, id: TransactionId) -> Option<LocalizedReceipt> {
         trace!("Get receipt id: {:?}", id);
 
@@ -983,7 +1061,12 @@ impl Chain {
                         .collect(),
                     log_bloom: last_receipt.log_bloom,
                     state_root: last_receipt.state_root,
-                    error: last_receipt.error,
+                    error: last_receipt.error.map(|error| match error {
+                        ReceiptError::Reverted(None) => ReceiptError::Reverted(
+                            self.revert_reason(id).map(|reason| reason.0),
+                        ),
+                        error => error,
+                    }),
                 };
                 Some(receipt)
             } else {
@@ -995,6 +1078,11 @@ impl Chain {
         })
     }
 
+    /// The reason given by `revert("reason")` of the transaction.
+    fn revert_reason(&self, id: TransactionId) -> Option<RevertReason> {
+        self.db.read().read(db::COL_EXTRA, &id)
+    }
+
     pub fn get_current_height(&self) -> u64 {
         self.current_height.load(Ordering::SeqCst) as u64
     }
@@ -1433,6 +1521,17 @@ impl Chain {
     }
 }
 
+fn merkle_proof_nodes(proof: merklehash::MerkleProof) -> Vec<MerkleProofNode> {
+    proof
+        .0
+        .into_iter()
+        .map(|node| MerkleProofNode {
+            is_right: node.is_right,
+            hash: node.hash,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,272 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Messages between any chains registered in `ChainManager`.
+//!
+//! A contract sends a message by calling
+//! `sendMessage(uint32,address,bytes,uint256)` of the `CrossChainMessenger`
+//! native contract, which emits a `CrossChainMessage` log numbered by the
+//! nonce of the source chain. The last argument is the gas the destination
+//! chain must forward to the destination contract.
+//! A relayer brings the `TxProof` of that transaction and the index of the
+//! log in its receipt to the destination chain, where
+//! `deliverMessage(bytes,uint256)` checks it against the authorities of
+//! the source chain stored in `ChainManager`, rejects a `(from_chain_id, nonce)`
+//! delivered before, and calls `receiveMessage(uint32,address,bytes)` of the
+//! destination contract. The delivery emits a `CrossChainAck` log, and its
+//! proof is brought back to the source chain by `deliverAck(bytes,uint256)`.
+//!
+//! The log data are ABI encoded, so contracts and external tools can read
+//! them without knowing anything else of CITA.
+
+use cita_types::{Address, H256, U256};
+use std::str::FromStr;
+use types::log_entry::LogEntry;
+
+lazy_static! {
+    /// Address of the `CrossChainMessenger` native contract.
+    pub static ref MESSENGER_ADDRESS: Address = Address::from(0x1302);
+    /// Topic of `CrossChainMessage(uint32,uint32,uint256,address,address,uint256,bytes)`.
+    pub static ref MESSAGE_TOPIC: H256 =
+        H256::from_str("f17cea81d8ed65a98b39105a226cabef1da4bec38be21767d1c2e05adec738bb")
+            .unwrap();
+    /// Topic of `CrossChainAck(uint32,uint32,uint256,address,bool)`.
+    pub static ref ACK_TOPIC: H256 =
+        H256::from_str("ead4a737b532a47db7ad25a2af57973d3bd4c9507add94647d8768752d16930c")
+            .unwrap();
+}
+
+/// A message sent from one chain to a contract on another chain.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CrossChainMessage {
+    pub from_chain_id: u32,
+    pub to_chain_id: u32,
+    pub nonce: U256,
+    pub sender: Address,
+    pub dest_contract: Address,
+    /// Gas forwarded to `receiveMessage` of the destination contract.
+    pub gas_limit: U256,
+    pub payload: Vec<u8>,
+}
+
+impl CrossChainMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let words = [
+            H256::from(self.from_chain_id as u64),
+            H256::from(self.to_chain_id as u64),
+            H256::from(self.nonce),
+            H256::from(self.sender),
+            H256::from(self.dest_contract),
+            H256::from(self.gas_limit),
+        ];
+        encode_with_bytes(&words, &self.payload)
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let payload = decode_bytes(data, 6)?;
+        Some(CrossChainMessage {
+            from_chain_id: decode_u32(word(data, 0)?)?,
+            to_chain_id: decode_u32(word(data, 1)?)?,
+            nonce: U256::from(word(data, 2)?),
+            sender: decode_address(word(data, 3)?)?,
+            dest_contract: decode_address(word(data, 4)?)?,
+            gas_limit: U256::from(word(data, 5)?),
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Get the message from a log emitted by the messenger.
+    pub fn from_log(log: &LogEntry) -> Option<Self> {
+        if log.address == *MESSENGER_ADDRESS && log.topics.first() == Some(&*MESSAGE_TOPIC) {
+            Self::decode(&log.data)
+        } else {
+            None
+        }
+    }
+}
+
+/// The acknowledgement of a message, emitted by the destination chain.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CrossChainAck {
+    pub from_chain_id: u32,
+    pub to_chain_id: u32,
+    pub nonce: U256,
+    /// Sender of the message, notified by `onMessageAck(uint32,uint256,bool)`.
+    pub sender: Address,
+    /// Whether `receiveMessage` of the destination contract succeeded.
+    pub success: bool,
+}
+
+impl CrossChainAck {
+    pub fn encode(&self) -> Vec<u8> {
+        let words = [
+            H256::from(self.from_chain_id as u64),
+            H256::from(self.to_chain_id as u64),
+            H256::from(self.nonce),
+            H256::from(self.sender),
+            H256::from(self.success as u64),
+        ];
+        words.iter().flat_map(|word| word.to_vec()).collect()
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() != 5 * 32 {
+            return None;
+        }
+        let success = match decode_u32(word(data, 4)?)? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        Some(CrossChainAck {
+            from_chain_id: decode_u32(word(data, 0)?)?,
+            to_chain_id: decode_u32(word(data, 1)?)?,
+            nonce: U256::from(word(data, 2)?),
+            sender: decode_address(word(data, 3)?)?,
+            success,
+        })
+    }
+
+    /// Get the acknowledgement from a log emitted by the messenger.
+    pub fn from_log(log: &LogEntry) -> Option<Self> {
+        if log.address == *MESSENGER_ADDRESS && log.topics.first() == Some(&*ACK_TOPIC) {
+            Self::decode(&log.data)
+        } else {
+            None
+        }
+    }
+}
+
+/// ABI encode the static `words` followed by the dynamic `bytes`.
+pub fn encode_with_bytes(words: &[H256], bytes: &[u8]) -> Vec<u8> {
+    let mut data: Vec<u8> = words.iter().flat_map(|word| word.to_vec()).collect();
+    data.extend_from_slice(&H256::from((words.len() as u64 + 1) * 32));
+    data.extend_from_slice(&H256::from(bytes.len() as u64));
+    data.extend_from_slice(bytes);
+    let padded = (data.len() + 31) / 32 * 32;
+    data.resize(padded, 0);
+    data
+}
+
+fn word(data: &[u8], index: usize) -> Option<&[u8]> {
+    data.get(index * 32..(index + 1) * 32)
+}
+
+/// Decode the dynamic bytes whose offset is the word at `index`.
+fn decode_bytes(data: &[u8], index: usize) -> Option<&[u8]> {
+    let offset = U256::from(word(data, index)?);
+    if offset > U256::from(data.len()) {
+        return None;
+    }
+    let offset = offset.low_u64() as usize;
+    let len = U256::from(data.get(offset..offset + 32)?);
+    if len > U256::from(data.len()) {
+        return None;
+    }
+    let start = offset + 32;
+    data.get(start..start + len.low_u64() as usize)
+}
+
+fn decode_u32(word: &[u8]) -> Option<u32> {
+    let value = U256::from(word);
+    if value > U256::from(u32::max_value()) {
+        None
+    } else {
+        Some(value.low_u32())
+    }
+}
+
+fn decode_address(word: &[u8]) -> Option<Address> {
+    if word[..12].iter().all(|byte| *byte == 0) {
+        Some(Address::from(H256::from(word)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(payload: Vec<u8>) -> CrossChainMessage {
+        CrossChainMessage {
+            from_chain_id: 1,
+            to_chain_id: 2,
+            nonce: 3.into(),
+            sender: Address::from(4),
+            dest_contract: Address::from(5),
+            gas_limit: 100_000.into(),
+            payload,
+        }
+    }
+
+    #[test]
+    fn message_encoding() {
+        for len in &[0, 1, 32, 33] {
+            let message = message(vec![0xab; *len]);
+            let data = message.encode();
+            assert_eq!(data.len() % 32, 0);
+            assert_eq!(&data[6 * 32..7 * 32], &H256::from(7 * 32)[..]);
+            assert_eq!(CrossChainMessage::decode(&data), Some(message));
+        }
+    }
+
+    #[test]
+    fn reject_malformed_message() {
+        let data = message(vec![1, 2, 3]).encode();
+        assert_eq!(CrossChainMessage::decode(&data[..7 * 32]), None);
+        let mut wrong_chain = data.clone();
+        wrong_chain[27] = 1;
+        assert_eq!(CrossChainMessage::decode(&wrong_chain), None);
+        let mut wrong_address = data.clone();
+        wrong_address[3 * 32] = 1;
+        assert_eq!(CrossChainMessage::decode(&wrong_address), None);
+        let mut wrong_length = data.clone();
+        wrong_length[8 * 32 - 1] = 33;
+        assert_eq!(CrossChainMessage::decode(&wrong_length), None);
+    }
+
+    #[test]
+    fn message_from_log() {
+        let message = message(vec![1, 2, 3]);
+        let mut log = LogEntry {
+            address: *MESSENGER_ADDRESS,
+            topics: vec![*MESSAGE_TOPIC],
+            data: message.encode(),
+        };
+        assert_eq!(CrossChainMessage::from_log(&log), Some(message));
+        assert_eq!(CrossChainAck::from_log(&log), None);
+        log.address = Address::from(0x1301);
+        assert_eq!(CrossChainMessage::from_log(&log), None);
+    }
+
+    #[test]
+    fn ack_encoding() {
+        let ack = CrossChainAck {
+            from_chain_id: 1,
+            to_chain_id: 2,
+            nonce: 3.into(),
+            sender: Address::from(4),
+            success: true,
+        };
+        let data = ack.encode();
+        assert_eq!(CrossChainAck::decode(&data), Some(ack));
+        let mut wrong_flag = data.clone();
+        wrong_flag[5 * 32 - 1] = 2;
+        assert_eq!(CrossChainAck::decode(&wrong_flag), None);
+    }
+}
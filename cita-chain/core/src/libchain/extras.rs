@@ -44,6 +44,8 @@ pub enum ExtrasIndex {
     BlockHeadHash = 4,
     /// Block body index
     BlockBodyHash = 5,
+    /// Revert reason index
+    RevertReason = 6,
 }
 
 pub struct CurrentHash;
@@ -193,6 +195,14 @@ impl Key<BlockReceipts> for H256 {
     }
 }
 
+impl Key<RevertReason> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ExtrasIndex::RevertReason)
+    }
+}
+
 pub struct LogGroupKey([u8; 6]);
 
 impl Deref for LogGroupKey {
@@ -306,6 +316,24 @@ impl Index<usize> for BlockReceipts {
     }
 }
 
+/// The reason given by `revert("reason")` of a transaction.
+///
+/// It is stored apart from the block receipts, which make up the receipts root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RevertReason(pub String);
+
+impl Decodable for RevertReason {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        Ok(RevertReason(rlp.as_val()?))
+    }
+}
+
+impl Encodable for RevertReason {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append(&self.0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::BlockReceipts;
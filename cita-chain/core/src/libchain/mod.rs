@@ -18,6 +18,7 @@
 pub mod block;
 pub mod cache;
 pub mod chain;
+pub mod cross_chain;
 pub mod extras;
 pub mod rich_status;
 pub mod status;
@@ -23,8 +23,8 @@ use core::libchain::block::Block;
 use core::libchain::chain::{BlockInQueue, Chain};
 use error::ErrorCode;
 use jsonrpc_types::rpctypes::{
-    self as rpctypes, BlockParamsByHash, BlockParamsByNumber, Filter as RpcFilter, Log as RpcLog,
-    Receipt as RpcReceipt, RpcBlock,
+    self as rpctypes, BlockParamsByHash, BlockParamsByNumber, BlockRange, Data,
+    Filter as RpcFilter, Log as RpcLog, Receipt as RpcReceipt, RpcBlock,
 };
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::snapshot::{Cmd, Resp, SnapshotReq, SnapshotResp};
@@ -56,6 +56,9 @@ use core::snapshot::io::{PackedReader, PackedWriter, SnapshotReader};
 use core::snapshot::service::{Service as SnapshotService, ServiceParams as SnapServiceParams};
 use core::snapshot::Progress;
 
+/// Max number of headers returned by `getBlockHeaders`
+const MAX_BLOCK_HEADERS: u64 = 1000;
+
 /// Message forwarding and query data
 #[derive(Clone)]
 pub struct Forward {
@@ -210,6 +213,62 @@ impl Forward {
                 };
             }
 
+            Request::receipt_proof(hash) => {
+                match self.chain.get_receipt_proof(H256::from_slice(&hash)) {
+                    Some(proof) => {
+                        response.set_receipt_proof(serde_json::to_string(&proof).unwrap());
+                    }
+                    None => {
+                        response.set_none(true);
+                    }
+                };
+            }
+
+            Request::transaction_inclusion_proof(hash) => {
+                match self
+                    .chain
+                    .get_transaction_inclusion_proof(H256::from_slice(&hash))
+                {
+                    Some(proof) => {
+                        let proof = serde_json::to_string(&proof).unwrap();
+                        response.set_transaction_inclusion_proof(proof);
+                    }
+                    None => {
+                        response.set_none(true);
+                    }
+                };
+            }
+
+            Request::block_header(number) => {
+                match serde_json::from_str::<rpctypes::BlockNumber>(&number) {
+                    Ok(number) => match self.chain.block_header(number.into()) {
+                        Some(header) => {
+                            response.set_block_header(header.rlp());
+                        }
+                        None => {
+                            response.set_none(true);
+                        }
+                    },
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    }
+                }
+            }
+
+            Request::block_headers(range) => {
+                serde_json::from_str::<BlockRange>(&range)
+                    .map_err(|err| format!("{:?}", err))
+                    .and_then(|range| self.block_headers(range))
+                    .map(|headers| {
+                        response.set_block_headers(serde_json::to_string(&headers).unwrap());
+                    })
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(err);
+                    });
+            }
+
             Request::transaction_receipt(hash) => {
                 let tx_hash = H256::from_slice(&hash);
                 let receipt = self.chain.localized_receipt(tx_hash);
@@ -285,6 +344,62 @@ impl Forward {
                 return;
             }
 
+            Request::storage_at(storage_key) => {
+                trace!("storage request from jsonrpc {:?}", storage_key);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::state_proof(storage_keys) => {
+                trace!("state proof request from jsonrpc {:?}", storage_keys);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::trace_transaction(hash) => {
+                trace!("trace transaction request from jsonrpc {:?}", hash);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::trace_block(number) => {
+                trace!("trace block request from jsonrpc {:?}", number);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::trace_filter(filter) => {
+                trace!("trace filter request from jsonrpc {:?}", filter);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::trace_call(call) => {
+                trace!("trace call request from jsonrpc {:?}", call);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
+            Request::estimate_quota(call) => {
+                trace!("estimate quota request from jsonrpc {:?}", call);
+                self.ctx_pub
+                    .send((routing_key!(Chain >> Request).into(), imsg))
+                    .unwrap();
+                return;
+            }
+
             Request::new_filter(new_filter) => {
                 trace!("new_filter {:?}", new_filter);
                 let new_filter: RpcFilter =
@@ -333,6 +448,32 @@ impl Forward {
             .unwrap();
     }
 
+    /// Get the RLP encoded headers of the blocks in the range.
+    fn block_headers(&self, range: BlockRange) -> Result<Vec<Data>, String> {
+        let number_of = |id: rpctypes::BlockNumber| {
+            self.chain
+                .block_header(id.into())
+                .map(|header| header.number())
+                .ok_or_else(|| "Block not found".to_owned())
+        };
+        let from = number_of(range.from)?;
+        let to = number_of(range.to)?;
+        if from > to {
+            return Err(format!("Invalid block range [{}, {}]", from, to));
+        }
+        if to - from >= MAX_BLOCK_HEADERS {
+            return Err(format!("At most {} headers in a request", MAX_BLOCK_HEADERS));
+        }
+        (from..to + 1)
+            .map(|number| {
+                self.chain
+                    .block_header(BlockId::Number(number))
+                    .map(|header| Data::new(header.rlp()))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| "Block not found".to_owned())
+    }
+
     // Consensus block enqueue
     fn consensus_block_enqueue(&self, proof_blk: BlockWithProof) {
         let current_height = self.chain.get_current_height() as usize;
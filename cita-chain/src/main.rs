@@ -66,45 +66,15 @@
 
 #![cfg_attr(feature = "clippy", feature(plugin))]
 #![cfg_attr(feature = "clippy", plugin(clippy))]
-#![allow(unused_must_use)]
-#![feature(custom_attribute)]
-#![feature(refcell_replace_swap)]
-#![feature(try_from)]
-extern crate byteorder;
-extern crate cita_types;
+extern crate cita_chain;
 extern crate clap;
-extern crate common_types as types;
-extern crate core;
 extern crate dotenv;
-extern crate error;
-extern crate jsonrpc_types;
-#[macro_use]
-extern crate libproto;
 #[macro_use]
 extern crate logger;
-extern crate proof;
-extern crate pubsub;
-extern crate serde_json;
 #[macro_use]
 extern crate util;
 
-mod block_processor;
-mod forward;
-
-use block_processor::BlockProcessor;
 use clap::App;
-use core::db;
-use core::libchain;
-use forward::Forward;
-use libproto::router::{MsgType, RoutingKey, SubModules};
-use pubsub::start_pubsub;
-use std::sync::mpsc::channel;
-use std::sync::Arc;
-use std::thread;
-use std::time;
-use std::time::Duration;
-use util::datapath::DataPath;
-use util::kvdb::{Database, DatabaseConfig};
 use util::set_panic_handler;
 
 include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
@@ -123,89 +93,5 @@ fn main() {
 
     let config_path = matches.value_of("config").unwrap_or("chain.toml");
 
-    let (tx, rx) = channel();
-    let (ctx_pub, crx_pub) = channel();
-    start_pubsub(
-        "chain",
-        routing_key!([
-            Net >> SyncResponse,
-            Net >> SyncRequest,
-            Consensus >> BlockWithProof,
-            Jsonrpc >> Request,
-            Auth >> BlockTxHashesReq,
-            Executor >> ExecutedResult,
-            Snapshot >> SnapshotReq,
-        ]),
-        tx,
-        crx_pub,
-    );
-
-    let nosql_path = DataPath::nosql_path();
-    trace!("nosql_path is {:?}", nosql_path);
-    let db_config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
-    let db = Database::open(&db_config, &nosql_path).unwrap();
-
-    let chain_config = libchain::chain::Config::new(config_path);
-    let chain = Arc::new(libchain::chain::Chain::init_chain(
-        Arc::new(db),
-        chain_config,
-    ));
-
-    let current_height = chain.get_current_height();
-    if let Some(block_tx_hashes) = chain.block_tx_hashes(current_height) {
-        chain.delivery_block_tx_hashes(current_height, block_tx_hashes, &ctx_pub);
-    }
-
-    let (write_sender, write_receiver) = channel();
-    let forward = Forward::new(Arc::clone(&chain), ctx_pub.clone(), write_sender);
-
-    let block_processor = BlockProcessor::new(Arc::clone(&chain), ctx_pub);
-    block_processor.broadcast_current_status();
-
-    //chain 读写分离
-    //chain 读数据 => 查询数据
-    thread::spawn(move || loop {
-        if let Ok((key, msg)) = rx.recv() {
-            forward.dispatch_msg(&key, &msg);
-        }
-    });
-
-    //chain 写数据 => 添加块
-    thread::spawn(move || {
-        let mut timeout_factor = 0u8;
-        loop {
-            if let Ok(einfo) = write_receiver
-                .recv_timeout(Duration::new(18 * (2u64.pow(timeout_factor as u32)), 0))
-            {
-                block_processor.set_executed_result(einfo);
-                timeout_factor = 0;
-            } else {
-                // Here will be these status:
-                // 1. Executor process restarts, lost cached block information.
-                // 2. Executor encountered an invalid block and cleared the block map.
-                // 3. Bft restarted, lost chain status information, unable to consensus, unable to generate block.
-                //
-                // This will trigger:
-                // 1. Network retransmits block information or initiates a synchronization request,
-                //    and then the executor will receive a block message
-                // 2. Bft will receive the latest status of chain
-                block_processor.reset_max_store_height();
-                block_processor.broadcast_current_status();
-                if timeout_factor < 6 {
-                    timeout_factor += 1
-                }
-            }
-        }
-    });
-
-    //garbage collect
-    let mut i: u32 = 0;
-    loop {
-        thread::sleep(time::Duration::from_millis(10_000));
-        if i > 100 {
-            chain.collect_garbage();
-            i = 0;
-        }
-        i += 1;
-    }
+    cita_chain::run(config_path);
 }
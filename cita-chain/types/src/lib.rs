@@ -18,6 +18,9 @@
 #![feature(custom_attribute)]
 #![allow(unused_attributes)]
 extern crate cita_crypto as crypto;
+extern crate cita_ed25519 as ed25519;
+extern crate cita_secp256k1 as secp256k1;
+extern crate cita_sm2 as sm2;
 extern crate cita_types;
 extern crate libproto;
 extern crate rlp;
@@ -27,7 +27,7 @@ use std::str::FromStr;
 use util::{Bytes, HeapSizeOf};
 use BlockNumber;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Eq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Eq)]
 pub enum ReceiptError {
     // ExecutionError
     NotEnoughBaseGas,
@@ -49,7 +49,8 @@ pub enum ReceiptError {
     Internal,
     MutableCallInStaticContext,
     OutOfBounds,
-    Reverted,
+    /// Reverted, with the reason given by `revert("reason")` if any
+    Reverted(Option<String>),
 }
 
 impl ReceiptError {
@@ -76,7 +77,8 @@ impl ReceiptError {
             ReceiptError::Internal => "EVM internal error.",
             ReceiptError::MutableCallInStaticContext => "Mutable call in static context.",
             ReceiptError::OutOfBounds => "Out of bounds.",
-            ReceiptError::Reverted => "Reverted.",
+            ReceiptError::Reverted(None) => "Reverted.",
+            ReceiptError::Reverted(Some(ref reason)) => return format!("Reverted: {}", reason),
         };
         desc.to_string()
     }
@@ -103,12 +105,12 @@ impl ReceiptError {
                 ProtoReceiptError::MutableCallInStaticContext
             }
             ReceiptError::OutOfBounds => ProtoReceiptError::OutOfBounds,
-            ReceiptError::Reverted => ProtoReceiptError::Reverted,
+            ReceiptError::Reverted(_) => ProtoReceiptError::Reverted,
         }
     }
 
-    fn from_proto(receipt_error: ProtoReceiptError) -> Self {
-        match receipt_error {
+    fn from_proto(receipt_error: &ReceiptErrorWithOption) -> Self {
+        match receipt_error.get_error() {
             ProtoReceiptError::NotEnoughBaseGas => ReceiptError::NotEnoughBaseGas,
             ProtoReceiptError::BlockGasLimitReached => ReceiptError::BlockGasLimitReached,
             ProtoReceiptError::AccountGasLimitReached => ReceiptError::AccountGasLimitReached,
@@ -129,7 +131,38 @@ impl ReceiptError {
                 ReceiptError::MutableCallInStaticContext
             }
             ProtoReceiptError::OutOfBounds => ReceiptError::OutOfBounds,
-            ProtoReceiptError::Reverted => ReceiptError::Reverted,
+            ProtoReceiptError::Reverted => {
+                let reason = receipt_error.get_revert_reason();
+                if reason.is_empty() {
+                    ReceiptError::Reverted(None)
+                } else {
+                    ReceiptError::Reverted(Some(reason.to_owned()))
+                }
+            }
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match *self {
+            ReceiptError::NotEnoughBaseGas => 0,
+            ReceiptError::BlockGasLimitReached => 1,
+            ReceiptError::AccountGasLimitReached => 2,
+            ReceiptError::InvalidNonce => 3,
+            ReceiptError::NotEnoughCash => 4,
+            ReceiptError::NoTransactionPermission => 5,
+            ReceiptError::NoContractPermission => 6,
+            ReceiptError::NoCallPermission => 7,
+            ReceiptError::ExecutionInternal => 8,
+            ReceiptError::TransactionMalformed => 9,
+            ReceiptError::OutOfGas => 10,
+            ReceiptError::BadJumpDestination => 11,
+            ReceiptError::BadInstruction => 12,
+            ReceiptError::StackUnderflow => 13,
+            ReceiptError::OutOfStack => 14,
+            ReceiptError::Internal => 15,
+            ReceiptError::MutableCallInStaticContext => 16,
+            ReceiptError::OutOfBounds => 17,
+            ReceiptError::Reverted(_) => 18,
         }
     }
 }
@@ -155,15 +188,17 @@ impl Decodable for ReceiptError {
             15 => Ok(ReceiptError::Internal),
             16 => Ok(ReceiptError::MutableCallInStaticContext),
             17 => Ok(ReceiptError::OutOfBounds),
-            18 => Ok(ReceiptError::Reverted),
+            18 => Ok(ReceiptError::Reverted(None)),
             _ => Err(DecoderError::Custom("Unknown Receipt error.")),
         }
     }
 }
 
+/// The revert reason is not encoded: the receipt RLP makes up the receipts root,
+/// so the reason is stored and served apart from it.
 impl Encodable for ReceiptError {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.append(&(*self as u8));
+        s.append(&self.code());
     }
 }
 
@@ -220,8 +255,11 @@ impl Receipt {
             receipt_proto.set_state_root(state_root_option);
         }
 
-        if let Some(error) = self.error {
+        if let Some(ref error) = self.error {
             receipt_error_with_option.set_error(error.protobuf());
+            if let ReceiptError::Reverted(Some(ref reason)) = *error {
+                receipt_error_with_option.set_revert_reason(reason.clone());
+            }
             receipt_proto.set_error(receipt_error_with_option);
         }
 
@@ -273,9 +311,7 @@ impl From<ProtoReceipt> for Receipt {
             .collect();
 
         if receipt.error.is_some() {
-            error = Some(ReceiptError::from_proto(
-                receipt.clone().take_error().get_error(),
-            ));
+            error = Some(ReceiptError::from_proto(receipt.get_error()));
         }
 
         Receipt::new(
@@ -430,4 +466,31 @@ mod tests {
         println!("decoded: {:?}", decoded);
         assert_eq!(decoded, r);
     }
+
+    #[test]
+    fn test_with_revert_reason() {
+        let r = Receipt::new(
+            None,
+            0x40cae.into(),
+            vec![],
+            Some(ReceiptError::Reverted(Some("Not enough balance".to_owned()))),
+            1.into(),
+            "2f697d671e9ae4ee24a43c4b0d7e15f1cb4ba6de1561120d43b9a4e8c4a8a6ee".into(),
+        );
+        assert_eq!(r.error.clone().unwrap().description(), "Reverted: Not enough balance");
+
+        // The reason is kept out of the receipts root
+        let without_reason = Receipt {
+            error: Some(ReceiptError::Reverted(None)),
+            ..r.clone()
+        };
+        let encoded = ::rlp::encode(&r);
+        assert_eq!(encoded, ::rlp::encode(&without_reason));
+        let decoded: Receipt = ::rlp::decode(&encoded);
+        assert_eq!(decoded, without_reason);
+
+        // But passed from the executor to the chain
+        let decoded = Receipt::from(r.protobuf());
+        assert_eq!(decoded, r);
+    }
 }
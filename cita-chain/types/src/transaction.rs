@@ -17,18 +17,19 @@
 
 use cita_types::traits::LowerHex;
 use cita_types::{clean_0x, Address, H256, U256};
-use crypto::{
-    pubkey_to_address, PubKey, Public, Signature, HASH_BYTES_LEN, PUBKEY_BYTES_LEN,
-    SIGNATURE_BYTES_LEN,
-};
+use crypto::HASH_BYTES_LEN;
+use ed25519;
 use libproto::blockchain::{
     Crypto as ProtoCrypto, SignedTransaction as ProtoSignedTransaction,
     Transaction as ProtoTransaction, UnverifiedTransaction as ProtoUnverifiedTransaction,
 };
 use rlp::*;
+use secp256k1;
+use sm2;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
-use util::{Bytes, HeapSizeOf};
+use util::crypto::Sign;
+use util::{Bytes, Hashable, HeapSizeOf};
 use BlockNumber;
 
 // pub const STORE_ADDRESS: H160 =  H160( [0xff; 20] );
@@ -114,11 +115,12 @@ impl Encodable for Action {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// crypto type.
 pub enum CryptoType {
     SECP,
     SM2,
+    ED25519,
 }
 
 impl Default for CryptoType {
@@ -132,6 +134,7 @@ impl Decodable for CryptoType {
         match rlp.as_val::<u8>()? {
             0 => Ok(CryptoType::SECP),
             1 => Ok(CryptoType::SM2),
+            2 => Ok(CryptoType::ED25519),
             _ => Err(DecoderError::Custom("Unknown Type.")),
         }
     }
@@ -142,6 +145,7 @@ impl Encodable for CryptoType {
         match *self {
             CryptoType::SECP => s.append_internal(&(0 as u8)),
             CryptoType::SM2 => s.append_internal(&(1 as u8)),
+            CryptoType::ED25519 => s.append_internal(&(2 as u8)),
         };
     }
 }
@@ -151,10 +155,58 @@ impl From<ProtoCrypto> for CryptoType {
         match c {
             ProtoCrypto::SECP => CryptoType::SECP,
             ProtoCrypto::SM2 => CryptoType::SM2,
+            ProtoCrypto::ED25519 => CryptoType::ED25519,
         }
     }
 }
 
+impl CryptoType {
+    /// Length of a signature of this scheme, in bytes.
+    pub fn signature_len(&self) -> usize {
+        match *self {
+            CryptoType::SECP => secp256k1::SIGNATURE_BYTES_LEN,
+            CryptoType::SM2 => sm2::SIGNATURE_BYTES_LEN,
+            CryptoType::ED25519 => ed25519::SIGNATURE_BYTES_LEN,
+        }
+    }
+
+    /// Length of a public key of this scheme, in bytes.
+    pub fn pubkey_len(&self) -> usize {
+        match *self {
+            CryptoType::SECP => secp256k1::PUBKEY_BYTES_LEN,
+            CryptoType::SM2 => sm2::PUBKEY_BYTES_LEN,
+            CryptoType::ED25519 => ed25519::PUBKEY_BYTES_LEN,
+        }
+    }
+
+    /// Recover the public key which signed the hash.
+    pub fn recover(&self, signature: &[u8], hash: &H256) -> Result<Bytes, Error> {
+        if signature.len() != self.signature_len() {
+            return Err(Error::InvalidSignature);
+        }
+        let pubkey = match *self {
+            CryptoType::SECP => secp256k1::Signature::from(signature)
+                .recover(&(*hash).into())
+                .map(|pubkey| pubkey.to_vec())
+                .ok(),
+            CryptoType::SM2 => sm2::Signature::from(signature)
+                .recover(&(*hash).into())
+                .map(|pubkey| pubkey.to_vec())
+                .ok(),
+            CryptoType::ED25519 => ed25519::Signature::from(signature)
+                .recover(&(*hash).into())
+                .map(|pubkey| pubkey.to_vec())
+                .ok(),
+        };
+        pubkey.ok_or(Error::InvalidSignature)
+    }
+}
+
+/// Address of the signer with the public key, derived the same way for all schemes.
+pub fn pubkey_to_address(pubkey: &[u8]) -> Address {
+    Address::from(pubkey.crypt_hash())
+}
+
 /// A set of information describing an externally-originating message call
 /// or contract creation operation.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -256,16 +308,16 @@ impl Transaction {
 
     // Specify the sender; this won't survive the serialize/deserialize process, but can be cloned.
     pub fn fake_sign(self, from: Address) -> SignedTransaction {
-        let signature = Signature::default();
+        let crypto_type = CryptoType::default();
         SignedTransaction {
             transaction: UnverifiedTransaction {
                 unsigned: self,
-                signature: signature,
+                signature: vec![0; crypto_type.signature_len()],
                 hash: 0.into(),
-                crypto_type: CryptoType::default(),
+                crypto_type: crypto_type,
             },
             sender: from,
-            public: Public::default(),
+            public: vec![0; crypto_type.pubkey_len()],
         }
     }
 
@@ -306,18 +358,30 @@ impl Transaction {
 }
 
 /// Signed transaction information without verified signature.
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UnverifiedTransaction {
     /// Plain Transaction.
     unsigned: Transaction,
-    /// The signature
-    signature: Signature,
+    /// The signature, its length depends on the crypto type
+    signature: Bytes,
     /// The Crypto Type
     crypto_type: CryptoType,
     /// Hash of the transaction
     hash: H256,
 }
 
+impl Default for UnverifiedTransaction {
+    fn default() -> Self {
+        let crypto_type = CryptoType::default();
+        UnverifiedTransaction {
+            unsigned: Transaction::default(),
+            signature: vec![0; crypto_type.signature_len()],
+            crypto_type: crypto_type,
+            hash: H256::default(),
+        }
+    }
+}
+
 impl Deref for UnverifiedTransaction {
     type Target = Transaction;
 
@@ -354,14 +418,15 @@ impl Encodable for UnverifiedTransaction {
 
 impl UnverifiedTransaction {
     fn new(utx: &ProtoUnverifiedTransaction, hash: H256) -> Result<Self, Error> {
-        if utx.get_signature().len() != SIGNATURE_BYTES_LEN {
+        let crypto_type = CryptoType::from(utx.get_crypto());
+        if utx.get_signature().len() != crypto_type.signature_len() {
             return Err(Error::InvalidSignature);
         }
 
         Ok(UnverifiedTransaction {
             unsigned: Transaction::new(utx.get_transaction())?,
-            signature: Signature::from(utx.get_signature()),
-            crypto_type: CryptoType::from(utx.get_crypto()),
+            signature: utx.get_signature().to_vec(),
+            crypto_type: crypto_type,
             hash: hash,
         })
     }
@@ -384,28 +449,43 @@ impl UnverifiedTransaction {
         self.hash
     }
 
+    pub fn crypto_type(&self) -> CryptoType {
+        self.crypto_type
+    }
+
     /// get protobuf unverified transaction
     pub fn proto_unverified(&self) -> ProtoUnverifiedTransaction {
         let mut untx = ProtoUnverifiedTransaction::new();
         let tx = self.unsigned.proto_transaction();
 
         untx.set_transaction(tx);
-        untx.set_signature(self.signature.to_vec());
+        untx.set_signature(self.signature.clone());
 
         match self.crypto_type {
             CryptoType::SECP => untx.set_crypto(ProtoCrypto::SECP),
             CryptoType::SM2 => untx.set_crypto(ProtoCrypto::SM2),
+            CryptoType::ED25519 => untx.set_crypto(ProtoCrypto::ED25519),
         }
         untx
     }
 }
 
 /// A `UnverifiedTransaction` with successfully recovered `sender`.
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignedTransaction {
     transaction: UnverifiedTransaction,
     sender: Address,
-    public: Public,
+    public: Bytes,
+}
+
+impl Default for SignedTransaction {
+    fn default() -> Self {
+        SignedTransaction {
+            transaction: UnverifiedTransaction::default(),
+            sender: Address::default(),
+            public: vec![0; CryptoType::default().pubkey_len()],
+        }
+    }
 }
 
 /// RLP dose not support struct nesting well
@@ -415,7 +495,7 @@ impl Decodable for SignedTransaction {
             return Err(DecoderError::RlpIncorrectListLen);
         }
 
-        let public: PubKey = d.val_at(12)?;
+        let public: Bytes = d.val_at(12)?;
 
         Ok(SignedTransaction {
             transaction: UnverifiedTransaction {
@@ -489,15 +569,16 @@ impl SignedTransaction {
             return Err(Error::InvalidHash);
         }
 
-        if stx.get_signer().len() != PUBKEY_BYTES_LEN {
+        let tx_hash = H256::from(stx.get_tx_hash());
+        let transaction = UnverifiedTransaction::new(stx.get_transaction_with_sig(), tx_hash)?;
+        if stx.get_signer().len() != transaction.crypto_type.pubkey_len() {
             return Err(Error::InvalidPubKey);
         }
 
-        let tx_hash = H256::from(stx.get_tx_hash());
-        let public = PubKey::from_slice(stx.get_signer());
+        let public = stx.get_signer().to_vec();
         let sender = pubkey_to_address(&public);
         Ok(SignedTransaction {
-            transaction: UnverifiedTransaction::new(stx.get_transaction_with_sig(), tx_hash)?,
+            transaction: transaction,
             sender: sender,
             public: public,
         })
@@ -519,7 +600,7 @@ impl SignedTransaction {
     }
 
     /// Returns a public key of the sender.
-    pub fn public_key(&self) -> &Public {
+    pub fn public_key(&self) -> &[u8] {
         &self.public
     }
 
@@ -529,7 +610,7 @@ impl SignedTransaction {
         let utx = self.transaction.proto_unverified();
         stx.set_transaction_with_sig(utx);
         stx.set_tx_hash(self.hash().to_vec());
-        stx.set_signer(self.public.to_vec());
+        stx.set_signer(self.public.clone());
         stx
     }
 }
@@ -538,6 +619,7 @@ impl SignedTransaction {
 mod tests {
     use super::*;
     use rlp;
+    use util::crypto::CreateKey;
 
     #[test]
     fn test_encode_and_decode() {
@@ -564,6 +646,32 @@ mod tests {
         assert_eq!(stx_rlp, stx_encoded);
     }
 
+    #[test]
+    fn recover_by_crypto_type() {
+        let hash = H256::from(1);
+
+        let secp_keypair = secp256k1::KeyPair::gen_keypair();
+        let secp_signature = secp256k1::Signature::sign(secp_keypair.privkey(), &hash.into())
+            .unwrap()
+            .to_vec();
+        let ed_keypair = ed25519::KeyPair::gen_keypair();
+        let ed_signature = ed25519::Signature::sign(ed_keypair.privkey(), &hash.into())
+            .unwrap()
+            .to_vec();
+
+        let secp_pubkey = CryptoType::SECP.recover(&secp_signature, &hash).unwrap();
+        assert_eq!(secp_pubkey, secp_keypair.pubkey().to_vec());
+        assert_eq!(pubkey_to_address(&secp_pubkey), *secp_keypair.address());
+        let ed_pubkey = CryptoType::ED25519.recover(&ed_signature, &hash).unwrap();
+        assert_eq!(ed_pubkey, ed_keypair.pubkey().to_vec());
+        assert_eq!(pubkey_to_address(&ed_pubkey), *ed_keypair.address());
+
+        assert_eq!(
+            CryptoType::SECP.recover(&ed_signature, &hash),
+            Err(Error::InvalidSignature)
+        );
+    }
+
     #[test]
     fn invalid_value() {
         let mut plain_transaction = ProtoTransaction::new();
@@ -0,0 +1,84 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pre-funded accounts of the dev chain.
+
+use cita_types::traits::LowerHex;
+use cita_types::U256;
+use crypto::{CreateKey, KeyPair};
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// The genesis created by the config tool, used as a template.
+pub const TEMPLATE: &str = "genesis.json";
+/// The genesis with pre-funded accounts. It is put next to the template,
+/// so the executor checks the same `resource` folder.
+pub const GENESIS: &str = "genesis-devchain.json";
+/// Private keys of the pre-funded accounts, by address.
+pub const ACCOUNTS: &str = "devchain-accounts.json";
+
+/// Write the genesis of the dev chain if not there yet, and return the
+/// private keys of the pre-funded accounts.
+pub fn prepare(count: usize, balance: U256) -> BTreeMap<String, String> {
+    if Path::new(GENESIS).exists() && Path::new(ACCOUNTS).exists() {
+        let file = File::open(ACCOUNTS).unwrap();
+        return serde_json::from_reader(BufReader::new(file)).expect("Failed to load accounts.");
+    }
+
+    let file = File::open(TEMPLATE).expect("Failed to open genesis.json");
+    let mut genesis: Value =
+        serde_json::from_reader(BufReader::new(file)).expect("Failed to load genesis.");
+    let accounts: BTreeMap<String, String> = (0..count)
+        .map(|_| {
+            let key_pair = KeyPair::gen_keypair();
+            (
+                format!("0x{}", key_pair.address().lower_hex()),
+                format!("0x{}", key_pair.privkey().lower_hex()),
+            )
+        })
+        .collect();
+    {
+        let alloc = genesis["alloc"]
+            .as_object_mut()
+            .expect("No alloc in genesis.");
+        for address in accounts.keys() {
+            let account = json!({
+                "nonce": "1",
+                "code": "",
+                "storage": {},
+                "value": format!("0x{:x}", balance),
+            });
+            alloc.insert(address.clone(), account);
+        }
+    }
+
+    serde_json::to_writer_pretty(File::create(GENESIS).unwrap(), &genesis).unwrap();
+    serde_json::to_writer_pretty(File::create(ACCOUNTS).unwrap(), &accounts).unwrap();
+    accounts
+}
+
+/// Remove the generated genesis and accounts.
+pub fn reset() {
+    for path in &[GENESIS, ACCOUNTS] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ## Summary
+//! A development chain for contract developers.
+//!
+//! It runs auth, chain, executor and jsonrpc of one node in a single process,
+//! connected by in-process channels instead of a message queue, and seals
+//! blocks by itself instead of running consensus. No network service is started.
+//!
+//! Run it in a node directory created by `create_cita_config.py`:
+//!
+//! ```shell
+//! # seal a block as soon as a transaction arrives
+//! cita-devchain -d test-chain/0
+//! # seal a block every 3 seconds
+//! cita-devchain -d test-chain/0 -i 3000
+//! # remove the chain data and the pre-funded accounts
+//! cita-devchain -d test-chain/0 reset
+//! ```
+//!
+//! On the first start, the accounts are added to a copy of `genesis.json` with
+//! the given balance, and their private keys are saved in `devchain-accounts.json`.
+//!
+
+#![feature(try_from)]
+extern crate bincode;
+extern crate cita_auth;
+extern crate cita_chain;
+extern crate cita_crypto as crypto;
+extern crate cita_executor;
+extern crate cita_jsonrpc;
+extern crate cita_types;
+#[macro_use]
+extern crate clap;
+extern crate dotenv;
+#[macro_use]
+extern crate libproto;
+#[macro_use]
+extern crate logger;
+extern crate proof;
+extern crate pubsub_channel as pubsub;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate util;
+
+mod genesis;
+mod sealer;
+
+use cita_types::{clean_0x, U256};
+use clap::{App, SubCommand};
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use pubsub::start_pubsub;
+use sealer::{SealMode, Sealer};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+use util::datapath::DataPath;
+use util::set_panic_handler;
+
+const DEFAULT_ACCOUNTS: usize = 10;
+const DEFAULT_BALANCE: &str = "0xffffffffffffffffffffffff";
+
+fn main() {
+    let matches = App::new("devchain")
+        .version("0.1")
+        .author("Cryptape")
+        .about("CITA development chain in a single process")
+        .arg_from_usage("-d, --node-dir=[DIR] 'Sets the node directory'")
+        .arg_from_usage("-i, --interval=[MS] 'Seals a block every interval instead of instantly'")
+        .arg_from_usage("-a, --accounts=[NUM] 'Sets the number of pre-funded accounts'")
+        .arg_from_usage("-b, --balance=[HEX] 'Sets the balance of each pre-funded account'")
+        .subcommand(SubCommand::with_name("reset").about("Removes chain data and accounts"))
+        .get_matches();
+
+    if let Some(node_dir) = matches.value_of("node-dir") {
+        env::set_current_dir(node_dir).expect("Failed to enter the node directory.");
+    }
+    dotenv::dotenv().ok();
+    if env::var("DATA_PATH").is_err() {
+        env::set_var("DATA_PATH", "./data");
+    }
+    logger::init();
+    set_panic_handler();
+
+    if matches.subcommand_matches("reset").is_some() {
+        let data_path = DataPath::root_node_path();
+        if Path::new(&data_path).exists() {
+            fs::remove_dir_all(&data_path).unwrap();
+        }
+        genesis::reset();
+        info!("CITA:devchain reset");
+        return;
+    }
+
+    let mode = match value_t!(matches, "interval", u64) {
+        Ok(interval) => SealMode::Interval(Duration::from_millis(interval)),
+        Err(_) => SealMode::Instant,
+    };
+    let count = value_t!(matches, "accounts", usize).unwrap_or(DEFAULT_ACCOUNTS);
+    let balance = matches.value_of("balance").unwrap_or(DEFAULT_BALANCE);
+    let balance = U256::from_str(clean_0x(balance)).expect("Invalid balance.");
+
+    if !Path::new(genesis::GENESIS).exists() && Path::new(&DataPath::root_node_path()).exists() {
+        warn!("The chain data is not created by devchain, run `reset` to get pre-funded accounts");
+    }
+    let accounts = genesis::prepare(count, balance);
+    info!("CITA:devchain {:?}", mode);
+    for (address, privkey) in &accounts {
+        info!("account {} privkey {}", address, privkey);
+    }
+
+    // The sealer subscribes first and chain starts last, so the first status
+    // of chain reaches everyone.
+    let (tx_sub, rx_sub) = channel();
+    let (tx_pub, rx_pub) = channel();
+    start_pubsub(
+        "consensus",
+        routing_key!([
+            Auth >> BlockTxs,
+            Auth >> PendingTxHashes,
+            Chain >> RichStatus,
+        ]),
+        tx_sub,
+        rx_pub,
+    );
+
+    thread::spawn(|| cita_executor::run("executor.toml", genesis::GENESIS));
+    thread::spawn(|| cita_auth::run("auth.toml", false));
+    thread::spawn(|| cita_jsonrpc::run("jsonrpc.toml"));
+    thread::spawn(|| cita_chain::run("chain.toml"));
+
+    Sealer::new(mode, tx_pub).run(&rx_sub);
+}
@@ -0,0 +1,311 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The built-in consensus of the dev chain.
+//!
+//! It signs every block with a throwaway key, the same way as `consensus-mock` does.
+
+use bincode::{serialize, Infinite};
+use cita_types::{Address, H256};
+use crypto::{CreateKey, KeyPair, PrivKey, Sign, Signature};
+use libproto::blockchain::{Block, BlockTxs, BlockWithProof, RichStatus};
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use libproto::Message;
+use proof::TendermintProof;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use util::Hashable;
+
+pub type PubType = (String, Vec<u8>);
+
+/// How long to wait for a message before checking whether to seal.
+const TICK_MILLIS: u64 = 100;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+    Commit,
+}
+
+/// When to seal a new block.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SealMode {
+    /// Seal as soon as there are transactions.
+    Instant,
+    /// Seal a block every interval, empty or not.
+    Interval(Duration),
+}
+
+pub struct Sealer {
+    mode: SealMode,
+    key_pair: KeyPair,
+    tx_pub: Sender<PubType>,
+    status: Option<RichStatus>,
+    block_txs: HashMap<u64, BlockTxs>,
+    sealed_height: u64,
+    // Auth has new transactions that are not in the stored `BlockTxs` yet.
+    has_pending: bool,
+    last_sealed_at: Instant,
+}
+
+impl Sealer {
+    pub fn new(mode: SealMode, tx_pub: Sender<PubType>) -> Self {
+        Sealer {
+            mode: mode,
+            key_pair: KeyPair::gen_keypair(),
+            tx_pub: tx_pub,
+            status: None,
+            block_txs: HashMap::new(),
+            sealed_height: 0,
+            has_pending: false,
+            last_sealed_at: Instant::now(),
+        }
+    }
+
+    pub fn run(&mut self, rx_sub: &Receiver<PubType>) {
+        let tick = Duration::from_millis(TICK_MILLIS);
+        loop {
+            match rx_sub.recv_timeout(tick) {
+                Ok((key, body)) => self.handle(&key, body),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => {
+                    error!("devchain sealer stopped: {:?}", err);
+                    return;
+                }
+            }
+            self.try_seal();
+        }
+    }
+
+    fn handle(&mut self, key: &str, body: Vec<u8>) {
+        let mut msg = Message::try_from(body).unwrap();
+        match RoutingKey::from(key) {
+            routing_key!(Auth >> BlockTxs) => {
+                if let Some(block_txs) = msg.take_block_txs() {
+                    self.block_txs.insert(block_txs.get_height(), block_txs);
+                }
+            }
+            routing_key!(Auth >> PendingTxHashes) => {
+                self.has_pending = true;
+            }
+            routing_key!(Chain >> RichStatus) => {
+                if let Some(status) = msg.take_rich_status() {
+                    let height = status.get_height();
+                    self.block_txs.retain(|h, _| *h >= height);
+                    trace!("get new local status {:?}", status);
+                    self.status = Some(status);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn try_seal(&mut self) {
+        let (height, pre_hash) = match self.status {
+            Some(ref status) if status.get_height() >= self.sealed_height => {
+                (status.get_height(), H256::from_slice(status.get_hash()))
+            }
+            _ => return,
+        };
+
+        // A pending notification means the stored `BlockTxs` is stale, so an
+        // instant seal goes ahead and lets auth package the new transactions
+        // for the next height.
+        let ready = match self.mode {
+            SealMode::Instant => {
+                self.has_pending || self.block_txs.get(&height).map_or(false, |block_txs| {
+                    !block_txs.get_body().get_transactions().is_empty()
+                })
+            }
+            SealMode::Interval(interval) => self.last_sealed_at.elapsed() >= interval,
+        };
+        if !ready {
+            return;
+        }
+
+        if let Some(block_txs) = self.block_txs.remove(&height) {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("get timestamp error");
+            let timestamp =
+                timestamp.as_secs() * 1000 + u64::from(timestamp.subsec_nanos() / 1_000_000);
+            self.sealed_height = height + 1;
+            self.has_pending = false;
+            self.last_sealed_at = Instant::now();
+            info!(
+                "seal block {} with {} transactions",
+                self.sealed_height,
+                block_txs.get_body().get_transactions().len()
+            );
+            let send_data = build_block(
+                &block_txs,
+                pre_hash,
+                self.sealed_height,
+                self.key_pair.privkey(),
+                timestamp,
+            );
+            self.tx_pub
+                .send((routing_key!(Consensus >> BlockWithProof).into(), send_data))
+                .unwrap();
+        }
+    }
+}
+
+fn build_proof(height: u64, sender: Address, privkey: &PrivKey) -> TendermintProof {
+    let mut proof = TendermintProof::default();
+    proof.height = (height - 1) as usize;
+    proof.round = 0;
+    proof.proposal = H256::default();
+
+    let mut commits = HashMap::new();
+    let message = serialize(
+        &(
+            proof.height,
+            proof.round,
+            Step::Precommit,
+            sender,
+            Some(proof.proposal),
+        ),
+        Infinite,
+    ).unwrap();
+
+    let signature = Signature::sign(privkey, &message.crypt_hash().into()).unwrap();
+    commits.insert((*sender).into(), signature.into());
+    proof.commits = commits;
+    proof
+}
+
+fn build_block(
+    block_txs: &BlockTxs,
+    pre_block_hash: H256,
+    height: u64,
+    privkey: &PrivKey,
+    timestamp: u64,
+) -> Vec<u8> {
+    let sender = *KeyPair::from_privkey(*privkey).unwrap().address();
+    let body = block_txs.get_body();
+    let proof = build_proof(height, sender, privkey);
+    let mut block = Block::new();
+    block.mut_header().set_timestamp(timestamp);
+    block.mut_header().set_height(height);
+    block.mut_header().set_prevhash(pre_block_hash.0.to_vec());
+    block.mut_header().set_proof(proof.clone().into());
+    block
+        .mut_header()
+        .set_transactions_root(body.transactions_root().to_vec());
+    block.set_body(body.clone());
+
+    let mut proof_blk = BlockWithProof::new();
+    proof_blk.set_blk(block);
+    proof_blk.set_proof(proof.into());
+
+    let msg: Message = proof_blk.into();
+    msg.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libproto::SignedTransaction;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    fn send_status(sealer: &mut Sealer, height: u64) {
+        let mut status = RichStatus::new();
+        status.set_height(height);
+        status.set_hash(H256::from(height + 1).to_vec());
+        let msg: Message = status.into();
+        let key: String = routing_key!(Chain >> RichStatus).into();
+        sealer.handle(&key, msg.try_into().unwrap());
+    }
+
+    fn send_block_txs(sealer: &mut Sealer, height: u64, count: u64) {
+        let transactions: Vec<_> = (0..count)
+            .map(|i| {
+                let mut tx = SignedTransaction::new();
+                tx.set_tx_hash(H256::from(i + 1).to_vec());
+                tx
+            })
+            .collect();
+        let mut block_txs = BlockTxs::new();
+        block_txs.set_height(height);
+        block_txs.mut_body().set_transactions(transactions.into());
+        let msg: Message = block_txs.into();
+        let key: String = routing_key!(Auth >> BlockTxs).into();
+        sealer.handle(&key, msg.try_into().unwrap());
+    }
+
+    /// Height and number of transactions of the sealed block, if any.
+    fn sealed(rx: &Receiver<PubType>) -> Option<(u64, usize)> {
+        rx.try_recv().ok().map(|(_, body)| {
+            let mut msg = Message::try_from(body).unwrap();
+            let proof_blk = msg.take_block_with_proof().unwrap();
+            let block = proof_blk.get_blk();
+            (
+                block.get_header().get_height(),
+                block.get_body().get_transactions().len(),
+            )
+        })
+    }
+
+    #[test]
+    fn instant_seal_on_transactions() {
+        let (tx_pub, rx_pub) = channel();
+        let mut sealer = Sealer::new(SealMode::Instant, tx_pub);
+
+        send_status(&mut sealer, 0);
+        send_block_txs(&mut sealer, 0, 0);
+        sealer.try_seal();
+        assert_eq!(sealed(&rx_pub), None);
+
+        send_block_txs(&mut sealer, 0, 2);
+        sealer.try_seal();
+        assert_eq!(sealed(&rx_pub), Some((1, 2)));
+
+        // Nothing is sealed again until chain has the block.
+        send_block_txs(&mut sealer, 0, 1);
+        sealer.try_seal();
+        assert_eq!(sealed(&rx_pub), None);
+
+        send_status(&mut sealer, 1);
+        send_block_txs(&mut sealer, 1, 1);
+        sealer.try_seal();
+        assert_eq!(sealed(&rx_pub), Some((2, 1)));
+    }
+
+    #[test]
+    fn interval_seal_empty_blocks() {
+        let interval = Duration::from_millis(50);
+        let (tx_pub, rx_pub) = channel();
+        let mut sealer = Sealer::new(SealMode::Interval(interval), tx_pub);
+
+        for height in 0..2 {
+            send_status(&mut sealer, height);
+            send_block_txs(&mut sealer, height, 0);
+            sealer.try_seal();
+            assert_eq!(sealed(&rx_pub), None);
+
+            thread::sleep(interval);
+            sealer.try_seal();
+            assert_eq!(sealed(&rx_pub), Some((height + 1, 0)));
+        }
+    }
+}
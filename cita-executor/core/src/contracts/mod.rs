@@ -48,10 +48,6 @@ trait ContractCallExt {
         from: Option<Address>,
         block_id: BlockId,
     ) -> Vec<u8>;
-    /// Call a contract method on latest block
-    fn call_method_latest(&self, address: &Address, encoded_method: &[u8]) -> Vec<u8> {
-        self.call_method(address, encoded_method, None, BlockId::Latest)
-    }
 }
 
 impl ContractCallExt for Executor {
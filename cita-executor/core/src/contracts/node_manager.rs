@@ -26,6 +26,7 @@ use rand::{Rng, SeedableRng, StdRng};
 use rustc_hex::ToHex;
 use std::iter;
 use std::str::FromStr;
+use types::ids::BlockId;
 
 const LIST_NODE: &'static [u8] = &*b"listNode()";
 const LIST_STAKE: &'static [u8] = *&b"listStake()";
@@ -74,10 +75,13 @@ impl<'a> NodeManager<'a> {
         NodeManager { executor, rng_seed }
     }
 
-    pub fn nodes(&self) -> Vec<Address> {
-        let output = self
-            .executor
-            .call_method_latest(&*CONTRACT_ADDRESS, &*LIST_NODE_ENCODED.as_slice());
+    pub fn nodes(&self, block_id: BlockId) -> Vec<Address> {
+        let output = self.executor.call_method(
+            &*CONTRACT_ADDRESS,
+            &*LIST_NODE_ENCODED.as_slice(),
+            None,
+            block_id,
+        );
 
         trace!(
             "node manager output: {:?}",
@@ -89,10 +93,13 @@ impl<'a> NodeManager<'a> {
         nodes
     }
 
-    pub fn stakes(&self) -> Vec<u64> {
-        let output = self
-            .executor
-            .call_method_latest(&*CONTRACT_ADDRESS, &*LIST_STAKE_ENCODED.as_slice());
+    pub fn stakes(&self, block_id: BlockId) -> Vec<u64> {
+        let output = self.executor.call_method(
+            &*CONTRACT_ADDRESS,
+            &*LIST_STAKE_ENCODED.as_slice(),
+            None,
+            block_id,
+        );
 
         trace!("stakes output: {:?}", ToHex::to_hex(output.as_slice()));
 
@@ -101,18 +108,18 @@ impl<'a> NodeManager<'a> {
         stakes
     }
 
-    pub fn shuffled_stake_nodes(&self) -> Vec<Address> {
-        let mut stake_nodes = self.stake_nodes();
+    pub fn shuffled_stake_nodes(&self, block_id: BlockId) -> Vec<Address> {
+        let mut stake_nodes = self.stake_nodes(block_id);
         shuffle(&mut stake_nodes, self.rng_seed);
         stake_nodes
     }
 
-    pub fn stake_nodes(&self) -> Vec<Address> {
-        let nodes = self.nodes();
+    pub fn stake_nodes(&self, block_id: BlockId) -> Vec<Address> {
+        let nodes = self.nodes(block_id);
         if let EconomicalModel::Quota = *self.executor.economical_model.read() {
             return nodes;
         }
-        let stakes = self.stakes();
+        let stakes = self.stakes(block_id);
         let total = stakes.iter().fold(0, |acc, &x| acc + x);
 
         if total == 0 {
@@ -132,6 +139,7 @@ mod tests {
     use cita_types::H160;
     use std::str::FromStr;
     use tests::helpers::init_executor;
+    use types::ids::BlockId;
 
     #[test]
     fn test_node_manager_contract() {
@@ -148,7 +156,7 @@ mod tests {
             ("NodeManager.stakes", "1,1,1,1"),
         ]);
         let node_manager = NodeManager::new(&executor, executor.genesis_header().timestamp());
-        let nodes = node_manager.nodes();
+        let nodes = node_manager.nodes(BlockId::Latest);
 
         assert_eq!(
             nodes,
@@ -21,6 +21,7 @@ use super::{encode_contract_name, to_address_vec, to_resource_vec};
 use cita_types::{Address, H160, H256};
 use libexecutor::executor::Executor;
 use std::collections::HashMap;
+use types::ids::BlockId;
 
 const ALLACCOUNTS: &'static [u8] = &*b"queryAllAccounts()";
 const PERMISSIONS: &'static [u8] = &*b"queryPermissions(address)";
@@ -67,16 +68,24 @@ impl Resource {
 pub struct PermissionManagement;
 
 impl PermissionManagement {
-    pub fn load_account_permissions(executor: &Executor) -> HashMap<Address, Vec<Resource>> {
+    pub fn load_account_permissions(
+        executor: &Executor,
+        block_id: BlockId,
+    ) -> HashMap<Address, Vec<Resource>> {
         let mut account_permissions = HashMap::new();
-        let accounts = PermissionManagement::all_accounts(executor);
+        let accounts = PermissionManagement::all_accounts(executor, block_id);
 
         trace!("ALl accounts: {:?}", accounts);
         for account in accounts {
-            let permissions = PermissionManagement::permissions(executor, &(H256::from(account)));
+            let permissions =
+                PermissionManagement::permissions(executor, &(H256::from(account)), block_id);
             let mut resources = vec![];
             for permission in permissions {
-                resources.extend(PermissionManagement::resources(executor, &permission));
+                resources.extend(PermissionManagement::resources(
+                    executor,
+                    &permission,
+                    block_id,
+                ));
             }
             account_permissions.insert(account, resources);
         }
@@ -85,15 +94,20 @@ impl PermissionManagement {
     }
 
     /// Account array
-    pub fn all_accounts(executor: &Executor) -> Vec<Address> {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*ALLACCOUNTS_HASH.as_slice());
+    pub fn all_accounts(executor: &Executor, block_id: BlockId) -> Vec<Address> {
+        let output = executor.call_method(
+            &*CONTRACT_ADDRESS,
+            &*ALLACCOUNTS_HASH.as_slice(),
+            None,
+            block_id,
+        );
         trace!("All accounts output: {:?}", output);
 
         to_address_vec(&output)
     }
 
-    pub fn get_super_admin_account(executor: &Executor) -> Option<Address> {
-        let accounts = PermissionManagement::all_accounts(executor);
+    pub fn get_super_admin_account(executor: &Executor, block_id: BlockId) -> Option<Address> {
+        let accounts = PermissionManagement::all_accounts(executor, block_id);
         if accounts.is_empty() {
             None
         } else {
@@ -102,19 +116,19 @@ impl PermissionManagement {
     }
 
     /// Permission array
-    pub fn permissions(executor: &Executor, param: &H256) -> Vec<Address> {
+    pub fn permissions(executor: &Executor, param: &H256, block_id: BlockId) -> Vec<Address> {
         let mut tx_data = PERMISSIONS_HASH.to_vec();
         tx_data.extend(param.to_vec());
         debug!("tx_data: {:?}", tx_data);
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &tx_data.as_slice());
+        let output = executor.call_method(&*CONTRACT_ADDRESS, &tx_data.as_slice(), None, block_id);
         debug!("Permissions output: {:?}", output);
 
         to_address_vec(&output)
     }
 
     /// Resources array
-    pub fn resources(executor: &Executor, address: &Address) -> Vec<Resource> {
-        let output = executor.call_method_latest(address, &*RESOURCES_HASH.as_slice());
+    pub fn resources(executor: &Executor, address: &Address, block_id: BlockId) -> Vec<Resource> {
+        let output = executor.call_method(address, &*RESOURCES_HASH.as_slice(), None, block_id);
         trace!("Resources output: {:?}", output);
 
         to_resource_vec(&output)
@@ -150,6 +164,7 @@ mod tests {
     use std::collections::HashMap;
     use std::str::FromStr;
     use tests::helpers::init_executor;
+    use types::ids::BlockId;
 
     #[test]
     fn test_contains_resource() {
@@ -204,7 +219,8 @@ mod tests {
             "Authorization.super_admin",
             "0x4b5ae4567ad5d9fb92bc9afd6a657e6fa1300000",
         )]);
-        let all_accounts: Vec<Address> = PermissionManagement::all_accounts(&executor);
+        let all_accounts: Vec<Address> =
+            PermissionManagement::all_accounts(&executor, BlockId::Latest);
 
         assert_eq!(
             all_accounts,
@@ -224,8 +240,11 @@ mod tests {
             )),
         ]);
         let super_admin = Address::from_str("4b5ae4567ad5d9fb92bc9afd6a657e6fa1300000").unwrap();
-        let mut permissions: Vec<Address> =
-            PermissionManagement::permissions(&executor, &(H256::from(super_admin)));
+        let mut permissions: Vec<Address> = PermissionManagement::permissions(
+            &executor,
+            &(H256::from(super_admin)),
+            BlockId::Latest,
+        );
         permissions.sort();
 
         let mut expected_permissions = vec![
@@ -254,7 +273,8 @@ mod tests {
     fn test_resources() {
         let executor = init_executor(vec![]);
         let permission = Address::from(0x13241b5);
-        let resources: Vec<Resource> = PermissionManagement::resources(&executor, &permission);
+        let resources: Vec<Resource> =
+            PermissionManagement::resources(&executor, &permission, BlockId::Latest);
         assert_eq!(
             resources,
             vec![Resource {
@@ -268,7 +288,8 @@ mod tests {
     fn test_resources_from_not_exist_permission() {
         let executor = init_executor(vec![]);
         let permission = Address::from(0x13);
-        let resources: Vec<Resource> = PermissionManagement::resources(&executor, &permission);
+        let resources: Vec<Resource> =
+            PermissionManagement::resources(&executor, &permission, BlockId::Latest);
         assert_eq!(resources, vec![]);
     }
 
@@ -280,7 +301,7 @@ mod tests {
         )]);
         let super_admin = Address::from_str("4b5ae4567ad5d9fb92bc9afd6a657e6fa1300000").unwrap();
         let account_permissions: HashMap<Address, Vec<Resource>> =
-            PermissionManagement::load_account_permissions(&executor);
+            PermissionManagement::load_account_permissions(&executor, BlockId::Latest);
         assert_eq!(account_permissions.contains_key(&super_admin), true);
 
         let mut resources = (*account_permissions.get(&super_admin).unwrap()).clone();
@@ -25,6 +25,7 @@ use libexecutor::executor::Executor;
 use libproto::blockchain::AccountGasLimit as ProtoAccountGasLimit;
 use std::collections::HashMap;
 use std::str::FromStr;
+use types::ids::BlockId;
 
 const QUOTAS: &'static [u8] = &*b"getQuotas()";
 const ACCOUNTS: &'static [u8] = &*b"getAccounts()";
@@ -89,9 +90,9 @@ pub struct QuotaManager;
 
 impl QuotaManager {
     /// Special account gas limit
-    pub fn specific(executor: &Executor) -> HashMap<Address, u64> {
-        let users = QuotaManager::users(executor);
-        let quota = QuotaManager::quota(executor);
+    pub fn specific(executor: &Executor, block_id: BlockId) -> HashMap<Address, u64> {
+        let users = QuotaManager::users(executor, block_id);
+        let quota = QuotaManager::quota(executor, block_id);
         let mut specific = HashMap::new();
         for (k, v) in users.iter().zip(quota.iter()) {
             specific.insert(*k, *v);
@@ -100,32 +101,40 @@ impl QuotaManager {
     }
 
     /// Quota array
-    pub fn quota(executor: &Executor) -> Vec<u64> {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*QUOTAS_HASH.as_slice());
+    pub fn quota(executor: &Executor, block_id: BlockId) -> Vec<u64> {
+        let output =
+            executor.call_method(&*CONTRACT_ADDRESS, &*QUOTAS_HASH.as_slice(), None, block_id);
         trace!("quota output: {:?}", output);
 
         to_u256_vec(&output).iter().map(|i| i.low_u64()).collect()
     }
 
     /// Account array
-    pub fn users(executor: &Executor) -> Vec<Address> {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*ACCOUNTS_HASH.as_slice());
+    pub fn users(executor: &Executor, block_id: BlockId) -> Vec<Address> {
+        let output =
+            executor.call_method(&*CONTRACT_ADDRESS, &*ACCOUNTS_HASH.as_slice(), None, block_id);
         trace!("users output: {:?}", output);
 
         to_address_vec(&output)
     }
 
     /// Global gas limit
-    pub fn block_gas_limit(executor: &Executor) -> u64 {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*BQL_HASH.as_slice());
+    pub fn block_gas_limit(executor: &Executor, block_id: BlockId) -> u64 {
+        let output =
+            executor.call_method(&*CONTRACT_ADDRESS, &*BQL_HASH.as_slice(), None, block_id);
         trace!("block_gas_limit output: {:?}", output);
 
         to_u256(&output).low_u64()
     }
 
     /// Global account gas limit
-    pub fn account_gas_limit(executor: &Executor) -> u64 {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*DEFAULT_AQL_HASH.as_slice());
+    pub fn account_gas_limit(executor: &Executor, block_id: BlockId) -> u64 {
+        let output = executor.call_method(
+            &*CONTRACT_ADDRESS,
+            &*DEFAULT_AQL_HASH.as_slice(),
+            None,
+            block_id,
+        );
         trace!("account_gas_limit output: {:?}", output);
 
         to_u256(&output).low_u64()
@@ -141,6 +150,7 @@ mod tests {
     use cita_types::H160;
     use std::str::FromStr;
     use tests::helpers::init_executor;
+    use types::ids::BlockId;
 
     #[test]
     fn test_users() {
@@ -164,7 +174,7 @@ mod tests {
         ]);
         println!("init executor finish");
 
-        let users = QuotaManager::users(&executor);
+        let users = QuotaManager::users(&executor, BlockId::Latest);
 
         assert_eq!(
             users,
@@ -189,7 +199,7 @@ mod tests {
         let executor = init_executor(vec![]);
         println!("init executor finish");
 
-        let quota = QuotaManager::quota(&executor);
+        let quota = QuotaManager::quota(&executor, BlockId::Latest);
 
         assert_eq!(quota, vec![1073741824]);
     }
@@ -211,7 +221,7 @@ mod tests {
         let executor = init_executor(vec![]);
         println!("init executor finish");
 
-        let block_gas_limit = QuotaManager::block_gas_limit(&executor);
+        let block_gas_limit = QuotaManager::block_gas_limit(&executor, BlockId::Latest);
 
         assert_eq!(block_gas_limit, 1073741824);
     }
@@ -233,7 +243,7 @@ mod tests {
         let executor = init_executor(vec![]);
         println!("init executor finish");
 
-        let account_gas_limit = QuotaManager::account_gas_limit(&executor);
+        let account_gas_limit = QuotaManager::account_gas_limit(&executor, BlockId::Latest);
 
         assert_eq!(account_gas_limit, 268435456);
     }
@@ -75,44 +75,35 @@ impl<'a> SysConfig<'a> {
     }
 
     /// Delay block number before validate
-    pub fn delay_block_number(&self) -> u64 {
-        let value =
-            self.get_value(
-                &[ParamType::Uint(256)],
-                DELAY_BLOCK_NUMBER.as_slice(),
-                Some(BlockId::Latest),
-            ).remove(0)
-                .to_uint()
-                .expect("decode delay number");
+    pub fn delay_block_number(&self, block_id: Option<BlockId>) -> u64 {
+        let value = self
+            .get_value(&[ParamType::Uint(256)], DELAY_BLOCK_NUMBER.as_slice(), block_id)
+            .remove(0)
+            .to_uint()
+            .expect("decode delay number");
         let number = H256::from(value).low_u64();
         debug!("delay block number: {:?}", number);
         number
     }
 
     /// Whether check permission or not
-    pub fn permission_check(&self) -> bool {
-        let check =
-            self.get_value(
-                &[ParamType::Bool],
-                PERMISSION_CHECK.as_slice(),
-                Some(BlockId::Latest),
-            ).remove(0)
-                .to_bool()
-                .expect("decode check permission");
+    pub fn permission_check(&self, block_id: Option<BlockId>) -> bool {
+        let check = self
+            .get_value(&[ParamType::Bool], PERMISSION_CHECK.as_slice(), block_id)
+            .remove(0)
+            .to_bool()
+            .expect("decode check permission");
         debug!("check permission: {:?}", check);
         check
     }
 
     /// Whether check quota or not
-    pub fn quota_check(&self) -> bool {
-        let check =
-            self.get_value(
-                &[ParamType::Bool],
-                QUOTA_CHECK.as_slice(),
-                Some(BlockId::Latest),
-            ).remove(0)
-                .to_bool()
-                .expect("decode check quota");
+    pub fn quota_check(&self, block_id: Option<BlockId>) -> bool {
+        let check = self
+            .get_value(&[ParamType::Bool], QUOTA_CHECK.as_slice(), block_id)
+            .remove(0)
+            .to_bool()
+            .expect("decode check quota");
         debug!("check quota: {:?}", check);
         check
     }
@@ -166,15 +157,12 @@ impl<'a> SysConfig<'a> {
     }
 
     /// The interval time for creating a block (milliseconds)
-    pub fn block_interval(&self) -> u64 {
-        let value =
-            self.get_value(
-                &[ParamType::Uint(64)],
-                BLOCK_INTERVAL.as_slice(),
-                Some(BlockId::Latest),
-            ).remove(0)
-                .to_uint()
-                .expect("decode block interval");
+    pub fn block_interval(&self, block_id: Option<BlockId>) -> u64 {
+        let value = self
+            .get_value(&[ParamType::Uint(64)], BLOCK_INTERVAL.as_slice(), block_id)
+            .remove(0)
+            .to_uint()
+            .expect("decode block interval");
         let interval = H256::from(value).low_u64();
         debug!("block interval: {:?}", interval);
         interval
@@ -234,21 +222,21 @@ mod tests {
     #[test]
     fn test_delay_block_number() {
         let executor = init_executor(vec![("SysConfig.delay_block_number", "2")]);
-        let number = SysConfig::new(&executor).delay_block_number();
+        let number = SysConfig::new(&executor).delay_block_number(None);
         assert_eq!(number, 2);
     }
 
     #[test]
     fn test_permission_check() {
         let executor = init_executor(vec![("SysConfig.check_permission", "false")]);
-        let check_permission = SysConfig::new(&executor).permission_check();
+        let check_permission = SysConfig::new(&executor).permission_check(None);
         assert_eq!(check_permission, false);
     }
 
     #[test]
     fn test_quota_check() {
         let executor = init_executor(vec![("SysConfig.check_quota", "true")]);
-        let check_quota = SysConfig::new(&executor).quota_check();
+        let check_quota = SysConfig::new(&executor).quota_check(None);
         assert_eq!(check_quota, true);
     }
 
@@ -283,7 +271,7 @@ mod tests {
     #[test]
     fn test_block_interval() {
         let executor = init_executor(vec![("SysConfig.block_interval", "3006")]);
-        let value = SysConfig::new(&executor).block_interval();
+        let value = SysConfig::new(&executor).block_interval(None);
         assert_eq!(value, 3006);
     }
 
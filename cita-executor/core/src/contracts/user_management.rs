@@ -21,6 +21,7 @@ use super::{encode_contract_name, to_address_vec};
 use cita_types::{Address, H160};
 use libexecutor::executor::Executor;
 use std::collections::HashMap;
+use types::ids::BlockId;
 
 const ALLGROUPS: &'static [u8] = &*b"queryGroups()";
 const ACCOUNTS: &'static [u8] = &*b"queryAccounts()";
@@ -34,13 +35,16 @@ lazy_static! {
 pub struct UserManagement;
 
 impl UserManagement {
-    pub fn load_group_accounts(executor: &Executor) -> HashMap<Address, Vec<Address>> {
+    pub fn load_group_accounts(
+        executor: &Executor,
+        block_id: BlockId,
+    ) -> HashMap<Address, Vec<Address>> {
         let mut group_accounts = HashMap::new();
-        let groups = UserManagement::all_groups(executor);
+        let groups = UserManagement::all_groups(executor, block_id);
 
         trace!("ALl groups: {:?}", groups);
         for group in groups {
-            let accounts = UserManagement::accounts(executor, &group);
+            let accounts = UserManagement::accounts(executor, &group, block_id);
             group_accounts.insert(group, accounts);
         }
 
@@ -48,16 +52,21 @@ impl UserManagement {
     }
 
     /// Group array
-    pub fn all_groups(executor: &Executor) -> Vec<Address> {
-        let output = executor.call_method_latest(&*CONTRACT_ADDRESS, &*ALLGROUPS_HASH.as_slice());
+    pub fn all_groups(executor: &Executor, block_id: BlockId) -> Vec<Address> {
+        let output = executor.call_method(
+            &*CONTRACT_ADDRESS,
+            &*ALLGROUPS_HASH.as_slice(),
+            None,
+            block_id,
+        );
         trace!("All groups output: {:?}", output);
 
         to_address_vec(&output)
     }
 
     /// Accounts array
-    pub fn accounts(executor: &Executor, address: &Address) -> Vec<Address> {
-        let output = executor.call_method_latest(address, &ACCOUNTS_HASH.as_slice());
+    pub fn accounts(executor: &Executor, address: &Address, block_id: BlockId) -> Vec<Address> {
+        let output = executor.call_method(address, &ACCOUNTS_HASH.as_slice(), None, block_id);
         debug!("Accounts output: {:?}", output);
 
         to_address_vec(&output)
@@ -73,11 +82,12 @@ mod tests {
     use cita_types::{Address, H160};
     use std::str::FromStr;
     use tests::helpers::init_executor;
+    use types::ids::BlockId;
 
     #[test]
     fn test_all_groups() {
         let executor = init_executor(vec![]);
-        let all_groups: Vec<Address> = UserManagement::all_groups(&executor);
+        let all_groups: Vec<Address> = UserManagement::all_groups(&executor, BlockId::Latest);
 
         assert_eq!(all_groups, vec![H160::from(0x13241b6)]);
     }
@@ -92,7 +102,8 @@ mod tests {
                 "0x9dcd6b234e2772c5451fd4ccf7582f4283140697"
             ),
         )]);
-        let accounts: Vec<Address> = UserManagement::accounts(&executor, &H160::from(0x13241b6));
+        let accounts: Vec<Address> =
+            UserManagement::accounts(&executor, &H160::from(0x13241b6), BlockId::Latest);
 
         assert_eq!(
             accounts,
@@ -115,7 +126,7 @@ mod tests {
             ),
         )]);
         let root = H160::from(0x13241b6);
-        let group_accounts = UserManagement::load_group_accounts(&executor);
+        let group_accounts = UserManagement::load_group_accounts(&executor, BlockId::Latest);
         assert_eq!(group_accounts.contains_key(&root), true);
         assert_eq!(
             *group_accounts.get(&root).unwrap(),
@@ -71,6 +71,42 @@ pub struct Executed {
     pub account_nonce: U256
 }
 
+impl Executed {
+    /// The reason given by `revert("reason")`, if the transaction is reverted with one.
+    pub fn revert_reason(&self) -> Option<String> {
+        match self.exception {
+            Some(evm::Error::Reverted) => decode_revert_reason(&self.output),
+            _ => None,
+        }
+    }
+}
+
+/// Selector of `Error(string)`, which solidity encodes the revert reason as.
+const REVERT_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Decode the ABI encoded `Error(string)` from the output of a reverted call.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    if output.len() < 4 + 64 || output[..4] != REVERT_SELECTOR {
+        return None;
+    }
+    let data = &output[4..];
+    let len = U256::from(data.len());
+
+    let offset = U256::from(&data[..32]);
+    if offset > len - U256::from(32) {
+        return None;
+    }
+    let start = offset.low_u64() as usize + 32;
+
+    let size = U256::from(&data[start - 32..start]);
+    if size > len - U256::from(start) {
+        return None;
+    }
+    let end = start + size.low_u64() as usize;
+
+    String::from_utf8(data[start..end].to_vec()).ok()
+}
+
 /// Result of executing the transaction.
 #[derive(PartialEq, Debug, Clone)]
 #[cfg_attr(feature = "ipc", binary)]
@@ -162,7 +198,9 @@ impl fmt::Display for ExecutionError {
 pub enum CallError {
     /// Couldn't find the transaction in the chain.
     TransactionNotFound,
-    /// Couldn't find requested block's state in the chain.
+    /// Couldn't find the requested block in the chain.
+    BlockNotFound,
+    /// Requested block's state has been pruned.
     StatePruned,
     /// Couldn't find an amount of gas that didn't result in an exception.
     Exceptional,
@@ -184,7 +222,8 @@ impl fmt::Display for CallError {
 
         let msg = match *self {
             TransactionNotFound => "Transaction couldn't be found in the chain".into(),
-            StatePruned => "Couldn't find the transaction block's state in the chain".into(),
+            BlockNotFound => "Couldn't find the requested block in the chain".into(),
+            StatePruned => "State of the requested block has been pruned".into(),
             Exceptional => "An exception happened in the execution".into(),
             StateCorrupt => "Stored state found to be corrupted.".into(),
             Execution(ref e) => format!("{}", e),
@@ -196,3 +235,29 @@ impl fmt::Display for CallError {
 
 /// Transaction execution result.
 pub type ExecutionResult = Result<Executed, ExecutionError>;
+
+#[cfg(test)]
+mod tests {
+    use super::decode_revert_reason;
+    use rustc_hex::FromHex;
+
+    #[test]
+    fn test_decode_revert_reason() {
+        // Error("Not enough balance")
+        let output = "08c379a0\
+                      0000000000000000000000000000000000000000000000000000000000000020\
+                      0000000000000000000000000000000000000000000000000000000000000012\
+                      4e6f7420656e6f7567682062616c616e63650000000000000000000000000000"
+            .from_hex()
+            .unwrap();
+        assert_eq!(decode_revert_reason(&output), Some("Not enough balance".to_owned()));
+
+        // Plain revert without reason
+        assert_eq!(decode_revert_reason(&[]), None);
+
+        // Bad offset
+        let mut bad = output.clone();
+        bad[35] = 0xff;
+        assert_eq!(decode_revert_reason(&bad), None);
+    }
+}
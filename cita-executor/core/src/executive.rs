@@ -67,6 +67,9 @@ const AMEND_KV_H256: u32 = 3;
 ///amend get the value of db
 const AMEND_GET_KV_H256: u32 = 4;
 
+/// Quota every transaction pays before it runs, the `CREATE` transaction cost
+pub const BASE_QUOTA_REQUIRED: u64 = 100;
+
 /// Returns new address created from address and given nonce.
 pub fn contract_address(address: &Address, nonce: &U256) -> Address {
     use rlp::RlpStream;
@@ -456,7 +459,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             )?;
         }
 
-        let base_gas_required = U256::from(100); // `CREATE` transaction cost
+        let base_gas_required = U256::from(BASE_QUOTA_REQUIRED);
 
         if sender != Address::zero() && t.gas < base_gas_required {
             return Err(ExecutionError::NotEnoughBaseGas {
@@ -744,7 +747,10 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 .transfer_balance(&params.sender, &params.address, &val)?;
         }
 
-        if let Some(mut contract) = self.native_factory.new_contract(params.code_address) {
+        if let Some(mut contract) = self
+            .native_factory
+            .new_contract(params.code_address, self.info.number)
+        {
             let cost = U256::from(100);
             if cost <= params.gas {
                 let mut unconfirmed_substate = Substate::new();
@@ -1073,7 +1079,12 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 cumulative_gas_used: self.info.gas_used + gas_used,
                 logs: substate.logs,
                 contracts_created: substate.contracts_created,
-                output: output,
+                // Keep the revert data of `CREATE`, which has no output
+                output: if !r.apply_state && output.is_empty() {
+                    r.return_data.to_vec()
+                } else {
+                    output
+                },
                 trace: trace,
                 vm_trace: vm_trace,
                 state_diff: None,
@@ -27,6 +27,7 @@ extern crate logger;
 extern crate lru_cache;
 extern crate proof;
 extern crate rlp;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 #[cfg(not(test))]
@@ -71,6 +72,8 @@ extern crate zktx;
 extern crate enum_primitive;
 extern crate ethcore_bloom_journal;
 extern crate largest_remainder_method;
+#[cfg(test)]
+extern crate light_client;
 extern crate num;
 extern crate rand;
 
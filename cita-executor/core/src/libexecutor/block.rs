@@ -31,7 +31,7 @@ use libproto::citacode::{ActionParams, EnvInfo as ProtoEnvInfo};
 use libproto::executor::{ExecutedInfo, ReceiptWithOption};
 use receipt::{Receipt, ReceiptError};
 use rlp::*;
-use state::State;
+use state::{failed_transaction_trace, State};
 use state_db::StateDB;
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
@@ -39,7 +39,7 @@ use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Instant;
-use trace::FlatTrace;
+use trace::{FlatTrace, TraceError};
 use types::transaction::{Action, SignedTransaction};
 use util::{merklehash, HeapSizeOf};
 
@@ -333,6 +333,11 @@ impl ExecutedBlock {
     pub fn transactions(&self) -> &[SignedTransaction] {
         self.body().transactions()
     }
+
+    /// Traces of each transaction, `None` if tracing is disabled.
+    pub fn traces(&self) -> &Option<Vec<Vec<FlatTrace>>> {
+        &self.traces
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -548,7 +553,12 @@ impl OpenBlock {
                 }
                 self.receipts.push(Some(outcome.receipt));
             }
-            Err(_) => info!("apply_transaction: There must be something wrong!"),
+            Err(_) => {
+                info!("apply_transaction: There must be something wrong!");
+                if let Some(ref mut traces) = self.traces {
+                    traces.push(failed_transaction_trace(t, TraceError::Internal));
+                }
+            }
         }
     }
 
@@ -577,6 +587,11 @@ impl OpenBlock {
             evm_impl.transact(executor, t, env_info, action_params, connect_info)
         };
 
+        // Go contracts are not traced, keep the traces aligned with transactions.
+        if let Some(ref mut traces) = self.traces {
+            traces.push(Vec::new());
+        }
+
         match ret {
             Ok(receipt) => {
                 let transaction_gas_used = receipt.gas_used - self.current_gas_used;
@@ -626,7 +641,12 @@ impl OpenBlock {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use cita_crypto::KeyPair;
+    use libproto::blockchain;
     use rlp;
+    use tests::helpers::init_executor;
+    use trace::trace::Res;
+    use util::crypto::CreateKey;
 
     #[test]
     fn test_encode_and_decode() {
@@ -642,4 +662,55 @@ mod tests {
 
         assert_eq!(body_rlp, body_encoded);
     }
+
+    #[test]
+    fn test_traces_of_failed_transactions() {
+        let executor = init_executor(vec![]);
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+
+        // The second transaction does not pay the base quota.
+        let transactions = [100, 99]
+            .iter()
+            .enumerate()
+            .map(|(nonce, quota)| {
+                let mut tx = blockchain::Transaction::new();
+                tx.set_to(Address::from(0x10).lower_hex());
+                tx.set_nonce(U256::from(nonce).lower_hex());
+                tx.set_valid_until_block(100);
+                tx.set_quota(*quota);
+                SignedTransaction::new(&tx.sign(*privkey)).unwrap()
+            })
+            .collect();
+        let mut body = BlockBody::new();
+        body.set_transactions(transactions);
+        let mut block = Block::new();
+        block.set_parent_hash(executor.get_current_hash());
+        block.set_number(executor.get_current_height() + 1);
+        block.set_body(body);
+
+        let conf = executor.get_sys_config(executor.get_max_height());
+        let parent_hash = *block.parent_hash();
+        let mut open_block = OpenBlock::new(
+            executor.factories.clone(),
+            conf.clone(),
+            true,
+            block,
+            executor.state_db.read().boxed_clone_canon(&parent_hash),
+            executor.current_state_root(),
+            Arc::new(Vec::new()),
+        ).unwrap();
+        assert!(open_block.apply_transactions(&executor, false, false));
+
+        let traces = open_block.traces().clone().unwrap();
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0].len(), 1);
+        assert_eq!(traces[0][0].result, Res::Call(Default::default()));
+        assert_eq!(traces[1].len(), 1);
+        assert_eq!(traces[1][0].result, Res::FailedCall(TraceError::Rejected));
+        assert_eq!(
+            open_block.receipts[1].clone().unwrap().error,
+            Some(ReceiptError::NotEnoughBaseGas)
+        );
+    }
 }
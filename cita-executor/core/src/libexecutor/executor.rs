@@ -22,20 +22,24 @@ use contracts::{
     AccountGasLimit, NodeManager, PermissionManagement, QuotaManager, Resource, SysConfig,
     UserManagement,
 };
+use core::libchain::cross_chain::MESSENGER_ADDRESS;
 use db;
 use db::*;
 use engines::NullEngine;
-use error::CallError;
+use error::{CallError, ExecutionError};
+use evm;
 use evm::env_info::{EnvInfo, LastHashes};
 use evm::Factory as EvmFactory;
-use executive::{Executed, Executive, TransactOptions};
+use executive::{Executed, Executive, TransactOptions, BASE_QUOTA_REQUIRED};
 use factory::*;
 use header::*;
+use jsonrpc_types::rpctypes::{StateProof, StorageProof};
 use libexecutor::blacklist::BlackList;
 pub use libexecutor::block::*;
 use libexecutor::call_request::CallRequest;
 use libexecutor::extras::*;
 use libexecutor::genesis::Genesis;
+use libexecutor::tracing::TraceExtras;
 pub use libexecutor::transaction::*;
 use libexecutor::ServiceMap;
 
@@ -45,15 +49,22 @@ use libproto::{ConsensusConfig, ExecutedResult, Message};
 
 use bincode::{deserialize as bin_deserialize, serialize as bin_serialize, Infinite};
 use cita_types::{Address, H256, U256};
+use native::crosschain_messenger::CrossChainMessenger;
 use native::factory::Factory as NativeFactory;
 use state::State;
 use state_db::StateDB;
+use std::cmp;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::convert::{Into, TryInto};
+use std::ops::Range;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::time::Instant;
+use trace::{
+    Config as TraceConfig, Database as TraceDatabase, Filter as TraceFilter,
+    FlatTransactionTraces, ImportRequest, LocalizedTrace, TraceDB,
+};
 use types::ids::BlockId;
 use types::receipt::ReceiptError;
 use types::transaction::{Action, SignedTransaction, Transaction};
@@ -63,11 +74,29 @@ use util::RwLock;
 use util::UtilError;
 use util::{journaldb, Bytes};
 
+// todo : cache_size would be set in config file.
+const STATE_CACHE_SIZE: usize = 5 * 1024 * 1024;
+/// Max number of blocks a trace filter searches
+const MAX_TRACE_FILTER_BLOCKS: u64 = 1000;
+
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Config {
     pub prooftype: u8,
     pub journaldb_type: String,
+    /// Number of recent states kept by a pruning journal db, 2 by default
+    #[serde(default = "default_pruning_history")]
+    pub pruning_history: u64,
+    /// Keep every state from this height on, even with a pruning journal db
+    #[serde(default)]
+    pub archive_from: Option<u64>,
     pub grpc_port: u16,
+    /// Record execution traces of every block, disabled by default
+    #[serde(default)]
+    pub tracing: bool,
+    /// Run the `CrossChainMessenger` native contract from this height on,
+    /// disabled by default. It must be the same on every node of the chain.
+    #[serde(default)]
+    pub cross_chain_messenger_from: Option<u64>,
 }
 
 impl Config {
@@ -75,7 +104,11 @@ impl Config {
         Config {
             prooftype: 2,
             journaldb_type: String::from("archive"),
+            pruning_history: default_pruning_history(),
+            archive_from: None,
             grpc_port: 5000,
+            tracing: false,
+            cross_chain_messenger_from: None,
         }
     }
 
@@ -84,6 +117,10 @@ impl Config {
     }
 }
 
+fn default_pruning_history() -> u64 {
+    2
+}
+
 impl BloomGroupDatabase for Executor {
     fn blooms_at(&self, position: &GroupPosition) -> Option<BloomGroup> {
         let position = LogGroupPosition::from(position.clone());
@@ -205,11 +242,17 @@ pub struct Executor {
     /// Proof type
     pub prooftype: u8,
 
+    /// Number of recent states kept while pruning
+    pruning_history: u64,
+    /// Height from which every state is kept
+    archive_from: Option<u64>,
+
     pub sys_configs: RwLock<VecDeque<GlobalSysConfig>>,
 
     pub service_map: Arc<ServiceMap>,
     pub economical_model: RwLock<EconomicalModel>,
     black_list_cache: RwLock<LRUCache<u64, Address>>,
+    tracedb: TraceDB<TraceExtras>,
 }
 
 /// Get latest header
@@ -231,20 +274,8 @@ impl Executor {
     ) -> Executor {
         info!("executor config: {:?}", executor_config);
 
-        let trie_factory = TrieFactory::new(TrieSpec::Generic);
-        let factories = Factories {
-            vm: EvmFactory::default(),
-            native: NativeFactory::default(),
-            trie: trie_factory,
-            accountdb: Default::default(),
-        };
-
-        let journaldb_type = executor_config
-            .journaldb_type
-            .parse()
-            .unwrap_or(journaldb::Algorithm::Archive);
-        let journal_db = journaldb::new(Arc::clone(&db), journaldb_type, COL_STATE);
-        let state_db = StateDB::new(journal_db, 5 * 1024 * 1024); // todo : cache_size would be set in config file.
+        let factories = Self::build_factories(&executor_config);
+        let state_db = Self::build_state_db(&db, &executor_config);
 
         let header = match get_current_header(&*db) {
             Some(header) => header,
@@ -257,6 +288,90 @@ impl Executor {
                 genesis.block.header().clone()
             }
         };
+
+        let tracedb = TraceDB::new(
+            Self::trace_config(&executor_config),
+            Arc::clone(&db),
+            Arc::new(TraceExtras::new(Arc::clone(&db))),
+        );
+        let executor = Self::new(db, state_db, factories, tracedb, header, &executor_config);
+
+        // The archive height may have been reached under a pruning journal db
+        let state_db = executor.state_db.read().boxed_clone();
+        if let Err(e) = executor.prune_ancient(state_db) {
+            warn!("Failed to prune the ancient states: {:?}", e);
+        }
+
+        executor
+    }
+
+    /// Build an executor over an existing database without writing to it:
+    /// the genesis is not executed and the ancient states are not pruned.
+    /// Returns `None` if no block has been executed in the database.
+    pub fn init_read_only(db: Arc<KeyValueDB>, executor_config: Config) -> Option<Executor> {
+        info!("executor config: {:?}", executor_config);
+
+        let header = get_current_header(&*db)?;
+        let factories = Self::build_factories(&executor_config);
+        let state_db = Self::build_state_db(&db, &executor_config);
+        let tracedb = TraceDB::open(
+            Self::trace_config(&executor_config),
+            Arc::clone(&db),
+            Arc::new(TraceExtras::new(Arc::clone(&db))),
+        );
+
+        Some(Self::new(
+            db,
+            state_db,
+            factories,
+            tracedb,
+            header,
+            &executor_config,
+        ))
+    }
+
+    fn trace_config(executor_config: &Config) -> TraceConfig {
+        TraceConfig {
+            enabled: executor_config.tracing,
+            ..Default::default()
+        }
+    }
+
+    fn build_factories(executor_config: &Config) -> Factories {
+        let trie_factory = TrieFactory::new(TrieSpec::Generic);
+        let mut native_factory = NativeFactory::default();
+        if let Some(height) = executor_config.cross_chain_messenger_from {
+            native_factory.register_from(
+                *MESSENGER_ADDRESS,
+                height,
+                Box::new(CrossChainMessenger::default()),
+            );
+        }
+        Factories {
+            vm: EvmFactory::default(),
+            native: native_factory,
+            trie: trie_factory,
+            accountdb: Default::default(),
+        }
+    }
+
+    fn build_state_db(db: &Arc<KeyValueDB>, executor_config: &Config) -> StateDB {
+        let journaldb_type = executor_config
+            .journaldb_type
+            .parse()
+            .unwrap_or(journaldb::Algorithm::Archive);
+        let journal_db = journaldb::new(Arc::clone(db), journaldb_type, COL_STATE);
+        StateDB::new(journal_db, STATE_CACHE_SIZE)
+    }
+
+    fn new(
+        db: Arc<KeyValueDB>,
+        state_db: StateDB,
+        factories: Factories,
+        tracedb: TraceDB<TraceExtras>,
+        header: Header,
+        executor_config: &Config,
+    ) -> Executor {
         let executed_header = header.clone().generate_executed_header();
         let mut executed_ret = ExecutedResult::new();
         executed_ret.mut_executed_info().set_header(executed_header);
@@ -281,10 +396,13 @@ impl Executor {
 
             executed_result: RwLock::new(executed_map),
             prooftype: executor_config.prooftype,
+            pruning_history: executor_config.pruning_history,
+            archive_from: executor_config.archive_from,
             sys_configs: RwLock::new(VecDeque::new()),
             service_map: Arc::new(ServiceMap::new()),
             economical_model: RwLock::new(EconomicalModel::Quota),
             black_list_cache: RwLock::new(LRUCache::new(10_000_000)),
+            tracedb: tracedb,
         };
 
         // Build executor config
@@ -441,7 +559,7 @@ impl Executor {
     /// Make sure it's longer than 3s
     pub fn validate_timestamp(&self, timestamp: u64) -> bool {
         let sys_config = SysConfig::new(self);
-        let block_interval = sys_config.block_interval();
+        let block_interval = sys_config.block_interval(None);
         let current_timestamp = self.get_current_timestamp();
         trace!(
             "validate_timestamp current_timestamp {:?} timestamp {:?}",
@@ -466,6 +584,14 @@ impl Executor {
                 return Arc::new(res);
             }
         }
+        let last_hashes = self.collect_last_hashes(parent_hash, parent_height);
+        let mut cached_hashes = self.last_hashes.write();
+        *cached_hashes = VecDeque::from(last_hashes.clone());
+        Arc::new(last_hashes)
+    }
+
+    /// Read the last 256 block hashes from db, leaving the cache untouched.
+    pub fn collect_last_hashes(&self, parent_hash: H256, parent_height: u64) -> LastHashes {
         let mut last_hashes = LastHashes::new();
         last_hashes.resize(256, H256::default());
         last_hashes[0] = parent_hash;
@@ -482,9 +608,7 @@ impl Executor {
                 None => break,
             }
         }
-        let mut cached_hashes = self.last_hashes.write();
-        *cached_hashes = VecDeque::from(last_hashes.clone());
-        Arc::new(last_hashes)
+        last_hashes
     }
 
     fn update_last_hashes(&self, hash: &H256) {
@@ -500,7 +624,12 @@ impl Executor {
             Some(n) => n,
             None => return Ok(()),
         };
-        let history = 2;
+        // Once the archive height is reached, commit every era so that none
+        // of the later states gets pruned.
+        let archiving = self
+            .archive_from
+            .map_or(false, |height| number + 1 >= height);
+        let history = if archiving { 0 } else { self.pruning_history };
         // prune all ancient eras until we're below the memory target,
         // but have at least the minimum number of states.
         loop {
@@ -520,15 +649,34 @@ impl Executor {
                 _ => break, // means that every era is kept, no pruning necessary.
             }
         }
+        if archiving && state_db.journal_db().is_pruned() {
+            self.switch_to_archive(number);
+        }
         Ok(())
     }
 
+    /// Replace the pruning journal db with an archive one on the same column.
+    fn switch_to_archive(&self, number: BlockNumber) {
+        info!("Keep all the states from height {}", number + 1);
+        let db = Arc::clone(&*self.db.read());
+        let journal_db = journaldb::new(db, journaldb::Algorithm::Archive, COL_STATE);
+        *self.state_db.write() = StateDB::new(journal_db, STATE_CACHE_SIZE);
+    }
+
     /// Attempt to get a copy of a specific block's final state.
     pub fn state_at(&self, id: BlockId) -> Option<State<StateDB>> {
         self.block_header(id)
             .map_or(None, |h| self.gen_state(*h.state_root(), *h.parent_hash()))
     }
 
+    /// Get a copy of a specific block's final state, telling a pruned state
+    /// apart from an unknown block.
+    pub fn checked_state_at(&self, id: BlockId) -> Result<State<StateDB>, CallError> {
+        let header = self.block_header(id).ok_or(CallError::BlockNotFound)?;
+        self.gen_state(*header.state_root(), *header.parent_hash())
+            .ok_or(CallError::StatePruned)
+    }
+
     /// Generate block's final state.
     pub fn gen_state(&self, root: H256, parent_hash: H256) -> Option<State<StateDB>> {
         let db = self.state_db.read().boxed_clone_canon(&parent_hash);
@@ -536,51 +684,213 @@ impl Executor {
     }
 
     /// Get code by address
-    pub fn code_at(&self, address: &Address, id: BlockId) -> Option<Option<Bytes>> {
-        self.state_at(id)
-            .and_then(|s| s.code(address).ok())
-            .map(|c| c.map(|c| (&*c).clone()))
+    pub fn code_at(&self, address: &Address, id: BlockId) -> Result<Option<Bytes>, CallError> {
+        let state = self.checked_state_at(id)?;
+        let code = state.code(address).map_err(|_| CallError::StateCorrupt)?;
+        Ok(code.map(|c| (&*c).clone()))
     }
 
     /// Get abi by address
-    pub fn abi_at(&self, address: &Address, id: BlockId) -> Option<Option<Bytes>> {
-        self.state_at(id)
-            .and_then(|s| s.abi(address).ok())
-            .map(|c| c.map(|c| (&*c).clone()))
+    pub fn abi_at(&self, address: &Address, id: BlockId) -> Result<Option<Bytes>, CallError> {
+        let state = self.checked_state_at(id)?;
+        let abi = state.abi(address).map_err(|_| CallError::StateCorrupt)?;
+        Ok(abi.map(|c| (&*c).clone()))
     }
 
     /// Get balance by address
-    pub fn balance_at(&self, address: &Address, id: BlockId) -> Option<Option<Bytes>> {
-        self.state_at(id)
-            .and_then(|s| s.balance(address).ok())
-            .map(|c| {
-                let mut bytes = [0u8; 32];
-                c.to_big_endian(&mut bytes);
-                Some(bytes.to_vec())
+    pub fn balance_at(&self, address: &Address, id: BlockId) -> Result<Bytes, CallError> {
+        let state = self.checked_state_at(id)?;
+        let balance = state.balance(address).map_err(|_| CallError::StateCorrupt)?;
+        let mut bytes = [0u8; 32];
+        balance.to_big_endian(&mut bytes);
+        Ok(bytes.to_vec())
+    }
+
+    pub fn nonce(&self, address: &Address, id: BlockId) -> Result<U256, CallError> {
+        let state = self.checked_state_at(id)?;
+        state.nonce(address).map_err(|_| CallError::StateCorrupt)
+    }
+
+    /// Get storage by address and key
+    pub fn storage_at(
+        &self,
+        address: &Address,
+        key: &H256,
+        id: BlockId,
+    ) -> Result<H256, CallError> {
+        let state = self.checked_state_at(id)?;
+        state
+            .storage_at(address, key)
+            .map_err(|_| CallError::StateCorrupt)
+    }
+
+    /// Get the merkle proofs of an account and some of its storage slots,
+    /// against the state root of the block
+    pub fn state_proof(
+        &self,
+        address: &Address,
+        keys: &[H256],
+        id: BlockId,
+    ) -> Result<StateProof, CallError> {
+        let state = self.checked_state_at(id)?;
+        let (account_proof, account) = state
+            .prove_account(address)
+            .map_err(|_| CallError::StateCorrupt)?;
+        let storage_proof = keys
+            .iter()
+            .map(|key| {
+                state.prove_storage(address, key).map(|(proof, value)| StorageProof {
+                    key: *key,
+                    value: value,
+                    proof: proof.into_iter().map(Into::into).collect(),
+                })
             })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| CallError::StateCorrupt)?;
+        Ok(StateProof {
+            address: *address,
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            abi_hash: account.abi_hash,
+            storage_hash: account.storage_root,
+            account_proof: account_proof.into_iter().map(Into::into).collect(),
+            storage_proof: storage_proof,
+        })
+    }
+
+    pub fn tracing_enabled(&self) -> bool {
+        self.tracedb.tracing_enabled()
+    }
+
+    /// Get traces of the transaction
+    pub fn transaction_traces(&self, hash: &H256) -> Option<Vec<LocalizedTrace>> {
+        let address: Option<TransactionAddress> = self.db.read().read(db::COL_EXTRA, hash);
+        address.and_then(|address| {
+            let header = self.block_header_by_hash(address.block_hash)?;
+            self.tracedb.transaction_traces(header.number(), address.index)
+        })
+    }
+
+    /// Get traces of all transactions in the block
+    pub fn block_traces(&self, id: BlockId) -> Option<Vec<LocalizedTrace>> {
+        self.block_header(id)
+            .and_then(|header| self.tracedb.block_traces(header.number()))
     }
 
-    pub fn nonce(&self, address: &Address, id: BlockId) -> Option<U256> {
-        self.state_at(id).and_then(|s| s.nonce(address).ok())
+    /// Get traces matching the addresses in blocks `[from, to]`
+    pub fn filter_traces(
+        &self,
+        from: BlockId,
+        to: BlockId,
+        from_address: Vec<Address>,
+        to_address: Vec<Address>,
+    ) -> Result<Vec<LocalizedTrace>, String> {
+        let number_of = |id: BlockId| {
+            self.block_header(id)
+                .map(|header| header.number())
+                .ok_or_else(|| "Block not found".to_owned())
+        };
+        let filter = TraceFilter {
+            range: trace_filter_range(number_of(from)?, number_of(to)?)?,
+            from_address: from_address.into(),
+            to_address: to_address.into(),
+        };
+        Ok(self.tracedb.filter(&filter))
     }
 
     pub fn eth_call(&self, request: CallRequest, id: BlockId) -> Result<Bytes, String> {
         let mut signed = self.sign_call(request);
         let result = self.call(&mut signed, id, Default::default());
         result
-            .map(|b| b.output.into())
             .or_else(|e| Err(format!("Call Error {}", e)))
+            .and_then(|executed| match executed.exception {
+                Some(evm::Error::Reverted) => {
+                    Err(ReceiptError::Reverted(executed.revert_reason()).description())
+                }
+                _ => Ok(executed.output),
+            })
+    }
+
+    /// Call with the VM trace and the state diff
+    pub fn trace_call(&self, request: CallRequest, id: BlockId) -> Result<Executed, String> {
+        let signed = self.sign_call(request);
+        let analytics = CallAnalytics {
+            transaction_tracing: false,
+            vm_tracing: true,
+            state_diffing: true,
+        };
+        self.call(&signed, id, analytics)
+            .map_err(|e| format!("Call Error {}", e))
+    }
+
+    /// Estimate the minimal quota needed by the call
+    ///
+    /// Binary search over quota, bounded by the block gas limit and
+    /// the account gas limit of the sender.
+    pub fn estimate_quota(&self, request: CallRequest, id: BlockId) -> Result<U256, String> {
+        let header = self
+            .block_header(id)
+            .ok_or_else(|| format!("Call Error {}", CallError::BlockNotFound))?;
+        let conf = self.get_sys_config(header.number());
+        let from = request.from.unwrap_or_else(Address::zero);
+        let account_gas_limit = conf
+            .account_gas_limit
+            .get_specific_gas_limit()
+            .get(&from)
+            .cloned()
+            .unwrap_or_else(|| conf.account_gas_limit.get_common_gas_limit());
+        let upper = U256::from(cmp::min(conf.block_gas_limit as u64, account_gas_limit));
+
+        // `Ok(None)` if the call succeeds with the quota.
+        let try_quota = |quota: U256| -> Result<Option<String>, String> {
+            let signed = self.sign_call_with_quota(&request, quota);
+            match self.call(&signed, id, Default::default()) {
+                Ok(executed) => Ok(executed.exception.as_ref().map(|err| match *err {
+                    evm::Error::Reverted => {
+                        ReceiptError::Reverted(executed.revert_reason()).description()
+                    }
+                    _ => format!("{}", err),
+                })),
+                Err(CallError::Execution(ExecutionError::NotEnoughBaseGas { .. })) => {
+                    Ok(Some("Not enough base quota".to_owned()))
+                }
+                Err(err) => Err(format!("Call Error {}", err)),
+            }
+        };
+
+        if let Some(err) = try_quota(upper)? {
+            return Err(format!("Call fails with the maximum quota {}: {}", upper, err));
+        }
+
+        // Every transaction costs at least the base quota, so a quota just
+        // below it is known to fail.
+        let mut lower = U256::from(BASE_QUOTA_REQUIRED - 1);
+        let mut upper = upper;
+        while upper - lower > U256::one() {
+            let mid = lower + (upper - lower) / 2;
+            if try_quota(mid)?.is_none() {
+                upper = mid;
+            } else {
+                lower = mid;
+            }
+        }
+        Ok(upper)
     }
 
     fn sign_call(&self, request: CallRequest) -> SignedTransaction {
+        self.sign_call_with_quota(&request, U256::from(50_000_000))
+    }
+
+    fn sign_call_with_quota(&self, request: &CallRequest, quota: U256) -> SignedTransaction {
         let from = request.from.unwrap_or_else(Address::zero);
         Transaction {
             nonce: "".to_string(),
             action: Action::Call(request.to),
-            gas: U256::from(50_000_000),
+            gas: quota,
             gas_price: U256::zero(),
             value: U256::zero(),
-            data: request.data.map_or_else(Vec::new, |d| d.to_vec()),
+            data: request.data.clone().unwrap_or_default(),
             block_limit: u64::max_value(),
             // TODO: Should Fixed?
             chain_id: u32::min_value(),
@@ -594,7 +904,7 @@ impl Executor {
         block_id: BlockId,
         analytics: CallAnalytics,
     ) -> Result<Executed, CallError> {
-        let header = self.block_header(block_id).ok_or(CallError::StatePruned)?;
+        let header = self.block_header(block_id).ok_or(CallError::BlockNotFound)?;
         let last_hashes = self.build_last_hashes(None, header.number());
         let env_info = EnvInfo {
             number: header.number(),
@@ -607,7 +917,7 @@ impl Executor {
             account_gas_limit: u64::max_value().into(),
         };
         // that's just a copy of the state.
-        let mut state = self.state_at(block_id).ok_or(CallError::StatePruned)?;
+        let mut state = self.checked_state_at(block_id)?;
 
         let engine = NullEngine::default();
 
@@ -619,7 +929,7 @@ impl Executor {
             check_quota: false,
         };
 
-        Executive::new(
+        let mut executed = Executive::new(
             &mut state,
             &env_info,
             &engine,
@@ -627,8 +937,16 @@ impl Executor {
             &self.factories.native,
             false,
             EconomicalModel::Quota,
-        ).transact(t, options)
-            .map_err(Into::into)
+        ).transact(t, options)?;
+
+        if analytics.state_diffing {
+            let original = self.state_at(block_id).ok_or(CallError::StatePruned)?;
+            let diff = state
+                .diff_from(&original)
+                .map_err(|_| CallError::StateCorrupt)?;
+            executed.state_diff = Some(diff);
+        }
+        Ok(executed)
     }
 
     pub fn set_gas_and_nodes(&self, height: u64) {
@@ -706,6 +1024,30 @@ impl Executor {
         batch.write(db::COL_EXTRA, &CurrentHash, &hash);
         batch.write(db::COL_EXTRA, &height, &hash);
 
+        if let Some(ref traces) = *block.traces() {
+            // Bodies and transaction addresses are needed to localize traces.
+            batch.write(db::COL_BODIES, &hash, block.body());
+            for (index, tx_hash) in block.body().transaction_hashes().into_iter().enumerate() {
+                let address = TransactionAddress {
+                    block_hash: hash,
+                    index: index,
+                };
+                batch.write(db::COL_EXTRA, &tx_hash, &address);
+            }
+            let traces: Vec<FlatTransactionTraces> =
+                traces.clone().into_iter().map(Into::into).collect();
+            self.tracedb.import(
+                &mut batch,
+                ImportRequest {
+                    traces: traces.into(),
+                    block_hash: hash,
+                    block_number: height,
+                    enacted: vec![hash],
+                    retracted: 0,
+                },
+            );
+        }
+
         let mut state = block.drain();
         // Store triedb changes in journal db
         state
@@ -755,33 +1097,40 @@ impl Executor {
         NodeManager::new(self, self.genesis_header().timestamp())
     }
 
-    /// Reorg system config from system contract
+    /// Build system config from system contract in the state of the block
     /// 1. Consensus nodes
     /// 2. BlockGasLimit and AccountGasLimit
     /// 3. Account permissions
-    /// 4. Prune history
-    pub fn reorg_config(&self) {
+    pub fn build_sys_config(&self, block_id: BlockId) -> GlobalSysConfig {
         let mut conf = GlobalSysConfig::new();
-        conf.nodes = self.node_manager().shuffled_stake_nodes();
-        conf.block_gas_limit = QuotaManager::block_gas_limit(self) as usize;
+        conf.nodes = self.node_manager().shuffled_stake_nodes(block_id);
+        conf.block_gas_limit = QuotaManager::block_gas_limit(self, block_id) as usize;
         let sys_config = SysConfig::new(self);
-        conf.delay_active_interval = sys_config.delay_block_number() as usize;
-        conf.check_permission = sys_config.permission_check();
-        conf.check_quota = sys_config.quota_check();
-        conf.block_interval = sys_config.block_interval();
-        conf.account_permissions = PermissionManagement::load_account_permissions(self);
-        conf.super_admin_account = PermissionManagement::get_super_admin_account(self);
-        conf.group_accounts = UserManagement::load_group_accounts(self);
-        {
-            *self.economical_model.write() = sys_config.economical_model();
-        }
+        conf.delay_active_interval = sys_config.delay_block_number(Some(block_id)) as usize;
+        conf.check_permission = sys_config.permission_check(Some(block_id));
+        conf.check_quota = sys_config.quota_check(Some(block_id));
+        conf.block_interval = sys_config.block_interval(Some(block_id));
+        conf.account_permissions = PermissionManagement::load_account_permissions(self, block_id);
+        conf.super_admin_account = PermissionManagement::get_super_admin_account(self, block_id);
+        conf.group_accounts = UserManagement::load_group_accounts(self, block_id);
 
-        let common_gas_limit = QuotaManager::account_gas_limit(self);
-        let specific = QuotaManager::specific(self);
+        let common_gas_limit = QuotaManager::account_gas_limit(self, block_id);
+        let specific = QuotaManager::specific(self, block_id);
 
         conf.account_gas_limit
             .set_common_gas_limit(common_gas_limit);
         conf.account_gas_limit.set_specific_gas_limit(specific);
+        conf
+    }
+
+    /// Reorg system config from system contract
+    /// 1. Build system config of the latest block
+    /// 2. Prune history
+    pub fn reorg_config(&self) {
+        let mut conf = self.build_sys_config(BlockId::Latest);
+        {
+            *self.economical_model.write() = SysConfig::new(self).economical_model();
+        }
 
         //fixbug when max_height is not equal to current_height such as sync
         let tmp_height = self.get_current_height();
@@ -814,7 +1163,7 @@ impl Executor {
         let mut open_block = OpenBlock::new(
             self.factories.clone(),
             conf.clone(),
-            false,
+            self.tracing_enabled(),
             block,
             self.state_db.read().boxed_clone_canon(&parent_hash),
             current_state_root,
@@ -841,7 +1190,7 @@ impl Executor {
         let mut open_block = OpenBlock::new(
             self.factories.clone(),
             conf,
-            false,
+            self.tracing_enabled(),
             block,
             self.state_db.read().boxed_clone_canon(&parent_hash),
             current_state_root,
@@ -1031,6 +1380,20 @@ where
     }
 }
 
+/// Blocks `[from, to]` to search, at most `MAX_TRACE_FILTER_BLOCKS` of them
+fn trace_filter_range(from: u64, to: u64) -> Result<Range<usize>, String> {
+    if from > to {
+        return Err(format!("Invalid block range [{}, {}]", from, to));
+    }
+    if to - from >= MAX_TRACE_FILTER_BLOCKS {
+        return Err(format!(
+            "At most {} blocks in a trace filter",
+            MAX_TRACE_FILTER_BLOCKS
+        ));
+    }
+    Ok(from as usize..to as usize + 1)
+}
+
 #[cfg(test)]
 mod tests {
     extern crate logger;
@@ -1042,9 +1405,11 @@ mod tests {
     use core::receipt::ReceiptError;
     use libproto::router::{MsgType, RoutingKey, SubModules};
     use libproto::Message;
+    use light_client::check_state_proof;
     use std::convert::TryFrom;
     use std::sync::mpsc::channel;
-    use tests::helpers::{create_block, init_chain, init_executor, solc};
+    use tests::helpers::{create_block, init_chain, init_executor, init_executor_with_config, solc};
+    use util::{Hashable, HASH_NULL_RLP};
 
     fn generate_contract() -> Vec<u8> {
         let source = r#"
@@ -1071,6 +1436,23 @@ mod tests {
         data
     }
 
+    fn pruning_executor(pruning_history: u64, archive_from: Option<u64>) -> Arc<Executor> {
+        let mut config = Config::default();
+        config.journaldb_type = String::from("fast");
+        config.pruning_history = pruning_history;
+        config.archive_from = archive_from;
+        init_executor_with_config(vec![], config)
+    }
+
+    /// Execute blocks which each create a new account, so every block has its own state root.
+    fn execute_blocks(executor: &Executor, count: u64) {
+        let (send, _recv) = channel::<(String, Vec<u8>)>();
+        for _ in 0..count {
+            let block = create_block(executor, Address::from(0x10), &vec![], (0, 1));
+            executor.execute_block(block, &send);
+        }
+    }
+
     #[test]
     fn test_contract_address_from_permission_denied() {
         let executor = init_executor(vec![("SysConfig.check_permission", "true")]);
@@ -1108,6 +1490,148 @@ mod tests {
         assert_eq!(receipt.error, Some(ReceiptError::NoTransactionPermission));
     }
 
+    #[test]
+    fn test_estimate_quota() {
+        let executor = init_executor(vec![]);
+        let request = || CallRequest {
+            from: Some(Address::from(0x10)),
+            to: Address::from(0x20),
+            data: None,
+        };
+
+        // A call without code only costs the base quota
+        let quota = executor.estimate_quota(request(), BlockId::Latest);
+        assert_eq!(quota, Ok(U256::from(BASE_QUOTA_REQUIRED)));
+
+        let quota = executor.estimate_quota(request(), BlockId::Number(100));
+        assert_eq!(quota, Err(format!("Call Error {}", CallError::BlockNotFound)));
+    }
+
+    #[test]
+    fn test_state_query_of_unknown_block() {
+        let executor = init_executor(vec![]);
+        let address = Address::from(0x10);
+
+        assert!(executor.balance_at(&address, BlockId::Latest).is_ok());
+        assert_eq!(
+            executor.nonce(&address, BlockId::Number(100)),
+            Err(CallError::BlockNotFound)
+        );
+    }
+
+    #[test]
+    fn test_filter_traces_range() {
+        let executor = init_executor(vec![]);
+        execute_blocks(&executor, 2);
+        let filter = |from, to| executor.filter_traces(from, to, vec![], vec![]);
+
+        assert!(filter(BlockId::Number(0), BlockId::Latest).is_ok());
+        assert_eq!(
+            filter(BlockId::Number(2), BlockId::Number(1)),
+            Err("Invalid block range [2, 1]".to_owned())
+        );
+        assert_eq!(
+            filter(BlockId::Number(0), BlockId::Number(100)),
+            Err("Block not found".to_owned())
+        );
+
+        let max = MAX_TRACE_FILTER_BLOCKS;
+        assert_eq!(trace_filter_range(1, max), Ok(1..max as usize + 1));
+        assert_eq!(
+            trace_filter_range(0, max),
+            Err(format!("At most {} blocks in a trace filter", max))
+        );
+    }
+
+    #[test]
+    fn test_pruning_history() {
+        let address = Address::from(0x10);
+
+        let executor = pruning_executor(2, None);
+        execute_blocks(&executor, 6);
+        assert_eq!(executor.get_current_height(), 6);
+        assert!(executor.nonce(&address, BlockId::Number(4)).is_ok());
+        assert!(executor.nonce(&address, BlockId::Latest).is_ok());
+        assert_eq!(
+            executor.nonce(&address, BlockId::Number(1)),
+            Err(CallError::StatePruned)
+        );
+        assert_eq!(
+            executor.balance_at(&address, BlockId::Number(1)),
+            Err(CallError::StatePruned)
+        );
+
+        let executor = pruning_executor(6, None);
+        execute_blocks(&executor, 6);
+        assert!(executor.nonce(&address, BlockId::Number(1)).is_ok());
+    }
+
+    #[test]
+    fn test_archive_from() {
+        let address = Address::from(0x10);
+        let executor = pruning_executor(2, Some(3));
+        assert!(executor.state_db.read().journal_db().is_pruned());
+
+        execute_blocks(&executor, 8);
+        assert!(!executor.state_db.read().journal_db().is_pruned());
+        assert_eq!(
+            executor.nonce(&address, BlockId::Number(1)),
+            Err(CallError::StatePruned)
+        );
+        for height in 3..9 {
+            assert!(executor.nonce(&address, BlockId::Number(height)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_state_proof_of_missing_account() {
+        let executor = init_executor(vec![]);
+        let address = Address::from(0x10);
+        let key = H256::from(1);
+
+        let proof = executor
+            .state_proof(&address, &[key], BlockId::Latest)
+            .unwrap();
+        let state_root = *executor.current_header.read().state_root();
+        let root_node: Vec<u8> = proof.account_proof[0].clone().into();
+        assert_eq!(root_node.crypt_hash(), state_root);
+        assert_eq!(proof.balance, U256::zero());
+        assert_eq!(proof.storage_proof[0].value, H256::zero());
+        assert!(proof.storage_proof[0].proof.is_empty());
+    }
+
+    #[test]
+    fn test_state_proof_of_existing_account_and_storage() {
+        // The first storage slot of SysConfig holds the delay block number.
+        let address = Address::from(0x31415926);
+        let key = H256::from(0);
+
+        let executor = pruning_executor(2, None);
+        execute_blocks(&executor, 6);
+        let header = executor.block_header(BlockId::Number(5)).unwrap();
+        let proof = executor
+            .state_proof(&address, &[key], BlockId::Number(5))
+            .unwrap();
+        assert_ne!(proof.storage_hash, HASH_NULL_RLP);
+        assert_eq!(proof.storage_proof[0].value, H256::from(1));
+        assert!(check_state_proof(header.state_root(), &proof));
+
+        let mut forged = proof.clone();
+        forged.storage_proof[0].value = H256::from(2);
+        assert!(!check_state_proof(header.state_root(), &forged));
+        let other = executor.block_header(BlockId::Number(6)).unwrap();
+        assert!(!check_state_proof(other.state_root(), &proof));
+
+        assert_eq!(
+            executor.state_proof(&address, &[key], BlockId::Number(1)),
+            Err(CallError::StatePruned)
+        );
+        assert_eq!(
+            executor.state_proof(&address, &[key], BlockId::Number(100)),
+            Err(CallError::BlockNotFound)
+        );
+    }
+
     #[test]
     fn test_global_sys_config_equal() {
         let mut lhs = GlobalSysConfig::new();
@@ -24,10 +24,11 @@ use factory::Factories;
 use libexecutor::block::Block;
 use libexecutor::extras::*;
 use rustc_hex::FromHex;
+use serde::{Serialize, Serializer};
 use serde_json;
 use state::State;
 use state_db::StateDB;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
@@ -37,16 +38,19 @@ use util::kvdb::KeyValueDB;
 #[cfg(feature = "privatetx")]
 use zktx::set_param_path;
 
-#[derive(Debug, PartialEq, Deserialize, Clone)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Contract {
     pub nonce: String,
     pub code: String,
+    #[serde(serialize_with = "serialize_ordered")]
     pub storage: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<U256>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Spec {
+    #[serde(serialize_with = "serialize_ordered")]
     pub alloc: HashMap<String, Contract>,
     pub prevhash: H256,
     pub timestamp: u64,
@@ -58,45 +62,123 @@ pub struct Genesis {
     pub block: Block,
 }
 
+impl Spec {
+    pub fn load(path: &str) -> Result<Spec, String> {
+        let config_file =
+            File::open(path).map_err(|err| format!("can not open {}: {}", path, err))?;
+        let fconfig = BufReader::new(config_file);
+        serde_json::from_reader(fconfig).map_err(|err| format!("{} is malformed: {}", path, err))
+    }
+
+    /// Check all the accounts in `alloc`, and return all errors found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        let mut addresses: Vec<&String> = self.alloc.keys().collect();
+        addresses.sort();
+        for address in addresses {
+            let contract = &self.alloc[address];
+            if !is_hex(address, 40, true) {
+                errors.push(format!("alloc: {} is not an address", address));
+            }
+            if !is_number(&contract.nonce) {
+                errors.push(format!(
+                    "alloc {}: nonce {} is not a number",
+                    address, contract.nonce
+                ));
+            }
+            if clean_0x(&contract.code).from_hex().is_err() {
+                errors.push(format!("alloc {}: code is not a hex string", address));
+            }
+            let mut keys: Vec<&String> = contract.storage.keys().collect();
+            keys.sort();
+            for key in keys {
+                if !is_hex(key, 64, false) {
+                    errors.push(format!(
+                        "alloc {}: storage key {} is not a hex string of at most 32 bytes",
+                        address, key
+                    ));
+                }
+                let value = &contract.storage[key];
+                if !is_hex(value, 64, false) {
+                    errors.push(format!(
+                        "alloc {}: storage value {} is not a hex string of at most 32 bytes",
+                        address, value
+                    ));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Hash of the files listed in `file_list` of the resource folder.
+///
+/// It is zero if there is no `file_list`.
+pub fn resource_hash(resource_path: &Path) -> Result<H256, String> {
+    let file_list_path = resource_path.join("file_list");
+    if !file_list_path.exists() {
+        return Ok(H256::zero());
+    }
+    let mut contents = String::new();
+    File::open(&file_list_path)
+        .and_then(|file| BufReader::new(file).read_to_string(&mut contents))
+        .map_err(|err| format!("can not read {}: {}", file_list_path.display(), err))?;
+    let mut hasher = Md5::new();
+    for p in contents.lines() {
+        let path = resource_path.join(p);
+        let mut buf = Vec::new();
+        File::open(&path)
+            .and_then(|file| BufReader::new(file).read_to_end(&mut buf))
+            .map_err(|err| {
+                format!(
+                    "can not read {} listed in {}: {}",
+                    path.display(),
+                    file_list_path.display(),
+                    err
+                )
+            })?;
+        hasher.input(&buf);
+    }
+    let mut hash_str = "0x00000000000000000000000000000000".to_string();
+    hash_str += &hasher.result_str();
+    Ok(H256::from_unaligned(hash_str.as_str()).unwrap())
+}
+
 impl Genesis {
     pub fn init(path: &str) -> Genesis {
-        let config_file = File::open(path).unwrap();
-        let fconfig = BufReader::new(config_file);
-        let spec: Spec = serde_json::from_reader(fconfig).expect("Failed to load genesis.");
+        let spec = Spec::load(path).unwrap_or_else(|err| panic!("Failed to load genesis: {}", err));
+        if let Err(errors) = spec.validate() {
+            panic!("Invalid genesis {}:\n{}", path, errors.join("\n"));
+        }
 
         // check resource with pre hash in genesis
         // default pre hash is zero
-        let mut pre_hash = H256::zero();
         // resource folder at the same place with genesis file
         let resource_path = Path::new(path).parent().unwrap().join("resource");
         #[cfg(feature = "privatetx")]
         {
             set_param_path(resource_path.join("PARAMS").to_str().unwrap());
         }
-        if resource_path.exists() {
-            let file_list_path = resource_path.join("file_list");
-            if file_list_path.exists() {
-                let file_list = File::open(file_list_path).unwrap();
-                let mut buf_reader = BufReader::new(file_list);
-                let mut contents = String::new();
-                buf_reader.read_to_string(&mut contents).unwrap();
-                let mut hasher = Md5::new();
-                for p in contents.lines() {
-                    let path = resource_path.join(p);
-                    let file = File::open(path).unwrap();
-                    let mut buf_reader = BufReader::new(file);
-                    let mut buf = Vec::new();
-                    buf_reader.read_to_end(&mut buf).unwrap();
-                    hasher.input(&buf);
-                }
-                let mut hash_str = "0x00000000000000000000000000000000".to_string();
-                hash_str += &hasher.result_str();
-                info!("resource hash {}", hash_str);
-                pre_hash = H256::from_unaligned(hash_str.as_str()).unwrap();
-            }
-        }
+        let pre_hash = if resource_path.exists() {
+            resource_hash(&resource_path).unwrap_or_else(|err| panic!("{}", err))
+        } else {
+            H256::zero()
+        };
+        info!("resource hash {:?}", pre_hash);
 
-        assert_eq!(pre_hash, spec.prevhash);
+        if pre_hash != spec.prevhash {
+            panic!(
+                "prevhash {:?} in {} does not match the hash {:?} of {}",
+                spec.prevhash,
+                path,
+                pre_hash,
+                resource_path.display()
+            );
+        }
 
         Genesis {
             spec: spec,
@@ -190,12 +272,40 @@ impl Genesis {
     }
 }
 
+fn serialize_ordered<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Whether `s` is a hex string of `len` digits, or at most `len` digits if not `exact`.
+fn is_hex(s: &str, len: usize, exact: bool) -> bool {
+    let s = clean_0x(s);
+    !s.is_empty()
+        && (s.len() == len || (!exact && s.len() < len))
+        && s.chars().all(|c| c.is_digit(16))
+}
+
+fn is_number(s: &str) -> bool {
+    if s.starts_with("0x") {
+        is_hex(s, 64, false)
+    } else {
+        !s.is_empty() && s.chars().all(|c| c.is_digit(10))
+    }
+}
+
 #[cfg(test)]
 mod test {
+    extern crate mktemp;
+
+    use self::mktemp::Temp;
     use cita_types::{H256, U256};
-    use libexecutor::genesis::{Contract, Spec};
+    use libexecutor::genesis::{resource_hash, Contract, Spec};
     use serde_json;
     use std::collections::HashMap;
+    use std::fs;
     use std::str::FromStr;
 
     #[test]
@@ -255,4 +365,66 @@ mod test {
         };
         assert_eq!(serde_json::from_value::<Spec>(genesis).unwrap(), spec);
     }
+
+    #[test]
+    fn test_spec_validate() {
+        let genesis = json!({
+            "timestamp": 1524000000,
+            "alloc": {
+                "0x000000000000000000000000000000000a3241b5": {
+                    "nonce": "1",
+                    "code": "0x6060604052600436106100745763",
+                    "storage": {
+                        "0x00": "0x013241b2",
+                    }
+                },
+                "0x0a3241b6": {
+                    "nonce": "one",
+                    "code": "0x606g",
+                    "storage": {
+                        "0x01": "0x0000000000000000000000000000000000000000000000000000000000000000ff",
+                    }
+                },
+            },
+            "prevhash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        });
+        let spec: Spec = serde_json::from_value(genesis).unwrap();
+        let errors = spec.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "alloc: 0x0a3241b6 is not an address".to_owned(),
+                "alloc 0x0a3241b6: nonce one is not a number".to_owned(),
+                "alloc 0x0a3241b6: code is not a hex string".to_owned(),
+                "alloc 0x0a3241b6: storage value \
+                 0x0000000000000000000000000000000000000000000000000000000000000000ff \
+                 is not a hex string of at most 32 bytes"
+                    .to_owned(),
+            ]
+        );
+
+        let spec: Spec = serde_json::from_str(&serde_json::to_string(&spec).unwrap()).unwrap();
+        assert_eq!(spec.validate().unwrap_err().len(), 4);
+    }
+
+    #[test]
+    fn test_resource_hash() {
+        // The directory is removed when `temp` is dropped.
+        let temp = Temp::new_dir().unwrap();
+        let resource_path = temp.to_path_buf();
+        assert_eq!(resource_hash(&resource_path), Ok(H256::zero()));
+
+        fs::write(resource_path.join("file_list"), "a\nb\n").unwrap();
+        assert!(resource_hash(&resource_path).is_err());
+
+        fs::write(resource_path.join("a"), "a").unwrap();
+        fs::write(resource_path.join("b"), "b").unwrap();
+        // md5("ab")
+        assert_eq!(
+            resource_hash(&resource_path),
+            Ok(H256::from_str(
+                "00000000000000000000000000000000187ef4436122d1cc2f40dc2b92f0eba0"
+            ).unwrap())
+        );
+    }
 }
@@ -4,7 +4,7 @@ use cita_types::traits::LowerHex;
 use cita_types::{Address, H160, H256, U256};
 use db::{self as db, Key, Readable, Writable};
 use error::{Error, ExecutionError};
-use executive::check_permission;
+use executive::{check_permission, BASE_QUOTA_REQUIRED};
 use grpc::Result as GrpcResult;
 use grpc::Server;
 use libexecutor::executor::Executor;
@@ -387,7 +387,7 @@ impl<'a, B: 'a + StateBackend> CallEvmImpl<'a, B> {
             )?;
         }
 
-        let base_gas_required = U256::from(100); // `CREATE` transaction cost
+        let base_gas_required = U256::from(BASE_QUOTA_REQUIRED);
 
         if sender != Address::zero() && t.action != Action::Store && t.gas < base_gas_required {
             return Err(From::from(ExecutionError::NotEnoughBaseGas {
@@ -23,6 +23,8 @@ pub mod executor;
 pub mod extras;
 pub mod genesis;
 pub mod govm_adapter;
+pub mod replay;
+pub mod tracing;
 pub mod transaction;
 
 pub use self::genesis::Genesis;
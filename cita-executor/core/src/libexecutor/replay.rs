@@ -0,0 +1,656 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dry-run replay of the stored blocks.
+//!
+//! The blocks `[from, to]` are taken from the chain database and executed
+//! again on top of the state at `from - 1`. Each block is applied on the state
+//! left in memory by the previous one, nothing is written back to the executor
+//! database. The results are compared with the stored header and receipts.
+
+use cita_types::traits::LowerHex;
+use cita_types::H256;
+use error::CallError;
+use libexecutor::block::{Block, ClosedBlock, Drain, OpenBlock};
+use libexecutor::executor::{Executor, GlobalSysConfig};
+use log_entry::LogEntry;
+use receipt::Receipt;
+use std::cmp;
+use std::fmt;
+use std::sync::Arc;
+use trace::LocalizedTrace;
+use types::ids::BlockId;
+
+pub use core::libchain::chain::{Chain, Config as ChainConfig};
+
+/// A mismatch between the stored and the re-executed result of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    StateRoot {
+        expected: H256,
+        actual: H256,
+    },
+    ReceiptsRoot {
+        expected: H256,
+        actual: H256,
+    },
+    ReceiptsCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// A field of the receipt of the transaction at `index`.
+    Receipt {
+        index: usize,
+        transaction_hash: H256,
+        field: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Divergence::StateRoot {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "state root: expected 0x{}, got 0x{}",
+                expected.lower_hex(),
+                actual.lower_hex()
+            ),
+            Divergence::ReceiptsRoot {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "receipts root: expected 0x{}, got 0x{}",
+                expected.lower_hex(),
+                actual.lower_hex()
+            ),
+            Divergence::ReceiptsCount { expected, actual } => write!(
+                f,
+                "receipts count: expected {}, got {}",
+                expected, actual
+            ),
+            Divergence::Receipt {
+                index,
+                ref transaction_hash,
+                ref field,
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "transaction {} (0x{}) {}: expected {}, got {}",
+                index,
+                transaction_hash.lower_hex(),
+                field,
+                expected,
+                actual
+            ),
+        }
+    }
+}
+
+/// Result of replaying one block.
+#[derive(Debug, Clone)]
+pub struct BlockReport {
+    pub height: u64,
+    pub hash: H256,
+    pub transactions: usize,
+    pub divergences: Vec<Divergence>,
+    /// Traces of the replayed transactions, `None` unless asked for.
+    pub traces: Option<Vec<LocalizedTrace>>,
+}
+
+impl BlockReport {
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    /// `from` must be in `1..=to`, the genesis block can't be replayed.
+    InvalidRange(u64, u64),
+    /// The block isn't stored in the chain or in the executor database.
+    BlockNotFound(u64),
+    /// The receipts of the block aren't stored in the chain database.
+    ReceiptsNotFound(u64),
+    /// The state the replay starts from is unavailable.
+    State(u64, CallError),
+    /// The block can't be executed at all.
+    Execution(u64, String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReplayError::InvalidRange(from, to) => write!(f, "invalid range {}:{}", from, to),
+            ReplayError::BlockNotFound(height) => write!(f, "block {} not found", height),
+            ReplayError::ReceiptsNotFound(height) => {
+                write!(f, "receipts of block {} not found", height)
+            }
+            ReplayError::State(height, ref err) => {
+                write!(f, "state of block {} unavailable: {}", height, err)
+            }
+            ReplayError::Execution(height, ref err) => {
+                write!(f, "failed to execute block {}: {}", height, err)
+            }
+        }
+    }
+}
+
+/// Re-executes the stored blocks without committing anything.
+///
+/// Each block is executed with the system config the executor picks for it,
+/// built from the system contracts in the stored state it was read from.
+pub struct Replayer {
+    executor: Arc<Executor>,
+    chain: Arc<Chain>,
+    tracing: bool,
+}
+
+impl Replayer {
+    pub fn new(executor: Arc<Executor>, chain: Arc<Chain>, tracing: bool) -> Self {
+        Replayer {
+            executor: executor,
+            chain: chain,
+            tracing: tracing,
+        }
+    }
+
+    /// Replay the blocks `[from, to]`, `f` is called with the report of each
+    /// block once it's executed.
+    ///
+    /// A divergent block doesn't stop the replay, the next block is executed
+    /// on the re-executed state.
+    pub fn replay<F>(&self, from: u64, to: u64, mut f: F) -> Result<(), ReplayError>
+    where
+        F: FnMut(BlockReport),
+    {
+        if from == 0 || from > to {
+            return Err(ReplayError::InvalidRange(from, to));
+        }
+        let state = self
+            .executor
+            .checked_state_at(BlockId::Number(from - 1))
+            .map_err(|err| ReplayError::State(from - 1, err))?;
+        let (mut state_root, mut state_db) = state.drop();
+
+        for height in from..to + 1 {
+            let stored = self
+                .chain
+                .block_by_height(height)
+                .ok_or(ReplayError::BlockNotFound(height))?;
+            let hash = stored.header.hash();
+            let expected = self
+                .chain
+                .block_receipts(hash)
+                .ok_or(ReplayError::ReceiptsNotFound(height))?
+                .receipts;
+
+            let block = Block::from(stored.protobuf());
+            let last_hashes = self.executor.collect_last_hashes(*block.parent_hash(), height - 1);
+            let conf = self.sys_config(height)?;
+            let (perm, check_quota) = (conf.check_permission, conf.check_quota);
+            let mut open_block = OpenBlock::new(
+                self.executor.factories.clone(),
+                conf,
+                self.tracing,
+                block,
+                state_db,
+                state_root,
+                Arc::new(last_hashes),
+            ).map_err(|err| ReplayError::Execution(height, format!("{}", err)))?;
+            if !open_block.apply_transactions(&self.executor, perm, check_quota) {
+                return Err(ReplayError::Execution(height, "interrupted".to_owned()));
+            }
+            let closed_block = open_block.close();
+
+            let mut divergences = Vec::new();
+            if closed_block.state_root() != stored.header.state_root() {
+                divergences.push(Divergence::StateRoot {
+                    expected: *stored.header.state_root(),
+                    actual: *closed_block.state_root(),
+                });
+            }
+            if closed_block.receipts_root() != stored.header.receipts_root() {
+                divergences.push(Divergence::ReceiptsRoot {
+                    expected: *stored.header.receipts_root(),
+                    actual: *closed_block.receipts_root(),
+                });
+            }
+            compare_receipts(&expected, &closed_block.receipts, &mut divergences);
+
+            let report = BlockReport {
+                height: height,
+                hash: hash,
+                transactions: closed_block.transactions().len(),
+                divergences: divergences,
+                traces: localize_traces(&closed_block, height, hash),
+            };
+            state_root = *closed_block.state_root();
+            state_db = closed_block.drain();
+            f(report);
+        }
+        Ok(())
+    }
+
+    /// The system config the executor runs the block `height` with.
+    ///
+    /// `Executor::finalize_block` reads the config before the block is
+    /// committed, so the config changed at height `c` is the one in the state
+    /// at `c`, and the latest one known when `height` is executed is at
+    /// `height - 2`. `Executor::get_sys_config` picks the latest config with
+    /// `c + delay <= height`, the delay being the one of the latest config, or
+    /// the latest config if there is none. As the config doesn't change
+    /// between two change heights, that's the one in the state at
+    /// `height - max(delay, 2)`.
+    fn sys_config(&self, height: u64) -> Result<GlobalSysConfig, ReplayError> {
+        let latest_height = height.saturating_sub(2);
+        let latest = self.sys_config_at(latest_height)?;
+        let delay = cmp::max(latest.delay_active_interval as u64, 2);
+        if delay == 2 || delay > height {
+            return Ok(latest);
+        }
+        self.sys_config_at(height - delay)
+    }
+
+    fn sys_config_at(&self, height: u64) -> Result<GlobalSysConfig, ReplayError> {
+        let id = BlockId::Number(height);
+        self.executor
+            .checked_state_at(id)
+            .map_err(|err| ReplayError::State(height, err))?;
+        Ok(self.executor.build_sys_config(id))
+    }
+}
+
+fn localize_traces(block: &ClosedBlock, height: u64, hash: H256) -> Option<Vec<LocalizedTrace>> {
+    block.traces().as_ref().map(|traces| {
+        traces
+            .iter()
+            .zip(block.body().transaction_hashes())
+            .enumerate()
+            .flat_map(|(index, (traces, tx_hash))| {
+                traces
+                    .iter()
+                    .map(|trace| LocalizedTrace {
+                        action: trace.action.clone(),
+                        result: trace.result.clone(),
+                        subtraces: trace.subtraces,
+                        trace_address: trace.trace_address.iter().cloned().collect(),
+                        transaction_number: index,
+                        transaction_hash: tx_hash,
+                        block_number: height,
+                        block_hash: hash,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    })
+}
+
+fn compare_receipts(
+    expected: &[Option<Receipt>],
+    actual: &[Option<Receipt>],
+    divergences: &mut Vec<Divergence>,
+) {
+    if expected.len() != actual.len() {
+        divergences.push(Divergence::ReceiptsCount {
+            expected: expected.len(),
+            actual: actual.len(),
+        });
+    }
+    for (index, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+        match (expected, actual) {
+            (&Some(ref expected), &Some(ref actual)) => {
+                compare_receipt(index, expected, actual, divergences)
+            }
+            (&None, &None) => {}
+            _ => {
+                let transaction_hash = expected
+                    .as_ref()
+                    .or_else(|| actual.as_ref())
+                    .map(|receipt| receipt.transaction_hash)
+                    .unwrap_or_default();
+                divergences.push(Divergence::Receipt {
+                    index: index,
+                    transaction_hash: transaction_hash,
+                    field: "receipt".to_owned(),
+                    expected: format!("{}", expected.is_some()),
+                    actual: format!("{}", actual.is_some()),
+                });
+            }
+        }
+    }
+}
+
+fn compare_receipt(
+    index: usize,
+    expected: &Receipt,
+    actual: &Receipt,
+    divergences: &mut Vec<Divergence>,
+) {
+    let mut fields = vec![
+        (
+            "transaction_hash".to_owned(),
+            format!("0x{}", expected.transaction_hash.lower_hex()),
+            format!("0x{}", actual.transaction_hash.lower_hex()),
+        ),
+        (
+            "state_root".to_owned(),
+            format!("{:?}", expected.state_root),
+            format!("{:?}", actual.state_root),
+        ),
+        (
+            "gas_used".to_owned(),
+            format!("{}", expected.gas_used),
+            format!("{}", actual.gas_used),
+        ),
+        (
+            "error".to_owned(),
+            format!("{:?}", expected.error),
+            format!("{:?}", actual.error),
+        ),
+        (
+            "account_nonce".to_owned(),
+            format!("{}", expected.account_nonce),
+            format!("{}", actual.account_nonce),
+        ),
+        (
+            "logs".to_owned(),
+            format!("{} entries", expected.logs.len()),
+            format!("{} entries", actual.logs.len()),
+        ),
+    ];
+    for (i, (expected, actual)) in expected.logs.iter().zip(actual.logs.iter()).enumerate() {
+        fields.push((format!("logs[{}]", i), format_log(expected), format_log(actual)));
+    }
+
+    divergences.extend(
+        fields
+            .into_iter()
+            .filter(|&(_, ref expected, ref actual)| expected != actual)
+            .map(|(field, expected_value, actual_value)| Divergence::Receipt {
+                index: index,
+                transaction_hash: expected.transaction_hash,
+                field: field,
+                expected: expected_value,
+                actual: actual_value,
+            }),
+    );
+}
+
+fn format_log(log: &LogEntry) -> String {
+    let topics: Vec<String> = log
+        .topics
+        .iter()
+        .map(|topic| format!("0x{}", topic.lower_hex()))
+        .collect();
+    format!(
+        "{{address: 0x{}, topics: [{}], data: 0x{}}}",
+        log.address.lower_hex(),
+        topics.join(", "),
+        log.data.lower_hex()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_crypto::KeyPair;
+    use cita_types::Address;
+    use contracts::encode_contract_name;
+    use core::libchain::block::Block as ChainBlock;
+    use core::receipt::ReceiptError;
+    use db;
+    use libexecutor::executor::Config;
+    use libproto::router::{MsgType, RoutingKey, SubModules};
+    use libproto::Message;
+    use std::convert::TryFrom;
+    use std::sync::mpsc::channel;
+    use tests::helpers::{
+        create_block, create_block_by, init_chain, init_executor, init_executor_with_config,
+    };
+    use util::crypto::CreateKey;
+    use util::kvdb::KeyValueDB;
+
+    /// Execute a block and store it with its result in the chain.
+    fn execute_and_store(executor: &Executor, chain: &Chain, block: Block) {
+        let height = block.number();
+        let (send, recv) = channel::<(String, Vec<u8>)>();
+        executor.execute_block(block.clone(), &send);
+        while let Ok((key, msg_vec)) = recv.recv() {
+            let mut msg = Message::try_from(&msg_vec).unwrap();
+            if let routing_key!(Executor >> ExecutedResult) = RoutingKey::from(&key) {
+                let info = msg.take_executed_result().unwrap();
+                let chain_block = ChainBlock::from(block.protobuf());
+                chain.set_block_body(height, &chain_block);
+                chain.set_db_result(&info, &chain_block);
+                break;
+            }
+        }
+    }
+
+    /// The call data of a `PermissionManagement` method taking an account and a permission.
+    fn authorization_data(method: &[u8], account: Address, permission: Address) -> Vec<u8> {
+        let mut data = encode_contract_name(method);
+        for address in &[account, permission] {
+            data.extend_from_slice(&[0; 12]);
+            data.extend_from_slice(address);
+        }
+        data
+    }
+
+    fn dump(db: &KeyValueDB) -> Vec<Vec<(Vec<u8>, Vec<u8>)>> {
+        (0..db::NUM_COLUMNS.unwrap())
+            .map(|col| {
+                db.iter(Some(col))
+                    .map(|(key, value)| (key.to_vec(), value.to_vec()))
+                    .collect()
+            }).collect()
+    }
+
+    #[test]
+    fn test_replay_invalid_range() {
+        let executor = init_executor(vec![]);
+        let chain = init_chain();
+        let replayer = Replayer::new(executor, chain, false);
+
+        assert_eq!(
+            replayer.replay(0, 1, |_| {}),
+            Err(ReplayError::InvalidRange(0, 1))
+        );
+        assert_eq!(
+            replayer.replay(2, 1, |_| {}),
+            Err(ReplayError::InvalidRange(2, 1))
+        );
+        assert_eq!(
+            replayer.replay(1, 1, |_| {}),
+            Err(ReplayError::BlockNotFound(1))
+        );
+    }
+
+    #[test]
+    fn test_replay_stored_block() {
+        let executor = init_executor(vec![]);
+        let chain = init_chain();
+
+        // Transfer to an empty account
+        let block = create_block(&executor, Address::from(0x10), &vec![], (0, 2));
+        execute_and_store(&executor, &chain, block);
+
+        let mut reports = Vec::new();
+        Replayer::new(executor, chain, true)
+            .replay(1, 1, |report| reports.push(report))
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].height, 1);
+        assert_eq!(reports[0].transactions, 2);
+        assert_eq!(reports[0].divergences, vec![]);
+        assert!(reports[0].traces.is_some());
+    }
+
+    #[test]
+    fn test_replay_read_only() {
+        let pruning_config = |pruning_history| {
+            let mut config = Config::default();
+            config.journaldb_type = String::from("fast");
+            config.pruning_history = pruning_history;
+            config
+        };
+        let executor = init_executor_with_config(vec![], pruning_config(10));
+        let chain = init_chain();
+        for _ in 0..3 {
+            let block = create_block(&executor, Address::from(0x10), &vec![], (0, 1));
+            execute_and_store(&executor, &chain, block);
+        }
+
+        let db = Arc::clone(&*executor.db.read());
+        let before = dump(&*db);
+
+        // Opened with this config `init_executor` would prune every era but the latest
+        let replayer = Executor::init_read_only(Arc::clone(&db), pruning_config(0)).unwrap();
+        assert_eq!(replayer.get_current_height(), 3);
+
+        let mut reports = Vec::new();
+        Replayer::new(Arc::new(replayer), chain, true)
+            .replay(1, 3, |report| reports.push(report))
+            .unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports.iter().all(|report| report.is_consistent()));
+        assert_eq!(dump(&*db), before);
+    }
+
+    /// Cancel and set the permission of creating contracts of a user more
+    /// times than the executor keeps configs of, with a contract created by the
+    /// user after each change, then replay all the blocks.
+    fn replay_across_permission_changes(delay_block_number: &str) {
+        let admin = KeyPair::gen_keypair();
+        let user = KeyPair::gen_keypair();
+        let super_admin = format!("0x{}", admin.address().lower_hex());
+        let executor = init_executor(vec![
+            ("SysConfig.check_permission", "true"),
+            ("SysConfig.delay_block_number", delay_block_number),
+            ("Authorization.super_admin", &super_admin),
+        ]);
+        let chain = init_chain();
+        let permission_management = Address::from(0x13241b2);
+        let create_contract = Address::from(0x2);
+
+        // Allow the user to send transactions and to create contracts
+        for (i, permission) in [0x1, 0x2].iter().enumerate() {
+            let data = authorization_data(
+                b"setAuthorization(address,address)",
+                user.address(),
+                Address::from(*permission),
+            );
+            let nonce = (i as u32, i as u32 + 1);
+            let block = create_block_by(
+                &executor,
+                admin.privkey(),
+                permission_management,
+                &data,
+                nonce,
+            );
+            execute_and_store(&executor, &chain, block);
+        }
+
+        let mut creations = Vec::new();
+        for i in 0..12 {
+            let method: &[u8] = if i % 2 == 0 {
+                b"cancelAuthorization(address,address)"
+            } else {
+                b"setAuthorization(address,address)"
+            };
+            let data = authorization_data(method, user.address(), create_contract);
+            let nonce = (i + 2, i + 3);
+            let block = create_block_by(
+                &executor,
+                admin.privkey(),
+                permission_management,
+                &data,
+                nonce,
+            );
+            execute_and_store(&executor, &chain, block);
+
+            let block = create_block_by(
+                &executor,
+                user.privkey(),
+                Address::from(0),
+                &vec![],
+                (i, i + 1),
+            );
+            creations.push(block.body().transactions()[0].hash());
+            execute_and_store(&executor, &chain, block);
+        }
+        // The changes took effect within the replayed range
+        let errors: Vec<_> = creations
+            .into_iter()
+            .map(|hash| chain.localized_receipt(hash).unwrap().error)
+            .collect();
+        assert!(errors.contains(&None));
+        assert!(errors.contains(&Some(ReceiptError::NoContractPermission)));
+
+        let mut reports = Vec::new();
+        Replayer::new(executor, chain, false)
+            .replay(1, 26, |report| reports.push(report))
+            .unwrap();
+        assert_eq!(reports.len(), 26);
+        for (height, report) in (1..27).zip(reports) {
+            assert_eq!(report.height, height);
+            assert_eq!(report.divergences, vec![], "block {}", height);
+        }
+    }
+
+    #[test]
+    fn test_replay_across_sys_config_changes() {
+        replay_across_permission_changes("1");
+    }
+
+    #[test]
+    fn test_replay_across_delayed_sys_config_changes() {
+        replay_across_permission_changes("2");
+        replay_across_permission_changes("3");
+    }
+
+    #[test]
+    fn test_compare_receipts() {
+        let receipt = Receipt::new(None, 100.into(), vec![], None, 0.into(), H256::from(1));
+        let mut other = receipt.clone();
+        other.gas_used = 200.into();
+
+        let mut divergences = Vec::new();
+        compare_receipts(
+            &[Some(receipt.clone()), Some(receipt.clone())],
+            &[Some(receipt), Some(other)],
+            &mut divergences,
+        );
+        assert_eq!(
+            divergences,
+            vec![Divergence::Receipt {
+                index: 1,
+                transaction_hash: H256::from(1),
+                field: "gas_used".to_owned(),
+                expected: "100".to_owned(),
+                actual: "200".to_owned(),
+            }]
+        );
+    }
+}
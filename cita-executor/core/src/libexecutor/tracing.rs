@@ -0,0 +1,171 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Glue between the executor database, the trace database and JSON-RPC.
+
+use cita_types::H256;
+use db::{self as db, Readable};
+use evm::call_type::CallType;
+use header::BlockNumber;
+use jsonrpc_types::rpctypes::{
+    CallAction, CallResult, CreateAction, CreateResult, MemoryDiff, StorageDiff, SuicideAction,
+    Trace as RpcTrace, TraceAction, TraceResult, VMExecutedOperation, VMOperation,
+    VMTrace as RpcVMTrace,
+};
+use libexecutor::block::BlockBody;
+use std::sync::Arc;
+use trace::trace::{Action, Res};
+use trace::{DatabaseExtras, LocalizedTrace, VMTrace};
+use util::kvdb::KeyValueDB;
+
+/// Extra data the trace database needs, read from the executor database.
+///
+/// Block bodies are only stored by the executor when tracing is enabled.
+pub struct TraceExtras {
+    db: Arc<KeyValueDB>,
+}
+
+impl TraceExtras {
+    pub fn new(db: Arc<KeyValueDB>) -> Self {
+        TraceExtras { db: db }
+    }
+}
+
+impl DatabaseExtras for TraceExtras {
+    fn block_hash(&self, block_number: BlockNumber) -> Option<H256> {
+        self.db.read(db::COL_EXTRA, &block_number)
+    }
+
+    fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256> {
+        self.block_hash(block_number)
+            .and_then(|hash| {
+                let body: Option<BlockBody> = self.db.read(db::COL_BODIES, &hash);
+                body
+            })
+            .and_then(|body| body.transactions().get(tx_position).map(|tx| tx.hash()))
+    }
+}
+
+fn call_type_name(call_type: &CallType) -> &'static str {
+    match *call_type {
+        CallType::None => "none",
+        CallType::Call => "call",
+        CallType::CallCode => "callcode",
+        CallType::DelegateCall => "delegatecall",
+        CallType::StaticCall => "staticcall",
+    }
+}
+
+impl From<Action> for TraceAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Call(call) => TraceAction::Call(CallAction {
+                from: call.from,
+                to: call.to,
+                value: call.value,
+                gas: call.gas,
+                input: call.input.into(),
+                call_type: call_type_name(&call.call_type).to_owned(),
+            }),
+            Action::Create(create) => TraceAction::Create(CreateAction {
+                from: create.from,
+                value: create.value,
+                gas: create.gas,
+                init: create.init.into(),
+            }),
+            Action::Suicide(suicide) => TraceAction::Suicide(SuicideAction {
+                address: suicide.address,
+                refund_address: suicide.refund_address,
+                balance: suicide.balance,
+            }),
+        }
+    }
+}
+
+impl From<Res> for TraceResult {
+    fn from(res: Res) -> Self {
+        match res {
+            Res::Call(call) => TraceResult::Call(CallResult {
+                gas_used: call.gas_used,
+                output: call.output.into(),
+            }),
+            Res::Create(create) => TraceResult::Create(CreateResult {
+                gas_used: create.gas_used,
+                code: create.code.into(),
+                address: create.address,
+            }),
+            Res::FailedCall(err) => TraceResult::FailedCall(format!("{}", err)),
+            Res::FailedCreate(err) => TraceResult::FailedCreate(format!("{}", err)),
+            Res::None => TraceResult::None,
+        }
+    }
+}
+
+impl From<LocalizedTrace> for RpcTrace {
+    fn from(trace: LocalizedTrace) -> Self {
+        RpcTrace {
+            action: trace.action.into(),
+            result: trace.result.into(),
+            subtraces: trace.subtraces,
+            trace_address: trace.trace_address,
+            transaction_index: trace.transaction_number,
+            transaction_hash: trace.transaction_hash,
+            block_number: trace.block_number.into(),
+            block_hash: trace.block_hash,
+        }
+    }
+}
+
+impl From<VMTrace> for RpcVMTrace {
+    fn from(trace: VMTrace) -> Self {
+        // Each sub trace belongs to the operation at its `parent_step`.
+        let mut subs = trace.subs.into_iter().peekable();
+        let ops = trace
+            .operations
+            .into_iter()
+            .enumerate()
+            .map(|(step, op)| {
+                let sub = if subs.peek().map_or(false, |sub| sub.parent_step == step) {
+                    subs.next().map(Into::into)
+                } else {
+                    None
+                };
+                VMOperation {
+                    pc: op.pc,
+                    cost: op.gas_cost,
+                    ex: op.executed.map(|ex| VMExecutedOperation {
+                        used: ex.gas_used,
+                        push: ex.stack_push,
+                        mem: ex.mem_diff.map(|mem| MemoryDiff {
+                            off: mem.offset,
+                            data: mem.data.into(),
+                        }),
+                        store: ex.store_diff.map(|store| StorageDiff {
+                            key: store.location,
+                            val: store.value,
+                        }),
+                    }),
+                    sub: sub,
+                }
+            })
+            .collect();
+        RpcVMTrace {
+            code: trace.code.into(),
+            ops: ops,
+        }
+    }
+}
@@ -0,0 +1,431 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Native contract of the cross-chain messaging, see `core::libchain::cross_chain`.
+
+use byteorder::{BigEndian, ByteOrder};
+use cita_types::{Address, H256, U256};
+use contracts::ChainManagement;
+use core::libchain::chain::TxProof;
+use core::libchain::cross_chain::{CrossChainAck, CrossChainMessage, ACK_TOPIC, MESSAGE_TOPIC};
+use ethabi::{self, ParamType, Token};
+use evm::action_params::ActionParams;
+use evm::call_type::CallType;
+use evm::ext::MessageCallResult;
+use evm::storage::{Map, Scalar};
+use evm::{Error, Ext, GasLeft, ReturnData};
+use native::factory::Contract;
+use rlp::UntrustedRlp;
+
+// sendMessage(uint32,address,bytes,uint256)
+const SEND_MESSAGE: u32 = 0x6c08_14da;
+// deliverMessage(bytes,uint256)
+const DELIVER_MESSAGE: u32 = 0x765e_80f6;
+// deliverAck(bytes,uint256)
+const DELIVER_ACK: u32 = 0xc8d2_a40c;
+// isDelivered(uint32,uint256)
+const IS_DELIVERED: u32 = 0xeb60_7bb8;
+// getAckStatus(uint32,uint256)
+const GET_ACK_STATUS: u32 = 0x0783_7c84;
+// receiveMessage(uint32,address,bytes) of the destination contract
+const RECEIVE_MESSAGE: u32 = 0xad63_07c6;
+// onMessageAck(uint32,uint256,bool) of the sender
+const ON_MESSAGE_ACK: u32 = 0x90a3_cfa7;
+
+const GAS_COST: u64 = 10000;
+
+/// The message is not acknowledged yet.
+pub const ACK_PENDING: u64 = 0;
+/// `receiveMessage` of the destination contract succeeded.
+pub const ACK_SUCCESS: u64 = 1;
+/// `receiveMessage` of the destination contract failed.
+pub const ACK_FAILURE: u64 = 2;
+
+#[derive(Clone)]
+pub struct CrossChainMessenger {
+    // Nonce of the next message sent from this chain.
+    nonce: Scalar,
+    // from_chain_id => nonce => delivered
+    delivered: Map,
+    // to_chain_id => nonce => ack status
+    acks: Map,
+    output: Vec<u8>,
+}
+
+impl Contract for CrossChainMessenger {
+    fn exec(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let signature = params
+            .data
+            .as_ref()
+            .and_then(|data| data.get(0..4))
+            .map(BigEndian::read_u32);
+        match signature {
+            Some(SEND_MESSAGE) => self.send_message(params, ext),
+            Some(DELIVER_MESSAGE) => self.deliver_message(params, ext),
+            Some(DELIVER_ACK) => self.deliver_ack(params, ext),
+            Some(IS_DELIVERED) => self.is_delivered(params, ext),
+            Some(GET_ACK_STATUS) => self.get_ack_status(params, ext),
+            _ => Err(Error::OutOfGas),
+        }
+    }
+    fn create(&self) -> Box<Contract> {
+        Box::new(CrossChainMessenger::default())
+    }
+}
+
+impl Default for CrossChainMessenger {
+    fn default() -> Self {
+        CrossChainMessenger {
+            nonce: Scalar::new(H256::from(0)),
+            delivered: Map::new(H256::from(1)),
+            acks: Map::new(H256::from(2)),
+            output: Vec::new(),
+        }
+    }
+}
+
+impl CrossChainMessenger {
+    fn send_message(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let gas_left = charge(&params)?;
+        let mut args = decode_args(
+            &params,
+            &[
+                ParamType::Uint(32),
+                ParamType::Address,
+                ParamType::Bytes,
+                ParamType::Uint(256),
+            ],
+        )?;
+        let gas_limit = args.pop().and_then(|arg| arg.to_uint()).unwrap_or_default();
+        let payload = args.pop().and_then(Token::to_bytes).unwrap_or_default();
+        let dest_contract = args.pop().and_then(|arg| arg.to_address()).unwrap_or_default();
+        let to_chain_id = args.pop().and_then(|arg| arg.to_uint()).unwrap_or_default();
+
+        let (gas_left, from_chain_id) = chain_id(ext, &gas_left, &params.address)?;
+        let nonce = self.nonce.get(ext)?;
+        self.nonce.set(ext, nonce + U256::one())?;
+        let message = CrossChainMessage {
+            from_chain_id,
+            to_chain_id: H256::from(to_chain_id).low_u64() as u32,
+            nonce,
+            sender: params.sender,
+            dest_contract: Address::from(dest_contract),
+            gas_limit: U256::from(gas_limit),
+            payload,
+        };
+        trace!("send cross-chain message {:?}", message);
+        ext.log(vec![*MESSAGE_TOPIC], &message.encode())?;
+
+        self.output = H256::from(nonce).to_vec();
+        Ok(GasLeft::NeedsReturn {
+            gas_left: gas_left,
+            data: ReturnData::new(self.output.clone(), 0, self.output.len()),
+            apply_state: true,
+        })
+    }
+
+    fn deliver_message(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let gas_left = charge(&params)?;
+        let (proof, log_index) = decode_proof(&params)?;
+        let message = proof
+            .cross_chain_message(log_index)
+            .ok_or_else(|| Error::Internal("no cross-chain message in proof".to_owned()))?;
+        trace!("deliver cross-chain message {:?}", message);
+
+        let (gas_left, chain_id) = chain_id(ext, &gas_left, &params.address)?;
+        if message.to_chain_id != chain_id {
+            return Err(Error::Internal("message is not sent to this chain".to_owned()));
+        }
+        let from_chain_id = message.from_chain_id;
+        let gas_left = verify_proof(ext, &gas_left, &params.address, &proof, from_chain_id)?;
+
+        let delivered = self.delivered.get_map(U256::from(message.from_chain_id))?;
+        if !delivered.get(ext, message.nonce)?.is_zero() {
+            return Err(Error::Internal("message is already delivered".to_owned()));
+        }
+        delivered.set(ext, message.nonce, U256::one())?;
+
+        // The destination contract gets the gas asked by the sender, so a relayer
+        // can not make the delivery fail by giving too little gas.
+        if gas_left < message.gas_limit {
+            return Err(Error::OutOfGas);
+        }
+
+        let data = encode_call(
+            RECEIVE_MESSAGE,
+            &[
+                Token::Uint(H256::from(message.from_chain_id as u64).0),
+                Token::Address(message.sender.0),
+                Token::Bytes(message.payload.clone()),
+            ],
+        );
+        let (gas_returned, success) = match ext.call(
+            &message.gas_limit,
+            &params.address,
+            &message.dest_contract,
+            Some(U256::zero()),
+            &data,
+            &message.dest_contract,
+            &mut [],
+            CallType::Call,
+        ) {
+            MessageCallResult::Success(gas_left, _) => (gas_left, true),
+            MessageCallResult::Reverted(gas_left, _) => (gas_left, false),
+            // Revert the whole delivery, so the message can be delivered again.
+            MessageCallResult::Failed => {
+                return Err(Error::Internal("receive message failed".to_owned()))
+            }
+        };
+        let gas_left = gas_left - message.gas_limit + gas_returned;
+
+        let ack = CrossChainAck {
+            from_chain_id: message.from_chain_id,
+            to_chain_id: message.to_chain_id,
+            nonce: message.nonce,
+            sender: message.sender,
+            success,
+        };
+        ext.log(vec![*ACK_TOPIC], &ack.encode())?;
+        Ok(GasLeft::Known(gas_left))
+    }
+
+    fn deliver_ack(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let gas_left = charge(&params)?;
+        let (proof, log_index) = decode_proof(&params)?;
+        let ack = proof
+            .cross_chain_ack(log_index)
+            .ok_or_else(|| Error::Internal("no cross-chain ack in proof".to_owned()))?;
+        trace!("deliver cross-chain ack {:?}", ack);
+
+        let (gas_left, chain_id) = chain_id(ext, &gas_left, &params.address)?;
+        if ack.from_chain_id != chain_id {
+            return Err(Error::Internal("message is not sent from this chain".to_owned()));
+        }
+        let gas_left = verify_proof(ext, &gas_left, &params.address, &proof, ack.to_chain_id)?;
+
+        let acks = self.acks.get_map(U256::from(ack.to_chain_id))?;
+        if acks.get(ext, ack.nonce)? != U256::from(ACK_PENDING) {
+            return Err(Error::Internal("message is already acknowledged".to_owned()));
+        }
+        let status = if ack.success {
+            ACK_SUCCESS
+        } else {
+            ACK_FAILURE
+        };
+        acks.set(ext, ack.nonce, U256::from(status))?;
+
+        // Notify the sender, whose failure does not revert the acknowledgement.
+        let data = encode_call(
+            ON_MESSAGE_ACK,
+            &[
+                Token::Uint(H256::from(ack.to_chain_id as u64).0),
+                Token::Uint(H256::from(ack.nonce).0),
+                Token::Bool(ack.success),
+            ],
+        );
+        let gas_left = match ext.call(
+            &gas_left,
+            &params.address,
+            &ack.sender,
+            Some(U256::zero()),
+            &data,
+            &ack.sender,
+            &mut [],
+            CallType::Call,
+        ) {
+            MessageCallResult::Success(gas_left, _) | MessageCallResult::Reverted(gas_left, _) => {
+                gas_left
+            }
+            MessageCallResult::Failed => U256::zero(),
+        };
+        Ok(GasLeft::Known(gas_left))
+    }
+
+    fn is_delivered(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let gas_left = charge(&params)?;
+        let (chain_id, nonce) = decode_chain_and_nonce(&params)?;
+        let delivered = self.delivered.get_map(chain_id)?.get(ext, nonce)?;
+        self.output = H256::from(delivered).to_vec();
+        Ok(GasLeft::NeedsReturn {
+            gas_left: gas_left,
+            data: ReturnData::new(self.output.clone(), 0, self.output.len()),
+            apply_state: true,
+        })
+    }
+
+    fn get_ack_status(&mut self, params: ActionParams, ext: &mut Ext) -> Result<GasLeft, Error> {
+        let gas_left = charge(&params)?;
+        let (chain_id, nonce) = decode_chain_and_nonce(&params)?;
+        let status = self.acks.get_map(chain_id)?.get(ext, nonce)?;
+        self.output = H256::from(status).to_vec();
+        Ok(GasLeft::NeedsReturn {
+            gas_left: gas_left,
+            data: ReturnData::new(self.output.clone(), 0, self.output.len()),
+            apply_state: true,
+        })
+    }
+}
+
+fn charge(params: &ActionParams) -> Result<U256, Error> {
+    let gas_cost = U256::from(GAS_COST);
+    if params.gas < gas_cost {
+        Err(Error::OutOfGas)
+    } else {
+        Ok(params.gas - gas_cost)
+    }
+}
+
+fn decode_args(params: &ActionParams, types: &[ParamType]) -> Result<Vec<Token>, Error> {
+    params
+        .data
+        .as_ref()
+        .and_then(|data| data.get(4..))
+        .and_then(|data| ethabi::decode(types, data).ok())
+        .ok_or_else(|| Error::Internal("invalid arguments".to_owned()))
+}
+
+fn decode_chain_and_nonce(params: &ActionParams) -> Result<(U256, U256), Error> {
+    let args = decode_args(params, &[ParamType::Uint(32), ParamType::Uint(256)])?;
+    let mut args = args.into_iter().map(|arg| arg.to_uint().unwrap_or_default());
+    let chain_id = U256::from(&args.next().unwrap_or_default()[..]);
+    let nonce = U256::from(&args.next().unwrap_or_default()[..]);
+    Ok((chain_id, nonce))
+}
+
+/// Decode the proof and the index of the log in its receipt.
+fn decode_proof(params: &ActionParams) -> Result<(TxProof, usize), Error> {
+    let mut args = decode_args(params, &[ParamType::Bytes, ParamType::Uint(256)])?;
+    let log_index = args.pop().and_then(|arg| arg.to_uint()).unwrap_or_default();
+    let log_index = U256::from(&log_index[..]);
+    if log_index > U256::from(u32::max_value()) {
+        return Err(Error::Internal("invalid log index".to_owned()));
+    }
+    let proof = args.pop().and_then(Token::to_bytes).unwrap_or_default();
+    let proof = UntrustedRlp::new(&proof)
+        .as_val()
+        .map_err(|_| Error::Internal("invalid proof".to_owned()))?;
+    Ok((proof, log_index.low_u64() as usize))
+}
+
+fn encode_call(signature: u32, args: &[Token]) -> Vec<u8> {
+    let mut data = vec![0; 4];
+    BigEndian::write_u32(&mut data, signature);
+    data.extend(ethabi::encode(args));
+    data
+}
+
+fn chain_id(ext: &mut Ext, gas: &U256, sender: &Address) -> Result<(U256, u32), Error> {
+    ChainManagement::ext_chain_id(ext, gas, sender)
+        .ok_or_else(|| Error::Internal("get chain id failed".to_owned()))
+}
+
+/// Verify the proof with the authorities of the chain where it comes from.
+fn verify_proof(
+    ext: &mut Ext,
+    gas: &U256,
+    sender: &Address,
+    proof: &TxProof,
+    chain_id: u32,
+) -> Result<U256, Error> {
+    let (gas_left, authorities) = ChainManagement::ext_authorities(ext, gas, sender, chain_id)
+        .ok_or_else(|| Error::Internal("get authorities failed".to_owned()))?;
+    if proof.verify(&authorities) {
+        Ok(gas_left)
+    } else {
+        Err(Error::Internal("verify proof failed".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm::fake_tests::FakeExt;
+
+    fn params(data: Vec<u8>) -> ActionParams {
+        let mut params = ActionParams::default();
+        params.address = Address::from(0x1302);
+        params.gas = U256::from(100_000);
+        params.data = Some(data);
+        params
+    }
+
+    fn query(signature: u32, chain_id: u64, nonce: u64) -> Vec<u8> {
+        encode_call(
+            signature,
+            &[
+                Token::Uint(H256::from(chain_id).0),
+                Token::Uint(H256::from(nonce).0),
+            ],
+        )
+    }
+
+    fn output(result: GasLeft) -> U256 {
+        match result {
+            GasLeft::NeedsReturn { data, .. } => U256::from(&*data),
+            GasLeft::Known(_) => panic!("no return data"),
+        }
+    }
+
+    #[test]
+    fn query_delivered_and_acks() {
+        let mut ext = FakeExt::new();
+        let mut messenger = CrossChainMessenger::default();
+
+        let result = messenger.exec(params(query(IS_DELIVERED, 2, 3)), &mut ext);
+        assert_eq!(output(result.unwrap()), U256::zero());
+        let result = messenger.exec(params(query(GET_ACK_STATUS, 2, 3)), &mut ext);
+        assert_eq!(output(result.unwrap()), U256::from(ACK_PENDING));
+
+        messenger
+            .delivered
+            .get_map(U256::from(2))
+            .unwrap()
+            .set(&mut ext, U256::from(3), U256::one())
+            .unwrap();
+        messenger
+            .acks
+            .get_map(U256::from(2))
+            .unwrap()
+            .set(&mut ext, U256::from(3), U256::from(ACK_FAILURE))
+            .unwrap();
+        let result = messenger.exec(params(query(IS_DELIVERED, 2, 3)), &mut ext);
+        assert_eq!(output(result.unwrap()), U256::one());
+        let result = messenger.exec(params(query(GET_ACK_STATUS, 2, 3)), &mut ext);
+        assert_eq!(output(result.unwrap()), U256::from(ACK_FAILURE));
+        let result = messenger.exec(params(query(IS_DELIVERED, 3, 3)), &mut ext);
+        assert_eq!(output(result.unwrap()), U256::zero());
+    }
+
+    #[test]
+    fn reject_invalid_calls() {
+        let mut ext = FakeExt::new();
+        let mut messenger = CrossChainMessenger::default();
+        assert!(messenger.exec(params(vec![]), &mut ext).is_err());
+        assert!(
+            messenger
+                .exec(params(vec![0xeb, 0x60, 0x7b, 0xb8]), &mut ext)
+                .is_err()
+        );
+        let data = encode_call(DELIVER_MESSAGE, &[Token::Bytes(vec![0xc0])]);
+        assert!(messenger.exec(params(data), &mut ext).is_err());
+        let data = encode_call(
+            DELIVER_MESSAGE,
+            &[Token::Bytes(vec![0xc0]), Token::Uint(H256::from(0).0)],
+        );
+        assert!(messenger.exec(params(data), &mut ext).is_err());
+    }
+}
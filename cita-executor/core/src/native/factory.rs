@@ -19,6 +19,7 @@ use evm::action_params::ActionParams;
 // pub use super::evm::error::Error as EvmError;
 use cita_types::Address;
 use evm::{self, Ext, GasLeft};
+use header::BlockNumber;
 use std::collections::HashMap;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -53,10 +54,19 @@ pub trait Contract: Sync + Send + ContractClone {
 #[derive(Clone)]
 pub struct Factory {
     contracts: HashMap<Address, Box<Contract>>,
+    // address => the first block the contract runs in
+    activations: HashMap<Address, BlockNumber>,
 }
 
 impl Factory {
-    pub fn new_contract(&self, address: Address) -> Option<Box<Contract>> {
+    pub fn new_contract(&self, address: Address, number: BlockNumber) -> Option<Box<Contract>> {
+        if self
+            .activations
+            .get(&address)
+            .map_or(false, |height| number < *height)
+        {
+            return None;
+        }
         if let Some(contract) = self.contracts.get(&address) {
             Some(contract.create())
         } else {
@@ -66,8 +76,19 @@ impl Factory {
     pub fn register(&mut self, address: Address, contract: Box<Contract>) {
         self.contracts.insert(address, contract);
     }
+    /// Register a contract which runs from the block `height` on.
+    pub fn register_from(
+        &mut self,
+        address: Address,
+        height: BlockNumber,
+        contract: Box<Contract>,
+    ) {
+        self.activations.insert(address, height);
+        self.register(address, contract);
+    }
     pub fn unregister(&mut self, address: Address) {
         self.contracts.remove(&address);
+        self.activations.remove(&address);
     }
 }
 
@@ -75,6 +96,7 @@ impl Default for Factory {
     fn default() -> Self {
         let mut factory = Factory {
             contracts: HashMap::new(),
+            activations: HashMap::new(),
         };
         // here we register contracts with addresses defined in genesis.json.
         {
@@ -192,7 +192,7 @@ fn test_native_contract() {
                 .expect("failed to serialize u64");
         }
         params.data = Some(input);
-        let mut contract = factory.new_contract(native_addr).unwrap();
+        let mut contract = factory.new_contract(native_addr, 0).unwrap();
         let output = contract.exec(params, &mut ext).unwrap();
         println!("===={:?}", output);
     }
@@ -204,7 +204,7 @@ fn test_native_contract() {
             .expect("failed to serialize u32");
         params.data = Some(input);
 
-        let mut contract = factory.new_contract(native_addr).unwrap();
+        let mut contract = factory.new_contract(native_addr, 0).unwrap();
         match contract.exec(params, &mut ext) {
             Ok(GasLeft::NeedsReturn {
                 gas_left: _,
@@ -218,3 +218,16 @@ fn test_native_contract() {
         };
     }
 }
+
+#[test]
+fn test_native_contract_activation() {
+    let mut factory = Factory::default();
+    let address = Address::from(0x401);
+    factory.register_from(address, 10, Box::new(SimpleStorage::default()));
+    assert!(factory.new_contract(address, 9).is_none());
+    assert!(factory.new_contract(address, 10).is_some());
+    assert!(factory.new_contract(address, 11).is_some());
+    factory.unregister(address);
+    factory.register(address, Box::new(SimpleStorage::default()));
+    assert!(factory.new_contract(address, 0).is_some());
+}
@@ -253,6 +253,19 @@ impl Account {
         Ok(value)
     }
 
+    /// Prove a storage key's existence or nonexistence in the account's storage trie.
+    /// Ignores the storage changes which are not committed yet.
+    pub fn prove_storage(&self, db: &HashDB, key: &H256) -> trie::Result<(Vec<Bytes>, H256)> {
+        let mut recorder = trie::Recorder::new();
+        let trie = TrieDB::new(db, &self.storage_root)?;
+        let item: U256 = {
+            let query = (&mut recorder, ::rlp::decode);
+            trie.get_with(key, query)?.unwrap_or_else(U256::zero)
+        };
+        let proof = recorder.drain().into_iter().map(|r| r.data).collect();
+        Ok((proof, item.into()))
+    }
+
     /// Get cached storage value if any. Returns `None` if the
     /// key is not in the cache.
     pub fn cached_storage_at(&self, key: &H256) -> Option<H256> {
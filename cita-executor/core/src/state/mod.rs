@@ -23,6 +23,7 @@ use cita_types::{Address, H256, U256};
 use contracts::Resource;
 use engines::NullEngine;
 use error::{Error, ExecutionError};
+use evm::call_type::CallType;
 use evm::env_info::EnvInfo;
 use evm::Error as EvmError;
 use executive::{Executive, TransactOptions};
@@ -32,11 +33,15 @@ use receipt::{Receipt, ReceiptError};
 use std::cell::{Ref, RefCell, RefMut};
 use std::cmp;
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::sync::Arc;
-use trace::FlatTrace;
-use types::transaction::SignedTransaction;
+use trace::trace::{Action as TraceAction, Call, Create, Res};
+use trace::{FlatTrace, TraceError};
+use types::account_diff::{AccountDiff, Diff};
+use types::basic_account::BasicAccount;
+use types::state_diff::StateDiff;
+use types::transaction::{Action, SignedTransaction};
 use util::trie;
 use util::*;
 
@@ -59,6 +64,42 @@ pub struct ApplyOutcome {
 /// Result type for the execution ("application") of a transaction.
 pub type ApplyResult = Result<ApplyOutcome, Error>;
 
+/// The trace of a transaction which failed before the EVM ran it.
+///
+/// Calls and creations get a single failed top-level entry, other actions are
+/// not traced even when they succeed.
+pub fn failed_transaction_trace(t: &SignedTransaction, error: TraceError) -> Vec<FlatTrace> {
+    let (action, result) = match t.action {
+        Action::Call(ref address) => (
+            TraceAction::Call(Call {
+                from: *t.sender(),
+                to: *address,
+                value: t.value,
+                gas: t.gas,
+                input: t.data.clone(),
+                call_type: CallType::Call,
+            }),
+            Res::FailedCall(error),
+        ),
+        Action::Create => (
+            TraceAction::Create(Create {
+                from: *t.sender(),
+                value: t.value,
+                gas: t.gas,
+                init: t.data.clone(),
+            }),
+            Res::FailedCreate(error),
+        ),
+        _ => return Vec::new(),
+    };
+    vec![FlatTrace {
+        action: action,
+        result: result,
+        subtraces: 0,
+        trace_address: VecDeque::new(),
+    }]
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 /// Account modification state. Used to check if the account was
 /// Modified in between commits and overall.
@@ -535,6 +576,43 @@ impl<B: Backend> State<B> {
         r
     }
 
+    /// Prove an account's existence or nonexistence in the state trie.
+    /// Returns the trie nodes on the path to the account, in order from the root,
+    /// along with the account, which is empty if it does not exist.
+    pub fn prove_account(&self, address: &Address) -> trie::Result<(Vec<Bytes>, BasicAccount)> {
+        let mut recorder = trie::Recorder::new();
+        let trie = TrieDB::new(self.db.as_hashdb(), &self.root)?;
+        let maybe_account: Option<BasicAccount> = {
+            let query = (&mut recorder, ::rlp::decode);
+            trie.get_with(address, query)?
+        };
+        let account = maybe_account.unwrap_or_else(|| BasicAccount {
+            nonce: self.account_start_nonce,
+            balance: U256::zero(),
+            storage_root: HASH_NULL_RLP,
+            code_hash: HASH_EMPTY,
+            abi_hash: HASH_EMPTY,
+        });
+        let proof = recorder.drain().into_iter().map(|r| r.data).collect();
+        Ok((proof, account))
+    }
+
+    /// Prove a storage key's existence or nonexistence in the storage trie of an account.
+    /// Returns the trie nodes on the path to the key, in order from the storage root,
+    /// along with the value, which is zero if the key or the account does not exist.
+    pub fn prove_storage(&self, address: &Address, key: &H256) -> trie::Result<(Vec<Bytes>, H256)> {
+        let trie = TrieDB::new(self.db.as_hashdb(), &self.root)?;
+        let account = match trie.get_with(address, Account::from_rlp)? {
+            Some(account) => account,
+            None => return Ok((Vec::new(), H256::zero())),
+        };
+        let account_db = self
+            .factories
+            .accountdb
+            .readonly(self.db.as_hashdb(), account.address_hash(address));
+        account.prove_storage(account_db.as_hashdb(), key)
+    }
+
     /// Get accounts' code.
     pub fn code(&self, a: &Address) -> trie::Result<Option<Arc<Bytes>>> {
         self.ensure_cached(a, RequireCache::Code, true, |a| {
@@ -714,6 +792,7 @@ impl<B: Backend> State<B> {
         {
             Ok(e) => {
                 // trace!("Applied transaction. Diff:\n{}\n", state_diff::diff_pod(&old, &self.to_pod()));
+                let revert_reason = e.revert_reason();
                 let receipt_error = e.exception.and_then(|evm_error| match evm_error {
                     EvmError::OutOfGas => Some(ReceiptError::OutOfGas),
                     EvmError::BadJumpDestination { .. } => Some(ReceiptError::BadJumpDestination),
@@ -725,7 +804,7 @@ impl<B: Backend> State<B> {
                     }
                     EvmError::Internal(_) => Some(ReceiptError::Internal),
                     EvmError::OutOfBounds => Some(ReceiptError::OutOfBounds),
-                    EvmError::Reverted => Some(ReceiptError::Reverted),
+                    EvmError::Reverted => Some(ReceiptError::Reverted(revert_reason)),
                 });
                 let receipt = Receipt::new(
                     None,
@@ -793,7 +872,11 @@ impl<B: Backend> State<B> {
                 );
                 Ok(ApplyOutcome {
                     receipt: receipt,
-                    trace: Vec::new(),
+                    trace: if tracing {
+                        failed_transaction_trace(t, TraceError::Rejected)
+                    } else {
+                        Vec::new()
+                    },
                 })
             }
         }
@@ -804,6 +887,62 @@ impl<B: Backend> State<B> {
         Ok(())
     }
 
+    /// Diff of the accounts modified in this state against `orig`.
+    /// Should be called before `commit`, which marks the accounts clean.
+    pub fn diff_from<X: Backend>(&self, orig: &State<X>) -> trie::Result<StateDiff> {
+        let dirty: Vec<(Address, Option<HashMap<H256, H256>>)> = self
+            .cache
+            .borrow()
+            .iter()
+            .filter(|&(_, ref a)| a.is_dirty())
+            .map(|(address, a)| {
+                let changes = a.account
+                    .as_ref()
+                    .map(|account| account.storage_changes().clone());
+                (*address, changes)
+            })
+            .collect();
+
+        let mut raw = BTreeMap::new();
+        for (address, changes) in dirty {
+            let existed = orig.exists(&address)?;
+            let exists = changes.is_some();
+            let mut storage = BTreeMap::new();
+            for (key, post) in changes.unwrap_or_default() {
+                let pre = orig.storage_at(&address, &key)?;
+                if pre != post {
+                    storage.insert(key, Diff::new(pre, post));
+                }
+            }
+            let code_of =
+                |code: Option<Arc<Bytes>>| code.map_or_else(Vec::new, |c| (*c).clone());
+            let diff = AccountDiff {
+                balance: account_diff(
+                    existed,
+                    orig.balance(&address)?,
+                    exists,
+                    self.balance(&address)?,
+                ),
+                nonce: account_diff(existed, orig.nonce(&address)?, exists, self.nonce(&address)?),
+                code: account_diff(
+                    existed,
+                    code_of(orig.code(&address)?),
+                    exists,
+                    code_of(self.code(&address)?),
+                ),
+                storage: storage,
+            };
+            if !(diff.balance.is_same()
+                && diff.nonce.is_same()
+                && diff.code.is_same()
+                && diff.storage.is_empty())
+            {
+                raw.insert(address, diff);
+            }
+        }
+        Ok(StateDiff { raw: raw })
+    }
+
     /// Commits our cached account changes into the trie.
     pub fn commit(&mut self) -> Result<(), Error> {
         assert!(self.checkpoints.borrow().is_empty());
@@ -1113,6 +1252,14 @@ impl<B: Backend> State<B> {
     }
 }
 
+fn account_diff<T: Eq>(existed: bool, pre: T, exists: bool, post: T) -> Diff<T> {
+    match (existed, exists) {
+        (false, true) => Diff::Born(post),
+        (true, false) => Diff::Died(pre),
+        _ => Diff::new(pre, post),
+    }
+}
+
 impl<B: Backend> fmt::Debug for State<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.cache.borrow())
@@ -2402,7 +2549,14 @@ mod tests {
         let mut new_state = state.clone();
         new_state.set_storage(&a, 0xb.into(), 0xd.into()).unwrap();
 
-        // new_state.diff_from(state).unwrap();
+        let diff = new_state.diff_from(&state).unwrap();
+        let account = diff.get(&a).unwrap();
+        assert!(account.balance.is_same());
+        assert!(account.code.is_same());
+        assert_eq!(
+            account.storage.get(&H256::from(0xb)),
+            Some(&Diff::Changed(0xc.into(), 0xd.into()))
+        );
     }
 
 }
@@ -20,7 +20,7 @@ extern crate rustc_serialize;
 
 use self::mktemp::Temp;
 use self::rustc_serialize::hex::FromHex;
-use cita_crypto::KeyPair;
+use cita_crypto::{KeyPair, PrivKey};
 use cita_types::traits::LowerHex;
 use cita_types::{Address, U256};
 use core::libchain::chain;
@@ -107,6 +107,13 @@ pub fn solc(name: &str, source: &str) -> (Vec<u8>, Vec<u8>) {
 }
 
 pub fn init_executor(contract_arguments: Vec<(&str, &str)>) -> Arc<Executor> {
+    init_executor_with_config(contract_arguments, Config::new(EXECUTOR_CONFIG))
+}
+
+pub fn init_executor_with_config(
+    contract_arguments: Vec<(&str, &str)>,
+    executor_config: Config,
+) -> Arc<Executor> {
     let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
     let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
     let db = Database::open(&config, &tempdir.to_str().unwrap()).unwrap();
@@ -161,7 +168,6 @@ pub fn init_executor(contract_arguments: Vec<(&str, &str)>) -> Arc<Executor> {
         block: Block::default(),
     };
 
-    let executor_config = Config::new(EXECUTOR_CONFIG);
     Arc::new(Executor::init_executor(
         Arc::new(db),
         genesis,
@@ -178,6 +184,18 @@ pub fn init_chain() -> Arc<chain::Chain> {
 }
 
 pub fn create_block(executor: &Executor, to: Address, data: &Vec<u8>, nonce: (u32, u32)) -> Block {
+    let keypair = KeyPair::gen_keypair();
+    create_block_by(executor, keypair.privkey(), to, data, nonce)
+}
+
+/// Create a block of transactions sent by the account of `privkey`.
+pub fn create_block_by(
+    executor: &Executor,
+    privkey: &PrivKey,
+    to: Address,
+    data: &Vec<u8>,
+    nonce: (u32, u32),
+) -> Block {
     let mut block = Block::new();
 
     block.set_parent_hash(executor.get_current_hash());
@@ -187,8 +205,6 @@ pub fn create_block(executor: &Executor, to: Address, data: &Vec<u8>, nonce: (u3
 
     let mut body = BlockBody::new();
     let mut txs = Vec::new();
-    let keypair = KeyPair::gen_keypair();
-    let privkey = keypair.privkey();
 
     for i in nonce.0..nonce.1 {
         let mut tx = blockchain::Transaction::new();
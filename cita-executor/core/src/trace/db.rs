@@ -145,6 +145,11 @@ where
         batch.put(db::COL_TRACE, b"version", TRACE_DB_VER);
         tracesdb.write(batch).expect("failed to update version");
 
+        Self::open(config, tracesdb, extras)
+    }
+
+    /// Creates new instance of `TraceDB` without writing the genesis traces and the version.
+    pub fn open(config: Config, tracesdb: Arc<KeyValueDB>, extras: Arc<T>) -> Self {
         TraceDB {
             traces: RwLock::new(HashMap::new()),
             blooms: RwLock::new(HashMap::new()),
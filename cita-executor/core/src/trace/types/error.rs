@@ -45,6 +45,9 @@ pub enum Error {
     OutOfBounds,
     /// Execution has been reverted with REVERT instruction.
     Reverted,
+    /// The transaction was rejected before execution, such as for a bad nonce
+    /// or a missing permission.
+    Rejected,
 }
 
 impl<'a> From<&'a EvmError> for Error {
@@ -82,6 +85,7 @@ impl fmt::Display for Error {
             MutableCallInStaticContext => "Mutable Call In Static Context",
             OutOfBounds => "Out of bounds",
             Reverted => "Reverted",
+            Rejected => "Rejected",
         };
         message.fmt(f)
     }
@@ -100,6 +104,7 @@ impl Encodable for Error {
             MutableCallInStaticContext => 6,
             OutOfBounds => 7,
             Reverted => 8,
+            Rejected => 9,
         };
 
         s.append_internal(&value);
@@ -120,6 +125,7 @@ impl Decodable for Error {
             6 => Ok(MutableCallInStaticContext),
             7 => Ok(OutOfBounds),
             8 => Ok(Reverted),
+            9 => Ok(Rejected),
             _ => Err(DecoderError::Custom("Invalid error type")),
         }
     }
@@ -1,4 +1,4 @@
-use cita_types::Address;
+use cita_types::{Address, H256};
 use core::contracts::sys_config::SysConfig;
 use core::db;
 use core::libexecutor::block::{Block, ClosedBlock};
@@ -7,7 +7,11 @@ use core::libexecutor::executor::{BlockInQueue, Config, Executor, Stage};
 use core::libexecutor::Genesis;
 use core::libexecutor::ServiceMap;
 use error::ErrorCode;
-use jsonrpc_types::rpctypes::{BlockNumber, BlockTag, CountOrCode, MetaData};
+use core::trace::LocalizedTrace;
+use jsonrpc_types::rpctypes::{
+    BlockNumber, BlockTag, CountOrCode, MetaData, StorageKey, StorageKeys, Trace as RpcTrace,
+    TraceCallResult, TraceFilter,
+};
 use libproto::auth::Miscellaneous;
 use libproto::blockchain::{BlockWithProof, Proof, ProofType, RichStatus};
 use libproto::consensus::SignedProposal;
@@ -36,6 +40,9 @@ use core::snapshot::Progress;
 use core::state::backend::Backend;
 use std::path::Path;
 
+/// Max number of storage keys proved by `getStateProof`
+const MAX_STATE_PROOF_KEYS: usize = 1000;
+
 #[derive(Clone)]
 pub struct ExecutorInstance {
     ctx_pub: Sender<(String, Vec<u8>)>,
@@ -327,11 +334,12 @@ impl ExecutorInstance {
                     .map(|tx_count| {
                         let address = Address::from_slice(tx_count.address.as_ref());
                         match self.ext.nonce(&address, tx_count.block_id.into()) {
-                            Some(nonce) => {
+                            Ok(nonce) => {
                                 response.set_transaction_count(u64::from(nonce));
                             }
-                            None => {
-                                response.set_transaction_count(0);
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
                             }
                         };
                     });
@@ -347,16 +355,12 @@ impl ExecutorInstance {
                     .map(|code_content| {
                         let address = Address::from_slice(code_content.address.as_ref());
                         match self.ext.code_at(&address, code_content.block_id.into()) {
-                            Some(code) => match code {
-                                Some(code) => {
-                                    response.set_contract_code(code);
-                                }
-                                None => {
-                                    response.set_contract_code(vec![]);
-                                }
-                            },
-                            None => {
-                                response.set_contract_code(vec![]);
+                            Ok(code) => {
+                                response.set_contract_code(code.unwrap_or_default());
+                            }
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
                             }
                         };
                     });
@@ -372,16 +376,12 @@ impl ExecutorInstance {
                     .map(|abi_content| {
                         let address = Address::from_slice(abi_content.address.as_ref());
                         match self.ext.abi_at(&address, abi_content.block_id.into()) {
-                            Some(abi) => match abi {
-                                Some(abi) => {
-                                    response.set_contract_abi(abi);
-                                }
-                                None => {
-                                    response.set_contract_abi(vec![]);
-                                }
-                            },
-                            None => {
-                                response.set_contract_abi(vec![]);
+                            Ok(abi) => {
+                                response.set_contract_abi(abi.unwrap_or_default());
+                            }
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
                             }
                         };
                     });
@@ -400,16 +400,74 @@ impl ExecutorInstance {
                             .ext
                             .balance_at(&address, balance_content.block_id.into())
                         {
-                            Some(balance) => match balance {
-                                Some(balance) => {
-                                    response.set_balance(balance);
-                                }
-                                None => {
-                                    response.set_balance(vec![]);
-                                }
-                            },
-                            None => {
-                                response.set_balance(vec![]);
+                            Ok(balance) => {
+                                response.set_balance(balance);
+                            }
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
+                            }
+                        };
+                    });
+            }
+
+            Request::storage_at(storage_key) => {
+                trace!("storage request from jsonrpc {:?}", storage_key);
+                serde_json::from_str::<StorageKey>(&storage_key)
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    })
+                    .map(|storage_key| {
+                        let address = Address::from_slice(storage_key.address.as_ref());
+                        let key = H256::from_slice(storage_key.key.as_ref());
+                        match self
+                            .ext
+                            .storage_at(&address, &key, storage_key.block_id.into())
+                        {
+                            Ok(value) => {
+                                response.set_storage_value(value.to_vec());
+                            }
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
+                            }
+                        };
+                    });
+            }
+
+            Request::state_proof(storage_keys) => {
+                trace!("state proof request from jsonrpc {:?}", storage_keys);
+                serde_json::from_str::<StorageKeys>(&storage_keys)
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    })
+                    .map(|storage_keys| {
+                        if storage_keys.keys.len() > MAX_STATE_PROOF_KEYS {
+                            response.set_code(ErrorCode::query_error());
+                            response.set_error_msg(format!(
+                                "At most {} keys in a request",
+                                MAX_STATE_PROOF_KEYS
+                            ));
+                            return;
+                        }
+                        let address = Address::from_slice(storage_keys.address.as_ref());
+                        let keys: Vec<H256> = storage_keys
+                            .keys
+                            .iter()
+                            .map(|key| H256::from_slice(key))
+                            .collect();
+                        match self
+                            .ext
+                            .state_proof(&address, &keys, storage_keys.block_id.into())
+                        {
+                            Ok(proof) => {
+                                response.set_state_proof(serde_json::to_string(&proof).unwrap());
+                            }
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
                             }
                         };
                     });
@@ -451,8 +509,11 @@ impl ExecutorInstance {
                             chain_name: sys_config.chain_name(Some(block_id)),
                             operator: sys_config.operator(Some(block_id)),
                             website: sys_config.website(Some(block_id)),
-                            validators: self.ext.node_manager().shuffled_stake_nodes(),
-                            block_interval: sys_config.block_interval(),
+                            validators: self
+                                .ext
+                                .node_manager()
+                                .shuffled_stake_nodes(BlockId::Latest),
+                            block_interval: sys_config.block_interval(None),
                             token_name: token.name,
                             token_avatar: token.avatar,
                             token_symbol: token.symbol,
@@ -468,6 +529,99 @@ impl ExecutorInstance {
                 }
             }
 
+            Request::trace_transaction(hash) => {
+                trace!("trace transaction request from jsonrpc {:?}", hash);
+                let traces = self.ext.transaction_traces(&H256::from_slice(&hash));
+                self.set_traces(&mut response, traces);
+            }
+
+            Request::trace_block(data) => {
+                trace!("trace block request from jsonrpc {:?}", data);
+                match serde_json::from_str::<BlockNumber>(&data) {
+                    Ok(number) => {
+                        let traces = self.ext.block_traces(number.into());
+                        self.set_traces(&mut response, traces);
+                    }
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    }
+                }
+            }
+
+            Request::trace_filter(data) => {
+                trace!("trace filter request from jsonrpc {:?}", data);
+                match serde_json::from_str::<TraceFilter>(&data) {
+                    Ok(filter) => {
+                        let traces = self.ext.filter_traces(
+                            filter.from_block.into(),
+                            filter.to_block.into(),
+                            filter.from_address.into_iter().map(Into::into).collect(),
+                            filter.to_address.into_iter().map(Into::into).collect(),
+                        );
+                        match traces {
+                            Ok(traces) => self.set_traces(&mut response, Some(traces)),
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(err);
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    }
+                }
+            }
+
+            Request::trace_call(call) => {
+                trace!("trace call request from jsonrpc {:?}", call);
+                serde_json::from_str::<BlockNumber>(&call.height)
+                    .map(|block_id| {
+                        let call_request = CallRequest::from(call);
+                        self.ext
+                            .trace_call(call_request, block_id.into())
+                            .map(|executed| {
+                                let result = TraceCallResult {
+                                    output: executed.output.into(),
+                                    vm_trace: executed.vm_trace.map(Into::into),
+                                    state_diff: executed.state_diff.map(Into::into),
+                                };
+                                response
+                                    .set_trace_call_result(serde_json::to_string(&result).unwrap());
+                            })
+                            .map_err(|err| {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(err);
+                            })
+                    })
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    });
+            }
+
+            Request::estimate_quota(call) => {
+                trace!("estimate quota request from jsonrpc {:?}", call);
+                serde_json::from_str::<BlockNumber>(&call.height)
+                    .map(|block_id| {
+                        let call_request = CallRequest::from(call);
+                        self.ext
+                            .estimate_quota(call_request, block_id.into())
+                            .map(|quota| {
+                                response.set_estimate_quota(H256::from(quota).to_vec());
+                            })
+                            .map_err(|err| {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(err);
+                            })
+                    })
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{:?}", err));
+                    });
+            }
+
             _ => {
                 error!("bad request msg!!!!");
             }
@@ -481,6 +635,28 @@ impl ExecutorInstance {
             .unwrap();
     }
 
+    fn set_traces(
+        &self,
+        response: &mut response::Response,
+        traces: Option<Vec<LocalizedTrace>>,
+    ) {
+        if !self.ext.tracing_enabled() {
+            response.set_code(ErrorCode::query_error());
+            response.set_error_msg("tracing is disabled".to_owned());
+            return;
+        }
+        match traces {
+            Some(traces) => {
+                let traces: Vec<RpcTrace> = traces.into_iter().map(Into::into).collect();
+                response.set_traces(serde_json::to_string(&traces).unwrap());
+            }
+            None => {
+                response.set_code(ErrorCode::query_error());
+                response.set_error_msg("traces not found".to_owned());
+            }
+        }
+    }
+
     fn consensus_block_enqueue(&self, proof_blk: BlockWithProof) {
         let current_height = self.ext.get_current_height();
         let mut proof_blk = proof_blk;
@@ -0,0 +1,185 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The executor service, see the binary of the same name for its messages.
+//!
+//! [`run`] is also used to host the service in a single process with others.
+//!
+//! [`run`]: fn.run.html
+
+#![cfg_attr(feature = "clippy", feature(plugin))]
+#![cfg_attr(feature = "clippy", plugin(clippy))]
+#![feature(custom_attribute)]
+#![allow(deprecated, unused_must_use, unused_mut, unused_assignments)]
+#![feature(refcell_replace_swap)]
+#![feature(try_from)]
+extern crate bincode;
+extern crate cita_types;
+extern crate common_types as types;
+extern crate core_executor as core;
+extern crate error;
+extern crate grpc;
+extern crate jsonrpc_types;
+#[macro_use]
+extern crate libproto;
+#[macro_use]
+extern crate logger;
+extern crate proof;
+#[cfg(not(feature = "channel"))]
+extern crate pubsub;
+#[cfg(feature = "channel")]
+extern crate pubsub_channel as pubsub;
+extern crate serde_json;
+#[macro_use]
+extern crate util;
+
+mod executor_instance;
+
+use cita_types::traits::LowerHex;
+use core::db;
+use core::libexecutor::executor::{Config, Executor};
+use core::libexecutor::replay::{Chain, ChainConfig, Replayer};
+use core::libexecutor::{vm_grpc_server, ServiceMap};
+use executor_instance::ExecutorInstance;
+use jsonrpc_types::rpctypes::Trace as RpcTrace;
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use pubsub::start_pubsub;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::datapath::DataPath;
+use util::kvdb::{Database, DatabaseConfig};
+
+/// Start the service with the given config, never returns.
+pub fn run(config_path: &str, genesis_path: &str) {
+    let (tx, rx) = channel();
+    let (ctx_pub, crx_pub) = channel();
+    let (write_sender, write_receiver) = channel();
+    start_pubsub(
+        "executor",
+        routing_key!([
+            Chain >> Request,
+            Chain >> RichStatus,
+            Consensus >> BlockWithProof,
+            Consensus >> SignedProposal,
+            Net >> SyncResponse,
+            Net >> SignedProposal,
+            Snapshot >> SnapshotReq,
+            Auth >> MiscellaneousReq,
+        ]),
+        tx,
+        crx_pub,
+    );
+
+    let service_map = Arc::new(ServiceMap::new());
+    let mut ext_instance = ExecutorInstance::new(
+        ctx_pub.clone(),
+        write_sender,
+        config_path,
+        genesis_path,
+        Arc::clone(&service_map),
+    );
+    let mut distribute_ext = ext_instance.clone();
+
+    thread::spawn(move || loop {
+        if let Ok((key, msg)) = rx.recv() {
+            distribute_ext.distribute_msg(&key, &msg);
+        }
+    });
+    let mut server: Option<::grpc::Server> = None;
+    let grpc_ext = ext_instance.clone();
+    thread::spawn(move || loop {
+        if server.is_none() {
+            server = vm_grpc_server(
+                grpc_ext.grpc_port,
+                Arc::clone(&service_map),
+                Arc::clone(&grpc_ext.ext),
+            );
+        } else {
+            thread::sleep(Duration::new(8, 0));
+        }
+    });
+
+    let mut timeout_factor = 0u8;
+    loop {
+        if let Ok(number) =
+            write_receiver.recv_timeout(Duration::new(18 * (2u64.pow(timeout_factor as u32)), 0))
+        {
+            ext_instance.execute_block(number);
+            timeout_factor = 0;
+        } else {
+            for height in ext_instance.ext.executed_result.read().keys() {
+                ext_instance
+                    .ext
+                    .send_executed_info_to_chain(*height, &ctx_pub);
+            }
+            if timeout_factor < 6 {
+                timeout_factor += 1
+            }
+        }
+    }
+}
+
+/// Re-execute the stored blocks `[from, to]` and print what differs from the
+/// chain, returns whether every block matches.
+///
+/// Nothing is written to the executor database, not even the pruning done at
+/// startup, but both databases are opened so the node has to be stopped, or
+/// the data directory copied, first.
+pub fn replay(config_path: &str, from: u64, to: u64, tracing: bool) -> Result<bool, String> {
+    let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
+    let state_path = DataPath::root_node_path() + "/statedb";
+    let state_db = Database::open(&config, &state_path).map_err(|err| format!("{}", err))?;
+    let chain_db =
+        Database::open(&config, &DataPath::nosql_path()).map_err(|err| format!("{}", err))?;
+
+    let executor = Executor::init_read_only(Arc::new(state_db), Config::new(config_path))
+        .ok_or_else(|| format!("no block executed in {}", state_path))?;
+    let chain = Chain::init_chain(Arc::new(chain_db), ChainConfig::default());
+
+    let mut diverged = 0;
+    Replayer::new(Arc::new(executor), Arc::new(chain), tracing)
+        .replay(from, to, |report| {
+            if report.is_consistent() {
+                println!(
+                    "block {} (0x{}): ok, {} transactions",
+                    report.height,
+                    report.hash.lower_hex(),
+                    report.transactions
+                );
+            } else {
+                diverged += 1;
+                println!(
+                    "block {} (0x{}): diverged, {} transactions",
+                    report.height,
+                    report.hash.lower_hex(),
+                    report.transactions
+                );
+                for divergence in &report.divergences {
+                    println!("    {}", divergence);
+                }
+            }
+            if let Some(traces) = report.traces {
+                let traces: Vec<RpcTrace> = traces.into_iter().map(Into::into).collect();
+                println!("    traces: {}", serde_json::to_string(&traces).unwrap());
+            }
+        })
+        .map_err(|err| format!("{}", err))?;
+    println!("replayed {} blocks, {} diverged", to - from + 1, diverged);
+    Ok(diverged == 0)
+}
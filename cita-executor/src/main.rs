@@ -49,6 +49,13 @@
 //!
 //! Of course there is an evm interface in this module.
 //!
+//! ### Replay
+//!
+//! With `--replay FROM:TO` the stored blocks are executed again on top of the state
+//! at `FROM - 1` instead of starting the service, and the state root, receipts and
+//! logs are compared with the chain. Nothing is committed. `--trace` also prints the
+//! traces of the replayed transactions.
+//!
 //! The contract/transaction submission is first cached in memory before being committed
 //! to the stateDB (disk).
 //!
@@ -63,40 +70,16 @@
 
 #![cfg_attr(feature = "clippy", feature(plugin))]
 #![cfg_attr(feature = "clippy", plugin(clippy))]
-#![feature(custom_attribute)]
-#![allow(deprecated, unused_must_use, unused_mut, unused_assignments)]
-#![feature(refcell_replace_swap)]
-#![feature(try_from)]
-extern crate bincode;
-extern crate cita_types;
+extern crate cita_executor;
 extern crate clap;
-extern crate common_types as types;
-extern crate core_executor as core;
 extern crate dotenv;
-extern crate error;
-extern crate grpc;
-extern crate jsonrpc_types;
-#[macro_use]
-extern crate libproto;
 #[macro_use]
 extern crate logger;
-extern crate proof;
-extern crate pubsub;
-extern crate serde_json;
 #[macro_use]
 extern crate util;
 
-mod executor_instance;
-
 use clap::App;
-use core::libexecutor::{vm_grpc_server, ServiceMap};
-use executor_instance::ExecutorInstance;
-use libproto::router::{MsgType, RoutingKey, SubModules};
-use pubsub::start_pubsub;
-use std::sync::mpsc::channel;
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use std::process;
 use util::set_panic_handler;
 
 include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
@@ -112,76 +95,43 @@ fn main() {
         .about("CITA Block Chain Node powered by Rust")
         .arg_from_usage("-g, --genesis=[FILE] 'Sets a genesis config file")
         .arg_from_usage("-c, --config=[FILE] 'Sets a switch config file'")
+        .arg_from_usage(
+            "--replay=[RANGE] 'Re-executes the stored blocks FROM:TO without committing, \
+             the node must be stopped'",
+        )
+        .arg_from_usage("--trace 'Prints the traces of the replayed transactions'")
         .get_matches();
 
     let genesis_path = matches.value_of("genesis").unwrap_or("genesis.json");
 
     let config_path = matches.value_of("config").unwrap_or("executor.toml");
 
-    let (tx, rx) = channel();
-    let (ctx_pub, crx_pub) = channel();
-    let (write_sender, write_receiver) = channel();
-    start_pubsub(
-        "executor",
-        routing_key!([
-            Chain >> Request,
-            Chain >> RichStatus,
-            Consensus >> BlockWithProof,
-            Consensus >> SignedProposal,
-            Net >> SyncResponse,
-            Net >> SignedProposal,
-            Snapshot >> SnapshotReq,
-            Auth >> MiscellaneousReq,
-        ]),
-        tx,
-        crx_pub,
-    );
-
-    let service_map = Arc::new(ServiceMap::new());
-    let mut ext_instance = ExecutorInstance::new(
-        ctx_pub.clone(),
-        write_sender,
-        config_path,
-        genesis_path,
-        Arc::clone(&service_map),
-    );
-    let mut distribute_ext = ext_instance.clone();
-
-    thread::spawn(move || loop {
-        if let Ok((key, msg)) = rx.recv() {
-            distribute_ext.distribute_msg(&key, &msg);
-        }
-    });
-    let mut server: Option<::grpc::Server> = None;
-    let grpc_ext = ext_instance.clone();
-    thread::spawn(move || loop {
-        if server.is_none() {
-            server = vm_grpc_server(
-                grpc_ext.grpc_port,
-                Arc::clone(&service_map),
-                Arc::clone(&grpc_ext.ext),
-            );
-        } else {
-            thread::sleep(Duration::new(8, 0));
-        }
-    });
-
-    let mut timeout_factor = 0u8;
-    loop {
-        if let Ok(number) =
-            write_receiver.recv_timeout(Duration::new(18 * (2u64.pow(timeout_factor as u32)), 0))
-        {
-            ext_instance.execute_block(number);
-            timeout_factor = 0;
-        } else {
-            for height in ext_instance.ext.executed_result.read().keys() {
-                ext_instance
-                    .ext
-                    .send_executed_info_to_chain(*height, &ctx_pub);
-            }
-            if timeout_factor < 6 {
-                timeout_factor += 1
+    if let Some(range) = matches.value_of("replay") {
+        let (from, to) = parse_range(range).unwrap_or_else(|| {
+            eprintln!("invalid replay range {}, expected FROM:TO", range);
+            process::exit(2);
+        });
+        let tracing = matches.is_present("trace");
+        match cita_executor::replay(config_path, from, to, tracing) {
+            Ok(true) => process::exit(0),
+            Ok(false) => process::exit(1),
+            Err(err) => {
+                eprintln!("replay failed: {}", err);
+                process::exit(2);
             }
         }
     }
+
+    cita_executor::run(config_path, genesis_path);
+}
+
+/// Parse `FROM:TO`, a single height replays only that block.
+fn parse_range(range: &str) -> Option<(u64, u64)> {
+    let mut heights = range.splitn(2, ':');
+    let from = heights.next()?.trim().parse().ok()?;
+    let to = match heights.next() {
+        Some(to) => to.trim().parse().ok()?,
+        None => from,
+    };
+    Some((from, to))
 }
@@ -6,6 +6,7 @@ use libproto::router::{MsgType, RoutingKey, SubModules};
 use std::collections::HashMap;
 use std::sync::mpsc;
 use std::sync::Arc;
+use subscription::SubscriptionTarget;
 use util::Mutex;
 use ws;
 
@@ -14,6 +15,8 @@ pub enum TransferType {
     HTTP((RequestInfo, oneshot::Sender<Output>)),
     /// websocket output sender
     WEBSOCKET((RequestInfo, ws::Sender)),
+    /// internal request for websocket subscriptions
+    SUBSCRIPTION(SubscriptionTarget),
 }
 
 pub type RpcMap = Arc<Mutex<HashMap<Vec<u8>, TransferType>>>;
@@ -21,8 +24,11 @@ pub type ReqSender = Mutex<mpsc::Sender<(String, ProtoRequest)>>;
 
 pub fn select_topic(method: &str) -> String {
     match method {
-        "peerCount" => routing_key!(Jsonrpc >> RequestNet).into(),
+        "peerCount" | "getPeersInfo" => routing_key!(Jsonrpc >> RequestNet).into(),
         "sendRawTransaction" | "sendTransaction" => routing_key!(Jsonrpc >> RequestNewTx).into(),
+        "getPoolStatus" | "getPoolTransactions" | "getPoolTransaction" => {
+            routing_key!(Jsonrpc >> RequestPool).into()
+        }
         _ => routing_key!(Jsonrpc >> Request).into(),
     }
 }
@@ -34,10 +40,18 @@ mod test {
     #[test]
     fn test_get_topic() {
         assert_eq!(select_topic("peerCount"), "jsonrpc.request_net".to_string());
+        assert_eq!(
+            select_topic("getPeersInfo"),
+            "jsonrpc.request_net".to_string()
+        );
         assert_eq!(
             select_topic("sendTransaction"),
             "jsonrpc.request_new_tx".to_string()
         );
+        assert_eq!(
+            select_topic("getPoolStatus"),
+            "jsonrpc.request_pool".to_string()
+        );
         assert_eq!(select_topic("blockNumber"), "jsonrpc.request".to_string());
         assert_eq!(
             select_topic("getBlockByNumber"),
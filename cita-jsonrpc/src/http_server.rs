@@ -513,6 +513,7 @@ mod integration_test {
                                 serde_json::to_string(&Output::from(content, req_info)).unwrap(),
                             );
                         }
+                        TransferType::SUBSCRIPTION(_) => {}
                     }
                 } else {
                     warn!("receive lost request_id {:?}", content.request_id);
@@ -15,22 +15,49 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use cita_types::H256;
 use helper::{RpcMap, TransferType};
-use jsonrpc_types::response::Output;
+use jsonrpc_types::request::{GetBlockByNumberParams, GetLogsParams, RequestInfo};
+use jsonrpc_types::response::{Output, ResultBody};
+use jsonrpc_types::rpctypes::{BlockNumber, NewHead, SubscriptionKind};
+use jsonrpc_types::Error;
+use libproto::request::Request as ProtoRequest;
+use libproto::response::Response;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::Message;
 use serde_json;
-use std::convert::TryFrom;
+use std::collections::VecDeque;
+use std::convert::{TryFrom, TryInto};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use subscription::{SubscriptionMap, SubscriptionTarget};
 
-#[derive(Default)]
 pub struct MqHandler {
     responses: RpcMap,
+    subscriptions: SubscriptionMap,
+    tx: mpsc::Sender<(String, ProtoRequest)>,
+    /// Height of the last block notified to subscriptions
+    notified_height: Option<u64>,
+    /// Requests for subscriptions in the order they are sent, with the time sent
+    sent_requests: VecDeque<(Instant, Vec<u8>)>,
+    /// A request for subscriptions not answered in time is dropped from `responses`
+    timeout: Duration,
 }
 
 impl MqHandler {
-    pub fn new(responses: RpcMap) -> Self {
+    pub fn new(
+        responses: RpcMap,
+        subscriptions: SubscriptionMap,
+        tx: mpsc::Sender<(String, ProtoRequest)>,
+        timeout: Duration,
+    ) -> Self {
         MqHandler {
             responses: responses,
+            subscriptions: subscriptions,
+            tx: tx,
+            notified_height: None,
+            sent_requests: VecDeque::new(),
+            timeout: timeout,
         }
     }
 
@@ -56,14 +83,171 @@ impl MqHandler {
                                 serde_json::to_string(&Output::from(content, req_info)).unwrap(),
                             );
                         }
+                        TransferType::SUBSCRIPTION(target) => {
+                            self.notify_subscription(target, content);
+                        }
                     }
                 } else {
                     warn!("receive lost request_id {:?}", content.request_id);
                 }
             }
+            routing_key!(Chain >> RichStatus) => {
+                let rich_status = msg.take_rich_status().unwrap();
+                self.new_height(rich_status.get_height());
+            }
+            routing_key!(Auth >> PendingTxHashes) => {
+                let pending_tx_hashes = msg.take_pending_tx_hashes().unwrap();
+                let tx_hashes: Vec<H256> = pending_tx_hashes
+                    .get_tx_hashes()
+                    .iter()
+                    .map(|tx_hash| H256::from_slice(tx_hash))
+                    .collect();
+                self.subscriptions
+                    .lock()
+                    .notify_pending_transactions(&tx_hashes);
+            }
             _ => {
                 warn!("receive unexpect key {}", key);
             }
         }
     }
+
+    /// Request blocks and logs since the last notified height for subscriptions.
+    fn new_height(&mut self, height: u64) {
+        self.remove_timeout_requests();
+        let from = match self.notified_height {
+            Some(notified) if notified >= height => return,
+            Some(notified) => notified + 1,
+            None => height,
+        };
+        self.notified_height = Some(height);
+
+        let (has_new_heads, logs_filters) = {
+            let subscriptions = self.subscriptions.lock();
+            (
+                subscriptions.has_kind(SubscriptionKind::NewHeads),
+                subscriptions.logs_filters(),
+            )
+        };
+        if has_new_heads {
+            for number in from..height + 1 {
+                let params =
+                    GetBlockByNumberParams::new(BlockNumber::new(number.into()), false.into());
+                self.send_request(params, SubscriptionTarget::NewHeads);
+            }
+        }
+        for (id, mut filter) in logs_filters {
+            filter.from_block = BlockNumber::new(from.into());
+            filter.to_block = BlockNumber::new(height.into());
+            self.send_request(GetLogsParams::new(filter), SubscriptionTarget::Logs(id));
+        }
+    }
+
+    fn send_request<T>(&mut self, params: T, target: SubscriptionTarget)
+    where
+        T: TryInto<ProtoRequest, Error = Error>,
+    {
+        match params.try_into() {
+            Ok(request) => {
+                {
+                    self.responses.lock().insert(
+                        request.request_id.clone(),
+                        TransferType::SUBSCRIPTION(target),
+                    );
+                }
+                self.sent_requests
+                    .push_back((Instant::now(), request.request_id.clone()));
+                let _ = self
+                    .tx
+                    .send((routing_key!(Jsonrpc >> Request).into(), request));
+            }
+            Err(err) => {
+                warn!("failed to request for subscriptions {:?}", err);
+            }
+        }
+    }
+
+    /// Drop the requests for subscriptions which are not answered in time,
+    /// e.g. the chain restarts or the message is lost.
+    fn remove_timeout_requests(&mut self) {
+        let mut responses = self.responses.lock();
+        while let Some(&(sent_at, _)) = self.sent_requests.front() {
+            if sent_at.elapsed() < self.timeout {
+                break;
+            }
+            let (_, request_id) = self.sent_requests.pop_front().unwrap();
+            if responses.remove(&request_id).is_some() {
+                warn!("request for subscriptions timeout {:?}", request_id);
+            }
+        }
+    }
+
+    fn notify_subscription(&self, target: SubscriptionTarget, content: Response) {
+        let result = match Output::from(content, RequestInfo::null()) {
+            Output::Success(success) => success.result,
+            Output::Failure(failure) => {
+                warn!("request for subscriptions failed {:?}", failure.error);
+                return;
+            }
+        };
+        let subscriptions = self.subscriptions.lock();
+        match (target, result) {
+            (SubscriptionTarget::NewHeads, ResultBody::FullBlock(block)) => {
+                subscriptions.notify_new_head(NewHead {
+                    hash: block.hash,
+                    header: block.header,
+                });
+            }
+            (SubscriptionTarget::Logs(id), ResultBody::Logs(logs)) => {
+                subscriptions.notify_logs(id, logs);
+            }
+            _ => {
+                warn!("unexpected result for subscriptions");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use subscription::Subscriptions;
+    use util::Mutex;
+
+    fn handler(timeout: Duration) -> (MqHandler, RpcMap) {
+        let responses = Arc::new(Mutex::new(HashMap::new()));
+        let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+        let (tx, _rx) = mpsc::channel();
+        let handler = MqHandler::new(Arc::clone(&responses), subscriptions, tx, timeout);
+        (handler, responses)
+    }
+
+    fn request_new_head(handler: &mut MqHandler, number: u64) {
+        let params = GetBlockByNumberParams::new(BlockNumber::new(number.into()), false.into());
+        handler.send_request(params, SubscriptionTarget::NewHeads);
+    }
+
+    #[test]
+    fn test_remove_timeout_requests() {
+        let (mut handler, responses) = handler(Duration::from_secs(0));
+        request_new_head(&mut handler, 1);
+        request_new_head(&mut handler, 2);
+        assert_eq!(responses.lock().len(), 2);
+
+        handler.remove_timeout_requests();
+        assert!(responses.lock().is_empty());
+        assert!(handler.sent_requests.is_empty());
+    }
+
+    #[test]
+    fn test_keep_requests_in_time() {
+        let (mut handler, responses) = handler(Duration::from_secs(60));
+        request_new_head(&mut handler, 1);
+
+        handler.remove_timeout_requests();
+        assert_eq!(responses.lock().len(), 1);
+        assert_eq!(handler.sent_requests.len(), 1);
+    }
 }
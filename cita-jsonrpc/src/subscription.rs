@@ -0,0 +1,134 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Subscriptions of WebSocket clients.
+
+use cita_types::H256;
+use jsonrpc_types::rpctypes::{
+    Filter, Log, NewHead, Quantity, SubscribeParams, SubscriptionKind, SubscriptionNotification,
+    SubscriptionResult,
+};
+use serde_json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use util::Mutex;
+use ws;
+
+pub type SubscriptionMap = Arc<Mutex<Subscriptions>>;
+
+/// What an internal request for subscriptions is sent for
+pub enum SubscriptionTarget {
+    /// Block of a new head
+    NewHeads,
+    /// Logs of the subscription with the id
+    Logs(u64),
+}
+
+struct Subscriber {
+    params: SubscribeParams,
+    sender: ws::Sender,
+}
+
+/// Subscriptions of all WebSocket connections
+#[derive(Default)]
+pub struct Subscriptions {
+    last_id: u64,
+    subscribers: HashMap<u64, Subscriber>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, params: SubscribeParams, sender: ws::Sender) -> u64 {
+        self.last_id += 1;
+        self.subscribers.insert(
+            self.last_id,
+            Subscriber {
+                params: params,
+                sender: sender,
+            },
+        );
+        self.last_id
+    }
+
+    /// Only the connection which subscribed can unsubscribe.
+    pub fn unsubscribe(&mut self, id: u64, sender: &ws::Sender) -> bool {
+        let owned = self
+            .subscribers
+            .get(&id)
+            .map_or(false, |subscriber| subscriber.sender.token() == sender.token());
+        if owned {
+            self.subscribers.remove(&id);
+        }
+        owned
+    }
+
+    /// Remove all subscriptions of the closed connection.
+    pub fn unsubscribe_all(&mut self, sender: &ws::Sender) {
+        let token = sender.token();
+        self.subscribers
+            .retain(|_, subscriber| subscriber.sender.token() != token);
+    }
+
+    pub fn has_kind(&self, kind: SubscriptionKind) -> bool {
+        self.subscribers
+            .values()
+            .any(|subscriber| subscriber.params.kind() == kind)
+    }
+
+    /// Filters of all `logs` subscriptions
+    pub fn logs_filters(&self) -> Vec<(u64, Filter)> {
+        self.subscribers
+            .iter()
+            .filter_map(|(id, subscriber)| match subscriber.params {
+                SubscribeParams::Logs(ref filter) => Some((*id, filter.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn notify_new_head(&self, head: NewHead) {
+        for (id, subscriber) in &self.subscribers {
+            if let SubscribeParams::NewHeads = subscriber.params {
+                notify(*id, subscriber, SubscriptionResult::Head(head.clone()));
+            }
+        }
+    }
+
+    pub fn notify_logs(&self, id: u64, logs: Vec<Log>) {
+        if let Some(subscriber) = self.subscribers.get(&id) {
+            for log in logs {
+                notify(id, subscriber, SubscriptionResult::Log(log));
+            }
+        }
+    }
+
+    pub fn notify_pending_transactions(&self, tx_hashes: &[H256]) {
+        for (id, subscriber) in &self.subscribers {
+            if let SubscribeParams::NewPendingTransactions = subscriber.params {
+                for tx_hash in tx_hashes {
+                    notify(*id, subscriber, SubscriptionResult::TransactionHash(*tx_hash));
+                }
+            }
+        }
+    }
+}
+
+fn notify(id: u64, subscriber: &Subscriber, result: SubscriptionResult) {
+    let notification = SubscriptionNotification::new(Quantity::from(id), result);
+    let _ = subscriber
+        .sender
+        .send(serde_json::to_string(&notification).unwrap());
+}
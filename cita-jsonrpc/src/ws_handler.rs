@@ -16,19 +16,21 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use helper::{select_topic, RpcMap, TransferType};
-use jsonrpc_types::request::{PartialRequest, RequestInfo};
-use jsonrpc_types::response::RpcFailure;
+use jsonrpc_types::request::{PartialRequest, RequestInfo, SubscriptionCall, SubscriptionRequest};
+use jsonrpc_types::response::{ResultBody, RpcFailure, RpcSuccess};
 use jsonrpc_types::Error;
 use libproto::request::Request as ProtoRequest;
 use num_cpus;
-use serde_json;
+use serde_json::{self, Value};
 use std::sync::{mpsc, Arc};
+use subscription::SubscriptionMap;
 use threadpool::ThreadPool;
 use ws::{self as ws, CloseCode, Factory, Handler};
 
 pub struct WsFactory {
     //TODO 定时清理工作
     responses: RpcMap,
+    subscriptions: SubscriptionMap,
     thread_pool: ThreadPool,
     tx: mpsc::Sender<(String, ProtoRequest)>,
 }
@@ -36,6 +38,7 @@ pub struct WsFactory {
 impl WsFactory {
     pub fn new(
         responses: RpcMap,
+        subscriptions: SubscriptionMap,
         tx: mpsc::Sender<(String, ProtoRequest)>,
         thread_num: usize,
     ) -> WsFactory {
@@ -47,6 +50,7 @@ impl WsFactory {
         let thread_pool = ThreadPool::with_name("ws_thread_pool".to_string(), thread_number);
         WsFactory {
             responses: responses,
+            subscriptions: subscriptions,
             thread_pool: thread_pool,
             tx: tx,
         }
@@ -59,6 +63,7 @@ impl Factory for WsFactory {
         WsHandler {
             sender: ws,
             responses: Arc::clone(&self.responses),
+            subscriptions: Arc::clone(&self.subscriptions),
             tx: self.tx.clone(),
             thread_pool: self.thread_pool.clone(),
         }
@@ -71,26 +76,43 @@ impl Handler for WsHandler {
         // let this = self.clone();
         let tx = self.tx.clone();
         let response = Arc::clone(&self.responses);
+        let subscriptions = Arc::clone(&self.subscriptions);
         let sender = self.sender.clone();
 
         self.thread_pool.execute(move || {
             let mut req_info = RequestInfo::null();
 
-            let _ = serde_json::from_str::<PartialRequest>(&msg.into_text().unwrap())
+            let _ = serde_json::from_str::<Value>(&msg.into_text().unwrap())
                 .map_err(|err_msg| Error::from(err_msg))
-                .and_then(|part_req| {
-                    req_info = part_req.get_info();
-                    part_req.complete_and_into_proto().map(|(full_req, req)| {
-                        let request_id = req.request_id.clone();
-                        let topic = select_topic(&full_req.get_method());
-                        let _ = tx.send((topic, req));
-                        let value = (req_info.clone(), sender.clone());
-                        {
-                            response
-                                .lock()
-                                .insert(request_id, TransferType::WEBSOCKET(value));
-                        }
-                    })
+                .and_then(|value| {
+                    let is_subscription = value
+                        .get("method")
+                        .and_then(Value::as_str)
+                        .map_or(false, SubscriptionRequest::is_subscription_method);
+                    if is_subscription {
+                        serde_json::from_value::<SubscriptionRequest>(value)
+                            .map_err(|err| Error::invalid_params(err.to_string()))
+                            .map(|sub_req| {
+                                handle_subscription(sub_req, &subscriptions, &sender);
+                            })
+                    } else {
+                        serde_json::from_value::<PartialRequest>(value)
+                            .map_err(|err_msg| Error::from(err_msg))
+                            .and_then(|part_req| {
+                                req_info = part_req.get_info();
+                                part_req.complete_and_into_proto().map(|(full_req, req)| {
+                                    let request_id = req.request_id.clone();
+                                    let topic = select_topic(&full_req.get_method());
+                                    let _ = tx.send((topic, req));
+                                    let value = (req_info.clone(), sender.clone());
+                                    {
+                                        response
+                                            .lock()
+                                            .insert(request_id, TransferType::WEBSOCKET(value));
+                                    }
+                                })
+                            })
+                    }
                 })
                 .map_err(|err| {
                     // TODO 错误返回
@@ -110,12 +132,35 @@ impl Handler for WsHandler {
             reason,
             self.sender.token().0
         );
+        self.subscriptions.lock().unsubscribe_all(&self.sender);
     }
 }
 
+fn handle_subscription(
+    sub_req: SubscriptionRequest,
+    subscriptions: &SubscriptionMap,
+    sender: &ws::Sender,
+) {
+    let req_info = sub_req.get_info();
+    let result = match sub_req.call {
+        SubscriptionCall::Subscribe { params } => {
+            let id = subscriptions.lock().subscribe(params, sender.clone());
+            ResultBody::SubscriptionId(id.into())
+        }
+        SubscriptionCall::Unsubscribe { params } => {
+            let id: u64 = (params.0).into();
+            let unsubscribed = subscriptions.lock().unsubscribe(id, sender);
+            ResultBody::Unsubscribed(unsubscribed.into())
+        }
+    };
+    let output = RpcSuccess::new(req_info).set_result(result).output();
+    let _ = sender.send(serde_json::to_string(&output).unwrap());
+}
+
 #[derive(Clone)]
 pub struct WsHandler {
     responses: RpcMap,
+    subscriptions: SubscriptionMap,
     thread_pool: ThreadPool,
     sender: ws::Sender,
     tx: mpsc::Sender<(String, ProtoRequest)>,
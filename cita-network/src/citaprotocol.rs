@@ -14,32 +14,67 @@
 
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
-
 //! A multiplexed cita protocol
 
 use byteorder::{ByteOrder, NetworkEndian};
 use bytes::BufMut;
 use bytes::BytesMut;
+use std::cmp;
 use std::io;
 use std::str;
-use tokio_io::codec::{Decoder, Encoder, Framed};
-use tokio_io::{AsyncRead, AsyncWrite};
-use tokio_proto::pipeline::ServerProto;
+use tokio_io::codec::{Decoder, Encoder};
+use util::snappy;
 
 pub type CitaRequest = (String, Vec<u8>);
 pub type CitaResponse = Option<(String, Vec<u8>)>;
 
+/// Default upper bound of a frame, both on the wire and decompressed.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 128 * 1024 * 1024;
+
+/// Versions of the frame this node speaks. The nodes tell each other theirs in
+/// the handshake and a connection uses the highest one both speak.
+pub const MIN_VERSION: u8 = 1;
+pub const MAX_VERSION: u8 = 1;
+
+/// Version of the frames to use with a peer speaking `[min, max]`, `None` if
+/// there's no version both speak.
+pub fn negotiate_version(min: u8, max: u8) -> Option<u8> {
+    let version = cmp::min(max, MAX_VERSION);
+    if version >= cmp::max(min, MIN_VERSION) {
+        Some(version)
+    } else {
+        None
+    }
+}
+
 /// Our multiplexed line-based codec
-pub struct CitaCodec;
+///
+/// Between the nodes its frames are sealed by `secure::SecureCodec`.
+pub struct CitaCodec {
+    version: u8,
+    max_frame_size: usize,
+}
 
-/// Protocol definition
-pub struct CitaProto;
+impl CitaCodec {
+    pub fn new(version: u8, max_frame_size: usize) -> Self {
+        CitaCodec {
+            version,
+            max_frame_size,
+        }
+    }
+}
+
+impl Default for CitaCodec {
+    fn default() -> Self {
+        CitaCodec::new(MAX_VERSION, DEFAULT_MAX_FRAME_SIZE)
+    }
+}
 
 /// Implementation of the multiplexed line-based protocol.
 ///
-/// Frames begin with a 4 byte header, consisting of the numeric request ID
-/// encoded in network order, followed by the frame payload encoded as a UTF-8
-/// string and terminated with a '\n' character:
+/// Frames begin with a 8 byte header, consisting of the start symbol and the
+/// length of the rest of the frame, encoded in network order. A frame with
+/// length 0 is a keepalive.
 ///
 /// # An example frame:
 ///
@@ -49,15 +84,31 @@ pub struct CitaProto;
 /// | Symbol for Start       | \xDEADBEEF               |
 /// | Length of Full Payload | u32                      |
 /// +------------------------+--------------------------+
+/// | Version                | u8                       |
+/// | Flags                  | u8                       |
+/// +------------------------+--------------------------+
 /// | Length of Key          | u8                       |
 /// | Key                    | bytes of a str           |
 /// +------------------------+--------------------------+
 /// | Message                | a serialize data         |
 /// +------------------------+--------------------------+
 ///
+/// The version is the one negotiated for the connection, a frame of another
+/// version is rejected.
+///
+/// The lowest bit of the flags tells the message is compressed with snappy,
+/// the other bits must be 0.
+///
+/// A message of `COMPRESS_THRESHOLD` bytes or more is compressed if that makes
+/// it smaller. Frames longer than the max frame size, on the wire or
+/// decompressed, are rejected.
 
 // Start of network messages.
 const NETMSG_START: u64 = 0xDEAD_BEEF_0000_0000;
+/// Length of version, flags and key length.
+const NETMSG_HEADER_LEN: usize = 3;
+const FLAG_SNAPPY: u8 = 1;
+pub const COMPRESS_THRESHOLD: usize = 4 * 1024;
 
 fn opt_bytes_extend(buf: &mut BytesMut, data: &[u8]) {
     buf.reserve(data.len());
@@ -67,12 +118,20 @@ fn opt_bytes_extend(buf: &mut BytesMut, data: &[u8]) {
     }
 }
 
+fn invalid_data<E: Into<String>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.into())
+}
+
+fn invalid_input<E: Into<String>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err.into())
+}
+
 impl Decoder for CitaCodec {
     type Item = CitaRequest;
     type Error = io::Error;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
-        Ok(network_message_to_pubsub_message(buf))
+        network_message_to_pubsub_message(buf, self.version, self.max_frame_size)
     }
 }
 
@@ -81,125 +140,278 @@ impl Encoder for CitaCodec {
     type Error = io::Error;
 
     fn encode(&mut self, msg: Self::Item, buf: &mut BytesMut) -> io::Result<()> {
-        pubsub_message_to_network_message(buf, msg);
-        Ok(())
+        pubsub_message_to_network_message(buf, msg, self.version, self.max_frame_size)
     }
 }
 
-impl<T: AsyncRead + AsyncWrite + 'static> ServerProto<T> for CitaProto {
-    type Request = CitaRequest;
-    type Response = CitaResponse;
+/// Write a keepalive frame.
+pub fn keepalive_message(buf: &mut BytesMut) {
+    let mut request_id_bytes = [0; 8];
+    NetworkEndian::write_u64(&mut request_id_bytes, NETMSG_START);
+    opt_bytes_extend(buf, &request_id_bytes);
+}
 
-    /// `Framed<T, CitaCodec>` is the return value of `io.framed(CitaCodec)`
-    type Transport = Framed<T, CitaCodec>;
-    type BindTransport = Result<Self::Transport, io::Error>;
+/// Write a frame of `version`, or a keepalive for `None`. Nothing is written for
+/// a message that can't be sent: an empty or too long key, or a frame too large.
+pub fn pubsub_message_to_network_message(
+    buf: &mut BytesMut,
+    msg: Option<(String, Vec<u8>)>,
+    version: u8,
+    max_frame_size: usize,
+) -> io::Result<()> {
+    let (key, body) = match msg {
+        Some(msg) => msg,
+        None => {
+            keepalive_message(buf);
+            return Ok(());
+        }
+    };
+    let length_key = key.len();
+    // Use 1 byte to store key length.
+    if length_key == 0 || length_key > u8::max_value() as usize {
+        return Err(invalid_input(format!(
+            "invalid length {} of the MQ message key {}",
+            length_key, key
+        )));
+    }
+    if body.len() > max_frame_size {
+        return Err(invalid_input(format!(
+            "The MQ message with key {} is too long {}",
+            key,
+            body.len()
+        )));
+    }
 
-    fn bind_transport(&self, io: T) -> Self::BindTransport {
-        Ok(io.framed(CitaCodec))
+    let mut flags = 0;
+    let mut body = body;
+    if body.len() >= COMPRESS_THRESHOLD {
+        let compressed = snappy::compress(&body);
+        if compressed.len() < body.len() {
+            flags = FLAG_SNAPPY;
+            body = compressed;
+        }
     }
-}
 
-pub fn pubsub_message_to_network_message(buf: &mut BytesMut, msg: Option<(String, Vec<u8>)>) {
+    // Store version, flags and the length for key, then store key, the last part is body.
+    let length_full = NETMSG_HEADER_LEN + length_key + body.len();
+    if length_full > max_frame_size || length_full > u32::max_value() as usize {
+        return Err(invalid_input(format!(
+            "The frame of MQ message with key {} is too long {}",
+            key, length_full
+        )));
+    }
     let mut request_id_bytes = [0; 8];
-    if let Some((key, body)) = msg {
-        let length_key = key.len();
-        // Use 1 byte to store key length.
-        if length_key > u8::max_value() as usize {
-            error!("The MQ message key is too long {}.", key);
+    NetworkEndian::write_u64(&mut request_id_bytes, NETMSG_START + length_full as u64);
+    buf.reserve(8 + length_full);
+    opt_bytes_extend(buf, &request_id_bytes);
+    buf.put_u8(version);
+    buf.put_u8(flags);
+    buf.put_u8(length_key as u8);
+    opt_bytes_extend(buf, key.as_bytes());
+    opt_bytes_extend(buf, &body);
+    Ok(())
+}
+
+/// Read the next frame of `version`, `None` if it isn't complete yet. The
+/// keepalives are skipped, and an error means the stream can't be read any more.
+pub fn network_message_to_pubsub_message(
+    buf: &mut BytesMut,
+    version: u8,
+    max_frame_size: usize,
+) -> io::Result<Option<(String, Vec<u8>)>> {
+    loop {
+        if buf.len() < 8 {
+            return Ok(None);
         }
-        // Use 1 bytes to store the length for key, then store key, the last part is body.
-        let length_full = 1 + length_key + body.len();
-        if length_full > u32::max_value() as usize {
-            error!(
-                "The MQ message with key {} is too long {}.",
-                key,
-                body.len()
-            );
+
+        let request_id = NetworkEndian::read_u64(buf.as_ref());
+        let netmsg_start = request_id & 0xffff_ffff_0000_0000;
+        let length_full = (request_id & 0x0000_0000_ffff_ffff) as usize;
+        if netmsg_start != NETMSG_START {
+            return Err(invalid_data("invalid start of network message"));
         }
-        let request_id = NETMSG_START + length_full as u64;
-        NetworkEndian::write_u64(&mut request_id_bytes, request_id);
-        opt_bytes_extend(buf, &request_id_bytes);
-        buf.put_u8(length_key as u8);
-        opt_bytes_extend(buf, key.as_bytes());
-        opt_bytes_extend(buf, &body);
-    } else {
-        let request_id = NETMSG_START;
-        NetworkEndian::write_u64(&mut request_id_bytes, request_id);
-        opt_bytes_extend(buf, &request_id_bytes);
-    }
-}
+        if length_full > max_frame_size {
+            return Err(invalid_data(format!(
+                "network message is too long {} > {}",
+                length_full, max_frame_size
+            )));
+        }
+        if length_full + 8 > buf.len() {
+            return Ok(None);
+        }
+        let _request_id_buf = buf.split_to(8);
 
-pub fn network_message_to_pubsub_message(buf: &mut BytesMut) -> Option<(String, Vec<u8>)> {
-    if buf.len() < 8 {
-        return None;
+        if length_full == 0 {
+            continue;
+        }
+        let payload_buf = buf.split_to(length_full);
+        return decode_payload(&payload_buf, version, max_frame_size).map(Some);
     }
+}
 
-    let request_id = NetworkEndian::read_u64(buf.as_ref());
-    let netmsg_start = request_id & 0xffff_ffff_0000_0000;
-    let length_full = (request_id & 0x0000_0000_ffff_ffff) as usize;
-    if netmsg_start != NETMSG_START {
-        return None;
+fn decode_payload(
+    payload: &[u8],
+    version: u8,
+    max_frame_size: usize,
+) -> io::Result<(String, Vec<u8>)> {
+    if payload.len() < NETMSG_HEADER_LEN {
+        return Err(invalid_data("network message header is truncated"));
     }
-    if length_full + 8 > buf.len() {
-        return None;
+    if payload[0] != version {
+        return Err(invalid_data(format!(
+            "version {} of network message, expected {}",
+            payload[0], version
+        )));
     }
-    let _request_id_buf = buf.split_to(8);
-
-    if length_full == 0 {
-        return None;
+    let flags = payload[1];
+    if flags & !FLAG_SNAPPY != 0 {
+        return Err(invalid_data(format!(
+            "unknown flags {:#b} of network message",
+            flags
+        )));
     }
-    let mut payload_buf = buf.split_to(length_full);
 
-    let length_key = payload_buf[0] as usize;
-    let _length_key_buf = payload_buf.split_to(1);
+    let length_key = payload[2] as usize;
+    let payload = &payload[NETMSG_HEADER_LEN..];
     if length_key == 0 {
-        error!("network message key is empty.");
-        return None;
+        return Err(invalid_data("network message key is empty"));
     }
-    if length_key > payload_buf.len() {
-        error!(
-            "Buffer is not enough for key {} > {}.",
+    if length_key > payload.len() {
+        return Err(invalid_data(format!(
+            "Buffer is not enough for key {} > {}",
             length_key,
-            buf.len()
-        );
-        return None;
-    }
-    let key_buf = payload_buf.split_to(length_key);
-    let key_str_result = str::from_utf8(&key_buf);
-    if key_str_result.is_err() {
-        error!("network message parse key error {:?}.", key_buf);
-        return None;
+            payload.len()
+        )));
     }
-    let key = key_str_result.unwrap().to_string();
-    if length_full == 1 + length_key {
+    let key = str::from_utf8(&payload[..length_key])
+        .map_err(|_| invalid_data("network message parse key error"))?
+        .to_string();
+    let body = &payload[length_key..];
+
+    let body = if flags & FLAG_SNAPPY != 0 {
+        let length = snappy::decompressed_len(body)
+            .map_err(|err| invalid_data(format!("invalid snappy message: {:?}", err)))?;
+        if length > max_frame_size {
+            return Err(invalid_data(format!(
+                "network message {} is too long decompressed {}",
+                key, length
+            )));
+        }
+        snappy::decompress(body)
+            .map_err(|err| invalid_data(format!("invalid snappy message: {:?}", err)))?
+    } else {
+        body.to_vec()
+    };
+    if body.is_empty() {
         warn!("network message is empty.");
     }
-    return Some((key, payload_buf.to_vec()));
+    Ok((key, body))
 }
 
 #[cfg(test)]
 mod test {
-    use super::{network_message_to_pubsub_message, pubsub_message_to_network_message};
-    use bytes::BytesMut;
+    use super::*;
+
+    fn encode(key: &str, msg: &[u8], max_frame_size: usize) -> io::Result<BytesMut> {
+        let mut buf = BytesMut::new();
+        pubsub_message_to_network_message(
+            &mut buf,
+            Some((key.to_string(), msg.to_vec())),
+            MAX_VERSION,
+            max_frame_size,
+        )?;
+        Ok(buf)
+    }
+
+    fn decode(buf: &mut BytesMut, max_frame_size: usize) -> io::Result<Option<(String, Vec<u8>)>> {
+        network_message_to_pubsub_message(buf, MAX_VERSION, max_frame_size)
+    }
 
     #[test]
     fn convert_empty_message() {
         let mut buf = BytesMut::with_capacity(4 + 4);
-        pubsub_message_to_network_message(&mut buf, None);
-        let pub_msg_opt = network_message_to_pubsub_message(&mut buf);
-        assert!(pub_msg_opt.is_none());
+        pubsub_message_to_network_message(&mut buf, None, MAX_VERSION, DEFAULT_MAX_FRAME_SIZE)
+            .unwrap();
+        let pub_msg_opt = decode(&mut buf, DEFAULT_MAX_FRAME_SIZE);
+        assert!(pub_msg_opt.unwrap().is_none());
+        assert!(buf.is_empty());
     }
 
     #[test]
     fn convert_messages() {
         let key = "this-is-the-key".to_string();
         let msg: Vec<u8> = vec![1, 3, 5, 7, 9];
-        let mut buf = BytesMut::with_capacity(4 + 4 + 1 + key.len() + msg.len());
-        pubsub_message_to_network_message(&mut buf, Some((key.clone(), msg.clone())));
-        let pub_msg_opt = network_message_to_pubsub_message(&mut buf);
-        assert!(pub_msg_opt.is_some());
-        let (key_new, msg_new) = pub_msg_opt.unwrap();
+        let mut buf = encode(&key, &msg, DEFAULT_MAX_FRAME_SIZE).unwrap();
+        // Keepalives before a message are skipped
+        let mut keepalive = BytesMut::new();
+        keepalive_message(&mut keepalive);
+        keepalive.extend_from_slice(&buf);
+        buf = keepalive;
+
+        let pub_msg_opt = decode(&mut buf, DEFAULT_MAX_FRAME_SIZE);
+        let (key_new, msg_new) = pub_msg_opt.unwrap().unwrap();
         assert_eq!(key, key_new);
         assert_eq!(msg, msg_new);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn compress_large_messages() {
+        let msg = vec![7u8; COMPRESS_THRESHOLD * 4];
+        let mut buf = encode("key", &msg, DEFAULT_MAX_FRAME_SIZE).unwrap();
+        assert!(buf.len() < msg.len());
+        assert_eq!(buf[9], FLAG_SNAPPY);
+
+        // Only the first part is received
+        let mut part = buf.split_to(10);
+        assert!(
+            decode(&mut part, DEFAULT_MAX_FRAME_SIZE)
+                .unwrap()
+                .is_none()
+        );
+        part.extend_from_slice(&buf);
+        let (key, msg_new) = decode(&mut part, DEFAULT_MAX_FRAME_SIZE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(key, "key");
+        assert_eq!(msg, msg_new);
+    }
+
+    #[test]
+    fn reject_invalid_messages() {
+        assert!(encode("", &[1], DEFAULT_MAX_FRAME_SIZE).is_err());
+        let long_key: String = (0..256).map(|_| 'k').collect();
+        assert!(encode(&long_key, &[1], DEFAULT_MAX_FRAME_SIZE).is_err());
+        assert!(encode("key", &[1; 64], 32).is_err());
+
+        // Too long on the wire
+        let mut buf = encode("key", &[1; 64], DEFAULT_MAX_FRAME_SIZE).unwrap();
+        assert!(decode(&mut buf, 32).is_err());
+
+        // Too long decompressed
+        let msg = vec![7u8; COMPRESS_THRESHOLD * 4];
+        let mut buf = encode("key", &msg, DEFAULT_MAX_FRAME_SIZE).unwrap();
+        let max_frame_size = buf.len() + 1;
+        assert!(decode(&mut buf, max_frame_size).is_err());
+
+        // Another version and unknown flags
+        for &(index, value) in &[(8, 2), (9, 0b100), (9, 2)] {
+            let mut buf = encode("key", &[1], DEFAULT_MAX_FRAME_SIZE).unwrap();
+            buf[index] = value;
+            assert!(decode(&mut buf, DEFAULT_MAX_FRAME_SIZE).is_err());
+        }
+
+        let mut buf = BytesMut::from(vec![0u8; 8]);
+        assert!(decode(&mut buf, DEFAULT_MAX_FRAME_SIZE).is_err());
+    }
+
+    #[test]
+    fn negotiate_versions() {
+        assert_eq!(negotiate_version(MIN_VERSION, MAX_VERSION), Some(MAX_VERSION));
+        // A newer peer still speaks this version
+        assert_eq!(negotiate_version(MIN_VERSION, MAX_VERSION + 1), Some(MAX_VERSION));
+        assert_eq!(negotiate_version(MAX_VERSION + 1, MAX_VERSION + 2), None);
+        assert_eq!(negotiate_version(0, MIN_VERSION - 1), None);
+        assert_eq!(negotiate_version(MAX_VERSION, MIN_VERSION - 1), None);
     }
 }
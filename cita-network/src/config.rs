@@ -15,10 +15,22 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use cita_types::{clean_0x, Address};
+use citaprotocol::DEFAULT_MAX_FRAME_SIZE;
+use std::str::FromStr;
+
 #[derive(Debug, Deserialize)]
 pub struct NetConfig {
     pub id_card: Option<u32>,
     pub port: Option<u64>,
+    /// File of the node key used in the handshake, `privkey` by default
+    pub privkey_path: Option<String>,
+    /// Discover the nodes of the node manager contract, see module `discovery`
+    pub discovery: Option<bool>,
+    /// IP the other nodes reach this one with, announced when discovery is on
+    pub external_ip: Option<String>,
+    /// Upper bound of a network message in bytes, on the wire and decompressed
+    pub max_frame_size: Option<usize>,
     pub peers: Option<Vec<PeerConfig>>,
 }
 
@@ -27,29 +39,59 @@ pub struct PeerConfig {
     pub id_card: Option<u32>,
     pub ip: Option<String>,
     pub port: Option<u64>,
+    /// Address of the peer's node key, the peer must prove it in the handshake
+    pub address: Option<String>,
 }
 
 impl NetConfig {
     pub fn new(path: &str) -> Self {
         parse_config!(NetConfig, path)
     }
+
+    pub fn privkey_path(&self) -> &str {
+        self.privkey_path
+            .as_ref()
+            .map_or("privkey", |path| path.as_str())
+    }
+
+    pub fn discovery(&self) -> bool {
+        self.discovery.unwrap_or(false)
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size.unwrap_or(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl PeerConfig {
+    pub fn node_address(&self) -> Option<Address> {
+        self.address
+            .as_ref()
+            .and_then(|address| Address::from_str(clean_0x(address)).ok())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::NetConfig;
+    use cita_types::Address;
     use std::io::Write;
+    use std::str::FromStr;
     use tempfile::NamedTempFile;
     #[test]
     fn basics() {
         let toml_str = r#"
         port = 40000
+        discovery = true
+        external_ip = "10.0.0.1"
         [[peers]]
         ip = "127.0.0.1"
         port = 40001
+        address = "0x2b3c2d2e8a1d7e3a4c1f0b2e5f6a7b8c9d0e1f20"
         [[peers]]
         ip = "127.0.0.1"
         port = 40002
+        address = "invalid"
         "#;
 
         let mut tmpfile: NamedTempFile = NamedTempFile::new().unwrap();
@@ -58,5 +100,15 @@ mod tests {
         let value = parse_config!(NetConfig, path);
 
         assert_eq!(value.port, Some(40000));
+        assert_eq!(value.privkey_path(), "privkey");
+        assert!(value.discovery());
+        assert_eq!(value.max_frame_size(), DEFAULT_MAX_FRAME_SIZE);
+        assert_eq!(value.external_ip, Some("10.0.0.1".to_owned()));
+        let peers = value.peers.unwrap();
+        assert_eq!(
+            peers[0].node_address(),
+            Some(Address::from_str("2b3c2d2e8a1d7e3a4c1f0b2e5f6a7b8c9d0e1f20").unwrap())
+        );
+        assert_eq!(peers[1].node_address(), None);
     }
 }
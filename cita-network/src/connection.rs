@@ -16,14 +16,20 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use bytes::BytesMut;
+use cita_types::traits::LowerHex;
+use cita_types::Address;
 use citaprotocol::pubsub_message_to_network_message;
 use config;
 use config::NetConfig;
-use libproto::{Message, OperateType};
+use discovery::{encode_records, AddressRecord, Discovery, GOSSIP_INTERVAL};
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use libproto::{Message, MsgClass, OperateType};
 use notify::DebouncedEvent;
+use metrics::Metrics;
+use outbound::{start_writer, Frame, Outbound, Priority};
+use secure::Identity;
 use std::convert::{TryFrom, TryInto};
-use std::io::Write;
-use std::net::{Shutdown, TcpStream};
+use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Receiver;
 use std::sync::Arc;
@@ -31,35 +37,111 @@ use std::thread;
 use std::time::Duration;
 use util::RwLock;
 
-const TIMEOUT: u64 = 15;
-// list of peer: id, addr, tcp_connect
-type PeerPairs = Arc<RwLock<Vec<(u32, String, Option<TcpStream>)>>>;
+// list of peer: id, addr, send queue, node address
+type PeerPairs = Arc<RwLock<Vec<(u32, String, Arc<Outbound>, Address)>>>;
 
 /// Manage p2p networks
 pub struct Connection {
     pub id_card: u32,
-    /// list of peer: id, addr, tcp_connect
+    pub identity: Arc<Identity>,
+    /// list of peer: id, addr, send queue, node address
     pub peers_pair: PeerPairs,
     pub is_disconnect: Arc<AtomicBool>,
+    pub discovery: RwLock<Discovery>,
+    /// Upper bound of the frames sent and received
+    pub max_frame_size: usize,
+    pub metrics: Arc<Metrics>,
+    /// Whether the writers of the peers are running, see `manage_connect`
+    is_started: AtomicBool,
 }
 
-impl Connection {
-    pub fn new(config: &config::NetConfig) -> Self {
-        let id_card = config.id_card.unwrap();
-        let mut peers_pair = Vec::default();
-        if let Some(peers) = config.peers.as_ref() {
-            for peer in peers.iter() {
-                let id_card: u32 = peer.id_card.unwrap();
-                let addr = format!("{}:{}", peer.ip.clone().unwrap(), peer.port.unwrap());
-                peers_pair.push((id_card, addr, None));
+/// Peers in the config, the ones without a valid node address are skipped
+/// since they can't be authenticated, and so are the ones with the id card of
+/// this node or of an earlier peer.
+fn configured_peers(config: &config::NetConfig) -> Vec<(u32, String, Address)> {
+    let mut peers: Vec<(u32, String, Address)> = Vec::new();
+    if let Some(peer_configs) = config.peers.as_ref() {
+        for peer in peer_configs.iter() {
+            let id_card: u32 = peer.id_card.unwrap();
+            let addr = format!("{}:{}", peer.ip.clone().unwrap(), peer.port.unwrap());
+            if Some(id_card) == config.id_card || peers.iter().any(|known| known.0 == id_card) {
+                error!("peer {} has duplicate id card {}, skip it", addr, id_card);
+                continue;
+            }
+            match peer.node_address() {
+                Some(address) => peers.push((id_card, addr, address)),
+                None => error!("peer {} has no valid address, skip it", addr),
             }
         }
+    }
+    peers
+}
+
+impl Connection {
+    pub fn new(config: &config::NetConfig, identity: Arc<Identity>) -> Self {
+        let id_card = config.id_card.unwrap();
+        let mut discovery = Discovery::new(config, &identity);
+        discovery.set_configured(configured_peers(config));
+        let peers_pair = discovery
+            .peers()
+            .into_iter()
+            .map(|(id_card, addr, address)| (id_card, addr, Arc::new(Outbound::new()), address))
+            .collect();
+        info!("node address 0x{}", identity.address().lower_hex());
 
         Connection {
             id_card,
+            identity,
             peers_pair: Arc::new(RwLock::new(peers_pair)),
             is_disconnect: Arc::new(AtomicBool::new(false)),
+            discovery: RwLock::new(discovery),
+            max_frame_size: config.max_frame_size(),
+            metrics: Arc::new(Metrics::new()),
+            is_started: AtomicBool::new(false),
+        }
+    }
+
+    fn spawn_writer(&self, peer: &(u32, String, Arc<Outbound>, Address)) {
+        start_writer(
+            Arc::clone(&peer.2),
+            peer.1.clone(),
+            peer.3,
+            Arc::clone(&self.identity),
+            Arc::clone(&self.is_disconnect),
+            Arc::clone(&self.metrics),
+        );
+    }
+
+    /// Start the writers of the peers, the ones added later start at once.
+    fn start(&self) {
+        let peers_pair = self.peers_pair.read();
+        self.is_started.store(true, Ordering::SeqCst);
+        for peer in peers_pair.iter() {
+            self.spawn_writer(peer);
+        }
+    }
+
+    /// Whether the node may connect: a configured peer or, with discovery, a node on chain.
+    pub fn is_allowed(&self, address: &Address) -> bool {
+        self.discovery.read().is_allowed(address)
+    }
+
+    /// Id card of the peer with the node address proven by the handshake,
+    /// `None` if this node or another peer has the same id card.
+    pub fn trusted_peer(&self, address: &Address) -> Option<u32> {
+        let peers_pair = self.peers_pair.read();
+        let id_card = peers_pair
+            .iter()
+            .find(|peer| peer.3 == *address)
+            .map(|peer| peer.0)?;
+        if id_card == self.id_card
+            || peers_pair
+                .iter()
+                .any(|peer| peer.0 == id_card && peer.3 != *address)
+        {
+            return None;
         }
+        Some(id_card)
     }
 
     pub fn is_send(id_card: u32, origin: u32, operate: OperateType) -> bool {
@@ -70,45 +152,75 @@ impl Connection {
 
     pub fn update(&self, config: &config::NetConfig) {
         //添加更新的配置到self
-        match config.peers.as_ref() {
-            Some(peers) => {
-                let mut peers_addr = Vec::new();
-                for peer in self.peers_pair.read().iter() {
-                    peers_addr.push(peer.1.clone());
-                }
-                info!("peers before update {:?}", peers_addr);
-                let mut config_addr = Vec::new();
-                for peer in peers.iter() {
-                    let id_card: u32 = peer.id_card.unwrap();
-                    let addr = format!("{}:{}", peer.ip.clone().unwrap(), peer.port.unwrap());
-                    config_addr.push(addr.clone());
-                    if peers_addr.contains(&addr) {
-                        continue;
-                    }
-                    peers_addr.push(addr.clone());
-                    self.peers_pair.write().push((id_card, addr, None));
-                }
-                loop {
-                    let index_opt = peers_addr
-                        .iter()
-                        .position(|addr| !config_addr.contains(addr));
-                    if let Some(index) = index_opt {
-                        peers_addr.remove(index);
-                        self.peers_pair.write().remove(index);
-                    } else {
-                        break;
-                    }
-                }
-                info!("peers after update {:?}", peers_addr);
+        self.discovery
+            .write()
+            .set_configured(configured_peers(config));
+        self.refresh();
+    }
+
+    /// Nodes of the node manager contract from the `RichStatus` of chain.
+    pub fn update_nodes(&self, nodes: Vec<Address>) {
+        if self.discovery.write().set_nodes(nodes) {
+            info!("nodes on chain changed");
+            self.refresh();
+        }
+    }
+
+    /// Address records gossiped by the peers.
+    pub fn receive_records(&self, records: Vec<AddressRecord>) {
+        let mut changed = false;
+        {
+            let mut discovery = self.discovery.write();
+            for record in records {
+                changed |= discovery.insert(record);
             }
-            None => {
-                info!("clear all peers after update!");
-                self.peers_pair.write().clear();
+        }
+        if changed {
+            self.refresh();
+        }
+    }
+
+    /// Connect the known peers, disconnect the ones no longer allowed.
+    fn refresh(&self) {
+        let peers = self.discovery.read().peers();
+        let mut peers_pair = self.peers_pair.write();
+        {
+            let peers_addr: Vec<&String> = peers_pair.iter().map(|peer| &peer.1).collect();
+            info!("peers before update {:?}", peers_addr);
+        }
+        // A peer whose node address or id card changed is connected again
+        peers_pair.retain(|pair| {
+            let keep = peers
+                .iter()
+                .any(|peer| peer.0 == pair.0 && peer.1 == pair.1 && peer.2 == pair.3);
+            if !keep {
+                pair.2.close();
+            }
+            keep
+        });
+        for (id_card, addr, address) in peers {
+            if !peers_pair
+                .iter()
+                .any(|pair| pair.0 == id_card && pair.1 == addr && pair.3 == address)
+            {
+                let peer = (id_card, addr, Arc::new(Outbound::new()), address);
+                if self.is_started.load(Ordering::SeqCst) {
+                    self.spawn_writer(&peer);
+                }
+                peers_pair.push(peer);
             }
         }
+        let peers_addr: Vec<&String> = peers_pair.iter().map(|peer| &peer.1).collect();
+        info!("peers after update {:?}", peers_addr);
+        let addresses: Vec<Address> = peers_pair.iter().map(|peer| peer.3).collect();
+        self.metrics.retain(&addresses);
     }
 
+    /// Queue the message for the peers, it never waits for the network.
     pub fn broadcast(&self, key: String, mut msg: Message) {
+        if self.is_disconnect.load(Ordering::SeqCst) {
+            return;
+        }
         let origin = msg.get_origin();
         let operate = msg.get_operate();
         msg.set_origin(self.id_card);
@@ -116,15 +228,32 @@ impl Connection {
         trace!("broadcast msg {:?} from key {}", msg, key);
         let msg_bytes: Vec<u8> = msg.try_into().unwrap();
 
-        let mut buf = BytesMut::with_capacity(4 + 4 + 1 + key.len() + msg_bytes.len());
-        pubsub_message_to_network_message(&mut buf, Some((key, msg_bytes)));
-
+        let priority = Priority::of(&key);
+        // The frame in each version the peers speak
+        let mut frames: Vec<(u8, Arc<Frame>)> = Vec::new();
         let mut peers = vec![];
-        for peer in self.peers_pair.write().iter_mut() {
-            if Connection::is_send(peer.0, origin, operate) {
-                if let Some(ref mut stream) = peer.2 {
+        for peer in self.peers_pair.read().iter() {
+            if Connection::is_send(peer.0, origin, operate) && peer.2.is_connected() {
+                let version = peer.2.version();
+                let frame = match frames.iter().find(|frame| frame.0 == version) {
+                    Some(frame) => Arc::clone(&frame.1),
+                    None => match self.encode(&key, &msg_bytes, version) {
+                        Ok(frame) => {
+                            frames.push((version, Arc::clone(&frame)));
+                            frame
+                        }
+                        Err(err) => {
+                            error!("drop message {}: {}", key, err);
+                            return;
+                        }
+                    },
+                };
+                if peer.2.push(frame, priority) {
                     peers.push(peer.0);
-                    let _ = stream.write(&buf);
+                } else if priority == Priority::High {
+                    warn!("consensus queue of node {} is full, disconnect", peer.0);
+                } else {
+                    debug!("send queue of node {} is full, drop message", peer.0);
                 }
             }
         }
@@ -137,48 +266,52 @@ impl Connection {
         );
     }
 
+    fn encode(&self, key: &str, msg_bytes: &[u8], version: u8) -> io::Result<Arc<Frame>> {
+        let mut buf = BytesMut::with_capacity(4 + 4 + 3 + key.len() + msg_bytes.len());
+        pubsub_message_to_network_message(
+            &mut buf,
+            Some((key.to_owned(), msg_bytes.to_vec())),
+            version,
+            self.max_frame_size,
+        )?;
+        Ok(Arc::new(Frame {
+            key: key.to_owned(),
+            data: buf.to_vec(),
+        }))
+    }
+
     pub fn broadcast_rawbytes(&self, key: String, data: &[u8]) {
         let mut msg = Message::try_from(data).unwrap();
         self.broadcast(key, msg);
     }
 }
 
-fn connect(con: Arc<Connection>) {
+/// Send the known address records to the peers.
+fn gossip(con: Arc<Connection>) {
     thread::spawn(move || loop {
-        for peer in con.peers_pair.write().iter_mut() {
-            if con.is_disconnect.load(Ordering::SeqCst) {
-                if let Some(ref mut stream) = peer.2 {
-                    stream
-                        .shutdown(Shutdown::Both)
-                        .expect("shutdown call failed");
-                }
-                peer.2 = None;
-                thread::sleep(Duration::from_millis(TIMEOUT * 1000));
-                continue;
-            }
-
-            let mut need_reconnect = true;
-            let mut buf = BytesMut::with_capacity(4 + 4);
-            pubsub_message_to_network_message(&mut buf, None);
-            if let Some(ref mut stream) = peer.2 {
-                let res = stream.write(&buf);
-                if res.is_ok() {
-                    need_reconnect = false;
-                }
-            }
-            if need_reconnect {
-                info!("connect {:?}!", peer.1);
-                peer.2 = TcpStream::connect(peer.1.clone()).ok();
-            }
+        thread::sleep(Duration::from_secs(GOSSIP_INTERVAL));
+        if con.is_disconnect.load(Ordering::SeqCst) {
+            continue;
         }
-
-        thread::sleep(Duration::from_millis(TIMEOUT * 1000));
-        trace!("after sleep retry connect!");
+        let records = con.discovery.read().records();
+        if records.is_empty() {
+            continue;
+        }
+        trace!("gossip {} address records", records.len());
+        let msg = Message::init(
+            OperateType::Broadcast,
+            0,
+            MsgClass::RawBytes(encode_records(&records)),
+        );
+        con.broadcast(routing_key!(Net >> RawBytes).into(), msg);
     });
 }
 
 pub fn manage_connect(con: &Arc<Connection>, config_path: &str, rx: Receiver<DebouncedEvent>) {
-    connect(Arc::clone(con));
+    con.start();
+    if con.discovery.read().is_enabled() {
+        gossip(Arc::clone(con));
+    }
     let config = String::from(config_path);
 
     let con = Arc::clone(con);
@@ -205,7 +338,49 @@ pub fn manage_connect(con: &Arc<Connection>, config_path: &str, rx: Receiver<Deb
 #[cfg(test)]
 mod test {
     use super::Connection;
+    use cita_crypto::{CreateKey, KeyPair};
+    use cita_types::traits::LowerHex;
+    use config::{NetConfig, PeerConfig};
     use libproto::OperateType;
+    use secure::Identity;
+    use std::sync::Arc;
+
+    fn identity() -> Identity {
+        Identity::new(*KeyPair::gen_keypair().privkey())
+    }
+
+    fn peer(id_card: u32, port: u64, node: &Identity) -> PeerConfig {
+        PeerConfig {
+            id_card: Some(id_card),
+            ip: Some("127.0.0.1".to_owned()),
+            port: Some(port),
+            address: Some(node.address().lower_hex()),
+        }
+    }
+
+    #[test]
+    fn duplicate_id_cards_are_not_trusted() {
+        let (first, second, own) = (identity(), identity(), identity());
+        let config = NetConfig {
+            id_card: Some(0),
+            port: Some(40000),
+            privkey_path: None,
+            discovery: Some(false),
+            external_ip: None,
+            max_frame_size: None,
+            peers: Some(vec![
+                peer(1, 40001, &first),
+                peer(1, 40002, &second),
+                peer(0, 40003, &own),
+            ]),
+        };
+        let con = Connection::new(&config, Arc::new(identity()));
+        assert_eq!(con.peers_pair.read().len(), 1);
+        assert_eq!(con.trusted_peer(first.address()), Some(1));
+        assert_eq!(con.trusted_peer(second.address()), None);
+        assert_eq!(con.trusted_peer(own.address()), None);
+    }
+
     #[test]
     fn is_send_msg() {
         assert!(Connection::is_send(0, 0, OperateType::Broadcast));
@@ -0,0 +1,436 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Discovery of the peers and the membership from the chain.
+//!
+//! With `discovery = true` in `network.toml`, the nodes allowed to connect are
+//! the configured peers plus the nodes of the node manager contract, taken from
+//! the `RichStatus` of chain. So a node added by the governance is connected
+//! without editing the config files of every node.
+//!
+//! The nodes find each other by gossiping signed address records, with the key
+//! `Net >> RawBytes` no other message between the nodes uses, every
+//! `GOSSIP_INTERVAL` seconds. A record is signed by the node key of its node,
+//! so it can be relayed by any node:
+//!
+//! | Address  | Id card | Port | Version | IP length | IP    | Signature length | Signature |
+//! | -------- | ------- | ---- | ------- | --------- | ----- | ---------------- | --------- |
+//! | 20 bytes | u32     | u64  | u64     | u8        | bytes | u8               | bytes     |
+//!
+//! The version is the start time of the node, the newer record of a node wins.
+//! A node without `external_ip` doesn't announce itself, it only dials out.
+//!
+//! The id card of a record is chosen by its node, the messages of the node are
+//! only taken with that origin once the handshake proves its node key. A record
+//! with the id card of this node, of a configured peer or of another known node
+//! is dropped, so a node can't take the id card of another one.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use cita_crypto::{Sign, Signature, SIGNATURE_BYTES_LEN};
+use cita_types::{Address, H256};
+use config::NetConfig;
+use secure::Identity;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use util::Hashable;
+
+pub const GOSSIP_INTERVAL: u64 = 30;
+/// Upper bound of the records in one message.
+const MAX_RECORDS: usize = 1024;
+const RECORD_TAG: &[u8] = b"cita-p2p record";
+
+/// Signed ip and port of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressRecord {
+    pub address: Address,
+    pub id_card: u32,
+    pub ip: String,
+    pub port: u64,
+    pub version: u64,
+    signature: Vec<u8>,
+}
+
+impl AddressRecord {
+    pub fn new(identity: &Identity, id_card: u32, ip: String, port: u64, version: u64) -> Self {
+        let mut record = AddressRecord {
+            address: *identity.address(),
+            id_card: id_card,
+            ip: ip,
+            port: port,
+            version: version,
+            signature: Vec::new(),
+        };
+        let signature = identity
+            .sign(&record.hash())
+            .expect("failed to sign address record");
+        record.signature = signature[..].to_vec();
+        record
+    }
+
+    fn unsigned(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; 20 + 4 + 8 + 8];
+        buf[..20].copy_from_slice(&self.address.0);
+        NetworkEndian::write_u32(&mut buf[20..24], self.id_card);
+        NetworkEndian::write_u64(&mut buf[24..32], self.port);
+        NetworkEndian::write_u64(&mut buf[32..40], self.version);
+        buf.push(self.ip.len() as u8);
+        buf.extend_from_slice(self.ip.as_bytes());
+        buf
+    }
+
+    fn hash(&self) -> H256 {
+        let mut message = RECORD_TAG.to_vec();
+        message.extend_from_slice(&self.unsigned());
+        message.crypt_hash()
+    }
+
+    /// Whether the record is signed by the node key of its address.
+    pub fn verify(&self) -> bool {
+        if self.signature.len() != SIGNATURE_BYTES_LEN {
+            return false;
+        }
+        Signature::from(&self.signature[..])
+            .recover(&self.hash().into())
+            .map(|pubkey| Address::from(pubkey.crypt_hash()) == self.address)
+            .unwrap_or(false)
+    }
+
+    pub fn peer_addr(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+pub fn encode_records(records: &[AddressRecord]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for record in records.iter().take(MAX_RECORDS) {
+        buf.extend_from_slice(&record.unsigned());
+        buf.push(record.signature.len() as u8);
+        buf.extend_from_slice(&record.signature);
+    }
+    buf
+}
+
+/// Decode the records of a gossip message, `None` if it's malformed.
+/// The signatures are not checked here.
+pub fn decode_records(mut data: &[u8]) -> Option<Vec<AddressRecord>> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        if records.len() == MAX_RECORDS || data.len() < 20 + 4 + 8 + 8 + 1 {
+            return None;
+        }
+        let address = Address::from_slice(&data[..20]);
+        let id_card = NetworkEndian::read_u32(&data[20..24]);
+        let port = NetworkEndian::read_u64(&data[24..32]);
+        let version = NetworkEndian::read_u64(&data[32..40]);
+        let ip_len = data[40] as usize;
+        data = &data[41..];
+        if data.len() < ip_len + 1 {
+            return None;
+        }
+        let ip = String::from_utf8(data[..ip_len].to_vec()).ok()?;
+        let signature_len = data[ip_len] as usize;
+        data = &data[ip_len + 1..];
+        if data.len() < signature_len {
+            return None;
+        }
+        let signature = data[..signature_len].to_vec();
+        data = &data[signature_len..];
+        records.push(AddressRecord {
+            address: address,
+            id_card: id_card,
+            ip: ip,
+            port: port,
+            version: version,
+            signature: signature,
+        });
+    }
+    Some(records)
+}
+
+/// Known peers of the node: the configured ones, the nodes on chain and their
+/// records.
+pub struct Discovery {
+    address: Address,
+    id_card: u32,
+    enabled: bool,
+    local: Option<AddressRecord>,
+    /// list of configured peer: id, addr, node address
+    configured: Vec<(u32, String, Address)>,
+    nodes: HashSet<Address>,
+    records: HashMap<Address, AddressRecord>,
+}
+
+impl Discovery {
+    pub fn new(config: &NetConfig, identity: &Identity) -> Self {
+        let enabled = config.discovery();
+        let local = match config.external_ip {
+            Some(ref ip) if enabled => {
+                let version = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .unwrap_or(0);
+                Some(AddressRecord::new(
+                    identity,
+                    config.id_card.unwrap(),
+                    ip.clone(),
+                    config.port.unwrap(),
+                    version,
+                ))
+            }
+            _ => None,
+        };
+        if enabled && local.is_none() {
+            warn!("no external_ip, this node won't be discovered by the others");
+        }
+
+        Discovery {
+            address: *identity.address(),
+            id_card: config.id_card.unwrap(),
+            enabled: enabled,
+            local: local,
+            configured: Vec::new(),
+            nodes: HashSet::new(),
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_configured(&mut self, peers: Vec<(u32, String, Address)>) {
+        self.configured = peers;
+    }
+
+    /// Replace the nodes of the node manager contract, returns whether they changed.
+    /// The records of the removed nodes are dropped.
+    pub fn set_nodes(&mut self, nodes: Vec<Address>) -> bool {
+        let nodes: HashSet<Address> = nodes.into_iter().collect();
+        if !self.enabled || nodes == self.nodes {
+            return false;
+        }
+        self.records.retain(|address, _| nodes.contains(address));
+        self.nodes = nodes;
+        true
+    }
+
+    /// Whether a node may connect to this one.
+    pub fn is_allowed(&self, address: &Address) -> bool {
+        self.configured.iter().any(|peer| peer.2 == *address)
+            || (self.enabled && self.nodes.contains(address))
+    }
+
+    /// Keep a gossiped record, returns whether it's new. Only the records of
+    /// the nodes on chain are kept.
+    pub fn insert(&mut self, record: AddressRecord) -> bool {
+        if !self.enabled || record.address == self.address {
+            return false;
+        }
+        if !self.nodes.contains(&record.address) {
+            return false;
+        }
+        if let Some(known) = self.records.get(&record.address) {
+            if known.version >= record.version {
+                return false;
+            }
+        }
+        if self.is_duplicate(&record) {
+            warn!("drop address record with duplicate id card {:?}", record);
+            return false;
+        }
+        if !record.verify() {
+            warn!("drop address record with invalid signature {:?}", record);
+            return false;
+        }
+        self.records.insert(record.address, record);
+        true
+    }
+
+    /// Whether the id card of a record is taken by this node, a configured
+    /// peer or the record of another node.
+    fn is_duplicate(&self, record: &AddressRecord) -> bool {
+        record.id_card == self.id_card
+            || self
+                .configured
+                .iter()
+                .any(|peer| peer.0 == record.id_card && peer.2 != record.address)
+            || self
+                .records
+                .values()
+                .any(|known| known.id_card == record.id_card && known.address != record.address)
+    }
+
+    /// Peers to connect, a configured peer takes precedence over its record
+    /// and over a record with its id card.
+    pub fn peers(&self) -> Vec<(u32, String, Address)> {
+        let mut peers = self.configured.clone();
+        for record in self.records.values() {
+            if !peers
+                .iter()
+                .any(|peer| peer.0 == record.id_card || peer.2 == record.address)
+            {
+                peers.push((record.id_card, record.peer_addr(), record.address));
+            }
+        }
+        peers
+    }
+
+    /// Records to gossip, the own one first.
+    pub fn records(&self) -> Vec<AddressRecord> {
+        self.local
+            .iter()
+            .chain(self.records.values())
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_crypto::{CreateKey, KeyPair};
+
+    fn identity() -> Identity {
+        Identity::new(*KeyPair::gen_keypair().privkey())
+    }
+
+    fn config(discovery: bool) -> NetConfig {
+        NetConfig {
+            id_card: Some(0),
+            port: Some(40000),
+            privkey_path: None,
+            discovery: Some(discovery),
+            external_ip: Some("127.0.0.1".to_owned()),
+            max_frame_size: None,
+            peers: None,
+        }
+    }
+
+    #[test]
+    fn record_roundtrip() {
+        let node = identity();
+        let record = AddressRecord::new(&node, 3, "10.0.0.3".to_owned(), 40003, 7);
+        assert!(record.verify());
+        assert_eq!(record.peer_addr(), "10.0.0.3:40003");
+
+        let records = decode_records(&encode_records(&[record.clone(), record.clone()])).unwrap();
+        assert_eq!(records, vec![record.clone(), record.clone()]);
+
+        let mut tampered = record.clone();
+        tampered.port = 40004;
+        assert!(!tampered.verify());
+
+        let data = encode_records(&[record]);
+        assert!(decode_records(&data[..data.len() - 1]).is_none());
+        assert_eq!(decode_records(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn records_of_nodes_on_chain() {
+        let local = identity();
+        let (node, other) = (identity(), identity());
+        let mut discovery = Discovery::new(&config(true), &local);
+        let record = AddressRecord::new(&node, 1, "10.0.0.1".to_owned(), 40001, 1);
+
+        // Not on chain yet
+        assert!(!discovery.is_allowed(node.address()));
+        assert!(!discovery.insert(record.clone()));
+
+        assert!(discovery.set_nodes(vec![*node.address(), *local.address()]));
+        assert!(!discovery.set_nodes(vec![*local.address(), *node.address()]));
+        assert!(discovery.is_allowed(node.address()));
+        assert!(!discovery.is_allowed(other.address()));
+        assert!(discovery.insert(record.clone()));
+        assert!(!discovery.insert(record.clone()));
+        assert_eq!(
+            discovery.peers(),
+            vec![(1, "10.0.0.1:40001".to_owned(), *node.address())]
+        );
+
+        // The newer record wins
+        let moved = AddressRecord::new(&node, 1, "10.0.0.2".to_owned(), 40001, 2);
+        assert!(discovery.insert(moved.clone()));
+        assert!(!discovery.insert(record));
+        assert_eq!(discovery.records().len(), 2);
+        assert_eq!(discovery.records()[1], moved);
+
+        // The own record is never taken from the others
+        let own = AddressRecord::new(&local, 0, "10.0.0.9".to_owned(), 40000, 9);
+        assert!(!discovery.insert(own));
+
+        // Removed by the governance
+        assert!(discovery.set_nodes(vec![*local.address()]));
+        assert!(!discovery.is_allowed(node.address()));
+        assert!(discovery.peers().is_empty());
+    }
+
+    #[test]
+    fn configured_peers_take_precedence() {
+        let local = identity();
+        let node = identity();
+        let mut discovery = Discovery::new(&config(true), &local);
+        discovery.set_configured(vec![(1, "127.0.0.1:40001".to_owned(), *node.address())]);
+        discovery.set_nodes(vec![*node.address()]);
+        discovery.insert(AddressRecord::new(&node, 1, "10.0.0.1".to_owned(), 40001, 1));
+        assert_eq!(
+            discovery.peers(),
+            vec![(1, "127.0.0.1:40001".to_owned(), *node.address())]
+        );
+    }
+
+    #[test]
+    fn duplicate_id_cards_are_rejected() {
+        let local = identity();
+        let (configured, node, other) = (identity(), identity(), identity());
+        let mut discovery = Discovery::new(&config(true), &local);
+        discovery.set_configured(vec![(1, "127.0.0.1:40001".to_owned(), *configured.address())]);
+        discovery.set_nodes(vec![*configured.address(), *node.address(), *other.address()]);
+
+        // The id cards of this node and of a configured peer
+        assert!(!discovery.insert(AddressRecord::new(&node, 0, "10.0.0.2".to_owned(), 40002, 1)));
+        assert!(!discovery.insert(AddressRecord::new(&node, 1, "10.0.0.2".to_owned(), 40002, 1)));
+
+        // The id card of a known node
+        assert!(discovery.insert(AddressRecord::new(&node, 2, "10.0.0.2".to_owned(), 40002, 1)));
+        assert!(!discovery.insert(AddressRecord::new(&other, 2, "10.0.0.3".to_owned(), 40003, 1)));
+        assert!(discovery.insert(AddressRecord::new(&other, 3, "10.0.0.3".to_owned(), 40003, 1)));
+
+        // A node may move to a free id card, not to a taken one
+        assert!(!discovery.insert(AddressRecord::new(&node, 3, "10.0.0.2".to_owned(), 40002, 2)));
+        assert!(discovery.insert(AddressRecord::new(&node, 4, "10.0.0.2".to_owned(), 40002, 3)));
+
+        // A configured peer added later takes the id card from the record
+        discovery.set_configured(vec![
+            (1, "127.0.0.1:40001".to_owned(), *configured.address()),
+            (3, "127.0.0.1:40003".to_owned(), *identity().address()),
+        ]);
+        let peers = discovery.peers();
+        assert_eq!(peers.len(), 3);
+        assert!(!peers.iter().any(|peer| peer.2 == *other.address()));
+    }
+
+    #[test]
+    fn disabled_discovery_uses_config_only() {
+        let local = identity();
+        let node = identity();
+        let mut discovery = Discovery::new(&config(false), &local);
+        assert!(discovery.records().is_empty());
+        assert!(!discovery.set_nodes(vec![*node.address()]));
+        assert!(!discovery.is_allowed(node.address()));
+        let record = AddressRecord::new(&node, 1, "10.0.0.1".to_owned(), 40001, 1);
+        assert!(!discovery.insert(record));
+    }
+}
@@ -30,6 +30,7 @@
 //!     | network_consensus | Consensus | SignedProposal |
 //!     | network_consensus | Consensus | RawBytes       |
 //!     | network           | Chain     | Status         |
+//!     | network           | Chain     | RichStatus     |
 //!     | network           | Chain     | syncResponse   |
 //!     | network           | Jonsonrpc | RequestNet     |
 //!
@@ -45,11 +46,34 @@
 //!     | network_consensus | Net       | Consensus           | RawBytes       |
 //!
 //! ### p2p binary protocol
-//! | Start      | Full length | Key length | Key value      | Message value    |
-//! | ---------- | ----------- | ---------- | -------------- | ---------------- |
-//! | \xDEADBEEF | u32         | u8(byte)   | bytes of a str | a serialize data |
+//! | Start      | Full length | Version | Flags | Key length | Key value      | Message value    |
+//! | ---------- | ----------- | ------- | ----- | ---------- | -------------- | ---------------- |
+//! | \xDEADBEEF | u32         | u8(1)   | u8    | u8(byte)   | bytes of a str | a serialize data |
 //!
-//! full_len = 1 + key_len + body_len
+//! full_len = 3 + key_len + body_len
+//!
+//! The version is negotiated in the handshake, the highest one both nodes speak.
+//! Large messages are compressed with snappy, as told by the flags. Frames over
+//! `max_frame_size` of `network.toml` are rejected.
+//!
+//! ### Handshake and encryption
+//!
+//! Each connection starts with a Noise handshake where both nodes also prove their
+//! node key, the one in `privkey`. Only the peers listed in `network.toml` with a
+//! matching `address` are accepted. After that the frames above are encrypted, see
+//! module `secure`.
+//!
+//! ### Discovery
+//!
+//! With `discovery = true`, the nodes of the node manager contract are accepted
+//! as well, and the nodes gossip their signed addresses to find each other, see
+//! module `discovery`.
+//!
+//! ### Outbound delivery
+//!
+//! Every peer has a bounded send queue and a writer thread, consensus messages
+//! are sent first and a slow peer only loses its own messages, or is disconnected
+//! once it can't keep up with consensus, see module `outbound`.
 //!
 //! ### Key behavoir
 //!
@@ -81,22 +105,31 @@
 #![feature(try_from)]
 extern crate byteorder;
 extern crate bytes;
+extern crate cita_crypto;
+extern crate cita_types;
 extern crate clap;
 extern crate dotenv;
 extern crate futures;
+extern crate jsonrpc_types;
 #[macro_use]
 extern crate libproto;
 #[macro_use]
 extern crate logger;
 extern crate notify;
+#[cfg(not(feature = "channel"))]
 extern crate pubsub;
+#[cfg(feature = "channel")]
+extern crate pubsub_channel as pubsub;
 extern crate rand;
 extern crate rustc_serialize;
+extern crate serde_json;
+extern crate snow;
 #[cfg(test)]
 extern crate tempfile;
 extern crate tokio_io;
 extern crate tokio_proto;
 extern crate tokio_service;
+extern crate tokio_timer;
 #[macro_use]
 extern crate util;
 
@@ -106,7 +139,11 @@ extern crate serde_derive;
 pub mod citaprotocol;
 pub mod config;
 pub mod connection;
+pub mod discovery;
+pub mod metrics;
 pub mod netserver;
+pub mod outbound;
+pub mod secure;
 pub mod synchronizer;
 //pub mod sync_vec;
 pub mod network;
@@ -120,6 +157,7 @@ use netserver::NetServer;
 use network::NetWork;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use pubsub::start_pubsub;
+use secure::{Identity, SecureProto};
 use std::convert::TryFrom;
 use std::net::SocketAddr;
 use std::sync::mpsc::channel;
@@ -176,6 +214,7 @@ fn main() {
         "network",
         routing_key!([
             Chain >> Status,
+            Chain >> RichStatus,
             Chain >> SyncResponse,
             Jsonrpc >> RequestNet,
             Snapshot >> SnapshotReq,
@@ -185,15 +224,19 @@ fn main() {
     );
 
     let (net_work_tx, net_work_rx) = channel();
+    let identity = Arc::new(Identity::load(config.privkey_path()));
+    let con = Arc::new(Connection::new(&config, Arc::clone(&identity)));
+
     // start server
     // This brings up our server.
     // all server recv msg directly publish to mq
     let address_str = format!("0.0.0.0:{}", config.port.unwrap());
     let address = address_str.parse::<SocketAddr>().unwrap();
     let net_server = NetServer::new(net_work_tx.clone());
+    let proto = SecureProto::new(identity, Arc::clone(&con));
 
     //network server listener
-    thread::spawn(move || net_server.server(address));
+    thread::spawn(move || net_server.server(address, proto));
 
     //connections manage to loop
     let (tx, rx) = channel();
@@ -201,7 +244,6 @@ fn main() {
     watcher.watch(".", RecursiveMode::NonRecursive);
 
     let (sync_tx, sync_rx) = channel();
-    let con = Arc::new(Connection::new(&config));
     let net_work = NetWork::new(
         Arc::clone(&con),
         ctx_pub.clone(),
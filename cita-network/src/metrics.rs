@@ -0,0 +1,175 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Traffic with the peers and the sync status, reported by `getPeersInfo`.
+
+use cita_types::Address;
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+use util::{Mutex, RwLock};
+
+/// Counters of one routing key.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Traffic {
+    pub bytes: u64,
+    pub messages: u64,
+}
+
+/// Entry of the keys other than the ones the nodes send each other.
+pub const OTHER_KEY: &str = "other";
+
+/// The entry to count a frame in, so the peers can't grow the map with keys
+/// of their choice.
+fn traffic_key(key: &str) -> &str {
+    match RoutingKey::from(key) {
+        routing_key!(Synchronizer >> Status)
+        | routing_key!(Synchronizer >> SyncRequest)
+        | routing_key!(Synchronizer >> SyncResponse)
+        | routing_key!(Auth >> Request)
+        | routing_key!(Consensus >> SignedProposal)
+        | routing_key!(Consensus >> RawBytes)
+        | routing_key!(Net >> RawBytes) => key,
+        _ => OTHER_KEY,
+    }
+}
+
+fn record(traffic: &mut BTreeMap<String, Traffic>, key: &str, bytes: usize) {
+    let counters = traffic
+        .entry(traffic_key(key).to_owned())
+        .or_insert_with(Traffic::default);
+    counters.bytes += bytes as u64;
+    counters.messages += 1;
+}
+
+/// Frames with a peer, counted by routing key. The bytes are the ones of the
+/// frames before encryption.
+#[derive(Debug, Default, Clone)]
+pub struct PeerMetrics {
+    pub sent: BTreeMap<String, Traffic>,
+    pub received: BTreeMap<String, Traffic>,
+    /// When the peer sent its last frame, keepalives included
+    pub last_seen: Option<SystemTime>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SyncState {
+    pub is_synchronizing: bool,
+    pub current_height: u64,
+    pub global_height: u64,
+}
+
+/// Metrics of the network service, keyed by node address.
+#[derive(Default)]
+pub struct Metrics {
+    peers: Mutex<HashMap<Address, PeerMetrics>>,
+    sync: RwLock<SyncState>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    fn with_peer<F: FnOnce(&mut PeerMetrics)>(&self, address: &Address, f: F) {
+        let mut peers = self.peers.lock();
+        f(peers.entry(*address).or_insert_with(PeerMetrics::default));
+    }
+
+    pub fn record_sent(&self, address: &Address, key: &str, bytes: usize) {
+        self.with_peer(address, |peer| record(&mut peer.sent, key, bytes));
+    }
+
+    pub fn record_received(&self, address: &Address, key: &str, bytes: usize) {
+        self.with_peer(address, |peer| record(&mut peer.received, key, bytes));
+    }
+
+    pub fn record_seen(&self, address: &Address) {
+        self.with_peer(address, |peer| peer.last_seen = Some(SystemTime::now()));
+    }
+
+    pub fn peer(&self, address: &Address) -> PeerMetrics {
+        self.peers
+            .lock()
+            .get(address)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Forget the nodes which are no longer peers.
+    pub fn retain(&self, addresses: &[Address]) {
+        self.peers
+            .lock()
+            .retain(|address, _| addresses.contains(address));
+    }
+
+    pub fn set_sync(&self, state: SyncState) {
+        *self.sync.write() = state;
+    }
+
+    pub fn sync(&self) -> SyncState {
+        *self.sync.read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traffic_by_key() {
+        let metrics = Metrics::new();
+        let (peer, other) = (Address::from(1), Address::from(2));
+        let proposal: String = routing_key!(Consensus >> SignedProposal).into();
+        let tx: String = routing_key!(Auth >> Request).into();
+        let status: String = routing_key!(Synchronizer >> Status).into();
+        metrics.record_sent(&peer, &proposal, 100);
+        metrics.record_sent(&peer, &proposal, 50);
+        metrics.record_sent(&peer, &tx, 10);
+        metrics.record_received(&other, &status, 20);
+        metrics.record_seen(&other);
+
+        let sent = metrics.peer(&peer).sent;
+        assert_eq!(
+            sent[&proposal],
+            Traffic {
+                bytes: 150,
+                messages: 2,
+            }
+        );
+        assert_eq!(sent[&tx].messages, 1);
+        assert!(metrics.peer(&peer).last_seen.is_none());
+        assert!(metrics.peer(&other).last_seen.is_some());
+        assert_eq!(metrics.peer(&other).received[&status].bytes, 20);
+
+        // The other keys share one entry
+        metrics.record_received(&other, "key-1", 1);
+        metrics.record_received(&other, "key-2", 2);
+        let received = metrics.peer(&other).received;
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            received[OTHER_KEY],
+            Traffic {
+                bytes: 3,
+                messages: 2,
+            }
+        );
+
+        metrics.retain(&[peer]);
+        assert!(metrics.peer(&other).received.is_empty());
+        assert_eq!(metrics.peer(&peer).sent.len(), 2);
+    }
+}
@@ -15,9 +15,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use citaprotocol::{CitaProto, CitaRequest, CitaResponse};
+use citaprotocol::{CitaRequest, CitaResponse};
 use futures::future::result;
 use futures::{BoxFuture, Future};
+use secure::SecureProto;
 use std::io;
 use std::net::SocketAddr;
 use std::sync::mpsc::Sender;
@@ -61,8 +62,8 @@ impl NetServer {
         }
     }
 
-    pub fn server(self, addr: SocketAddr) {
-        TcpServer::new(CitaProto, addr).serve(self);
+    pub fn server(self, addr: SocketAddr, proto: SecureProto) {
+        TcpServer::new(proto, addr).serve(self);
     }
 }
 
@@ -1,13 +1,33 @@
+use cita_types::{Address, U256};
 use connection::Connection;
+use discovery::decode_records;
+use jsonrpc_types::rpctypes::{PeerInfo, PeersInfo, SyncStatus, Traffic as RpcTraffic};
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::snapshot::{Cmd, Resp, SnapshotResp};
 use libproto::{Message, Response};
+use metrics::Traffic;
+use serde_json;
+use std::collections::BTreeMap;
 use std::convert::{Into, TryFrom, TryInto};
 use std::sync::atomic::Ordering;
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 use Source;
 
+fn rpc_traffic(traffic: BTreeMap<String, Traffic>) -> BTreeMap<String, RpcTraffic> {
+    traffic
+        .into_iter()
+        .map(|(key, traffic)| {
+            let traffic = RpcTraffic {
+                bytes: U256::from(traffic.bytes),
+                messages: U256::from(traffic.messages),
+            };
+            (key, traffic)
+        })
+        .collect()
+}
+
 /// Message forwarding, include p2p and local
 pub struct NetWork {
     con: Arc<Connection>,
@@ -62,6 +82,9 @@ impl NetWork {
                     info!("set disconnect and response");
                     self.snapshot_req(&data);
                 }
+                routing_key!(Chain >> RichStatus) => {
+                    self.update_nodes(&data);
+                }
                 _ => {
                     error!("Unexpected key {} from {:?}", key, source);
                 }
@@ -88,6 +111,9 @@ impl NetWork {
                     self.tx_consensus
                         .send((routing_key!(Net >> RawBytes).into(), data));
                 }
+                routing_key!(Net >> RawBytes) => {
+                    self.receive_records(&data);
+                }
                 _ => {
                     error!("Unexpected key {} from {:?}", key, source);
                 }
@@ -95,6 +121,29 @@ impl NetWork {
         }
     }
 
+    fn update_nodes(&self, data: &[u8]) {
+        let mut msg = Message::try_from(data).unwrap();
+        if let Some(status) = msg.take_rich_status() {
+            let nodes = status
+                .get_nodes()
+                .iter()
+                .map(|node| Address::from_slice(node))
+                .collect();
+            self.con.update_nodes(nodes);
+        }
+    }
+
+    fn receive_records(&self, data: &[u8]) {
+        let records = Message::try_from(data)
+            .ok()
+            .and_then(|mut msg| msg.take_raw_bytes())
+            .and_then(|raw| decode_records(&raw));
+        match records {
+            Some(records) => self.con.receive_records(records),
+            None => warn!("receive malformed address records"),
+        }
+    }
+
     fn snapshot_req(&self, data: &[u8]) {
         let mut msg = Message::try_from(data).unwrap();
         let req = msg.take_snapshot_req().unwrap();
@@ -134,6 +183,43 @@ impl NetWork {
         }
     }
 
+    fn peers_info(&self) -> PeersInfo {
+        let peers = self
+            .con
+            .peers_pair
+            .read()
+            .iter()
+            .map(|peer| {
+                let metrics = self.con.metrics.peer(&peer.3);
+                let last_seen = metrics
+                    .last_seen
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|elapsed| {
+                        let millis = u64::from(elapsed.subsec_nanos() / 1_000_000);
+                        U256::from(elapsed.as_secs() * 1000 + millis)
+                    });
+                PeerInfo {
+                    id_card: U256::from(peer.0),
+                    address: peer.3,
+                    endpoint: peer.1.clone(),
+                    connected: peer.2.is_connected(),
+                    last_seen: last_seen,
+                    sent: rpc_traffic(metrics.sent),
+                    received: rpc_traffic(metrics.received),
+                }
+            })
+            .collect();
+        let sync = self.con.metrics.sync();
+        PeersInfo {
+            peers: peers,
+            sync: SyncStatus {
+                is_synchronizing: sync.is_synchronizing,
+                current_height: U256::from(sync.current_height),
+                global_height: U256::from(sync.global_height),
+            },
+        }
+    }
+
     pub fn reply_rpc(&self, data: &[u8]) {
         let mut msg = Message::try_from(data).unwrap();
         let req_opt = msg.take_request();
@@ -147,14 +233,20 @@ impl NetWork {
                         .peers_pair
                         .read()
                         .iter()
-                        .filter(|x| x.2.is_some())
+                        .filter(|x| x.2.is_connected())
                         .count();
                     response.set_peercount(peercount as u32);
-                    let ms: Message = response.into();
-                    self.tx_pub
-                        .send((routing_key!(Net >> Response).into(), ms.try_into().unwrap()))
-                        .unwrap();
+                } else if ts.has_peers_info() {
+                    let peers_info = self.peers_info();
+                    response.set_peers_info(serde_json::to_string(&peers_info).unwrap());
+                } else {
+                    warn!("receive unexpected rpc request");
+                    return;
                 }
+                let ms: Message = response.into();
+                self.tx_pub
+                    .send((routing_key!(Net >> Response).into(), ms.try_into().unwrap()))
+                    .unwrap();
             } else {
                 warn!("receive unexpected rpc data");
             }
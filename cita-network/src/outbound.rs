@@ -0,0 +1,432 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Outbound delivery, one queue and one writer thread per peer.
+//!
+//! `Connection::broadcast` only puts a frame into the queues of the peers and
+//! never waits for the network. The writer of a peer sends the consensus
+//! messages before the others, see [`Priority`].
+//!
+//! Each priority of a queue holds a bounded number of bytes, so a slow peer can't
+//! stall the broadcasts to the others. A low priority frame that doesn't fit is
+//! dropped for that peer only. A consensus frame is never dropped silently: if it
+//! doesn't fit, the peer is taken as broken, its stream is closed and the queue
+//! cleared, and the peer catches up by sync once connected again.
+//!
+//! A failed write closes the stream and the writer connects again at once, then
+//! with a growing delay up to `TIMEOUT` seconds. The queue is cleared while the
+//! peer can't be connected, like the messages used to be skipped for a peer
+//! without a stream.
+//!
+//! [`Priority`]: ./enum.Priority.html
+
+use bytes::BytesMut;
+use cita_types::Address;
+use citaprotocol::keepalive_message;
+use libproto::router::{RoutingKey, SubModules};
+use metrics::Metrics;
+use secure::{Identity, SecureStream};
+use std::cmp;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Interval of the keepalive and the longest delay to connect again, in seconds.
+pub const TIMEOUT: u64 = 15;
+const MAX_HIGH_BYTES: usize = 64 * 1024 * 1024;
+const MAX_LOW_BYTES: usize = 128 * 1024 * 1024;
+const MIN_RETRY_DELAY: u64 = 1000;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Priority {
+    /// Consensus messages
+    High,
+    /// Sync, transactions and the others
+    Low,
+}
+
+impl Priority {
+    pub fn of(key: &str) -> Self {
+        match RoutingKey::from(key).get_sub_module() {
+            SubModules::Consensus => Priority::High,
+            _ => Priority::Low,
+        }
+    }
+}
+
+/// An encoded message and its routing key.
+pub struct Frame {
+    pub key: String,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    frames: VecDeque<Arc<Frame>>,
+    bytes: usize,
+}
+
+impl Queue {
+    /// A frame larger than the limit is still taken by an empty queue.
+    fn push(&mut self, frame: Arc<Frame>, max_bytes: usize) -> bool {
+        if !self.frames.is_empty() && self.bytes + frame.len() > max_bytes {
+            return false;
+        }
+        self.bytes += frame.len();
+        self.frames.push_back(frame);
+        true
+    }
+
+    fn pop(&mut self) -> Option<Arc<Frame>> {
+        self.frames.pop_front().map(|frame| {
+            self.bytes -= frame.len();
+            frame
+        })
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+    }
+}
+
+#[derive(Default)]
+struct State {
+    high: Queue,
+    low: Queue,
+    connected: bool,
+    /// Version of the frames negotiated with the peer
+    version: u8,
+    closed: bool,
+    /// The consensus queue overflowed, the stream is to be closed
+    reset: bool,
+    dropped: u64,
+}
+
+impl State {
+    fn clear(&mut self) {
+        self.high.clear();
+        self.low.clear();
+    }
+}
+
+enum Next {
+    Frame(Arc<Frame>),
+    Idle,
+    Reset,
+    Closed,
+}
+
+/// Send queue of a peer.
+#[derive(Default)]
+pub struct Outbound {
+    state: Mutex<State>,
+    ready: Condvar,
+}
+
+impl Outbound {
+    pub fn new() -> Self {
+        Outbound::default()
+    }
+
+    /// Queue a frame, returns false if it's dropped since the queue is full.
+    /// A consensus frame that doesn't fit disconnects the peer.
+    pub fn push(&self, frame: Arc<Frame>, priority: Priority) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return false;
+        }
+        let pushed = match priority {
+            Priority::High => state.high.push(frame, MAX_HIGH_BYTES),
+            Priority::Low => state.low.push(frame, MAX_LOW_BYTES),
+        };
+        if pushed {
+            self.ready.notify_one();
+        } else {
+            state.dropped += 1;
+            if priority == Priority::High {
+                state.reset = true;
+                state.connected = false;
+                state.clear();
+                self.ready.notify_one();
+            }
+        }
+        pushed
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    /// Version of the frames to queue, the one of the current stream.
+    pub fn version(&self) -> u8 {
+        self.state.lock().unwrap().version
+    }
+
+    /// Number of frames dropped since the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.state.lock().unwrap().dropped
+    }
+
+    /// Bytes waiting to be sent.
+    pub fn pending(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.high.bytes + state.low.bytes
+    }
+
+    /// Stop the writer, the peer is removed.
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.clear();
+        self.ready.notify_all();
+    }
+
+    fn set_version(&self, version: u8) {
+        self.state.lock().unwrap().version = version;
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    fn set_connected(&self, connected: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.connected = connected;
+        state.reset = false;
+        if !connected {
+            state.clear();
+        }
+    }
+
+    /// The next frame to send, waits for `timeout` at most.
+    fn next(&self, timeout: Duration) -> Next {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return Next::Closed;
+            }
+            if state.reset {
+                state.reset = false;
+                return Next::Reset;
+            }
+            let frame = match state.high.pop() {
+                Some(frame) => Some(frame),
+                None => state.low.pop(),
+            };
+            if let Some(frame) = frame {
+                return Next::Frame(frame);
+            }
+            let (guard, wait) = self.ready.wait_timeout(state, timeout).unwrap();
+            state = guard;
+            if wait.timed_out() {
+                return Next::Idle;
+            }
+        }
+    }
+
+    /// Sleep for `timeout`, or till the queue is closed.
+    fn sleep(&self, timeout: Duration) {
+        let state = self.state.lock().unwrap();
+        if !state.closed {
+            let _ = self.ready.wait_timeout(state, timeout).unwrap();
+        }
+    }
+}
+
+/// Start the writer of a peer, it ends once the queue is closed.
+pub fn start_writer(
+    outbound: Arc<Outbound>,
+    addr: String,
+    address: Address,
+    identity: Arc<Identity>,
+    is_disconnect: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
+) {
+    thread::spawn(move || {
+        let mut stream: Option<SecureStream> = None;
+        let mut retry_delay = MIN_RETRY_DELAY;
+        let mut keepalive = BytesMut::with_capacity(4 + 4);
+        keepalive_message(&mut keepalive);
+
+        loop {
+            if outbound.is_closed() {
+                break;
+            }
+            if is_disconnect.load(Ordering::SeqCst) {
+                if let Some(stream) = stream.take() {
+                    let _ = stream.shutdown();
+                }
+                outbound.set_connected(false);
+                outbound.sleep(Duration::from_secs(TIMEOUT));
+                continue;
+            }
+
+            if stream.is_none() {
+                info!("connect {:?}!", addr);
+                match SecureStream::connect(&addr, &identity, &address) {
+                    Ok(connected) => {
+                        outbound.set_version(connected.version());
+                        stream = Some(connected);
+                        retry_delay = MIN_RETRY_DELAY;
+                        outbound.set_connected(true);
+                    }
+                    Err(err) => {
+                        warn!("failed to connect {}: {}", addr, err);
+                        outbound.set_connected(false);
+                        outbound.sleep(Duration::from_millis(retry_delay));
+                        retry_delay = cmp::min(retry_delay * 2, TIMEOUT * 1000);
+                        continue;
+                    }
+                }
+            }
+
+            let res = match outbound.next(Duration::from_secs(TIMEOUT)) {
+                Next::Frame(frame) => stream.as_mut().unwrap().send(&frame.data).map(|_| {
+                    metrics.record_sent(&address, &frame.key, frame.len());
+                }),
+                Next::Idle => stream.as_mut().unwrap().send(&keepalive),
+                Next::Reset => Err(io::Error::new(io::ErrorKind::Other, "consensus queue is full")),
+                Next::Closed => continue,
+            };
+            if let Err(err) = res {
+                warn!("failed to send to {}: {}, connect again", addr, err);
+                if let Some(stream) = stream.take() {
+                    let _ = stream.shutdown();
+                }
+                outbound.set_connected(false);
+            }
+        }
+
+        if let Some(stream) = stream {
+            let _ = stream.shutdown();
+        }
+        info!("stop writer of {:?}", addr);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libproto::router::MsgType;
+
+    fn frame(len: usize) -> Arc<Frame> {
+        Arc::new(Frame {
+            key: String::new(),
+            data: vec![0u8; len],
+        })
+    }
+
+    fn next_len(outbound: &Outbound) -> Option<usize> {
+        match outbound.next(Duration::from_millis(10)) {
+            Next::Frame(frame) => Some(frame.len()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn priority_of_key() {
+        let consensus: String = routing_key!(Consensus >> SignedProposal).into();
+        let tx: String = routing_key!(Auth >> Request).into();
+        let sync: String = routing_key!(Synchronizer >> SyncResponse).into();
+        assert_eq!(Priority::of(&consensus), Priority::High);
+        assert_eq!(Priority::of(&tx), Priority::Low);
+        assert_eq!(Priority::of(&sync), Priority::Low);
+    }
+
+    #[test]
+    fn consensus_goes_first() {
+        let outbound = Outbound::new();
+        assert!(outbound.push(frame(1), Priority::Low));
+        assert!(outbound.push(frame(2), Priority::High));
+        assert!(outbound.push(frame(3), Priority::Low));
+        assert!(outbound.push(frame(4), Priority::High));
+        assert_eq!(outbound.pending(), 10);
+        assert_eq!(next_len(&outbound), Some(2));
+        assert_eq!(next_len(&outbound), Some(4));
+        assert_eq!(next_len(&outbound), Some(1));
+        assert_eq!(next_len(&outbound), Some(3));
+        assert_eq!(next_len(&outbound), None);
+        assert_eq!(outbound.pending(), 0);
+    }
+
+    #[test]
+    fn full_queue_drops_frames() {
+        let outbound = Outbound::new();
+        // One large frame still fits into an empty queue
+        assert!(outbound.push(frame(MAX_LOW_BYTES + 1), Priority::Low));
+        assert!(!outbound.push(frame(1), Priority::Low));
+        // The other priority has its own bound
+        assert!(outbound.push(frame(MAX_HIGH_BYTES), Priority::High));
+        assert_eq!(outbound.dropped(), 1);
+
+        assert_eq!(next_len(&outbound), Some(MAX_HIGH_BYTES));
+        assert_eq!(next_len(&outbound), Some(MAX_LOW_BYTES + 1));
+        assert!(outbound.push(frame(1), Priority::Low));
+    }
+
+    #[test]
+    fn full_consensus_queue_disconnects() {
+        let outbound = Outbound::new();
+        outbound.set_connected(true);
+        assert!(outbound.push(frame(MAX_HIGH_BYTES), Priority::High));
+        assert!(outbound.push(frame(1), Priority::Low));
+        assert!(!outbound.push(frame(1), Priority::High));
+        assert_eq!(outbound.dropped(), 1);
+
+        // The writer closes the stream and nothing is sent on it any more
+        assert!(!outbound.is_connected());
+        assert_eq!(outbound.pending(), 0);
+        match outbound.next(Duration::from_millis(10)) {
+            Next::Reset => {}
+            _ => panic!("stream should be reset"),
+        }
+        assert_eq!(next_len(&outbound), None);
+    }
+
+    #[test]
+    fn closed_queue() {
+        let outbound = Outbound::new();
+        assert!(outbound.push(frame(1), Priority::Low));
+        outbound.close();
+        assert!(!outbound.push(frame(1), Priority::Low));
+        assert_eq!(outbound.pending(), 0);
+        match outbound.next(Duration::from_millis(10)) {
+            Next::Closed => {}
+            _ => panic!("queue should be closed"),
+        }
+    }
+
+    #[test]
+    fn disconnected_queue_is_cleared() {
+        let outbound = Outbound::new();
+        outbound.set_connected(true);
+        assert!(outbound.is_connected());
+        assert!(outbound.push(frame(1), Priority::High));
+        outbound.set_connected(false);
+        assert!(!outbound.is_connected());
+        assert_eq!(outbound.pending(), 0);
+    }
+}
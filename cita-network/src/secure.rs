@@ -0,0 +1,705 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authenticated and encrypted transport between the nodes.
+//!
+//! Every connection starts with a Noise handshake, `Noise_XX_25519_ChaChaPoly_SHA256`.
+//! The static Noise key of a node is generated at startup, so each side also
+//! sends, as the payload of its static key message, a signature over that key
+//! made with its node key, the one in `privkey`. The node address is recovered
+//! from it and must belong to an accepted node, or the connection is closed.
+//!
+//! | Min version | Max version | Signature |
+//! | ----------- | ----------- | --------- |
+//! | u8          | u8          | bytes     |
+//!
+//! The versions are the ones of the `CitaCodec` frame the node speaks, the
+//! connection uses the highest one both sides speak, or is closed if there's none.
+//!
+//! After that the frames of `CitaCodec` are sent as Noise transport messages of
+//! at most 65535 bytes, each prefixed with its length:
+//!
+//! | Length of Ciphertext | Ciphertext | Tag      |
+//! | -------------------- | ---------- | -------- |
+//! | u16                  | bytes      | 16 bytes |
+//!
+//! A frame of `CitaCodec` may take several messages, its size is limited by
+//! `max_frame_size` only.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use bytes::BytesMut;
+use cita_crypto::{CreateKey, KeyPair, PrivKey, Sign, Signature, SIGNATURE_BYTES_LEN};
+use cita_types::traits::LowerHex;
+use cita_types::{clean_0x, Address, H256};
+use citaprotocol::{
+    negotiate_version, network_message_to_pubsub_message, pubsub_message_to_network_message,
+    CitaRequest, CitaResponse, MAX_VERSION, MIN_VERSION,
+};
+use connection::Connection;
+use futures::{future, Future};
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use libproto::Message;
+use snow::{Builder, Keypair, Session, SnowError};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_io::codec::{Decoder, Encoder, Framed};
+use tokio_io::io::{read_exact, write_all};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_proto::pipeline::ServerProto;
+use tokio_timer::Timer;
+use util::Hashable;
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+/// Largest message of Noise.
+const MAX_MESSAGE_LEN: usize = 65_535;
+const TAG_LEN: usize = 16;
+const MAX_CHUNK_LEN: usize = MAX_MESSAGE_LEN - TAG_LEN;
+/// Both sides of a connection give up a handshake not done by then.
+const HANDSHAKE_TIMEOUT: u64 = 15;
+/// A peer not reading for this long is taken as broken and connected again.
+const WRITE_TIMEOUT: u64 = 30;
+
+/// Prefix of the message signed with the node key to bind the static Noise key to it.
+const STATIC_KEY_PREFIX: &[u8] = b"cita-p2p static key";
+
+fn invalid_data<E: Into<String>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.into())
+}
+
+fn noise_error(err: SnowError) -> io::Error {
+    invalid_data(format!("noise: {:?}", err))
+}
+
+fn noise_builder<'a>() -> Builder<'a> {
+    Builder::new(NOISE_PARAMS.parse().expect("invalid noise params"))
+}
+
+/// Hash signed with the node key to prove it owns the static Noise key.
+fn static_key_hash(static_key: &[u8]) -> H256 {
+    let mut message = STATIC_KEY_PREFIX.to_vec();
+    message.extend_from_slice(static_key);
+    message.crypt_hash()
+}
+
+/// Node key proven to the peers, with the static Noise key of this run.
+pub struct Identity {
+    privkey: PrivKey,
+    address: Address,
+    static_key: Keypair,
+}
+
+impl Identity {
+    pub fn new(privkey: PrivKey) -> Self {
+        let keypair = KeyPair::from_privkey(privkey).expect("invalid node key");
+        let static_key = noise_builder()
+            .generate_keypair()
+            .expect("failed to generate noise key");
+        Identity {
+            privkey: privkey,
+            address: *keypair.address(),
+            static_key: static_key,
+        }
+    }
+
+    /// Load the node key from a file with its hex, the same one consensus uses.
+    pub fn load(path: &str) -> Self {
+        let mut content = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut content))
+            .unwrap_or_else(|err| panic!("failed to read node key {}: {}", path, err));
+        let privkey = PrivKey::from_str(clean_0x(content.trim()))
+            .unwrap_or_else(|_| panic!("invalid node key in {}", path));
+        Identity::new(privkey)
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn sign(&self, hash: &H256) -> io::Result<Signature> {
+        Signature::sign(&self.privkey, &(*hash).into())
+            .map_err(|err| invalid_data(format!("failed to sign: {:?}", err)))
+    }
+
+    /// Signature of the static Noise key, the payload of the handshake.
+    pub fn auth(&self) -> io::Result<Vec<u8>> {
+        let signature = self.sign(&static_key_hash(&self.static_key.public))?;
+        Ok(signature[..].to_vec())
+    }
+
+    /// Payload of the static key message: the versions and the signature.
+    pub fn payload(&self) -> io::Result<Vec<u8>> {
+        let mut payload = vec![MIN_VERSION, MAX_VERSION];
+        payload.extend_from_slice(&self.auth()?);
+        Ok(payload)
+    }
+}
+
+/// One side of a handshake.
+pub struct Handshake {
+    state: Session,
+}
+
+impl Handshake {
+    pub fn new(identity: &Identity, initiator: bool) -> io::Result<Self> {
+        let builder = noise_builder().local_private_key(&identity.static_key.private);
+        let state = if initiator {
+            builder.build_initiator()
+        } else {
+            builder.build_responder()
+        };
+        Ok(Handshake {
+            state: state.map_err(noise_error)?,
+        })
+    }
+
+    /// The next handshake message with `payload`, prefixed with its length.
+    pub fn write(&mut self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let mut message = vec![0u8; 2 + MAX_MESSAGE_LEN];
+        let length = self
+            .state
+            .write_message(payload, &mut message[2..])
+            .map_err(noise_error)?;
+        NetworkEndian::write_u16(&mut message[..2], length as u16);
+        message.truncate(2 + length);
+        Ok(message)
+    }
+
+    /// Read a handshake message without its length, returns its payload.
+    pub fn read(&mut self, message: &[u8]) -> io::Result<Vec<u8>> {
+        let mut payload = vec![0u8; MAX_MESSAGE_LEN];
+        let length = self
+            .state
+            .read_message(message, &mut payload)
+            .map_err(noise_error)?;
+        payload.truncate(length);
+        Ok(payload)
+    }
+
+    /// Recover the address of the remote node key from its signature of the
+    /// remote static key, which the handshake has proven.
+    pub fn verify(&self, signature: &[u8]) -> io::Result<Address> {
+        if signature.len() != SIGNATURE_BYTES_LEN {
+            return Err(invalid_data("invalid handshake signature length"));
+        }
+        let static_key = self
+            .state
+            .get_remote_static()
+            .ok_or_else(|| invalid_data("no remote static key"))?;
+        let pubkey = Signature::from(signature)
+            .recover(&static_key_hash(static_key).into())
+            .map_err(|_| invalid_data("invalid handshake signature"))?;
+        Ok(Address::from(pubkey.crypt_hash()))
+    }
+
+    /// Check the payload of the remote static key message, returns the node
+    /// address and the version of the frames.
+    pub fn check(&self, payload: &[u8]) -> io::Result<(Address, u8)> {
+        if payload.len() < 2 {
+            return Err(invalid_data("invalid handshake payload"));
+        }
+        let version = negotiate_version(payload[0], payload[1]).ok_or_else(|| {
+            invalid_data(format!(
+                "no common version, the peer speaks {} to {}",
+                payload[0], payload[1]
+            ))
+        })?;
+        Ok((self.verify(&payload[2..])?, version))
+    }
+
+    pub fn finish(self) -> io::Result<Transport> {
+        let state = self.state.into_transport_mode().map_err(noise_error)?;
+        Ok(Transport { state: state })
+    }
+}
+
+/// Seals and opens the messages of a connection after the handshake.
+pub struct Transport {
+    state: Session,
+}
+
+impl Transport {
+    /// Seal `plain` into as many messages as it takes.
+    pub fn seal(&mut self, plain: &[u8]) -> io::Result<Vec<u8>> {
+        let mut sealed = Vec::with_capacity(plain.len() + plain.len() / MAX_CHUNK_LEN * 32 + 32);
+        let mut message = vec![0u8; MAX_MESSAGE_LEN];
+        for chunk in plain.chunks(MAX_CHUNK_LEN) {
+            let length = self
+                .state
+                .write_message(chunk, &mut message)
+                .map_err(noise_error)?;
+            let mut prefix = [0u8; 2];
+            NetworkEndian::write_u16(&mut prefix, length as u16);
+            sealed.extend_from_slice(&prefix);
+            sealed.extend_from_slice(&message[..length]);
+        }
+        Ok(sealed)
+    }
+
+    /// Open the next message in `buf`, `None` if it isn't complete yet.
+    pub fn open(&mut self, buf: &mut BytesMut) -> io::Result<Option<Vec<u8>>> {
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+        let length = NetworkEndian::read_u16(&buf[..2]) as usize;
+        if buf.len() < 2 + length {
+            return Ok(None);
+        }
+        let message = buf.split_to(2 + length);
+        let mut plain = vec![0u8; length];
+        let length = self
+            .state
+            .read_message(&message[2..], &mut plain)
+            .map_err(|_| invalid_data("failed to open frame"))?;
+        plain.truncate(length);
+        Ok(Some(plain))
+    }
+}
+
+/// Read a handshake message from a blocking stream.
+fn read_message<R: Read>(stream: &mut R) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length)?;
+    let mut message = vec![0u8; NetworkEndian::read_u16(&length) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(message)
+}
+
+/// Read a handshake message from an incoming connection.
+fn read_message_async<T: AsyncRead>(
+    io: T,
+) -> impl Future<Item = (T, Vec<u8>), Error = io::Error> {
+    read_exact(io, [0u8; 2]).and_then(|(io, length)| {
+        read_exact(io, vec![0u8; NetworkEndian::read_u16(&length) as usize])
+    })
+}
+
+/// Outgoing connection to a peer.
+pub struct SecureStream {
+    stream: TcpStream,
+    version: u8,
+    transport: Transport,
+}
+
+impl SecureStream {
+    /// Connect to `addr` and make sure it's the node with address `expected`.
+    pub fn connect(addr: &str, identity: &Identity, expected: &Address) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(Duration::from_secs(HANDSHAKE_TIMEOUT)))?;
+        stream.set_write_timeout(Some(Duration::from_secs(HANDSHAKE_TIMEOUT)))?;
+
+        let mut handshake = Handshake::new(identity, true)?;
+        stream.write_all(&handshake.write(&[])?)?;
+        let payload = handshake.read(&read_message(&mut stream)?)?;
+        let (remote, version) = handshake.check(&payload)?;
+        if remote != *expected {
+            return Err(invalid_data(format!(
+                "{} is node 0x{}, expected 0x{}",
+                addr,
+                remote.lower_hex(),
+                expected.lower_hex()
+            )));
+        }
+        stream.write_all(&handshake.write(&identity.payload()?)?)?;
+        let transport = handshake.finish()?;
+
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(Some(Duration::from_secs(WRITE_TIMEOUT)))?;
+        Ok(SecureStream {
+            stream: stream,
+            version: version,
+            transport: transport,
+        })
+    }
+
+    /// Version of the frames to send.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Seal and send a frame of `CitaCodec`.
+    pub fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        let sealed = self.transport.seal(frame)?;
+        self.stream.write_all(&sealed)
+    }
+
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Both)
+    }
+}
+
+/// Codec of an incoming connection, opens the frames and checks the origin
+/// of the messages against the authenticated peer.
+pub struct SecureCodec {
+    remote: Address,
+    con: Arc<Connection>,
+    version: u8,
+    transport: Transport,
+    plain: BytesMut,
+}
+
+impl SecureCodec {
+    fn new(remote: Address, con: Arc<Connection>, version: u8, transport: Transport) -> Self {
+        SecureCodec {
+            remote: remote,
+            con: con,
+            version: version,
+            transport: transport,
+            plain: BytesMut::new(),
+        }
+    }
+
+    /// The id card of a discovered node is known from its record only, till
+    /// then it may send its records and nothing else.
+    fn is_from_peer(&self, key: &str, body: &[u8]) -> bool {
+        match self.con.trusted_peer(&self.remote) {
+            Some(id_card) => match Message::try_from(body) {
+                Ok(msg) => msg.get_origin() == id_card,
+                Err(_) => false,
+            },
+            None => match RoutingKey::from(key) {
+                routing_key!(Net >> RawBytes) => self.con.is_allowed(&self.remote),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Decoder for SecureCodec {
+    type Item = CitaRequest;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
+        loop {
+            let max_frame_size = self.con.max_frame_size;
+            let plain_len = self.plain.len();
+            let frame =
+                network_message_to_pubsub_message(&mut self.plain, self.version, max_frame_size)?;
+            if let Some((key, body)) = frame {
+                if self.is_from_peer(&key, &body) {
+                    let bytes = plain_len - self.plain.len();
+                    self.con.metrics.record_received(&self.remote, &key, bytes);
+                    return Ok(Some((key, body)));
+                }
+                warn!(
+                    "drop message {} not originated from node 0x{}",
+                    key,
+                    self.remote.lower_hex()
+                );
+                continue;
+            }
+            match self.transport.open(buf)? {
+                Some(plain) => {
+                    self.con.metrics.record_seen(&self.remote);
+                    self.plain.extend_from_slice(&plain);
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Encoder for SecureCodec {
+    type Item = CitaResponse;
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: Self::Item, buf: &mut BytesMut) -> io::Result<()> {
+        // Nothing is sent back for the empty responses of the pipeline
+        if msg.is_some() {
+            let mut plain = BytesMut::new();
+            pubsub_message_to_network_message(
+                &mut plain,
+                msg,
+                self.version,
+                self.con.max_frame_size,
+            )?;
+            buf.extend_from_slice(&self.transport.seal(&plain)?);
+        }
+        Ok(())
+    }
+}
+
+/// Protocol of the server, accepts the configured peers and the discovered nodes only.
+pub struct SecureProto {
+    identity: Arc<Identity>,
+    con: Arc<Connection>,
+    timer: Timer,
+}
+
+impl SecureProto {
+    pub fn new(identity: Arc<Identity>, con: Arc<Connection>) -> Self {
+        SecureProto {
+            identity: identity,
+            con: con,
+            timer: Timer::default(),
+        }
+    }
+}
+
+/// Check the peer of an incoming connection from its last handshake message.
+fn accept(
+    mut handshake: Handshake,
+    con: &Arc<Connection>,
+    message: &[u8],
+) -> io::Result<SecureCodec> {
+    let payload = handshake.read(message)?;
+    let (remote, version) = handshake.check(&payload)?;
+    if !con.is_allowed(&remote) {
+        return Err(invalid_data(format!("reject unknown node 0x{}", remote.lower_hex())));
+    }
+    info!("accept node 0x{}", remote.lower_hex());
+    let transport = handshake.finish()?;
+    Ok(SecureCodec::new(remote, Arc::clone(con), version, transport))
+}
+
+impl<T: AsyncRead + AsyncWrite + 'static> ServerProto<T> for SecureProto {
+    type Request = CitaRequest;
+    type Response = CitaResponse;
+
+    type Transport = Framed<T, SecureCodec>;
+    type BindTransport = Box<Future<Item = Self::Transport, Error = io::Error>>;
+
+    fn bind_transport(&self, io: T) -> Self::BindTransport {
+        let mut handshake = match Handshake::new(&self.identity, false) {
+            Ok(handshake) => handshake,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let identity = Arc::clone(&self.identity);
+        let con = Arc::clone(&self.con);
+
+        let transport = read_message_async(io)
+            .and_then(move |(io, message)| {
+                let reply = handshake
+                    .read(&message)
+                    .and_then(|_| identity.payload())
+                    .and_then(|payload| handshake.write(&payload));
+                future::result(reply)
+                    .and_then(|reply| write_all(io, reply))
+                    .map(move |(io, _)| (io, handshake))
+            })
+            .and_then(|(io, handshake)| {
+                read_message_async(io).map(move |(io, message)| (io, handshake, message))
+            })
+            .and_then(move |(io, handshake, message)| {
+                accept(handshake, &con, &message).map(move |codec| io.framed(codec))
+            });
+        let transport = self
+            .timer
+            .timeout(transport, Duration::from_secs(HANDSHAKE_TIMEOUT))
+            .map_err(|err| {
+                warn!("handshake failed: {}", err);
+                err
+            });
+        Box::new(transport)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use citaprotocol::DEFAULT_MAX_FRAME_SIZE;
+    use config::{NetConfig, PeerConfig};
+    use libproto::Response;
+    use std::convert::TryInto;
+
+    /// Connection of node 0 with `peer` configured as node 1.
+    fn connection(peer: &Identity, discovery: bool) -> Arc<Connection> {
+        let config = NetConfig {
+            id_card: Some(0),
+            port: Some(40000),
+            privkey_path: None,
+            discovery: Some(discovery),
+            external_ip: None,
+            max_frame_size: None,
+            peers: Some(vec![PeerConfig {
+                id_card: Some(1),
+                ip: Some("127.0.0.1".to_owned()),
+                port: Some(40001),
+                address: Some(peer.address().lower_hex()),
+            }]),
+        };
+        Arc::new(Connection::new(&config, Arc::new(identity())))
+    }
+
+    fn identity() -> Identity {
+        Identity::new(*KeyPair::gen_keypair().privkey())
+    }
+
+    fn seal_message(send: &mut Transport, key: &str, origin: u32) -> Vec<u8> {
+        let mut msg: Message = Response::new().into();
+        msg.set_origin(origin);
+        let body: Vec<u8> = msg.try_into().unwrap();
+        let mut plain = BytesMut::new();
+        pubsub_message_to_network_message(
+            &mut plain,
+            Some((key.to_owned(), body)),
+            MAX_VERSION,
+            DEFAULT_MAX_FRAME_SIZE,
+        ).unwrap();
+        send.seal(&plain).unwrap()
+    }
+
+    /// Run the handshake of `initiator` with `responder`, the initiator sends
+    /// `payload` in its static key message, returned with the one it received.
+    fn handshake_with(
+        initiator: &Identity,
+        responder: &Identity,
+        payload: &[u8],
+    ) -> (Handshake, Handshake, Vec<u8>, Vec<u8>) {
+        let mut left = Handshake::new(initiator, true).unwrap();
+        let mut right = Handshake::new(responder, false).unwrap();
+
+        right.read(&left.write(&[]).unwrap()[2..]).unwrap();
+        let reply = right.write(&responder.payload().unwrap()).unwrap();
+        let left_payload = left.read(&reply[2..]).unwrap();
+        let right_payload = right.read(&left.write(payload).unwrap()[2..]).unwrap();
+        (left, right, left_payload, right_payload)
+    }
+
+    fn handshake(initiator: &Identity, responder: &Identity) -> (Transport, Transport) {
+        let payload = initiator.payload().unwrap();
+        let (left, right, _, _) = handshake_with(initiator, responder, &payload);
+        (left.finish().unwrap(), right.finish().unwrap())
+    }
+
+    #[test]
+    fn handshake_authenticates_both_sides() {
+        let initiator = identity();
+        let responder = identity();
+        let payload = initiator.payload().unwrap();
+        let (left, right, left_payload, right_payload) =
+            handshake_with(&initiator, &responder, &payload);
+        assert_eq!(right_payload, payload);
+        assert_eq!(
+            left.check(&left_payload).unwrap(),
+            (*responder.address(), MAX_VERSION)
+        );
+        assert_eq!(
+            right.check(&right_payload).unwrap(),
+            (*initiator.address(), MAX_VERSION)
+        );
+
+        let (mut send, mut recv) = (left.finish().unwrap(), right.finish().unwrap());
+        let mut buf = BytesMut::new();
+        for plain in &[b"first".to_vec(), b"second".to_vec()] {
+            buf.extend_from_slice(&send.seal(plain).unwrap());
+        }
+        assert_eq!(recv.open(&mut buf).unwrap(), Some(b"first".to_vec()));
+        assert_eq!(recv.open(&mut buf).unwrap(), Some(b"second".to_vec()));
+        assert_eq!(recv.open(&mut buf).unwrap(), None);
+
+        // And the other way round
+        let mut buf = BytesMut::from(recv.seal(b"reply").unwrap());
+        assert_eq!(send.open(&mut buf).unwrap(), Some(b"reply".to_vec()));
+    }
+
+    #[test]
+    fn relayed_signature_is_rejected() {
+        let node = identity();
+        let attacker = identity();
+        let responder = identity();
+        // The signature of another node is bound to its own static key
+        let payload = node.payload().unwrap();
+        let (_, right, _, right_payload) = handshake_with(&attacker, &responder, &payload);
+        let seen = right.check(&right_payload);
+        assert!(seen.map_or(true, |(seen, _)| seen != *node.address()));
+    }
+
+    #[test]
+    fn version_is_negotiated() {
+        let (initiator, responder) = (identity(), identity());
+        let mut payload = initiator.payload().unwrap();
+
+        // A newer node speaking this version too
+        payload[1] = MAX_VERSION + 1;
+        let (_, right, _, right_payload) = handshake_with(&initiator, &responder, &payload);
+        assert_eq!(right.check(&right_payload).unwrap().1, MAX_VERSION);
+
+        // A node speaking the newer versions only
+        payload[0] = MAX_VERSION + 1;
+        let (_, right, _, right_payload) = handshake_with(&initiator, &responder, &payload);
+        assert!(right.check(&right_payload).is_err());
+        assert!(right.check(&[]).is_err());
+    }
+
+    #[test]
+    fn large_frame_is_chunked() {
+        let (mut send, mut recv) = handshake(&identity(), &identity());
+        let plain: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+        let mut buf = BytesMut::from(send.seal(&plain).unwrap());
+
+        let mut opened = Vec::new();
+        while let Some(chunk) = recv.open(&mut buf).unwrap() {
+            assert!(chunk.len() <= MAX_CHUNK_LEN);
+            opened.extend_from_slice(&chunk);
+        }
+        assert_eq!(opened, plain);
+    }
+
+    #[test]
+    fn tampered_frame_is_rejected() {
+        let (mut send, mut recv) = handshake(&identity(), &identity());
+
+        let mut frame = send.seal(b"message").unwrap();
+        frame[4] ^= 1;
+        let mut buf = BytesMut::from(frame);
+        assert!(recv.open(&mut buf).is_err());
+    }
+
+    #[test]
+    fn codec_checks_origin() {
+        let initiator = identity();
+        let (mut send, recv) = handshake(&initiator, &identity());
+        let con = connection(&initiator, false);
+        let mut codec = SecureCodec::new(*initiator.address(), con, MAX_VERSION, recv);
+
+        let mut buf = BytesMut::new();
+        for origin in &[2, 1] {
+            buf.extend_from_slice(&seal_message(&mut send, "key", *origin));
+        }
+        let (key, body) = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(key, "key");
+        assert_eq!(Message::try_from(&body).unwrap().get_origin(), 1);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn codec_limits_nodes_without_record() {
+        let initiator = identity();
+        let node = identity();
+        let (mut send, recv) = handshake(&node, &identity());
+        let con = connection(&initiator, true);
+        con.update_nodes(vec![*node.address()]);
+        assert!(con.is_allowed(node.address()));
+        assert_eq!(con.trusted_peer(node.address()), None);
+        let mut codec = SecureCodec::new(*node.address(), con, MAX_VERSION, recv);
+
+        // Only the records pass till the id card of the node is known
+        let records: String = routing_key!(Net >> RawBytes).into();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&seal_message(&mut send, "key", 5));
+        buf.extend_from_slice(&seal_message(&mut send, &records, 5));
+        let (key, _) = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(key, records);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+}
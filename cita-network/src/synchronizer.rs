@@ -2,6 +2,7 @@ use connection::Connection;
 use libproto::blockchain::{Block, Status};
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::{Message, OperateType, SyncRequest, SyncResponse};
+use metrics::SyncState;
 use rand::{thread_rng, Rng, ThreadRng};
 use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::convert::{Into, TryFrom, TryInto};
@@ -220,6 +221,16 @@ impl Synchronizer {
                 error!("receive: unexpected data key = {:?}", key);
             }
         }
+        self.report_status();
+    }
+
+    /// Publish the sync status for `getPeersInfo`.
+    fn report_status(&self) {
+        self.con.metrics.set_sync(SyncState {
+            is_synchronizing: self.is_synchronizing,
+            current_height: self.current_status.get_height(),
+            global_height: self.global_status.get_height(),
+        });
     }
 
     // Initiate a sync request
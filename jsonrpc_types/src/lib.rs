@@ -29,6 +29,7 @@ extern crate libproto;
 #[macro_use]
 extern crate logger;
 extern crate proof;
+extern crate rlp;
 extern crate rustc_serialize;
 extern crate serde;
 #[macro_use]
@@ -38,6 +39,7 @@ extern crate serde_json;
 #[cfg(test)]
 #[macro_use]
 extern crate serde_json;
+extern crate util;
 extern crate uuid;
 
 extern crate jsonrpc_types_internals;
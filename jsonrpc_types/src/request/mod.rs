@@ -22,11 +22,17 @@ mod rpcrequest;
 mod tests;
 
 pub use self::request::{
-    BlockNumberParams, CallParams, GetAbiParams, GetBalanceParams, GetBlockByHashParams,
-    GetBlockByNumberParams, GetCodeParams, GetFilterChangesParams, GetFilterLogsParams,
-    GetLogsParams, GetMetaDataParams, GetTransactionCountParams, GetTransactionParams,
-    GetTransactionProofParams, GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams,
-    PeerCountParams, SendRawTransactionParams, SendTransactionParams, UninstallFilterParams,
+    BlockNumberParams, CallParams, EstimateQuotaParams, GetAbiParams, GetBalanceParams,
+    GetBlockByHashParams, GetBlockByNumberParams, GetBlockHeaderParams, GetBlockHeadersParams,
+    GetCodeParams, GetFilterChangesParams, GetFilterLogsParams, GetLogsParams, GetMetaDataParams,
+    GetPeersInfoParams, GetPoolStatusParams, GetPoolTransactionParams, GetPoolTransactionsParams,
+    GetReceiptProofParams, GetStateProofParams, GetStorageAtParams, GetTransactionCountParams,
+    GetTransactionInclusionProofParams, GetTransactionParams, GetTransactionProofParams,
+    GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams, PeerCountParams,
+    SendRawTransactionParams, SendTransactionParams, TraceBlockParams, TraceCallParams,
+    TraceFilterParams, TraceTransactionParams, UninstallFilterParams,
+};
+pub use self::request::{
+    Call, PartialCall, PartialRequest, Request, RequestInfo, SubscriptionCall, SubscriptionRequest,
 };
-pub use self::request::{Call, PartialCall, PartialRequest, Request, RequestInfo};
 pub use self::rpcrequest::RpcRequest;
@@ -28,14 +28,20 @@ use libproto::request::{Call as ProtoCall, Request as ProtoRequest};
 use libproto::UnverifiedTransaction;
 
 use super::request::{
-    BlockNumberParams, CallParams, GetAbiParams, GetBalanceParams, GetBlockByHashParams,
-    GetBlockByNumberParams, GetCodeParams, GetFilterChangesParams, GetFilterLogsParams,
-    GetLogsParams, GetMetaDataParams, GetTransactionCountParams, GetTransactionParams,
-    GetTransactionProofParams, GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams,
-    PeerCountParams, SendRawTransactionParams, SendTransactionParams, UninstallFilterParams,
+    BlockNumberParams, CallParams, EstimateQuotaParams, GetAbiParams, GetBalanceParams,
+    GetBlockByHashParams, GetBlockByNumberParams, GetBlockHeaderParams, GetBlockHeadersParams,
+    GetCodeParams, GetFilterChangesParams, GetFilterLogsParams, GetLogsParams, GetMetaDataParams,
+    GetPeersInfoParams, GetPoolStatusParams, GetPoolTransactionParams, GetPoolTransactionsParams,
+    GetReceiptProofParams, GetStateProofParams, GetStorageAtParams, GetTransactionCountParams,
+    GetTransactionInclusionProofParams, GetTransactionParams, GetTransactionProofParams,
+    GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams, PeerCountParams,
+    SendRawTransactionParams, SendTransactionParams, TraceBlockParams, TraceCallParams,
+    TraceFilterParams, TraceTransactionParams, UninstallFilterParams,
 };
 use error::Error;
-use rpctypes::{BlockParamsByHash, BlockParamsByNumber, CountOrCode};
+use rpctypes::{
+    BlockParamsByHash, BlockParamsByNumber, BlockRange, CountOrCode, StorageKey, StorageKeys,
+};
 
 fn create_request() -> ProtoRequest {
     let request_id = Uuid::new_v4().as_bytes().to_vec();
@@ -62,6 +68,15 @@ impl TryInto<ProtoRequest> for PeerCountParams {
     }
 }
 
+impl TryInto<ProtoRequest> for GetPeersInfoParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_peers_info(true);
+        Ok(request)
+    }
+}
+
 impl TryInto<ProtoRequest> for SendRawTransactionParams {
     type Error = Error;
     fn try_into(self) -> Result<ProtoRequest, Self::Error> {
@@ -154,6 +169,32 @@ impl TryInto<ProtoRequest> for GetBlockByNumberParams {
     }
 }
 
+impl TryInto<ProtoRequest> for GetBlockHeaderParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        serde_json::to_string(&self.0)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|number| {
+                request.set_block_header(number);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for GetBlockHeadersParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        serde_json::to_string(&BlockRange::new(self.0, self.1))
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|range| {
+                request.set_block_headers(range);
+                request
+            })
+    }
+}
+
 impl TryInto<ProtoRequest> for GetTransactionReceiptParams {
     type Error = Error;
     fn try_into(self) -> Result<ProtoRequest, Self::Error> {
@@ -251,6 +292,33 @@ impl TryInto<ProtoRequest> for GetBalanceParams {
     }
 }
 
+impl TryInto<ProtoRequest> for GetStorageAtParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        serde_json::to_string(&StorageKey::new(self.0.into(), self.1.into(), self.2))
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|jsonstr| {
+                request.set_storage_at(jsonstr);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for GetStateProofParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        let keys = self.1.into_iter().map(Into::into).collect();
+        serde_json::to_string(&StorageKeys::new(self.0.into(), keys, self.2))
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|jsonstr| {
+                request.set_state_proof(jsonstr);
+                request
+            })
+    }
+}
+
 impl TryInto<ProtoRequest> for NewFilterParams {
     type Error = Error;
     fn try_into(self) -> Result<ProtoRequest, Self::Error> {
@@ -307,6 +375,24 @@ impl TryInto<ProtoRequest> for GetTransactionProofParams {
     }
 }
 
+impl TryInto<ProtoRequest> for GetReceiptProofParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_receipt_proof(self.0.into());
+        Ok(request)
+    }
+}
+
+impl TryInto<ProtoRequest> for GetTransactionInclusionProofParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_transaction_inclusion_proof(self.0.into());
+        Ok(request)
+    }
+}
+
 impl TryInto<ProtoRequest> for GetMetaDataParams {
     type Error = Error;
     fn try_into(self) -> Result<ProtoRequest, Self::Error> {
@@ -319,3 +405,101 @@ impl TryInto<ProtoRequest> for GetMetaDataParams {
             })
     }
 }
+
+impl TryInto<ProtoRequest> for TraceTransactionParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_trace_transaction(self.0.into());
+        Ok(request)
+    }
+}
+
+impl TryInto<ProtoRequest> for TraceBlockParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        serde_json::to_string(&self.0)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|data| {
+                request.set_trace_block(data);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for TraceFilterParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        serde_json::to_string(&self.0)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|filter| {
+                request.set_trace_filter(filter);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for TraceCallParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        let mut call = ProtoCall::new();
+        call.set_from(self.0.from.unwrap_or_default().into());
+        call.set_to(self.0.to.into());
+        call.set_data(self.0.data.unwrap_or_default().into());
+        serde_json::to_string(&self.1)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|height| {
+                call.set_height(height);
+                request.set_trace_call(call);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for EstimateQuotaParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        let mut call = ProtoCall::new();
+        call.set_from(self.0.from.unwrap_or_default().into());
+        call.set_to(self.0.to.into());
+        call.set_data(self.0.data.unwrap_or_default().into());
+        serde_json::to_string(&self.1)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|height| {
+                call.set_height(height);
+                request.set_estimate_quota(call);
+                request
+            })
+    }
+}
+
+impl TryInto<ProtoRequest> for GetPoolStatusParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_pool_status(true);
+        Ok(request)
+    }
+}
+
+impl TryInto<ProtoRequest> for GetPoolTransactionsParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_pool_transactions(true);
+        Ok(request)
+    }
+}
+
+impl TryInto<ProtoRequest> for GetPoolTransactionParams {
+    type Error = Error;
+    fn try_into(self) -> Result<ProtoRequest, Self::Error> {
+        let mut request = create_request();
+        request.set_pool_transaction(self.0.into());
+        Ok(request)
+    }
+}
@@ -25,6 +25,7 @@ use libproto::request::Request as ProtoRequest;
 use error::Error;
 use rpctypes::{
     BlockNumber, Boolean, CallRequest, Data, Data20, Data32, Filter, OneItemTupleTrick, Quantity,
+    SubscribeParams, TraceFilter,
 };
 use rpctypes::{Id, Params as PartialParams, Version};
 
@@ -118,6 +119,32 @@ impl PartialRequest {
     }
 }
 
+/// JSON-RPC request of `subscribe` or `unsubscribe`, only served over WebSocket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubscriptionRequest {
+    pub jsonrpc: Option<Version>,
+    pub id: Id,
+    /// Contain method and params.
+    #[serde(flatten)]
+    pub call: SubscriptionCall,
+}
+
+impl SubscriptionRequest {
+    pub fn get_info(&self) -> RequestInfo {
+        RequestInfo::new(self.jsonrpc.clone(), self.id.clone())
+    }
+    pub fn is_subscription_method(method: &str) -> bool {
+        method == "subscribe" || method == "unsubscribe"
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum SubscriptionCall {
+    Subscribe { params: SubscribeParams },
+    Unsubscribe { params: (Quantity,) },
+}
+
 macro_rules! define_call {
     ($( ($enum_name:ident, $params_name:ident: $params_list:expr) ),+ ,) => {
         define_call!($( ($enum_name, $params_name: $params_list) ),+);
@@ -253,6 +280,8 @@ define_call!(
     (SendTransaction, SendTransactionParams: [Data]),
     (GetBlockByHash, GetBlockByHashParams: [Data32, Boolean]),
     (GetBlockByNumber, GetBlockByNumberParams: [BlockNumber, Boolean]),
+    (GetBlockHeader, GetBlockHeaderParams: [BlockNumber]),
+    (GetBlockHeaders, GetBlockHeadersParams: [BlockNumber, BlockNumber]),
     (GetTransactionReceipt, GetTransactionReceiptParams: [Data32]),
     (GetLogs, GetLogsParams: [Filter]),
     (Call, CallParams: [CallRequest, BlockNumber]),
@@ -261,11 +290,24 @@ define_call!(
     (GetCode, GetCodeParams: [Data20, BlockNumber]),
     (GetAbi, GetAbiParams: [Data20, BlockNumber]),
     (GetBalance, GetBalanceParams: [Data20, BlockNumber]),
+    (GetStorageAt, GetStorageAtParams: [Data20, Data32, BlockNumber]),
+    (GetStateProof, GetStateProofParams: [Data20, Vec::<Data32>, BlockNumber]),
     (NewFilter, NewFilterParams: [Filter]),
     (NewBlockFilter, NewBlockFilterParams: []),
     (UninstallFilter, UninstallFilterParams: [Quantity]),
     (GetFilterChanges, GetFilterChangesParams: [Quantity]),
     (GetFilterLogs, GetFilterLogsParams: [Quantity]),
     (GetTransactionProof, GetTransactionProofParams: [Data32]),
+    (GetReceiptProof, GetReceiptProofParams: [Data32]),
+    (GetTransactionInclusionProof, GetTransactionInclusionProofParams: [Data32]),
     (GetMetaData, GetMetaDataParams: [BlockNumber]),
+    (TraceTransaction, TraceTransactionParams: [Data32]),
+    (TraceBlock, TraceBlockParams: [BlockNumber]),
+    (TraceFilter, TraceFilterParams: [TraceFilter]),
+    (TraceCall, TraceCallParams: [CallRequest, BlockNumber]),
+    (EstimateQuota, EstimateQuotaParams: [CallRequest, BlockNumber]),
+    (GetPoolStatus, GetPoolStatusParams: []),
+    (GetPoolTransactions, GetPoolTransactionsParams: []),
+    (GetPoolTransaction, GetPoolTransactionParams: [Data32]),
+    (GetPeersInfo, GetPeersInfoParams: []),
 );
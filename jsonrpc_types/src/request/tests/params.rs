@@ -17,13 +17,17 @@
 
 use cita_types::{H160, H256, U256};
 use request::{
-    BlockNumberParams, CallParams, GetAbiParams, GetBalanceParams, GetBlockByHashParams,
-    GetBlockByNumberParams, GetCodeParams, GetFilterChangesParams, GetFilterLogsParams,
-    GetLogsParams, GetMetaDataParams, GetTransactionCountParams, GetTransactionParams,
-    GetTransactionProofParams, GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams,
-    PeerCountParams, SendRawTransactionParams, UninstallFilterParams,
+    BlockNumberParams, CallParams, EstimateQuotaParams, GetAbiParams, GetBalanceParams,
+    GetBlockByHashParams, GetBlockByNumberParams, GetBlockHeaderParams, GetBlockHeadersParams,
+    GetCodeParams, GetFilterChangesParams, GetFilterLogsParams, GetLogsParams, GetMetaDataParams,
+    GetPeersInfoParams, GetPoolStatusParams, GetPoolTransactionParams, GetPoolTransactionsParams,
+    GetReceiptProofParams, GetStateProofParams, GetStorageAtParams, GetTransactionCountParams,
+    GetTransactionInclusionProofParams, GetTransactionParams, GetTransactionProofParams,
+    GetTransactionReceiptParams, NewBlockFilterParams, NewFilterParams, PeerCountParams,
+    SendRawTransactionParams, TraceBlockParams, TraceCallParams, TraceFilterParams,
+    TraceTransactionParams, UninstallFilterParams,
 };
-use rpctypes::{BlockNumber, CallRequest, Filter, VariadicValue};
+use rpctypes::{BlockNumber, CallRequest, Filter, TraceFilter, VariadicValue};
 use serde_json;
 use std::convert::Into;
 
@@ -74,6 +78,14 @@ fn serialize_and_deserialize() {
         (BlockNumber::new(16u64.into()), false.into())
     );
 
+    test_ser_and_de!(GetBlockHeaderParams, ["0x11"], (BlockNumber::new(17u64.into())));
+
+    test_ser_and_de!(
+        GetBlockHeadersParams,
+        ["0x11", "latest"],
+        (BlockNumber::new(17u64.into()), BlockNumber::latest())
+    );
+
     test_ser_and_de!(
         GetTransactionReceiptParams,
         ["0x000000000000000000000000000000000000000000000000000000000000000a"],
@@ -158,6 +170,34 @@ fn serialize_and_deserialize() {
         (H160::from(13).into(), BlockNumber::new(19u64.into()))
     );
 
+    test_ser_and_de!(
+        GetStorageAtParams,
+        [
+            "0x000000000000000000000000000000000000000d",
+            "0x0000000000000000000000000000000000000000000000000000000000000001",
+            "latest"
+        ],
+        (
+            H160::from(13).into(),
+            H256::from(1).into(),
+            BlockNumber::latest()
+        )
+    );
+
+    test_ser_and_de!(
+        GetStateProofParams,
+        [
+            "0x000000000000000000000000000000000000000d",
+            ["0x0000000000000000000000000000000000000000000000000000000000000001"],
+            "0x13"
+        ],
+        (
+            H160::from(13).into(),
+            vec![H256::from(1).into()],
+            BlockNumber::new(19u64.into())
+        )
+    );
+
     test_ser_and_de!(
             value,
             NewFilterParams,
@@ -205,5 +245,83 @@ fn serialize_and_deserialize() {
         (H256::from(11).into())
     );
 
+    test_ser_and_de!(
+        GetReceiptProofParams,
+        ["0x000000000000000000000000000000000000000000000000000000000000000b"],
+        (H256::from(11).into())
+    );
+
+    test_ser_and_de!(
+        GetTransactionInclusionProofParams,
+        ["0x000000000000000000000000000000000000000000000000000000000000000b"],
+        (H256::from(11).into())
+    );
+
     test_ser_and_de!(GetMetaDataParams, ["earliest"], (BlockNumber::earliest()));
+
+    test_ser_and_de!(
+        TraceTransactionParams,
+        ["0x000000000000000000000000000000000000000000000000000000000000000c"],
+        (H256::from(12).into())
+    );
+
+    test_ser_and_de!(TraceBlockParams, ["0x14"], (BlockNumber::new(20u64.into())));
+
+    test_ser_and_de!(
+            value,
+            TraceFilterParams,
+            [{
+                "fromBlock": "0xd",
+                "toAddress": ["0x0000000000000000000000000000000000000010"],
+            }],
+            (TraceFilter::new(
+                BlockNumber::new(13u64.into()),
+                BlockNumber::latest(),
+                vec![],
+                vec![H160::from(16).into()],
+            ))
+        );
+
+    test_ser_and_de!(
+            TraceCallParams,
+            [
+                {
+                    "from": "0x000000000000000000000000000000000000000b",
+                    "to": "0x000000000000000000000000000000000000000c",
+                },
+                "0x15",
+            ],
+            (
+                CallRequest::new(Some(H160::from(11).into()),
+                H160::from(12).into(), None),
+                BlockNumber::new(21u64.into())
+            ));
+
+    test_ser_and_de!(
+            EstimateQuotaParams,
+            [
+                {
+                    "from": "0x000000000000000000000000000000000000000b",
+                    "to": "0x000000000000000000000000000000000000000c",
+                    "data": "0x6d4ce63c",
+                },
+                "latest",
+            ],
+            (
+                CallRequest::new(Some(H160::from(11).into()),
+                H160::from(12).into(), Some(vec![0x6d, 0x4c, 0xe6, 0x3c].into())),
+                BlockNumber::latest()
+            ));
+
+    test_ser_and_de!(GetPoolStatusParams, [], ());
+
+    test_ser_and_de!(GetPoolTransactionsParams, [], ());
+
+    test_ser_and_de!(
+        GetPoolTransactionParams,
+        ["0x000000000000000000000000000000000000000000000000000000000000000d"],
+        (H256::from(13).into())
+    );
+
+    test_ser_and_de!(GetPeersInfoParams, [], ());
 }
@@ -17,7 +17,11 @@
 
 use cita_types::H256;
 use error::Error;
-use request::{BlockNumberParams, GetTransactionReceiptParams, PartialRequest, Request};
+use request::{
+    BlockNumberParams, GetTransactionReceiptParams, PartialRequest, Request, SubscriptionCall,
+    SubscriptionRequest,
+};
+use rpctypes::{Id, Quantity, SubscribeParams, Version};
 use serde_json;
 use std::convert::Into;
 
@@ -177,3 +181,38 @@ fn serialize_and_deserialize() {
         Error::method_not_found()
     );
 }
+
+#[test]
+fn subscription_request() {
+    assert!(SubscriptionRequest::is_subscription_method("subscribe"));
+    assert!(SubscriptionRequest::is_subscription_method("unsubscribe"));
+    assert!(!SubscriptionRequest::is_subscription_method("getLogs"));
+
+    let req = SubscriptionRequest {
+        jsonrpc: Some(Version::default()),
+        id: Id::Num(1),
+        call: SubscriptionCall::Subscribe {
+            params: SubscribeParams::NewHeads,
+        },
+    };
+    test_ser_and_de!(SubscriptionRequest, req, {
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "subscribe",
+            "params": ["newHeads"],
+        });
+
+    let req = SubscriptionRequest {
+        jsonrpc: Some(Version::default()),
+        id: Id::Num(2),
+        call: SubscriptionCall::Unsubscribe {
+            params: (Quantity::new(1.into()),),
+        },
+    };
+    test_ser_and_de!(SubscriptionRequest, req, {
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "unsubscribe",
+            "params": ["0x1"],
+        });
+}
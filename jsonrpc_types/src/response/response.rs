@@ -15,12 +15,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use cita_types::H256;
 use error::Error;
 use libproto::response::{Response, Response_oneof_data};
 use request::RequestInfo;
 use rpctypes::{
-    Block, Boolean, Data, FilterChanges, Id, Log, MetaData, Quantity, Receipt, RpcBlock,
-    RpcTransaction, TxResponse, Version,
+    Block, Boolean, Data, Data32, FilterChanges, Id, Log, MetaData, PeersInfo, PoolStatus,
+    PoolTransaction, Quantity, Receipt, ReceiptProof, RpcBlock, RpcTransaction, StateProof, Trace,
+    TraceCallResult, TransactionInclusionProof, TxResponse, Version,
 };
 use serde::de::Error as SError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -51,6 +53,21 @@ pub enum ResultBody {
     TxProof(Data),
     MetaData(MetaData),
     Balance(Quantity),
+    StorageValue(Data32),
+    StateProof(StateProof),
+    Traces(Vec<Trace>),
+    TraceCallResult(TraceCallResult),
+    EstimateQuota(Quantity),
+    SubscriptionId(Quantity),
+    Unsubscribed(Boolean),
+    PoolStatus(PoolStatus),
+    PoolTransactions(Vec<PoolTransaction>),
+    PoolTransaction(PoolTransaction),
+    BlockHeader(Data),
+    BlockHeaders(Vec<Data>),
+    ReceiptProof(ReceiptProof),
+    TransactionInclusionProof(TransactionInclusionProof),
+    PeersInfo(PeersInfo),
 }
 
 impl Default for ResultBody {
@@ -125,6 +142,14 @@ impl Output {
                             .set_result(ResultBody::FullBlock(rpc_block.into()))
                             .output()
                     }
+                    Response_oneof_data::block_header(header) => success
+                        .set_result(ResultBody::BlockHeader(header.into()))
+                        .output(),
+                    Response_oneof_data::block_headers(headers) => success
+                        .set_result(ResultBody::BlockHeaders(
+                            serde_json::from_str::<Vec<Data>>(&headers).unwrap(),
+                        ))
+                        .output(),
                     Response_oneof_data::ts(x) => success
                         .set_result(ResultBody::Transaction(RpcTransaction::from(x)))
                         .output(),
@@ -158,6 +183,14 @@ impl Output {
                     Response_oneof_data::balance(x) => success
                         .set_result(ResultBody::Balance(x.as_slice().into()))
                         .output(),
+                    Response_oneof_data::storage_value(x) => success
+                        .set_result(ResultBody::StorageValue(H256::from_slice(&x).into()))
+                        .output(),
+                    Response_oneof_data::state_proof(data) => success
+                        .set_result(ResultBody::StateProof(
+                            serde_json::from_str::<StateProof>(&data).unwrap(),
+                        ))
+                        .output(),
                     Response_oneof_data::filter_id(id) => {
                         success.set_result(ResultBody::FilterId(id.into())).output()
                     }
@@ -176,6 +209,16 @@ impl Output {
                             serde_json::from_str::<Vec<Log>>(&log).unwrap(),
                         ))
                         .output(),
+                    Response_oneof_data::receipt_proof(proof) => success
+                        .set_result(ResultBody::ReceiptProof(
+                            serde_json::from_str::<ReceiptProof>(&proof).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::transaction_inclusion_proof(proof) => success
+                        .set_result(ResultBody::TransactionInclusionProof(
+                            serde_json::from_str::<TransactionInclusionProof>(&proof).unwrap(),
+                        ))
+                        .output(),
                     Response_oneof_data::transaction_proof(proof) => success
                         .set_result(ResultBody::TxProof(proof.into()))
                         .output(),
@@ -187,6 +230,39 @@ impl Output {
                             serde_json::from_str::<MetaData>(&data).unwrap(),
                         ))
                         .output(),
+                    Response_oneof_data::traces(data) => success
+                        .set_result(ResultBody::Traces(
+                            serde_json::from_str::<Vec<Trace>>(&data).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::estimate_quota(x) => success
+                        .set_result(ResultBody::EstimateQuota(x.as_slice().into()))
+                        .output(),
+                    Response_oneof_data::trace_call_result(data) => success
+                        .set_result(ResultBody::TraceCallResult(
+                            serde_json::from_str::<TraceCallResult>(&data).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::pool_status(data) => success
+                        .set_result(ResultBody::PoolStatus(
+                            serde_json::from_str::<PoolStatus>(&data).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::pool_transactions(data) => success
+                        .set_result(ResultBody::PoolTransactions(
+                            serde_json::from_str::<Vec<PoolTransaction>>(&data).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::pool_transaction(data) => success
+                        .set_result(ResultBody::PoolTransaction(
+                            serde_json::from_str::<PoolTransaction>(&data).unwrap(),
+                        ))
+                        .output(),
+                    Response_oneof_data::peers_info(data) => success
+                        .set_result(ResultBody::PeersInfo(
+                            serde_json::from_str::<PeersInfo>(&data).unwrap(),
+                        ))
+                        .output(),
                 }
             }
             _ => match data.data.unwrap() {
@@ -42,6 +42,52 @@ impl Default for CountOrCode {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StorageKey {
+    pub address: ::std::vec::Vec<u8>,
+    pub key: ::std::vec::Vec<u8>,
+    pub block_id: BlockNumber,
+}
+
+impl StorageKey {
+    pub fn new(address: Vec<u8>, key: Vec<u8>, block_id: BlockNumber) -> StorageKey {
+        StorageKey {
+            address: address,
+            key: key,
+            block_id: block_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StorageKeys {
+    pub address: ::std::vec::Vec<u8>,
+    pub keys: ::std::vec::Vec<Vec<u8>>,
+    pub block_id: BlockNumber,
+}
+
+impl StorageKeys {
+    pub fn new(address: Vec<u8>, keys: Vec<Vec<u8>>, block_id: BlockNumber) -> StorageKeys {
+        StorageKeys {
+            address: address,
+            keys: keys,
+            block_id: block_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BlockRange {
+    pub from: BlockNumber,
+    pub to: BlockNumber,
+}
+
+impl BlockRange {
+    pub fn new(from: BlockNumber, to: BlockNumber) -> BlockRange {
+        BlockRange { from: from, to: to }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct BlockParamsByHash {
     pub hash: ::std::vec::Vec<u8>,
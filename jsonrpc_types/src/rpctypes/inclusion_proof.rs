@@ -0,0 +1,384 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Merkle proofs of transactions and receipts rendered as plain JSON.
+//!
+//! The leaves and sibling hashes are given directly, so a proof can be
+//! checked by hashing the concatenation of two hashes step by step, without
+//! decoding any CITA specific encoding.
+//!
+//! The tree is stored like a binary heap with the leaves at the end, so the
+//! leaves of the last level come first. The path of a proof gives the position
+//! of its leaf in the heap, which is checked against `transactionIndex` with
+//! `transactionsCount`, the number of leaves.
+
+use cita_types::{H256, U256};
+use rlp::UntrustedRlp;
+use rpctypes::{Data, Receipt};
+use types::receipt::Receipt as RawReceipt;
+use util::Hashable;
+
+/// One step on the path from a leaf to the root: the sibling hash and whether
+/// the sibling is on the right.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MerkleProofNode {
+    #[serde(rename = "isRight")]
+    pub is_right: bool,
+    pub hash: H256,
+}
+
+/// Proof that a transaction hash is included in the `transactionsRoot` of a block.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TransactionInclusionProof {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+    #[serde(rename = "transactionIndex")]
+    pub transaction_index: U256,
+    #[serde(rename = "transactionsCount")]
+    pub transactions_count: U256,
+    #[serde(rename = "blockHash")]
+    pub block_hash: H256,
+    #[serde(rename = "blockNumber")]
+    pub block_number: U256,
+    #[serde(rename = "transactionsRoot")]
+    pub transactions_root: H256,
+    pub proof: Vec<MerkleProofNode>,
+}
+
+impl TransactionInclusionProof {
+    /// Check the proof against its own `transactionsRoot`.
+    ///
+    /// The caller should still compare `transactionsRoot` with a header it trusts.
+    pub fn verify(&self) -> bool {
+        leaf_index(&self.proof, self.transactions_count) == Some(self.transaction_index)
+            && verify_merkle_proof(&self.transactions_root, &self.transaction_hash, &self.proof)
+    }
+}
+
+/// Proof that a receipt is included in the `receiptsRoot` of a block.
+///
+/// The leaf is the hash of `receiptRlp`, the encoded receipt; `receipt` is
+/// checked against the decoded one, except for `gasUsed`, `contractAddress`
+/// and the indexes of the logs, which are not in the encoding.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReceiptProof {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+    #[serde(rename = "transactionIndex")]
+    pub transaction_index: U256,
+    #[serde(rename = "transactionsCount")]
+    pub transactions_count: U256,
+    #[serde(rename = "blockHash")]
+    pub block_hash: H256,
+    #[serde(rename = "blockNumber")]
+    pub block_number: U256,
+    #[serde(rename = "receiptsRoot")]
+    pub receipts_root: H256,
+    #[serde(rename = "receiptRlp")]
+    pub receipt_rlp: Data,
+    pub receipt: Receipt,
+    pub proof: Vec<MerkleProofNode>,
+}
+
+impl ReceiptProof {
+    /// Check the proof against its own `receiptsRoot`.
+    ///
+    /// The caller should still compare `receiptsRoot` with a header it trusts.
+    pub fn verify(&self) -> bool {
+        if leaf_index(&self.proof, self.transactions_count) != Some(self.transaction_index) {
+            return false;
+        }
+        let encoded: Vec<u8> = self.receipt_rlp.clone().into();
+        match UntrustedRlp::new(&encoded).as_val::<Option<RawReceipt>>() {
+            Ok(Some(ref raw)) if self.matches(raw) => {
+                verify_merkle_proof(&self.receipts_root, &encoded.crypt_hash(), &self.proof)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check that `receipt` is the decoded receipt, in the block of the proof.
+    fn matches(&self, raw: &RawReceipt) -> bool {
+        let receipt = &self.receipt;
+        raw.transaction_hash == self.transaction_hash
+            && receipt.transaction_hash == Some(self.transaction_hash)
+            && receipt.transaction_index == Some(self.transaction_index)
+            && receipt.block_hash == Some(self.block_hash)
+            && receipt.block_number == Some(self.block_number)
+            && receipt.cumulative_gas_used == raw.gas_used
+            && receipt.state_root == raw.state_root
+            && receipt.logs_bloom == raw.log_bloom
+            && receipt.error_message == raw.error.as_ref().map(|error| error.description())
+            && receipt.logs.len() == raw.logs.len()
+            && receipt.logs.iter().zip(raw.logs.iter()).all(|(log, entry)| {
+                log.address == entry.address
+                    && log.topics == entry.topics
+                    && log.data == Data::new(entry.data.clone())
+            })
+    }
+}
+
+/// Verify that `leaf` is in the Merkle tree of `root`.
+pub fn verify_merkle_proof(root: &H256, leaf: &H256, proof: &[MerkleProofNode]) -> bool {
+    let hash = proof.iter().fold(*leaf, |hash, node| {
+        if node.is_right {
+            merge(&hash, &node.hash)
+        } else {
+            merge(&node.hash, &hash)
+        }
+    });
+    hash == *root
+}
+
+/// Index of the leaf at the end of the path, in a tree of `count` leaves.
+///
+/// `None` if the path does not end at a leaf of the tree.
+pub fn leaf_index(proof: &[MerkleProofNode], count: U256) -> Option<U256> {
+    if count.is_zero() || count > U256::from(u32::max_value()) || proof.len() >= 64 {
+        return None;
+    }
+    let count = count.low_u64();
+    // Position in the heap, walking down from the root.
+    let position = proof.iter().rev().fold(0u64, |position, node| {
+        if node.is_right {
+            position * 2 + 1
+        } else {
+            position * 2 + 2
+        }
+    });
+    let nodes = count * 2 - 1;
+    let last_level = (1u64 << (63 - nodes.leading_zeros())) - 1;
+    if position >= nodes || position < count - 1 {
+        None
+    } else if position >= last_level {
+        Some((position - last_level).into())
+    } else {
+        Some((position + count - last_level).into())
+    }
+}
+
+fn merge(left: &H256, right: &H256) -> H256 {
+    let mut data = left.to_vec();
+    data.extend_from_slice(right);
+    data.crypt_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_types::Address;
+    use rlp::Encodable;
+    use rpctypes::Log;
+    use serde_json;
+    use types::log_entry::LogEntry;
+
+    fn hash_pair(left: H256, right: H256) -> H256 {
+        [left.to_vec(), right.to_vec()].concat().crypt_hash()
+    }
+
+    #[test]
+    fn verify_proofs() {
+        let leaves: Vec<H256> = (1..4).map(H256::from).collect();
+        // The tree of three leaves is ((1, 2), 3).
+        let left = hash_pair(leaves[0], leaves[1]);
+        let root = hash_pair(left, leaves[2]);
+        let proof = vec![
+            MerkleProofNode {
+                is_right: false,
+                hash: leaves[0],
+            },
+            MerkleProofNode {
+                is_right: true,
+                hash: leaves[2],
+            },
+        ];
+        assert!(verify_merkle_proof(&root, &leaves[1], &proof));
+        assert!(!verify_merkle_proof(&root, &leaves[0], &proof));
+        assert!(!verify_merkle_proof(&root, &leaves[1], &proof[..1]));
+        let proof = vec![MerkleProofNode {
+            is_right: false,
+            hash: left,
+        }];
+        assert!(verify_merkle_proof(&root, &leaves[2], &proof));
+    }
+
+    #[test]
+    fn leaf_index_of_path() {
+        for count in 1..10u64 {
+            let nodes = count * 2 - 1;
+            let mut last_level = 0;
+            while last_level * 2 + 1 < nodes {
+                last_level = last_level * 2 + 1;
+            }
+            for index in 0..count {
+                let mut position = index + last_level;
+                if position >= nodes {
+                    position -= count;
+                }
+                let mut proof = Vec::new();
+                while position > 0 {
+                    proof.push(MerkleProofNode {
+                        is_right: position % 2 == 1,
+                        hash: H256::zero(),
+                    });
+                    position = (position - 1) / 2;
+                }
+                assert_eq!(leaf_index(&proof, count.into()), Some(index.into()));
+            }
+        }
+        assert_eq!(leaf_index(&[], 0.into()), None);
+        // The root of a tree of two leaves is not a leaf.
+        assert_eq!(leaf_index(&[], 2.into()), None);
+    }
+
+    #[test]
+    fn reject_wrong_transaction_index() {
+        let leaves: Vec<H256> = (1..4).map(H256::from).collect();
+        let root = hash_pair(hash_pair(leaves[0], leaves[1]), leaves[2]);
+        let proof = TransactionInclusionProof {
+            transaction_hash: leaves[1],
+            transaction_index: 1.into(),
+            transactions_count: 3.into(),
+            block_hash: H256::from(4),
+            block_number: 5.into(),
+            transactions_root: root,
+            proof: vec![
+                MerkleProofNode {
+                    is_right: false,
+                    hash: leaves[0],
+                },
+                MerkleProofNode {
+                    is_right: true,
+                    hash: leaves[2],
+                },
+            ],
+        };
+        assert!(proof.verify());
+
+        let mut tampered = proof.clone();
+        tampered.transaction_index = 2.into();
+        assert!(!tampered.verify());
+
+        let mut tampered = proof.clone();
+        tampered.transactions_count = 2.into();
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn transaction_inclusion_proof_serialization() {
+        let proof = TransactionInclusionProof {
+            transaction_hash: H256::from(1),
+            transaction_index: 0.into(),
+            transactions_count: 1.into(),
+            block_hash: H256::from(2),
+            block_number: 3.into(),
+            transactions_root: H256::from(1),
+            proof: vec![],
+        };
+        let serialized = serde_json::to_string(&proof).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000001","transactionIndex":"0x0","transactionsCount":"0x1","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000002","blockNumber":"0x3","transactionsRoot":"0x0000000000000000000000000000000000000000000000000000000000000001","proof":[]}"#
+        );
+        let deserialized: TransactionInclusionProof = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, proof);
+        assert!(proof.verify());
+    }
+
+    /// Build the proof of a receipt which is the only one in the block.
+    fn receipt_proof() -> ReceiptProof {
+        let entry = LogEntry {
+            address: Address::from(1),
+            topics: vec![H256::from(2)],
+            data: vec![3],
+        };
+        let raw = RawReceipt::new(
+            None,
+            21000.into(),
+            vec![entry.clone()],
+            None,
+            0.into(),
+            H256::from(4),
+        );
+        let encoded = Some(raw.clone()).rlp_bytes().into_vec();
+        let receipt = Receipt {
+            transaction_hash: Some(H256::from(4)),
+            transaction_index: Some(0.into()),
+            block_hash: Some(H256::from(5)),
+            block_number: Some(6.into()),
+            cumulative_gas_used: 21000.into(),
+            gas_used: Some(21000.into()),
+            contract_address: None,
+            logs: vec![Log::from(entry)],
+            state_root: None,
+            logs_bloom: raw.log_bloom,
+            error_message: None,
+        };
+        ReceiptProof {
+            transaction_hash: H256::from(4),
+            transaction_index: 0.into(),
+            transactions_count: 1.into(),
+            block_hash: H256::from(5),
+            block_number: 6.into(),
+            receipts_root: encoded.crypt_hash(),
+            receipt_rlp: Data::new(encoded),
+            receipt: receipt,
+            proof: vec![],
+        }
+    }
+
+    #[test]
+    fn verify_receipt_proof() {
+        let proof = receipt_proof();
+        assert!(proof.verify());
+
+        let serialized = serde_json::to_string(&proof).unwrap();
+        let deserialized: ReceiptProof = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, proof);
+    }
+
+    #[test]
+    fn reject_tampered_receipt() {
+        let mut tampered = receipt_proof();
+        tampered.receipt.cumulative_gas_used = 1.into();
+        assert!(!tampered.verify());
+
+        let mut tampered = receipt_proof();
+        tampered.receipt.logs[0].data = Data::new(vec![4]);
+        assert!(!tampered.verify());
+
+        let mut tampered = receipt_proof();
+        tampered.receipt.error_message = Some("Out of quota.".to_owned());
+        assert!(!tampered.verify());
+
+        // A receipt encoded differently is not the leaf.
+        let mut tampered = receipt_proof();
+        let raw = RawReceipt::new(None, 1.into(), vec![], None, 0.into(), H256::from(4));
+        tampered.receipt_rlp = Data::new(Some(raw).rlp_bytes().into_vec());
+        assert!(!tampered.verify());
+
+        let mut tampered = receipt_proof();
+        tampered.receipt_rlp = Data::new(vec![0xff]);
+        assert!(!tampered.verify());
+
+        // The only leaf of the tree is at index 0.
+        let mut tampered = receipt_proof();
+        tampered.transaction_index = 1.into();
+        tampered.receipt.transaction_index = Some(1.into());
+        assert!(!tampered.verify());
+    }
+}
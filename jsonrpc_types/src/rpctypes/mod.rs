@@ -21,29 +21,53 @@ mod block_number;
 mod call_request;
 mod exchange;
 mod filter;
+mod inclusion_proof;
 mod index;
 mod log;
 mod meta_data;
+mod peers;
+mod pool;
 mod proof;
 mod receipt;
 mod specs;
+mod state_proof;
+mod subscription;
+mod trace;
 mod transaction;
 mod tx_response;
 
 pub use self::basic::{
     BlockTag, Boolean, Data, Data20, Data32, OneItemTupleTrick, Quantity, VariadicValue,
 };
-pub use self::exchange::{BlockParamsByHash, BlockParamsByNumber, CountOrCode, RpcBlock};
+pub use self::exchange::{
+    BlockParamsByHash, BlockParamsByNumber, BlockRange, CountOrCode, RpcBlock, StorageKey,
+    StorageKeys,
+};
 pub use self::specs::{Id, Params, Version};
 
 pub use self::block::{Block, BlockBody, BlockHeader};
 pub use self::block_number::BlockNumber;
 pub use self::call_request::CallRequest;
 pub use self::filter::{Filter, FilterAddress, FilterChanges, Topic};
+pub use self::inclusion_proof::{
+    leaf_index, verify_merkle_proof, MerkleProofNode, ReceiptProof, TransactionInclusionProof,
+};
 pub use self::index::Index;
 pub use self::log::Log;
 pub use self::meta_data::MetaData;
+pub use self::peers::{PeerInfo, PeersInfo, SyncStatus, Traffic};
+pub use self::pool::{PoolSender, PoolStatus, PoolTransaction};
 pub use self::proof::{AuthorityRoundProof, Proof, TendermintProof};
 pub use self::receipt::Receipt;
+pub use self::state_proof::{StateProof, StorageProof};
+pub use self::subscription::{
+    NewHead, SubscribeParams, SubscriptionKind, SubscriptionNotification,
+    SubscriptionNotificationParams, SubscriptionResult,
+};
+pub use self::trace::{
+    AccountDiff, CallAction, CallResult, ChangedType, CreateAction, CreateResult, Diff,
+    MemoryDiff, StateDiff, StorageDiff, SuicideAction, Trace, TraceAction, TraceCallResult,
+    TraceFilter, TraceResult, VMExecutedOperation, VMOperation, VMTrace,
+};
 pub use self::transaction::{BlockTransaction, FullTransaction, RpcTransaction};
 pub use self::tx_response::TxResponse;
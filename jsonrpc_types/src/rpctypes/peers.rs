@@ -0,0 +1,97 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use cita_types::{Address, U256};
+use std::collections::BTreeMap;
+
+/// Bytes and messages of one routing key.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Traffic {
+    pub bytes: U256,
+    pub messages: U256,
+}
+
+/// A peer of the network service and the traffic with it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    #[serde(rename = "idCard")]
+    pub id_card: U256,
+    pub address: Address,
+    pub endpoint: String,
+    pub connected: bool,
+    /// Unix time in milliseconds when the peer sent its last frame
+    #[serde(rename = "lastSeen")]
+    pub last_seen: Option<U256>,
+    pub sent: BTreeMap<String, Traffic>,
+    pub received: BTreeMap<String, Traffic>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct SyncStatus {
+    #[serde(rename = "isSynchronizing")]
+    pub is_synchronizing: bool,
+    #[serde(rename = "currentHeight")]
+    pub current_height: U256,
+    #[serde(rename = "globalHeight")]
+    pub global_height: U256,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PeersInfo {
+    pub peers: Vec<PeerInfo>,
+    pub sync: SyncStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn peers_info_serialization() {
+        let mut sent = BTreeMap::new();
+        sent.insert(
+            "consensus.signed_proposal".to_owned(),
+            Traffic {
+                bytes: 1024.into(),
+                messages: 2.into(),
+            },
+        );
+        let info = PeersInfo {
+            peers: vec![PeerInfo {
+                id_card: 1.into(),
+                address: Address::from(1),
+                endpoint: "127.0.0.1:4001".to_owned(),
+                connected: true,
+                last_seen: None,
+                sent: sent,
+                received: BTreeMap::new(),
+            }],
+            sync: SyncStatus {
+                is_synchronizing: false,
+                current_height: 16.into(),
+                global_height: 17.into(),
+            },
+        };
+        let serialized = serde_json::to_string(&info).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"peers":[{"idCard":"0x1","address":"0x0000000000000000000000000000000000000001","endpoint":"127.0.0.1:4001","connected":true,"lastSeen":null,"sent":{"consensus.signed_proposal":{"bytes":"0x400","messages":"0x2"}},"received":{}}],"sync":{"isSynchronizing":false,"currentHeight":"0x10","globalHeight":"0x11"}}"#
+        );
+        let deserialized: PeersInfo = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, info);
+    }
+}
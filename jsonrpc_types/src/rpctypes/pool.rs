@@ -0,0 +1,89 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use cita_types::{Address, H256, U256};
+use libproto::blockchain::SignedTransaction as ProtoSignedTransaction;
+use rpctypes::Data;
+use std::convert::TryInto;
+
+/// Pending transactions and quota of one sender in the transaction pool.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PoolSender {
+    pub address: Address,
+    pub pending: U256,
+    pub quota: U256,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PoolStatus {
+    pub pending: U256,
+    pub quota: U256,
+    pub senders: Vec<PoolSender>,
+}
+
+/// A transaction which is waiting in the transaction pool.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PoolTransaction {
+    pub hash: H256,
+    pub content: Data,
+    pub from: Address,
+    pub nonce: String,
+    pub quota: U256,
+    #[serde(rename = "validUntilBlock")]
+    pub valid_until_block: U256,
+}
+
+impl PoolTransaction {
+    pub fn new(stx: &ProtoSignedTransaction, from: Address) -> Self {
+        let unverified_tx = stx.get_transaction_with_sig();
+        let tx = unverified_tx.get_transaction();
+        PoolTransaction {
+            hash: H256::from_slice(stx.get_tx_hash()),
+            content: Data::new(unverified_tx.try_into().unwrap()),
+            from: from,
+            nonce: tx.get_nonce().to_owned(),
+            quota: U256::from(tx.get_quota()),
+            valid_until_block: U256::from(tx.get_valid_until_block()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn pool_status_serialization() {
+        let status = PoolStatus {
+            pending: 2.into(),
+            quota: 60000.into(),
+            senders: vec![PoolSender {
+                address: Address::from(1),
+                pending: 2.into(),
+                quota: 60000.into(),
+            }],
+        };
+        let serialized = serde_json::to_string(&status).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"pending":"0x2","quota":"0xea60","senders":[{"address":"0x0000000000000000000000000000000000000001","pending":"0x2","quota":"0xea60"}]}"#
+        );
+        let deserialized: PoolStatus = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, status);
+    }
+}
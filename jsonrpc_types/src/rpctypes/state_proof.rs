@@ -0,0 +1,80 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use cita_types::{Address, H256, U256};
+use rpctypes::Data;
+
+/// Merkle proof of one storage slot against the `storageHash` of the account.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct StorageProof {
+    pub key: H256,
+    pub value: H256,
+    pub proof: Vec<Data>,
+}
+
+/// Merkle proof of an account against the `stateRoot` of the block header,
+/// along with the proofs of the requested storage slots.
+///
+/// The proofs list the trie nodes on the path from the root, and prove the
+/// absence of a missing account or slot as well.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct StateProof {
+    pub address: Address,
+    pub balance: U256,
+    pub nonce: U256,
+    #[serde(rename = "codeHash")]
+    pub code_hash: H256,
+    #[serde(rename = "abiHash")]
+    pub abi_hash: H256,
+    #[serde(rename = "storageHash")]
+    pub storage_hash: H256,
+    #[serde(rename = "accountProof")]
+    pub account_proof: Vec<Data>,
+    #[serde(rename = "storageProof")]
+    pub storage_proof: Vec<StorageProof>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn state_proof_serialization() {
+        let proof = StateProof {
+            address: Address::from(1),
+            balance: 2.into(),
+            nonce: 0.into(),
+            code_hash: H256::from(3),
+            abi_hash: H256::from(4),
+            storage_hash: H256::from(5),
+            account_proof: vec![Data::new(vec![0xc0])],
+            storage_proof: vec![StorageProof {
+                key: H256::from(6),
+                value: H256::from(7),
+                proof: vec![],
+            }],
+        };
+        let serialized = serde_json::to_string(&proof).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"address":"0x0000000000000000000000000000000000000001","balance":"0x2","nonce":"0x0","codeHash":"0x0000000000000000000000000000000000000000000000000000000000000003","abiHash":"0x0000000000000000000000000000000000000000000000000000000000000004","storageHash":"0x0000000000000000000000000000000000000000000000000000000000000005","accountProof":["0xc0"],"storageProof":[{"key":"0x0000000000000000000000000000000000000000000000000000000000000006","value":"0x0000000000000000000000000000000000000000000000000000000000000007","proof":[]}]}"#
+        );
+        let deserialized: StateProof = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, proof);
+    }
+}
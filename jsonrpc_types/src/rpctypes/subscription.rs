@@ -0,0 +1,188 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use cita_types::H256;
+use rpctypes::{BlockHeader, Filter, Log, Quantity, Version};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{from_value, Value};
+
+/// Kind of the subscription
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    /// Headers of new blocks
+    NewHeads,
+    /// Logs matching the filter in new blocks
+    Logs,
+    /// Hashes of transactions newly accepted into the pool
+    NewPendingTransactions,
+}
+
+/// Params of `subscribe`: the kind, and the filter of `logs`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscribeParams {
+    NewHeads,
+    Logs(Filter),
+    NewPendingTransactions,
+}
+
+impl SubscribeParams {
+    pub fn kind(&self) -> SubscriptionKind {
+        match *self {
+            SubscribeParams::NewHeads => SubscriptionKind::NewHeads,
+            SubscribeParams::Logs(_) => SubscriptionKind::Logs,
+            SubscribeParams::NewPendingTransactions => SubscriptionKind::NewPendingTransactions,
+        }
+    }
+}
+
+impl Serialize for SubscribeParams {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            SubscribeParams::Logs(ref filter) => (self.kind(), filter).serialize(serializer),
+            _ => (self.kind(),).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SubscribeParams {
+    fn deserialize<D>(deserializer: D) -> Result<SubscribeParams, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut params = Vec::<Value>::deserialize(deserializer)?.into_iter();
+        let kind = params
+            .next()
+            .ok_or_else(|| D::Error::custom("the kind of subscription is required"))
+            .and_then(|kind| from_value::<SubscriptionKind>(kind).map_err(D::Error::custom))?;
+        let filter = params.next();
+        if params.next().is_some() {
+            return Err(D::Error::custom("too many params"));
+        }
+        match (kind, filter) {
+            (SubscriptionKind::Logs, Some(filter)) => from_value(filter)
+                .map(SubscribeParams::Logs)
+                .map_err(D::Error::custom),
+            (SubscriptionKind::Logs, None) => Ok(SubscribeParams::Logs(Filter::new(
+                Default::default(),
+                Default::default(),
+                None,
+                None,
+            ))),
+            (_, Some(_)) => Err(D::Error::custom("only logs subscription takes a filter")),
+            (SubscriptionKind::NewHeads, None) => Ok(SubscribeParams::NewHeads),
+            (SubscriptionKind::NewPendingTransactions, None) => {
+                Ok(SubscribeParams::NewPendingTransactions)
+            }
+        }
+    }
+}
+
+/// Header of a new block, with the block hash
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NewHead {
+    pub hash: H256,
+    #[serde(flatten)]
+    pub header: BlockHeader,
+}
+
+/// Result pushed to the subscriber
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum SubscriptionResult {
+    Head(NewHead),
+    Log(Log),
+    TransactionHash(H256),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubscriptionNotificationParams {
+    pub subscription: Quantity,
+    pub result: SubscriptionResult,
+}
+
+/// Notification of a subscription, which is a JSON-RPC request without id
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubscriptionNotification {
+    pub jsonrpc: Option<Version>,
+    pub method: String,
+    pub params: SubscriptionNotificationParams,
+}
+
+impl SubscriptionNotification {
+    pub fn new(subscription: Quantity, result: SubscriptionResult) -> Self {
+        SubscriptionNotification {
+            jsonrpc: Some(Version::default()),
+            method: "subscription".to_owned(),
+            params: SubscriptionNotificationParams {
+                subscription: subscription,
+                result: result,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_types::H160;
+    use rpctypes::{BlockNumber, VariadicValue};
+    use serde_json;
+
+    #[test]
+    fn subscribe_params_serialization() {
+        let params: SubscribeParams = serde_json::from_str(r#"["newHeads"]"#).unwrap();
+        assert_eq!(params, SubscribeParams::NewHeads);
+        assert_eq!(serde_json::to_string(&params).unwrap(), r#"["newHeads"]"#);
+
+        let params: SubscribeParams =
+            serde_json::from_str(r#"["newPendingTransactions"]"#).unwrap();
+        assert_eq!(params, SubscribeParams::NewPendingTransactions);
+
+        let params: SubscribeParams = serde_json::from_str(
+            r#"["logs",{"address":"0x0000000000000000000000000000000000000010"}]"#,
+        ).unwrap();
+        let filter = Filter::new(
+            BlockNumber::latest(),
+            BlockNumber::latest(),
+            Some(VariadicValue::Single(H160::from(16).into())),
+            None,
+        );
+        assert_eq!(params, SubscribeParams::Logs(filter));
+
+        assert!(serde_json::from_str::<SubscribeParams>(r#"[]"#).is_err());
+        assert!(serde_json::from_str::<SubscribeParams>(r#"["newHeads",{}]"#).is_err());
+        assert!(serde_json::from_str::<SubscribeParams>(r#"["unknown"]"#).is_err());
+    }
+
+    #[test]
+    fn notification_serialization() {
+        let notification = SubscriptionNotification::new(
+            Quantity::new(1.into()),
+            SubscriptionResult::TransactionHash(H256::from(2)),
+        );
+        let serialized = serde_json::to_string(&notification).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"jsonrpc":"2.0","method":"subscription","params":{"subscription":"0x1","result":"0x0000000000000000000000000000000000000000000000000000000000000002"}}"#
+        );
+    }
+}
@@ -0,0 +1,415 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use cita_types::{Address, H256, U256};
+use rpctypes::{BlockNumber, Data, Data20};
+use std::collections::BTreeMap;
+use types::account_diff::{AccountDiff as EthAccountDiff, Diff as EthDiff};
+use types::state_diff::StateDiff as EthStateDiff;
+
+/// Call action
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CallAction {
+    /// Sender
+    pub from: Address,
+    /// Recipient
+    pub to: Address,
+    /// Transferred value
+    pub value: U256,
+    /// Gas (quota) provided
+    pub gas: U256,
+    /// Input data
+    pub input: Data,
+    /// The type of the call: `call`, `callcode`, `delegatecall` or `staticcall`
+    #[serde(rename = "callType")]
+    pub call_type: String,
+}
+
+/// Create action
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CreateAction {
+    /// Sender
+    pub from: Address,
+    /// Transferred value
+    pub value: U256,
+    /// Gas (quota) provided
+    pub gas: U256,
+    /// Initialization code
+    pub init: Data,
+}
+
+/// Suicide action
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SuicideAction {
+    /// Suicided address
+    pub address: Address,
+    /// Address which receives the remaining balance
+    #[serde(rename = "refundAddress")]
+    pub refund_address: Address,
+    /// Balance of the suicided account
+    pub balance: U256,
+}
+
+/// Action performed by a trace
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceAction {
+    Call(CallAction),
+    Create(CreateAction),
+    Suicide(SuicideAction),
+}
+
+/// Result of a successful call
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CallResult {
+    /// Gas (quota) used
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Output bytes
+    pub output: Data,
+}
+
+/// Result of a successful create
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CreateResult {
+    /// Gas (quota) used
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Code of the newly created contract
+    pub code: Data,
+    /// Address of the newly created contract
+    pub address: Address,
+}
+
+/// Result of a traced action
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceResult {
+    Call(CallResult),
+    Create(CreateResult),
+    /// Failed call with the error message
+    FailedCall(String),
+    /// Failed create with the error message
+    FailedCreate(String),
+    None,
+}
+
+/// Trace with the position of it in the chain
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Trace {
+    pub action: TraceAction,
+    pub result: TraceResult,
+    /// Number of subtraces
+    pub subtraces: usize,
+    /// Exact location of the trace in the call tree
+    #[serde(rename = "traceAddress")]
+    pub trace_address: Vec<usize>,
+    /// Transaction index within the block
+    #[serde(rename = "transactionIndex")]
+    pub transaction_index: usize,
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+    #[serde(rename = "blockNumber")]
+    pub block_number: U256,
+    #[serde(rename = "blockHash")]
+    pub block_hash: H256,
+}
+
+/// Filter of `traceFilter`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TraceFilter {
+    /// From Block
+    #[serde(rename = "fromBlock", default, skip_serializing_if = "BlockNumber::is_default")]
+    pub from_block: BlockNumber,
+    /// To Block
+    #[serde(rename = "toBlock", default, skip_serializing_if = "BlockNumber::is_default")]
+    pub to_block: BlockNumber,
+    /// Senders, empty means any
+    #[serde(rename = "fromAddress", default, skip_serializing_if = "Vec::is_empty")]
+    pub from_address: Vec<Data20>,
+    /// Recipients, empty means any
+    #[serde(rename = "toAddress", default, skip_serializing_if = "Vec::is_empty")]
+    pub to_address: Vec<Data20>,
+}
+
+impl TraceFilter {
+    pub fn new(
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        from_address: Vec<Data20>,
+        to_address: Vec<Data20>,
+    ) -> Self {
+        TraceFilter {
+            from_block,
+            to_block,
+            from_address,
+            to_address,
+        }
+    }
+}
+
+/// Memory written by an operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MemoryDiff {
+    pub off: usize,
+    pub data: Data,
+}
+
+/// Storage written by an operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StorageDiff {
+    pub key: U256,
+    pub val: U256,
+}
+
+/// Effects of an executed operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VMExecutedOperation {
+    /// Quota left after the operation
+    pub used: U256,
+    /// Values pushed onto the stack
+    pub push: Vec<U256>,
+    pub mem: Option<MemoryDiff>,
+    pub store: Option<StorageDiff>,
+}
+
+/// A single opcode
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VMOperation {
+    /// Program counter
+    pub pc: usize,
+    /// Gas (quota) cost of the operation
+    pub cost: U256,
+    /// `None` if the operation failed
+    pub ex: Option<VMExecutedOperation>,
+    /// Trace of the call or create started by this operation
+    pub sub: Option<VMTrace>,
+}
+
+/// Opcode level trace of a call or create
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VMTrace {
+    pub code: Data,
+    pub ops: Vec<VMOperation>,
+}
+
+/// Value before and after
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ChangedType<T> {
+    pub from: T,
+    pub to: T,
+}
+
+/// Change of a value
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum Diff<T> {
+    #[serde(rename = "=")]
+    Same,
+    #[serde(rename = "+")]
+    Born(T),
+    #[serde(rename = "-")]
+    Died(T),
+    #[serde(rename = "*")]
+    Changed(ChangedType<T>),
+}
+
+impl<T, U> From<EthDiff<T>> for Diff<U>
+where
+    T: Eq,
+    U: From<T>,
+{
+    fn from(diff: EthDiff<T>) -> Self {
+        match diff {
+            EthDiff::Same => Diff::Same,
+            EthDiff::Born(post) => Diff::Born(post.into()),
+            EthDiff::Died(pre) => Diff::Died(pre.into()),
+            EthDiff::Changed(pre, post) => Diff::Changed(ChangedType {
+                from: pre.into(),
+                to: post.into(),
+            }),
+        }
+    }
+}
+
+/// Changes of an account
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AccountDiff {
+    pub balance: Diff<U256>,
+    pub nonce: Diff<U256>,
+    pub code: Diff<Data>,
+    pub storage: BTreeMap<H256, Diff<H256>>,
+}
+
+impl From<EthAccountDiff> for AccountDiff {
+    fn from(diff: EthAccountDiff) -> Self {
+        AccountDiff {
+            balance: diff.balance.into(),
+            nonce: diff.nonce.into(),
+            code: diff.code.into(),
+            storage: diff
+                .storage
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Changes of all the accounts touched
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StateDiff(pub BTreeMap<Address, AccountDiff>);
+
+impl From<EthStateDiff> for StateDiff {
+    fn from(diff: EthStateDiff) -> Self {
+        StateDiff(
+            diff.raw
+                .into_iter()
+                .map(|(address, account)| (address, account.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Result of `traceCall`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TraceCallResult {
+    pub output: Data,
+    #[serde(rename = "vmTrace")]
+    pub vm_trace: Option<VMTrace>,
+    #[serde(rename = "stateDiff")]
+    pub state_diff: Option<StateDiff>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn trace_serialization() {
+        let value = json!({
+            "action": {
+                "call": {
+                    "from": "0x0000000000000000000000000000000000000001",
+                    "to": "0x0000000000000000000000000000000000000002",
+                    "value": "0x0",
+                    "gas": "0x100",
+                    "input": "0x12",
+                    "callType": "call",
+                }
+            },
+            "result": {
+                "call": {
+                    "gasUsed": "0x10",
+                    "output": "0x",
+                }
+            },
+            "subtraces": 0,
+            "traceAddress": [],
+            "transactionIndex": 0,
+            "transactionHash": "0x0000000000000000000000000000000000000000000000000000000000000003",
+            "blockNumber": "0x1",
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000004",
+        });
+        let trace = Trace {
+            action: TraceAction::Call(CallAction {
+                from: Address::from(1),
+                to: Address::from(2),
+                value: U256::zero(),
+                gas: U256::from(0x100),
+                input: vec![0x12].into(),
+                call_type: "call".to_owned(),
+            }),
+            result: TraceResult::Call(CallResult {
+                gas_used: U256::from(0x10),
+                output: vec![].into(),
+            }),
+            subtraces: 0,
+            trace_address: vec![],
+            transaction_index: 0,
+            transaction_hash: H256::from(3),
+            block_number: U256::from(1),
+            block_hash: H256::from(4),
+        };
+        assert_eq!(serde_json::to_value(trace.clone()).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Trace>(value).unwrap(), trace);
+    }
+
+    #[test]
+    fn failed_result_serialization() {
+        let result = TraceResult::FailedCall("Out of gas".to_owned());
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"failedCall":"Out of gas"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&TraceResult::None).unwrap(),
+            r#""none""#
+        );
+    }
+
+    #[test]
+    fn state_diff_serialization() {
+        let mut storage = BTreeMap::new();
+        storage.insert(H256::from(1), EthDiff::Changed(H256::from(2), H256::from(3)));
+        let mut raw = BTreeMap::new();
+        raw.insert(
+            Address::from(10),
+            EthAccountDiff {
+                balance: EthDiff::Same,
+                nonce: EthDiff::Born(U256::from(1)),
+                code: EthDiff::Same,
+                storage: storage,
+            },
+        );
+        let diff: StateDiff = EthStateDiff { raw: raw }.into();
+        let value = json!({
+            "0x000000000000000000000000000000000000000a": {
+                "balance": "=",
+                "nonce": {"+": "0x1"},
+                "code": "=",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": {
+                        "*": {
+                            "from": "0x0000000000000000000000000000000000000000000000000000000000000002",
+                            "to": "0x0000000000000000000000000000000000000000000000000000000000000003",
+                        }
+                    }
+                },
+            }
+        });
+        assert_eq!(serde_json::to_value(diff.clone()).unwrap(), value);
+        assert_eq!(serde_json::from_value::<StateDiff>(value).unwrap(), diff);
+    }
+
+    #[test]
+    fn trace_filter_deserialization() {
+        let filter: TraceFilter = serde_json::from_str(
+            r#"{"fromBlock":"0x1","toAddress":["0x0000000000000000000000000000000000000002"]}"#,
+        ).unwrap();
+        assert_eq!(
+            filter,
+            TraceFilter::new(
+                BlockNumber::new(1u64.into()),
+                BlockNumber::latest(),
+                vec![],
+                vec![Address::from(2).into()],
+            )
+        );
+    }
+}
@@ -0,0 +1,695 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A light client which follows the block headers of a chain, and verifies
+//! the data returned by untrusted nodes against them.
+//!
+//! The signatures over the proposal of a block come with the header of the
+//! next block, and only the parent hash in the proposal covers the full
+//! content of a header, including its `state_root` and `receipts_root`.
+//! So a header is verified after two more headers are pushed, and the light
+//! client keeps them pending until then.
+//!
+//! The headers are fetched by `getBlockHeaders`, and the authorities are
+//! tracked from the `nodes` of `NodeManager` proved by `getStateProof`.
+
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate logger;
+extern crate cita_types;
+extern crate common_types as types;
+extern crate core;
+extern crate jsonrpc_types;
+extern crate proof;
+extern crate rlp;
+extern crate util;
+
+#[cfg(test)]
+extern crate bincode;
+#[cfg(test)]
+extern crate cita_crypto;
+#[cfg(test)]
+extern crate libproto;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
+
+use cita_types::{Address, H256, U256};
+use core::header::{BlockNumber, Header};
+use core::libchain::chain::TxProof;
+use jsonrpc_types::rpctypes::{ReceiptProof, StateProof, TransactionInclusionProof};
+use proof::TendermintProof;
+use types::receipt::Receipt;
+use rlp::{self, Encodable};
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use types::basic_account::BasicAccount;
+use util::hashdb::HashDB;
+use util::memorydb::MemoryDB;
+use util::{merklehash, sha3, Hashable, Trie, TrieDB, HASH_EMPTY, HASH_NULL_RLP};
+
+/// Storage slot of the `nodes` array in `NodeManager`.
+const NODES_SLOT: u64 = 3;
+/// Number of verified headers kept by default.
+const DEFAULT_CAPACITY: usize = 1024;
+
+lazy_static! {
+    /// Address of the `NodeManager` system contract.
+    pub static ref NODE_MANAGER_ADDRESS: Address =
+        Address::from_str("00000000000000000000000000000000013241a2").unwrap();
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    /// The header does not follow the latest one.
+    NotContinuous(BlockNumber),
+    /// The parent hash of the header does not match the latest one.
+    ParentMismatch(BlockNumber),
+    /// The proposal of the header is not signed by the authorities.
+    InvalidSignatures(BlockNumber),
+    /// The header is not verified, or is no longer kept.
+    UnknownHeader(BlockNumber),
+    /// The proof does not match the verified header.
+    InvalidProof,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NotContinuous(n) => write!(f, "header {} does not follow the latest one", n),
+            Error::ParentMismatch(n) => write!(f, "parent hash of header {} mismatches", n),
+            Error::InvalidSignatures(n) => write!(f, "proposal {} is not signed", n),
+            Error::UnknownHeader(n) => write!(f, "header {} is not verified", n),
+            Error::InvalidProof => write!(f, "invalid proof"),
+        }
+    }
+}
+
+pub struct LightClient {
+    authorities: Vec<Address>,
+    /// Headers whose full content is verified, by height
+    verified: BTreeMap<BlockNumber, Header>,
+    /// Headers after the latest verified one, waiting for the signatures
+    pending: VecDeque<Header>,
+    capacity: usize,
+}
+
+impl LightClient {
+    /// Start from a trusted header, e.g. the genesis, and the authorities
+    /// which sign the blocks after it.
+    pub fn new(trusted: Header, authorities: Vec<Address>) -> Self {
+        let mut verified = BTreeMap::new();
+        verified.insert(trusted.number(), trusted);
+        LightClient {
+            authorities: authorities,
+            verified: verified,
+            pending: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Set the number of verified headers kept, the oldest ones are dropped first.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = cmp::max(capacity, 1);
+        self.prune();
+    }
+
+    pub fn authorities(&self) -> &[Address] {
+        &self.authorities
+    }
+
+    /// Get the latest verified header.
+    pub fn latest_header(&self) -> &Header {
+        self.verified
+            .values()
+            .next_back()
+            .expect("at least the trusted header is kept")
+    }
+
+    /// Get a verified header by height.
+    pub fn header(&self, number: BlockNumber) -> Option<&Header> {
+        self.verified.get(&number)
+    }
+
+    /// Height of the next header to push.
+    pub fn next_number(&self) -> BlockNumber {
+        self.tip().number() + 1
+    }
+
+    fn tip(&self) -> &Header {
+        self.pending
+            .back()
+            .unwrap_or_else(|| self.latest_header())
+    }
+
+    /// Push the next header, returns the height of the header which gets
+    /// verified by it, if any.
+    pub fn push_header(&mut self, header: Header) -> Result<Option<BlockNumber>, Error> {
+        let number = header.number();
+        if number != self.next_number() {
+            return Err(Error::NotContinuous(number));
+        }
+        if *header.parent_hash() != self.tip().hash() {
+            return Err(Error::ParentMismatch(number));
+        }
+        if self.pending.len() < 2 {
+            self.pending.push_back(header);
+            return Ok(None);
+        }
+
+        // The header signs the proposal of its parent, whose parent hash
+        // covers the first pending header.
+        {
+            let proof = TendermintProof::from(header.proof().clone());
+            let parent = &self.pending[1];
+            if proof.proposal != parent.proposal_protobuf().crypt_hash()
+                || !proof.check(parent.number() as usize, &self.authorities)
+            {
+                return Err(Error::InvalidSignatures(parent.number()));
+            }
+        }
+        self.pending.push_back(header);
+        let verified = self.pending.pop_front().expect("three headers are pending");
+        let verified_number = verified.number();
+        self.verified.insert(verified_number, verified);
+        self.prune();
+        Ok(Some(verified_number))
+    }
+
+    /// Push the headers in order, returns the height of the latest verified header.
+    pub fn push_headers<I>(&mut self, headers: I) -> Result<BlockNumber, Error>
+    where
+        I: IntoIterator<Item = Header>,
+    {
+        for header in headers {
+            self.push_header(header)?;
+        }
+        Ok(self.latest_header().number())
+    }
+
+    fn prune(&mut self) {
+        while self.verified.len() > self.capacity {
+            let oldest = *self.verified.keys().next().expect("more than one header");
+            self.verified.remove(&oldest);
+        }
+    }
+
+    fn verified_header(&self, number: BlockNumber) -> Result<&Header, Error> {
+        self.header(number).ok_or(Error::UnknownHeader(number))
+    }
+
+    /// Verify that the receipt is in the block.
+    pub fn verify_receipt(
+        &self,
+        number: BlockNumber,
+        receipt: &Receipt,
+        proof: &merklehash::MerkleProof,
+    ) -> Result<(), Error> {
+        let header = self.verified_header(number)?;
+        let hash = Some(receipt.clone()).rlp_bytes().into_vec().crypt_hash();
+        if merklehash::verify_proof(*header.receipts_root(), proof, hash) {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Verify that the transaction is in the block.
+    pub fn verify_transaction(
+        &self,
+        number: BlockNumber,
+        tx_hash: H256,
+        proof: &merklehash::MerkleProof,
+    ) -> Result<(), Error> {
+        let header = self.verified_header(number)?;
+        if merklehash::verify_proof(*header.transactions_root(), proof, tx_hash) {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Verify a proof returned by `getTransactionProof` against the verified header.
+    pub fn verify_tx_proof(&self, proof: &TxProof) -> Result<(), Error> {
+        let header = self.verified_header(proof.block_header().number())?;
+        if header.hash() == proof.block_header().hash() && proof.verify(&self.authorities) {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Verify a proof returned by `getReceiptProof` against the verified header.
+    pub fn verify_receipt_proof(&self, proof: &ReceiptProof) -> Result<(), Error> {
+        let header = self.verified_header(proof.block_number.low_u64())?;
+        if header.hash() == proof.block_hash
+            && *header.receipts_root() == proof.receipts_root
+            && proof.verify()
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Verify a proof returned by `getTransactionInclusionProof` against the verified header.
+    pub fn verify_transaction_inclusion_proof(
+        &self,
+        proof: &TransactionInclusionProof,
+    ) -> Result<(), Error> {
+        let header = self.verified_header(proof.block_number.low_u64())?;
+        if header.hash() == proof.block_hash
+            && *header.transactions_root() == proof.transactions_root
+            && proof.verify()
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Verify a proof returned by `getStateProof` against the state root of the
+    /// verified header.
+    pub fn verify_state_proof(&self, number: BlockNumber, proof: &StateProof) -> Result<(), Error> {
+        let header = self.verified_header(number)?;
+        if check_state_proof(header.state_root(), proof) {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof)
+        }
+    }
+
+    /// Update the authorities from the `nodes` of `NodeManager` in the state
+    /// of the verified header, proved by `getStateProof` with the keys of
+    /// `node_manager_keys`. The update applies to the headers pushed later,
+    /// so it should be done at the height where the new nodes take effect.
+    pub fn update_authorities(
+        &mut self,
+        number: BlockNumber,
+        proof: &StateProof,
+    ) -> Result<(), Error> {
+        self.verify_state_proof(number, proof)?;
+        if proof.address != *NODE_MANAGER_ADDRESS {
+            return Err(Error::InvalidProof);
+        }
+        let values: BTreeMap<H256, H256> = proof
+            .storage_proof
+            .iter()
+            .map(|storage| (storage.key, storage.value))
+            .collect();
+        let count = values
+            .get(&H256::from(NODES_SLOT))
+            .map(|count| U256::from(count).low_u64() as usize)
+            .ok_or(Error::InvalidProof)?;
+        let authorities = node_manager_keys(count)
+            .iter()
+            .skip(1)
+            .map(|key| values.get(key).map(|value| Address::from(*value)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::InvalidProof)?;
+        info!("Authorities are updated at height {}: {:?}", number, authorities);
+        self.authorities = authorities;
+        Ok(())
+    }
+}
+
+/// The storage keys of the length and the first `count` items of the `nodes`
+/// array in `NodeManager`.
+pub fn node_manager_keys(count: usize) -> Vec<H256> {
+    let slot = H256::from(NODES_SLOT);
+    let start = U256::from(sha3(&*slot));
+    let mut keys = vec![slot];
+    keys.extend((0..count).map(|i| H256::from(start + U256::from(i))));
+    keys
+}
+
+/// Check the account and the storage slots in the proof against the state root.
+pub fn check_state_proof(state_root: &H256, proof: &StateProof) -> bool {
+    let proved = match get_proved(state_root, &proof.account_proof, &proof.address) {
+        Some(value) => value.map(|value| rlp::decode::<BasicAccount>(&value)),
+        None => return false,
+    };
+    let account = BasicAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        storage_root: proof.storage_hash,
+        code_hash: proof.code_hash,
+        abi_hash: proof.abi_hash,
+    };
+    let is_account_valid = match proved {
+        Some(proved) => proved == account,
+        None => {
+            account.balance.is_zero()
+                && account.storage_root == HASH_NULL_RLP
+                && account.code_hash == HASH_EMPTY
+                && account.abi_hash == HASH_EMPTY
+        }
+    };
+    is_account_valid
+        && proof.storage_proof.iter().all(|storage| {
+            match get_proved(&account.storage_root, &storage.proof, &storage.key) {
+                Some(value) => {
+                    let value = value.map_or_else(U256::zero, |value| rlp::decode(&value));
+                    H256::from(value) == storage.value
+                }
+                None => false,
+            }
+        })
+}
+
+/// Look up the key in the trie made of the proof nodes, `None` if the nodes
+/// are not enough to tell whether the key exists.
+fn get_proved<N, K>(root: &H256, nodes: &[N], key: &K) -> Option<Option<Vec<u8>>>
+where
+    N: Clone + Into<Vec<u8>>,
+    K: AsRef<[u8]>,
+{
+    let mut db = MemoryDB::new();
+    for node in nodes {
+        let node: Vec<u8> = node.clone().into();
+        db.insert(&node);
+    }
+    let trie = TrieDB::new(&db, root).ok()?;
+    trie.get(key.as_ref())
+        .ok()
+        .map(|value| value.map(|value| value.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::{serialize, Infinite};
+    use cita_crypto::{CreateKey, KeyPair, Sign, Signature};
+    use jsonrpc_types::rpctypes::{Data, Receipt as RpcReceipt, StorageProof};
+    use libproto::blockchain::Proof;
+    use util::trie::Recorder;
+    use util::{TrieDBMut, TrieMut};
+
+    #[allow(dead_code)]
+    #[derive(Serialize)]
+    enum Step {
+        Propose,
+        Prevote,
+        Precommit,
+    }
+
+    fn sign_proposal(key_pair: &KeyPair, header: &Header) -> Proof {
+        let sender = key_pair.address().clone();
+        let mut proof = TendermintProof::default();
+        proof.height = header.number() as usize;
+        proof.proposal = header.proposal_protobuf().crypt_hash();
+        let msg = serialize(
+            &(
+                proof.height,
+                proof.round,
+                Step::Precommit,
+                sender.clone(),
+                Some(proof.proposal.clone()),
+            ),
+            Infinite,
+        ).unwrap();
+        let signature = Signature::sign(key_pair.privkey(), &msg.crypt_hash().into()).unwrap();
+        proof.commits.insert(sender, signature.into());
+        proof.into()
+    }
+
+    /// Build the next header which signs the proposal of its parent.
+    fn next_header(key_pair: &KeyPair, parent: &Header) -> Header {
+        let mut header = Header::new();
+        header.set_number(parent.number() + 1);
+        header.set_parent_hash(parent.hash());
+        header.set_state_root(H256::from(parent.number() + 1));
+        header.set_proof(sign_proposal(key_pair, parent));
+        header
+    }
+
+    /// Collect the trie nodes on the path to the key, in order from the root.
+    fn prove(db: &MemoryDB, root: &H256, key: &[u8]) -> Vec<Data> {
+        let mut recorder = Recorder::new();
+        let trie = TrieDB::new(db, root).unwrap();
+        let _ = trie
+            .get_with(key, (&mut recorder, |value: &[u8]| value.to_vec()))
+            .unwrap();
+        recorder
+            .drain()
+            .into_iter()
+            .map(|record| Data::new(record.data))
+            .collect()
+    }
+
+    /// Build a state in which `NodeManager` holds the nodes, returns the state
+    /// root and the proof of the keys of `node_manager_keys`.
+    fn node_manager_state(nodes: &[Address]) -> (H256, StateProof) {
+        let mut db = MemoryDB::new();
+        let keys = node_manager_keys(nodes.len());
+        let mut values = vec![H256::from(nodes.len() as u64)];
+        values.extend(
+            nodes
+                .iter()
+                .map(|node| H256::from_slice(&[&[0u8; 12][..], &node[..]].concat())),
+        );
+
+        let mut storage_root = HASH_NULL_RLP;
+        {
+            let mut trie = TrieDBMut::new(&mut db, &mut storage_root);
+            for (key, value) in keys.iter().zip(values.iter()) {
+                let value = rlp::encode(&U256::from(value)).into_vec();
+                trie.insert(key, &value).unwrap();
+            }
+        }
+        let account = BasicAccount {
+            nonce: U256::zero(),
+            balance: U256::zero(),
+            storage_root: storage_root,
+            code_hash: H256::from(1),
+            abi_hash: HASH_EMPTY,
+        };
+        let mut state_root = HASH_NULL_RLP;
+        {
+            let mut trie = TrieDBMut::new(&mut db, &mut state_root);
+            let account = rlp::encode(&account).into_vec();
+            trie.insert(&*NODE_MANAGER_ADDRESS, &account).unwrap();
+        }
+
+        let proof = StateProof {
+            address: *NODE_MANAGER_ADDRESS,
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            abi_hash: account.abi_hash,
+            storage_hash: storage_root,
+            account_proof: prove(&db, &state_root, &*NODE_MANAGER_ADDRESS),
+            storage_proof: keys
+                .iter()
+                .zip(values.into_iter())
+                .map(|(key, value)| StorageProof {
+                    key: *key,
+                    value: value,
+                    proof: prove(&db, &storage_root, key),
+                })
+                .collect(),
+        };
+        (state_root, proof)
+    }
+
+    #[test]
+    fn follow_headers() {
+        let key_pair = KeyPair::gen_keypair();
+        let genesis = Header::new();
+        let mut client = LightClient::new(genesis.clone(), vec![key_pair.address().clone()]);
+
+        let h1 = next_header(&key_pair, &genesis);
+        let h2 = next_header(&key_pair, &h1);
+        let h3 = next_header(&key_pair, &h2);
+        assert_eq!(client.push_header(h1.clone()), Ok(None));
+        assert_eq!(client.push_header(h2.clone()), Ok(None));
+        assert_eq!(client.header(1), None);
+        assert_eq!(client.push_header(h3.clone()), Ok(Some(1)));
+        assert_eq!(client.latest_header(), &h1);
+
+        let h4 = next_header(&key_pair, &h3);
+        assert_eq!(client.push_headers(vec![h4]), Ok(2));
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let key_pair = KeyPair::gen_keypair();
+        let other = KeyPair::gen_keypair();
+        let genesis = Header::new();
+        let mut client = LightClient::new(genesis.clone(), vec![key_pair.address().clone()]);
+
+        let h1 = next_header(&key_pair, &genesis);
+        let h2 = next_header(&key_pair, &h1);
+        client.push_headers(vec![h1.clone(), h2.clone()]).unwrap();
+
+        let mut forked = next_header(&key_pair, &h1);
+        forked.set_number(3);
+        assert_eq!(client.push_header(forked), Err(Error::ParentMismatch(3)));
+        assert_eq!(client.push_header(next_header(&key_pair, &h1)), Err(Error::NotContinuous(2)));
+        assert_eq!(client.push_header(next_header(&other, &h2)), Err(Error::InvalidSignatures(2)));
+        assert_eq!(client.push_header(next_header(&key_pair, &h2)), Ok(Some(1)));
+    }
+
+    #[test]
+    fn verify_receipt() {
+        let receipts: Vec<Option<Receipt>> = (0..3u64)
+            .map(|i| {
+                Some(Receipt::new(
+                    None,
+                    U256::from(i),
+                    Vec::new(),
+                    None,
+                    U256::zero(),
+                    H256::from(i),
+                ))
+            })
+            .collect();
+        let tree = merklehash::MerkleTree::from_bytes(
+            receipts.iter().map(|r| r.rlp_bytes().into_vec()),
+        );
+        let mut genesis = Header::new();
+        genesis.set_receipts_root(tree.get_root_hash());
+        let client = LightClient::new(genesis, Vec::new());
+
+        let proof = tree.get_proof_by_input_index(1).unwrap();
+        let receipt = receipts[1].clone().unwrap();
+        assert_eq!(client.verify_receipt(0, &receipt, &proof), Ok(()));
+
+        let mut tampered = receipt.clone();
+        tampered.gas_used = U256::from(100);
+        assert_eq!(
+            client.verify_receipt(0, &tampered, &proof),
+            Err(Error::InvalidProof)
+        );
+        assert_eq!(
+            client.verify_receipt(1, &receipt, &proof),
+            Err(Error::UnknownHeader(1))
+        );
+    }
+
+    #[test]
+    fn verify_state_proof() {
+        let nodes = vec![Address::from(1), Address::from(2)];
+        let (state_root, proof) = node_manager_state(&nodes);
+        let mut genesis = Header::new();
+        genesis.set_state_root(state_root);
+        let client = LightClient::new(genesis, Vec::new());
+        assert_eq!(client.verify_state_proof(0, &proof), Ok(()));
+        assert_eq!(
+            client.verify_state_proof(1, &proof),
+            Err(Error::UnknownHeader(1))
+        );
+
+        let mut tampered = proof.clone();
+        tampered.balance = U256::from(1);
+        assert_eq!(
+            client.verify_state_proof(0, &tampered),
+            Err(Error::InvalidProof)
+        );
+
+        let mut tampered = proof.clone();
+        tampered.storage_proof[1].value = H256::from(3);
+        assert_eq!(
+            client.verify_state_proof(0, &tampered),
+            Err(Error::InvalidProof)
+        );
+
+        let mut tampered = proof.clone();
+        tampered.account_proof.pop();
+        assert_eq!(
+            client.verify_state_proof(0, &tampered),
+            Err(Error::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn update_authorities() {
+        let key_pair = KeyPair::gen_keypair();
+        let (state_root, proof) = node_manager_state(&[key_pair.address().clone()]);
+        let mut genesis = Header::new();
+        genesis.set_state_root(state_root);
+        let mut client = LightClient::new(genesis.clone(), vec![Address::from(1)]);
+
+        let mut incomplete = proof.clone();
+        incomplete.storage_proof.pop();
+        assert_eq!(
+            client.update_authorities(0, &incomplete),
+            Err(Error::InvalidProof)
+        );
+        assert_eq!(client.authorities(), &[Address::from(1)]);
+
+        assert_eq!(client.update_authorities(0, &proof), Ok(()));
+        assert_eq!(client.authorities(), &[key_pair.address().clone()]);
+
+        // The headers signed by the new authorities are accepted.
+        let h1 = next_header(&key_pair, &genesis);
+        let h2 = next_header(&key_pair, &h1);
+        let h3 = next_header(&key_pair, &h2);
+        assert_eq!(client.push_headers(vec![h1, h2, h3]), Ok(1));
+    }
+
+    #[test]
+    fn verify_receipt_proof() {
+        let receipt = Receipt::new(
+            None,
+            U256::from(100),
+            Vec::new(),
+            None,
+            U256::zero(),
+            H256::from(1),
+        );
+        let encoded = Some(receipt.clone()).rlp_bytes().into_vec();
+        let mut genesis = Header::new();
+        genesis.set_receipts_root(encoded.crypt_hash());
+        let client = LightClient::new(genesis.clone(), Vec::new());
+
+        let proof = ReceiptProof {
+            transaction_hash: H256::from(1),
+            transaction_index: U256::zero(),
+            transactions_count: U256::one(),
+            block_hash: genesis.hash(),
+            block_number: U256::zero(),
+            receipts_root: *genesis.receipts_root(),
+            receipt_rlp: Data::new(encoded),
+            receipt: RpcReceipt {
+                transaction_hash: Some(H256::from(1)),
+                transaction_index: Some(U256::zero()),
+                block_hash: Some(genesis.hash()),
+                block_number: Some(U256::zero()),
+                cumulative_gas_used: U256::from(100),
+                gas_used: Some(U256::from(100)),
+                contract_address: None,
+                logs: Vec::new(),
+                state_root: None,
+                logs_bloom: receipt.log_bloom,
+                error_message: None,
+            },
+            proof: Vec::new(),
+        };
+        assert_eq!(client.verify_receipt_proof(&proof), Ok(()));
+
+        let mut tampered = proof.clone();
+        tampered.receipt.cumulative_gas_used = U256::from(200);
+        assert_eq!(
+            client.verify_receipt_proof(&tampered),
+            Err(Error::InvalidProof)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An in-process message queue with the same interface as `pubsub`.
+//!
+//! All services which call `start_pubsub` in one process share a single broker,
+//! so they can run together without RabbitMQ or Kafka.
+//! Routing keys are matched like a RabbitMQ topic exchange:
+//! `*` matches exactly one word and `#` matches zero or more words.
+
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate logger;
+
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+struct Subscriber {
+    name: String,
+    keys: Vec<String>,
+    tx: Sender<(String, Vec<u8>)>,
+}
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+}
+
+fn matches_words(pattern: &[&str], key: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => key.is_empty(),
+        Some((&"#", rest)) => (0..key.len() + 1).any(|skip| matches_words(rest, &key[skip..])),
+        Some((&word, rest)) => match key.split_first() {
+            Some((&key_word, key_rest)) => {
+                (word == "*" || word == key_word) && matches_words(rest, key_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+pub fn matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let key: Vec<&str> = key.split('.').collect();
+    matches_words(&pattern, &key)
+}
+
+fn publish(key: String, msg: Vec<u8>) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    subscribers.retain(|subscriber| {
+        if !subscriber.keys.iter().any(|pattern| matches(pattern, &key)) {
+            return true;
+        }
+        let sent = subscriber.tx.send((key.clone(), msg.clone())).is_ok();
+        if !sent {
+            info!("subscriber {} is gone", subscriber.name);
+        }
+        sent
+    });
+}
+
+/// Subscribe `keys` into `tx`, and publish everything received from `rx`.
+pub fn start_pubsub(
+    name: &str,
+    keys: Vec<String>,
+    tx: Sender<(String, Vec<u8>)>,
+    rx: Receiver<(String, Vec<u8>)>,
+) {
+    SUBSCRIBERS.lock().unwrap().push(Subscriber {
+        name: name.to_owned(),
+        keys: keys,
+        tx: tx,
+    });
+
+    thread::spawn(move || loop {
+        match rx.recv() {
+            Ok((key, msg)) => publish(key, msg),
+            Err(_) => break,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    #[test]
+    fn topic_matches() {
+        assert!(matches("chain.status", "chain.status"));
+        assert!(!matches("chain.status", "chain.rich_status"));
+        assert!(matches("chain.*", "chain.status"));
+        assert!(!matches("*", "chain.status"));
+        assert!(matches("#", "chain.status"));
+        assert!(matches("chain.#", "chain"));
+        assert!(matches("#.status", "net.chain.status"));
+        assert!(!matches("#.status", "net.chain.request"));
+    }
+
+    #[test]
+    fn publish_between_services() {
+        let (tx_sub_a, rx_sub_a) = channel();
+        let (tx_pub_a, rx_pub_a) = channel();
+        start_pubsub("test_a", vec!["test.b_to_a".to_owned()], tx_sub_a, rx_pub_a);
+
+        let (tx_sub_b, rx_sub_b) = channel();
+        let (tx_pub_b, rx_pub_b) = channel();
+        start_pubsub("test_b", vec!["test.a_to_b".to_owned()], tx_sub_b, rx_pub_b);
+
+        tx_pub_a.send(("test.a_to_b".to_owned(), vec![1])).unwrap();
+        tx_pub_b.send(("test.b_to_a".to_owned(), vec![2])).unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            rx_sub_b.recv_timeout(timeout).unwrap(),
+            ("test.a_to_b".to_owned(), vec![1])
+        );
+        assert_eq!(
+            rx_sub_a.recv_timeout(timeout).unwrap(),
+            ("test.b_to_a".to_owned(), vec![2])
+        );
+    }
+}
@@ -1,6 +1,7 @@
 pragma solidity ^0.4.18;
 
 import "./error.sol";
+import "./node_manager.sol";
 
 
 /// @title Chain Manager
@@ -42,6 +43,19 @@ contract ChainManager is Error {
         }
     }
 
+    // Only the admins of NodeManager can change the authorities, which
+    // the CrossChainMessenger verifies the proofs from other chains with.
+    modifier onlyAdmin {
+        // NodeManager Contract
+        address nodeManagerAddr = 0x00000000000000000000000000000000013241a2;
+        if (NodeInterface(nodeManagerAddr).isAdmin(msg.sender))
+            _;
+        else {
+            ErrorLog(ErrorType.NotAdmin, "Not the admin account");
+            return;
+        }
+    }
+
     // Constructor.
     function ChainManager(uint32 _pid, address[] _addrs)
         public
@@ -113,6 +127,20 @@ contract ChainManager is Error {
         sideChains[id].status = ChainStatus.Disable;
     }
 
+    // Update the nodes of the parent chain or a side chain.
+    function updateAuthorities(uint32 id, address[] addrs)
+        public
+        onlyAdmin
+    {
+        require(addrs.length > 0);
+        if (parentChainId != 0 && parentChainId == id) {
+            parentChainNodes = addrs;
+        } else {
+            require(sideChains[id].status != ChainStatus.Unknown);
+            sideChains[id].nodes = addrs;
+        }
+    }
+
     function getAuthorities(uint32 id)
         public
         view
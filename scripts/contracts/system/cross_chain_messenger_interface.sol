@@ -0,0 +1,32 @@
+pragma solidity ^0.4.18;
+
+
+/// @title The interface of the native CrossChainMessenger at 0x1302
+/// @author ["Cryptape Technologies <contact@cryptape.com>"]
+interface CrossChainMessengerInterface {
+
+    event CrossChainMessage(uint32 fromChainId, uint32 toChainId, uint nonce, address sender, address destContract, uint gasLimit, bytes payload);
+    event CrossChainAck(uint32 fromChainId, uint32 toChainId, uint nonce, address sender, bool success);
+
+    /// @notice Send the payload to the contract of another chain, which is called with _gasLimit
+    function sendMessage(uint32 _toChainId, address _destContract, bytes _payload, uint _gasLimit) public returns (uint);
+    /// @notice Deliver the message of the log at _logIndex with the transaction proof from the source chain
+    function deliverMessage(bytes _txProof, uint _logIndex) public;
+    /// @notice Deliver the ack of the log at _logIndex with the transaction proof from the destination chain
+    function deliverAck(bytes _txProof, uint _logIndex) public;
+    /// @notice Check whether the message from a chain is delivered
+    function isDelivered(uint32 _fromChainId, uint _nonce) view public returns (bool);
+    /// @notice Get the ack status of a message sent to a chain: 0 pending, 1 success, 2 failure
+    function getAckStatus(uint32 _toChainId, uint _nonce) view public returns (uint);
+}
+
+
+/// @title The interface implemented by the contracts using CrossChainMessenger
+/// @author ["Cryptape Technologies <contact@cryptape.com>"]
+interface CrossChainReceiverInterface {
+
+    /// @notice Called by CrossChainMessenger with a message from another chain
+    function receiveMessage(uint32 _fromChainId, address _sender, bytes _payload) public;
+    /// @notice Called by CrossChainMessenger with the ack of a message sent
+    function onMessageAck(uint32 _toChainId, uint _nonce, bool _success) public;
+}
@@ -301,6 +301,8 @@ module.exports = {
         constant: false, inputs: [{ name: 'sideChainId', type: 'uint32' }, { name: 'addrs', type: 'address[]' }], name: 'newSideChain', outputs: [], payable: false, stateMutability: 'nonpayable', type: 'function',
       }, {
         constant: false, inputs: [{ name: 'id', type: 'uint32' }], name: 'disableSideChain', outputs: [], payable: false, stateMutability: 'nonpayable', type: 'function',
+      }, {
+        constant: false, inputs: [{ name: 'id', type: 'uint32' }, { name: 'addrs', type: 'address[]' }], name: 'updateAuthorities', outputs: [], payable: false, stateMutability: 'nonpayable', type: 'function',
       }, {
         constant: true, inputs: [{ name: '', type: 'uint32' }], name: 'sideChains', outputs: [{ name: 'status', type: 'uint8' }], payable: false, stateMutability: 'view', type: 'function',
       }, {
@@ -34,6 +34,15 @@ const disableSideChain = function disableSideChain(id, _sender = sender) {
   );
 };
 
+// updateAuthorities
+const updateAuthorities = function updateAuthorities(id, address, _sender = sender) {
+  return sContractInstance.updateAuthorities.sendTransaction(
+    id,
+    address,
+    genTxParams(_sender),
+  );
+};
+
 // getChainId
 const getChainId = function getChainId() {
   return sContractInstance.getChainId.call();
@@ -46,13 +55,14 @@ const getParentChainId = function getParentChainId() {
 
 // Get the nodes of side chain
 const getAuthorities = function getAuthoriti(id) {
-  return sContractInstance.getAuthoritirs.call(id);
+  return sContractInstance.getAuthorities.call(id);
 };
 
 module.exports = {
   newSideChain,
   enableSideChain,
   disableSideChain,
+  updateAuthorities,
   getChainId,
   getParentChainId,
   getAuthorities,
@@ -8,7 +8,8 @@ const config = require('../config');
 const { getTxReceipt, logger } = util;
 
 const {
-  newSideChain, enableSideChain, disableSideChain, getChainId, getParentChainId,
+  newSideChain, enableSideChain, disableSideChain, updateAuthorities, getAuthorities,
+  getChainId, getParentChainId,
 } = chainManager;
 
 const { describe, it, before } = mocha;
@@ -74,6 +75,52 @@ describe('test side chain management contract', () => {
     });
   });
 
+  describe('\ntest update authorities of side chain\n', () => {
+    const authorities = config.testAddr.slice(1);
+
+    it('should send a updateAuthorities tx and get receipt', (done) => {
+      const res = updateAuthorities(100, authorities);
+
+      getTxReceipt(res)
+        .then((receipt) => {
+          logger.debug('\nSend ok and get receipt:\n', receipt);
+          assert.equal(receipt.errorMessage, null, JSON.stringify(receipt.errorMessage));
+          done();
+        })
+        .catch((err) => {
+          logger.error('\n!!!!Get updateAuthorities receipt err:!!!!\n', err);
+          this.skip();
+        });
+    });
+
+    it('should have the new authorities', () => {
+      const res = getAuthorities(100);
+      logger.debug('\nThe authorities of side chain:\n', res);
+      assert.deepEqual(res, authorities);
+    });
+
+    it('should send a updateAuthorities tx by a non-admin and get receipt', (done) => {
+      const res = updateAuthorities(100, config.testAddr.slice(0, 1), config.testSender);
+
+      getTxReceipt(res)
+        .then((receipt) => {
+          logger.debug('\nSend ok and get receipt:\n', receipt);
+          assert.equal(receipt.errorMessage, null, JSON.stringify(receipt.errorMessage));
+          done();
+        })
+        .catch((err) => {
+          logger.error('\n!!!!Get updateAuthorities receipt err:!!!!\n', err);
+          this.skip();
+        });
+    });
+
+    it('should not change the authorities by a non-admin', () => {
+      const res = getAuthorities(100);
+      logger.debug('\nThe authorities of side chain:\n', res);
+      assert.deepEqual(res, authorities);
+    });
+  });
+
   describe('\ntest disable side chain\n', () => {
     it('should send a disableSideChain tx and get receipt', (done) => {
       // let res = disableSideChain(chainId);
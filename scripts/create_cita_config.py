@@ -113,9 +113,9 @@ class AddressList(list):
             if addr['host'] == host and addr['port'] == port:
                 raise Exception('address {}:{} has been added twice'.format(
                     host, port))
-        self.append(dict(host=host, port=port, signer=''))
+        self.append(dict(host=host, port=port, signer='', address=''))
 
-    def add_signers(self, signers):
+    def add_signers(self, signers, addresses=None):
         if len(self) != len(signers):
             raise Exception('Size of signers [{}] is not equal to'
                             ' size of addresses [{}].'.format(
@@ -123,6 +123,8 @@ class AddressList(list):
         size = len(self)
         for idx in range(0, size):
             self[idx]['signer'] = signers[idx]
+            if addresses:
+                self[idx]['address'] = addresses[idx]
 
 
 class AuthorityList(list):
@@ -268,11 +270,19 @@ class ChainInfo(object):
         with open(consensus_config, 'wt') as stream:
             stream.write(node['signer'])
 
+        # Peers are authenticated by the address of their node key
+        peer = dict(id_card=node_id, ip=node['host'], port=node['port'])
+        if node.get('address'):
+            peer['address'] = node['address']
+        else:
+            logging.warning('The address of node %d is unknown, add it to'
+                            ' the peers in network.toml, or the node will'
+                            ' not be connected.', node_id)
+
         network_config = os.path.join(self.configs_dir, 'network.toml')
         with open(network_config, 'rt') as stream:
             network_data = toml.load(stream)
-            network_data['peers'].append(
-                dict(id_card=node_id, ip=node['host'], port=node['port']))
+            network_data['peers'].append(peer)
         with open(network_config, 'wt') as stream:
             toml.dump(network_data, stream)
 
@@ -281,8 +291,7 @@ class ChainInfo(object):
             network_config = os.path.join(old_dir, 'network.toml')
             with open(network_config, 'rt') as stream:
                 network_data = toml.load(stream)
-                network_data['peers'].append(
-                    dict(id_card=node_id, ip=node['host'], port=node['port']))
+                network_data['peers'].append(peer)
             with open(network_config, 'wt') as stream:
                 toml.dump(network_data, stream)
 
@@ -409,7 +418,7 @@ def parse_arguments():
                 logging.critical('Both --authorities and --nodes is empty.')
                 sys.exit(1)
             authorities, signers = generate_authorities(len(args.nodes))
-            args.nodes.add_signers(signers)
+            args.nodes.add_signers(signers, authorities)
             setattr(args, 'authorities', authorities)
         for val in (('authorities', 'NodeManager', 'nodes'),
                     ('chain_name', 'SysConfig', 'chain_name')):
@@ -429,8 +438,8 @@ def parse_arguments():
         if args.signer:
             args.node.add_signers([args.signer])
         else:
-            _, signers = generate_authorities(1)
-            args.node.add_signers(signers)
+            authorities, signers = generate_authorities(1)
+            args.node.add_signers(signers, authorities)
     else:
         logging.critical('Please select a valid subcommand.')
         sys.exit(1)
@@ -25,6 +25,7 @@ for binary in \
         cita-auth \
         cita-bft \
         cita-chain \
+        cita-devchain \
         cita-executor \
         cita-forever \
         cita-jsonrpc \
@@ -32,6 +33,7 @@ for binary in \
         create_key_addr \
         cita-relayer-parser \
         snapshot_tool \
+        genesis_tool \
         consensus-mock \
         ; do
     cp -rf "target/${type}/${binary}" target/install/bin/
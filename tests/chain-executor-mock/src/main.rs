@@ -17,7 +17,15 @@
 
 #![feature(try_from)]
 extern crate bincode;
+#[cfg(feature = "channel")]
+extern crate cita_auth;
+#[cfg(feature = "channel")]
+extern crate cita_chain;
 extern crate cita_crypto as crypto;
+#[cfg(feature = "channel")]
+extern crate cita_executor;
+#[cfg(feature = "channel")]
+extern crate cita_jsonrpc;
 extern crate cita_types;
 extern crate clap;
 extern crate common_types;
@@ -32,7 +40,10 @@ extern crate util;
 
 #[macro_use]
 extern crate logger;
+#[cfg(not(feature = "channel"))]
 extern crate pubsub;
+#[cfg(feature = "channel")]
+extern crate pubsub_channel as pubsub;
 extern crate rlp;
 #[macro_use]
 extern crate serde_derive;
@@ -46,6 +57,8 @@ use std::io::Read;
 use std::str::FromStr;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "channel")]
+use std::thread;
 use std::time;
 use std::{fs, u8};
 
@@ -129,11 +142,30 @@ fn send_block(
         .unwrap();
 }
 
+/// Runs the services of the node in `node_dir` in this process, connected to
+/// the mock by in-process channels, so no message queue is needed.
+#[cfg(feature = "channel")]
+fn start_node(node_dir: Option<&str>) {
+    if let Some(node_dir) = node_dir {
+        std::env::set_current_dir(node_dir).expect("Failed to enter the node directory.");
+    }
+    dotenv::dotenv().ok();
+    if std::env::var("DATA_PATH").is_err() {
+        std::env::set_var("DATA_PATH", "./data");
+    }
+    // The mock subscribes first and chain starts last, so the first status of
+    // chain reaches the mock.
+    thread::spawn(|| cita_executor::run("executor.toml", "genesis.json"));
+    thread::spawn(|| cita_auth::run("auth.toml", false));
+    thread::spawn(|| cita_jsonrpc::run("jsonrpc.toml"));
+    thread::spawn(|| cita_chain::run("chain.toml"));
+}
+
 fn main() {
     logger::init();
     info!("CITA:Chain executor mock");
 
-    let matches = App::new("Chain executor mock")
+    let app = App::new("Chain executor mock")
         .version("0.1")
         .author("Cryptape")
         .arg(
@@ -143,8 +175,10 @@ fn main() {
                 .required(true)
                 .takes_value(true)
                 .help("YAML format mock data"),
-        )
-        .get_matches();
+        );
+    #[cfg(feature = "channel")]
+    let app = app.arg_from_usage("-d, --node-dir=[DIR] 'Runs the node in DIR in process'");
+    let matches = app.get_matches();
 
     let mock_data_path = matches.value_of("mock-data").unwrap();
     let mut mock_data_string = String::new();
@@ -165,8 +199,13 @@ fn main() {
         tx_sub,
         rx_pub,
     );
-    let amqp_url = std::env::var("AMQP_URL").expect("AMQP_URL empty");
-    info!("AMQP_URL={}", amqp_url);
+    #[cfg(not(feature = "channel"))]
+    {
+        let amqp_url = std::env::var("AMQP_URL").expect("AMQP_URL empty");
+        info!("AMQP_URL={}", amqp_url);
+    }
+    #[cfg(feature = "channel")]
+    start_node(matches.value_of("node-dir"));
     let sys_time = Arc::new(Mutex::new(time::SystemTime::now()));
 
     let privkey: PrivKey = {
@@ -237,4 +276,10 @@ fn main() {
         }
     }
     info!("[[DONE]]");
+
+    // The node goes away with the mock, so keep it for the checks.
+    #[cfg(feature = "channel")]
+    loop {
+        thread::park();
+    }
 }
@@ -1,15 +1,28 @@
 #![feature(try_from)]
 extern crate bincode;
 extern crate chrono;
+#[cfg(feature = "channel")]
+extern crate cita_auth;
+#[cfg(feature = "channel")]
+extern crate cita_chain;
+#[cfg(feature = "channel")]
+extern crate cita_executor;
+#[cfg(feature = "channel")]
+extern crate cita_jsonrpc;
 extern crate cita_crypto as crypto;
 #[macro_use]
 extern crate clap;
+#[cfg(feature = "channel")]
+extern crate dotenv;
 #[macro_use]
 extern crate libproto;
 #[macro_use]
 extern crate logger;
 extern crate proof;
+#[cfg(not(feature = "channel"))]
 extern crate pubsub;
+#[cfg(feature = "channel")]
+extern crate pubsub_channel as pubsub;
 #[macro_use]
 extern crate serde_derive;
 extern crate cita_types as types;
@@ -27,6 +40,8 @@ use pubsub::start_pubsub;
 use std::collections::HashMap;
 use std::convert::{Into, TryFrom, TryInto};
 use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+#[cfg(feature = "channel")]
+use std::thread;
 use std::thread::sleep;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use types::{Address, H256};
@@ -118,12 +133,31 @@ fn send_block(
         .unwrap();
 }
 
+/// Runs the services of the node in `node_dir` in this process, connected to
+/// the mock by in-process channels, so no message queue is needed.
+#[cfg(feature = "channel")]
+fn start_node(node_dir: Option<&str>) {
+    if let Some(node_dir) = node_dir {
+        std::env::set_current_dir(node_dir).expect("Failed to enter the node directory.");
+    }
+    dotenv::dotenv().ok();
+    if std::env::var("DATA_PATH").is_err() {
+        std::env::set_var("DATA_PATH", "./data");
+    }
+    // The mock subscribes first and chain starts last, so the first status of
+    // chain reaches the mock.
+    thread::spawn(|| cita_executor::run("executor.toml", "genesis.json"));
+    thread::spawn(|| cita_auth::run("auth.toml", false));
+    thread::spawn(|| cita_jsonrpc::run("jsonrpc.toml"));
+    thread::spawn(|| cita_chain::run("chain.toml"));
+}
+
 fn main() {
     logger::init();
     info!("CITA: Consensus Mock");
 
     // set up the clap to receive info from CLI
-    let matches = App::new("consensus mock")
+    let app = App::new("consensus mock")
         .version("0.1")
         .author("Cryptape")
         .about("Mock the process of consensus")
@@ -134,8 +168,10 @@ fn main() {
                 .required(true)
                 .takes_value(true)
                 .help("Set the path of mock data in YAML format"),
-        )
-        .get_matches();
+        );
+    #[cfg(feature = "channel")]
+    let app = app.arg_from_usage("-d, --node-dir=[DIR] 'Runs the node in DIR in process'");
+    let matches = app.get_matches();
 
     let default_interval = 3;
     // get the mock data and parse it to serde_yaml format
@@ -152,6 +188,8 @@ fn main() {
         tx_sub,
         rx_pub,
     );
+    #[cfg(feature = "channel")]
+    start_node(matches.value_of("node-dir"));
 
     let mut received_block_txs: HashMap<usize, BlockTxs> = HashMap::new();
 
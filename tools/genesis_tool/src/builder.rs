@@ -0,0 +1,628 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deploy the system contracts in a temporary state, and dump the state as the
+//! `alloc` of the genesis.
+//!
+//! The contracts are constructed by the admin at their final addresses, while
+//! `create_genesis.py` constructs them by a test account at other addresses and
+//! copies the code and storage. None of the constructors reads the sender, its
+//! own address or another contract, so both give the same state.
+
+use cita_types::traits::LowerHex;
+use cita_types::{Address, H256, U256};
+use config::GenesisConfig;
+use contracts::SystemContracts;
+use core_executor::contracts::encode_contract_name;
+use core_executor::engines::NullEngine;
+use core_executor::executive::Executive;
+use core_executor::journaldb;
+use core_executor::libexecutor::executor::EconomicalModel;
+use core_executor::libexecutor::genesis::{Contract, Spec};
+use core_executor::native::factory::Factory as NativeFactory;
+use core_executor::state::{State, Substate};
+use core_executor::state_db::StateDB;
+use core_executor::trace::{NoopTracer, NoopVMTracer};
+use ethabi::{self, Token};
+use evm::action_params::{ActionParams, ActionValue};
+use evm::call_type::CallType;
+use evm::env_info::EnvInfo;
+use evm::{Factory, VMType};
+use rustc_hex::ToHex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use util::kvdb::in_memory;
+use util::BytesRef;
+
+/// Same as the start gas used by `create_genesis.py`.
+const GAS: u64 = 30_000_000;
+
+pub fn build(
+    config: &GenesisConfig,
+    contracts: &SystemContracts,
+    prevhash: H256,
+    timestamp: u64,
+) -> Result<Spec, String> {
+    let mut deployer = Deployer::new(config.admin);
+    for contract in &contracts.normal_contracts {
+        let (code, types) = contracts.compile(&contract.name, &contract.file)?;
+        let args = match constructor_args(&contract.name, config) {
+            Some(args) => args,
+            None if types.is_empty() => Vec::new(),
+            None => {
+                return Err(format!(
+                    "{}: the arguments of the constructor are unknown",
+                    contract.name
+                ))
+            }
+        };
+        if args.len() != types.len() {
+            return Err(format!(
+                "{}: the constructor takes {} arguments, but {} are given",
+                contract.name,
+                types.len(),
+                args.len()
+            ));
+        }
+        deployer.deploy(&contract.name, contract.address, code, &args)?;
+    }
+    let (permission_code, _) = contracts.compile("Permission", &contracts.permission_file)?;
+    for permission in &contracts.permission_contracts {
+        let args = [
+            Token::FixedBytes(to_bytes32(&permission.name)),
+            Token::Array(
+                permission
+                    .contracts
+                    .iter()
+                    .map(|address| Token::Address(address.0))
+                    .collect(),
+            ),
+            Token::Array(
+                permission
+                    .functions
+                    .iter()
+                    .map(|function| Token::FixedBytes(function.clone()))
+                    .collect(),
+            ),
+        ];
+        deployer.deploy(&permission.name, permission.address, permission_code.clone(), &args)?;
+    }
+    set_quotas(&mut deployer, config, contracts)?;
+
+    let mut alloc = deployer.alloc();
+    add_accounts(&mut alloc, config, contracts)?;
+
+    Ok(Spec {
+        alloc,
+        prevhash,
+        timestamp,
+    })
+}
+
+/// Add the admin and the validators with the initial balance, and the accounts
+/// of `alloc`, which must not be any of them or a system contract.
+fn add_accounts(
+    alloc: &mut HashMap<String, Contract>,
+    config: &GenesisConfig,
+    contracts: &SystemContracts,
+) -> Result<(), String> {
+    let admin_and_validators = Some(config.admin)
+        .into_iter()
+        .chain(config.validators.iter().map(|v| v.address));
+    for address in admin_and_validators {
+        alloc.insert(
+            hex_address(&address),
+            Contract {
+                nonce: "1".to_owned(),
+                code: "".to_owned(),
+                storage: HashMap::new(),
+                value: Some(config.token.initial_balance),
+            },
+        );
+    }
+    for account in &config.alloc {
+        let key = hex_address(&account.address);
+        if let Some((name, _)) = contracts
+            .addresses()
+            .into_iter()
+            .find(|&(_, address)| address == account.address)
+        {
+            return Err(format!("alloc: {} is the address of system contract {}", key, name));
+        }
+        if alloc.contains_key(&key) {
+            return Err(format!("alloc: {} is the address of the admin or a validator", key));
+        }
+        alloc.insert(
+            key,
+            Contract {
+                nonce: "1".to_owned(),
+                code: account.code.clone(),
+                storage: account.storage.clone().into_iter().collect(),
+                value: account.value,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// The constructor arguments in the same order and with the same values as the
+/// `init_data.yml` written by `create_init_data.py`.
+///
+/// `None` for a contract which is not known here.
+fn constructor_args(name: &str, config: &GenesisConfig) -> Option<Vec<Token>> {
+    let chain = &config.chain;
+    let token = &config.token;
+    let admin = Token::Address(config.admin.0);
+    let args = match name {
+        "SysConfig" => vec![
+            uint(chain.delay_block_number),
+            Token::Bool(chain.check_permission),
+            Token::Bool(chain.check_quota),
+            Token::String(chain.chain_name.clone()),
+            uint(u64::from(chain.chain_id)),
+            Token::String(chain.operator.clone()),
+            Token::String(chain.website.clone()),
+            uint(chain.block_interval),
+            uint(u64::from(chain.economical_model)),
+            Token::String(token.name.clone()),
+            Token::String(token.symbol.clone()),
+            Token::String(token.avatar.clone()),
+        ],
+        "NodeManager" => vec![
+            Token::Array(
+                config
+                    .validators
+                    .iter()
+                    .map(|validator| Token::Address(validator.address.0))
+                    .collect(),
+            ),
+            Token::Array(vec![admin]),
+            Token::Array(
+                config
+                    .validators
+                    .iter()
+                    .map(|validator| uint(validator.stake))
+                    .collect(),
+            ),
+        ],
+        "QuotaManager" | "Authorization" => vec![admin],
+        "ChainManager" => vec![
+            uint(u64::from(chain.parent_chain_id)),
+            Token::Array(
+                chain
+                    .parent_chain_authorities
+                    .iter()
+                    .map(|address| Token::Address(address.0))
+                    .collect(),
+            ),
+        ],
+        "Group" => vec![
+            Token::Address(Address::zero().0),
+            Token::FixedBytes(to_bytes32("rootGroup")),
+            Token::Array(vec![admin]),
+        ],
+        _ => return None,
+    };
+    Some(args)
+}
+
+/// Set the quotas by calling `QuotaManager` as the admin.
+fn set_quotas(
+    deployer: &mut Deployer,
+    config: &GenesisConfig,
+    contracts: &SystemContracts,
+) -> Result<(), String> {
+    let calls = quota_calls(config);
+    if calls.is_empty() {
+        return Ok(());
+    }
+    let quota_manager = contracts
+        .normal_contracts
+        .iter()
+        .find(|contract| contract.name == "QuotaManager")
+        .map(|contract| contract.address)
+        .ok_or_else(|| "QuotaManager is not in the system contracts".to_owned())?;
+    for (name, data) in calls {
+        deployer.call(name, quota_manager, data)?;
+    }
+    Ok(())
+}
+
+/// The calls to `QuotaManager` which set the quotas of the config.
+fn quota_calls(config: &GenesisConfig) -> Vec<(&'static str, Vec<u8>)> {
+    let quota = &config.quota;
+    let mut calls = Vec::new();
+    if let Some(limit) = quota.block_quota_limit {
+        let data = encode_call("setBQL(uint256)", &[uint(limit)]);
+        calls.push(("QuotaManager.setBQL", data));
+    }
+    if let Some(limit) = quota.default_account_quota_limit {
+        let data = encode_call("setDefaultAQL(uint256)", &[uint(limit)]);
+        calls.push(("QuotaManager.setDefaultAQL", data));
+    }
+    for account in &quota.accounts {
+        let data = encode_call(
+            "setAQL(address,uint256)",
+            &[Token::Address(account.address.0), uint(account.quota)],
+        );
+        calls.push(("QuotaManager.setAQL", data));
+    }
+    calls
+}
+
+struct Deployer {
+    state: State<StateDB>,
+    info: EnvInfo,
+    engine: NullEngine,
+    factory: Factory,
+    native_factory: NativeFactory,
+    sender: Address,
+}
+
+impl Deployer {
+    fn new(sender: Address) -> Self {
+        let journal_db = journaldb::new(
+            Arc::new(in_memory(8)),
+            journaldb::Algorithm::Archive,
+            ::core_executor::db::COL_STATE,
+        );
+        let state_db = StateDB::new(journal_db, 5 * 1024 * 1024);
+        Deployer {
+            state: State::new(state_db, 0.into(), Default::default()),
+            info: EnvInfo::default(),
+            engine: NullEngine::default(),
+            factory: Factory::new(VMType::Interpreter, 1024 * 32),
+            native_factory: NativeFactory::default(),
+            sender,
+        }
+    }
+
+    fn params(&self, address: Address) -> ActionParams {
+        let mut params = ActionParams::default();
+        params.code_address = address;
+        params.address = address;
+        params.sender = self.sender;
+        params.origin = self.sender;
+        params.gas = U256::from(GAS);
+        params
+    }
+
+    /// Run the constructor, and put the contract at `address`.
+    fn deploy(
+        &mut self,
+        name: &str,
+        address: Address,
+        mut code: Vec<u8>,
+        args: &[Token],
+    ) -> Result<(), String> {
+        code.extend(ethabi::encode(args));
+        let mut params = self.params(address);
+        params.value = ActionValue::Apparent(0.into());
+        params.code = Some(Arc::new(code));
+        let mut substate = Substate::new();
+        let result = Executive::new(
+            &mut self.state,
+            &self.info,
+            &self.engine,
+            &self.factory,
+            &self.native_factory,
+            false,
+            EconomicalModel::Quota,
+        ).create(params, &mut substate, &mut NoopTracer, &mut NoopVMTracer);
+        match result {
+            Ok(ref result) if result.apply_state => Ok(()),
+            Ok(_) => Err(format!("constructor of {} is reverted", name)),
+            Err(err) => Err(format!("constructor of {} failed: {:?}", name, err)),
+        }
+    }
+
+    fn call(&mut self, name: &str, address: Address, data: Vec<u8>) -> Result<(), String> {
+        let mut params = self.params(address);
+        params.value = ActionValue::Transfer(0.into());
+        params.code = self.state.code(&address).map_err(|err| format!("{:?}", err))?;
+        params.code_hash = self
+            .state
+            .code_hash(&address)
+            .map_err(|err| format!("{:?}", err))?;
+        params.data = Some(data);
+        params.call_type = CallType::Call;
+        let mut substate = Substate::new();
+        let mut output = Vec::new();
+        let result = Executive::new(
+            &mut self.state,
+            &self.info,
+            &self.engine,
+            &self.factory,
+            &self.native_factory,
+            false,
+            EconomicalModel::Quota,
+        ).call(
+            params,
+            &mut substate,
+            BytesRef::Flexible(&mut output),
+            &mut NoopTracer,
+            &mut NoopVMTracer,
+        );
+        match result {
+            Ok(ref result) if result.apply_state => Ok(()),
+            Ok(_) => Err(format!("{} is reverted", name)),
+            Err(err) => Err(format!("{} failed: {:?}", name, err)),
+        }
+    }
+
+    /// The contracts in the state, which is never committed.
+    fn alloc(&self) -> HashMap<String, Contract> {
+        self.state
+            .cache()
+            .iter()
+            .filter_map(|(address, entry)| {
+                let account = entry.account()?;
+                let code = account.code().filter(|code| !code.is_empty())?;
+                let storage = account
+                    .storage_changes()
+                    .iter()
+                    .filter(|&(_, value)| !value.is_zero())
+                    .map(|(key, value)| {
+                        (
+                            format!("0x{}", key.lower_hex()),
+                            format!("0x{}", value.lower_hex()),
+                        )
+                    })
+                    .collect();
+                let contract = Contract {
+                    nonce: account.nonce().to_string(),
+                    code: format!("0x{}", code.to_hex()),
+                    storage,
+                    value: None,
+                };
+                Some((hex_address(address), contract))
+            })
+            .collect()
+    }
+}
+
+fn encode_call(function: &str, args: &[Token]) -> Vec<u8> {
+    let mut data = encode_contract_name(function.as_bytes());
+    data.extend(ethabi::encode(args));
+    data
+}
+
+fn uint(value: u64) -> Token {
+    Token::Uint(H256::from(value).0)
+}
+
+/// Encode a name as `bytes32`, the same as `create_genesis.py`.
+fn to_bytes32(name: &str) -> Vec<u8> {
+    let mut bytes = name.as_bytes().to_vec();
+    bytes.resize(32, 0);
+    bytes
+}
+
+fn hex_address(address: &Address) -> String {
+    format!("0x{}", address.lower_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate mktemp;
+
+    use self::mktemp::Temp;
+    use super::{add_accounts, build, constructor_args, encode_call, quota_calls, uint};
+    use cita_types::{Address, H256, U256};
+    use config::GenesisConfig;
+    use contracts::SystemContracts;
+    use core_executor::db::{COL_STATE, NUM_COLUMNS};
+    use core_executor::factory::Factories;
+    use core_executor::journaldb;
+    use core_executor::libexecutor::block::Block;
+    use core_executor::libexecutor::genesis::{Genesis, Spec};
+    use core_executor::state_db::StateDB;
+    use ethabi::Token;
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::Path;
+    use std::process::Command;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use toml;
+    use util::kvdb::in_memory;
+
+    const SCRIPTS_DIR: &str = "../../scripts";
+    const TIMESTAMP: u64 = 1524000000000;
+
+    /// The genesis written by `create_genesis.py` with the default `init_data.yml`.
+    fn create_genesis_py(dir: &Path) -> Spec {
+        let scripts = Path::new(SCRIPTS_DIR);
+        let init_data = dir.join("init_data.yml");
+        let genesis = dir.join("genesis.json");
+        let status = Command::new("python3")
+            .arg(scripts.join("config_tool/create_init_data.py"))
+            .arg("--output")
+            .arg(&init_data)
+            .status()
+            .expect("failed to run create_init_data.py");
+        assert!(status.success());
+        let status = Command::new("python3")
+            .arg(scripts.join("config_tool/create_genesis.py"))
+            .arg("--output")
+            .arg(&genesis)
+            .arg("--init_data_file")
+            .arg(&init_data)
+            .arg("--contracts_dir")
+            .arg(scripts.join("contracts"))
+            .arg("--timestamp")
+            .arg(TIMESTAMP.to_string())
+            .status()
+            .expect("failed to run create_genesis.py");
+        assert!(status.success());
+        Spec::load(genesis.to_str().unwrap()).unwrap()
+    }
+
+    fn state_root(spec: Spec) -> H256 {
+        let journal_db = journaldb::new(
+            Arc::new(in_memory(NUM_COLUMNS.unwrap())),
+            journaldb::Algorithm::Archive,
+            COL_STATE,
+        );
+        let state_db = StateDB::new(journal_db, 5 * 1024 * 1024);
+        let mut genesis = Genesis {
+            spec,
+            block: Block::default(),
+        };
+        genesis
+            .lazy_execute(&state_db, &Factories::default())
+            .unwrap();
+        *genesis.block.header().state_root()
+    }
+
+    fn codes(spec: &Spec) -> BTreeMap<String, String> {
+        spec.alloc
+            .iter()
+            .map(|(address, contract)| (address.to_lowercase(), contract.code.to_lowercase()))
+            .collect()
+    }
+
+    #[test]
+    fn same_as_create_genesis() {
+        let dir = Temp::new_dir().unwrap();
+        let expected = create_genesis_py(&dir.to_path_buf());
+
+        let contracts = SystemContracts::load(&format!("{}/contracts", SCRIPTS_DIR)).unwrap();
+        let config = GenesisConfig::default();
+        let spec = build(&config, &contracts, H256::zero(), TIMESTAMP).unwrap();
+
+        assert_eq!(spec.timestamp, expected.timestamp);
+        assert_eq!(spec.prevhash, expected.prevhash);
+        assert_eq!(codes(&spec), codes(&expected));
+        assert_eq!(state_root(spec), state_root(expected));
+    }
+
+    const CONFIG: &str = r#"
+admin = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+
+[chain]
+chain_id = 2
+delay_block_number = 3
+check_permission = true
+economical_model = 1
+parent_chain_id = 1
+parent_chain_authorities = ["0x0000000000000000000000000000000000000009"]
+
+[[validators]]
+address = "0x0000000000000000000000000000000000000001"
+stake = 2
+
+[[validators]]
+address = "0x0000000000000000000000000000000000000002"
+stake = 3
+
+[quota]
+block_quota_limit = 1073741824
+
+[[quota.accounts]]
+address = "0x0000000000000000000000000000000000000001"
+quota = 4194304
+
+[[alloc]]
+address = "0x0000000000000000000000000000000000000003"
+value = "0x10"
+"#;
+
+    fn address(value: u64) -> Token {
+        Token::Address(Address::from(value).0)
+    }
+
+    #[test]
+    fn arguments_of_config() {
+        let config: GenesisConfig = toml::from_str(CONFIG).unwrap();
+        let admin = Address::from_str("2e988a386a799f506693793c6a5af6b54dfaabfb").unwrap();
+        let admin = Token::Address(admin.0);
+
+        let sys_config = constructor_args("SysConfig", &config).unwrap();
+        assert_eq!(sys_config[0], uint(3));
+        assert_eq!(sys_config[1], Token::Bool(true));
+        assert_eq!(sys_config[2], Token::Bool(false));
+        assert_eq!(sys_config[4], uint(2));
+        assert_eq!(sys_config[8], uint(1));
+        assert_eq!(
+            constructor_args("NodeManager", &config),
+            Some(vec![
+                Token::Array(vec![address(1), address(2)]),
+                Token::Array(vec![admin.clone()]),
+                Token::Array(vec![uint(2), uint(3)]),
+            ])
+        );
+        assert_eq!(
+            constructor_args("Authorization", &config),
+            Some(vec![admin.clone()])
+        );
+        assert_eq!(
+            constructor_args("ChainManager", &config),
+            Some(vec![uint(1), Token::Array(vec![address(9)])])
+        );
+        assert_eq!(constructor_args("Unknown", &config), None);
+
+        assert_eq!(
+            quota_calls(&config),
+            vec![
+                (
+                    "QuotaManager.setBQL",
+                    encode_call("setBQL(uint256)", &[uint(1 << 30)]),
+                ),
+                (
+                    "QuotaManager.setAQL",
+                    encode_call("setAQL(address,uint256)", &[address(1), uint(1 << 22)]),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn alloc_of_config() {
+        let contracts = SystemContracts::load(&format!("{}/contracts", SCRIPTS_DIR)).unwrap();
+        let mut config: GenesisConfig = toml::from_str(CONFIG).unwrap();
+        let mut alloc = HashMap::new();
+        add_accounts(&mut alloc, &config, &contracts).unwrap();
+        assert_eq!(alloc.len(), 4);
+        assert_eq!(
+            alloc["0x0000000000000000000000000000000000000001"].value,
+            Some(config.token.initial_balance)
+        );
+        assert_eq!(
+            alloc["0x0000000000000000000000000000000000000003"].value,
+            Some(U256::from(0x10))
+        );
+
+        config.alloc[0].address = Address::from(2);
+        assert_eq!(
+            add_accounts(&mut HashMap::new(), &config, &contracts),
+            Err(
+                "alloc: 0x0000000000000000000000000000000000000002 is the address of the admin \
+                 or a validator"
+                    .to_owned()
+            )
+        );
+        config.alloc[0].address = Address::from(0x31415926);
+        assert_eq!(
+            add_accounts(&mut HashMap::new(), &config, &contracts),
+            Err(
+                "alloc: 0x0000000000000000000000000000000031415926 is the address of system \
+                 contract SysConfig"
+                    .to_owned()
+            )
+        );
+    }
+}
@@ -0,0 +1,349 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The declarative config of a new chain.
+//!
+//! Every value has the same default as `scripts/config_tool/create_init_data.py`,
+//! so the default config builds the same genesis as `create_genesis.py` with the
+//! default `init_data.yml`.
+
+use cita_types::traits::LowerHex;
+use cita_types::{Address, U256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::str::FromStr;
+use toml;
+
+// Limits checked by `QuotaManager`.
+const MAX_QUOTA_LIMIT: u64 = (1 << 63) - 1;
+const MIN_QUOTA_LIMIT: u64 = (1 << 22) - 1;
+const MIN_BLOCK_QUOTA_LIMIT: u64 = 1 << 28;
+
+/// The super admin and the only validator of the default chain.
+const DEFAULT_ADMIN: &str = "4b5ae4567ad5d9fb92bc9afd6a657e6fa13a2523";
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisConfig {
+    /// Timestamp of the genesis block in milliseconds, the current time if not set.
+    pub timestamp: Option<u64>,
+    /// Super admin of the chain.
+    pub admin: Address,
+    pub chain: ChainConfig,
+    pub token: TokenConfig,
+    pub validators: Vec<Validator>,
+    pub quota: QuotaConfig,
+    /// Extra accounts in the genesis block.
+    pub alloc: Vec<AllocAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainConfig {
+    pub chain_id: u32,
+    pub chain_name: String,
+    pub operator: String,
+    pub website: String,
+    /// Interval between blocks in milliseconds.
+    pub block_interval: u64,
+    pub delay_block_number: u64,
+    pub check_permission: bool,
+    pub check_quota: bool,
+    /// 0 for quota, 1 for charge.
+    pub economical_model: u8,
+    /// 0 if the chain has no parent chain.
+    pub parent_chain_id: u32,
+    pub parent_chain_authorities: Vec<Address>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    pub name: String,
+    pub symbol: String,
+    pub avatar: String,
+    /// Balance of the admin and each validator.
+    pub initial_balance: U256,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Validator {
+    pub address: Address,
+    #[serde(default)]
+    pub stake: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaConfig {
+    /// The default of `QuotaManager` if not set.
+    pub block_quota_limit: Option<u64>,
+    /// The default of `QuotaManager` if not set.
+    pub default_account_quota_limit: Option<u64>,
+    #[serde(default)]
+    pub accounts: Vec<AccountQuota>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountQuota {
+    pub address: Address,
+    pub quota: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllocAccount {
+    pub address: Address,
+    pub value: Option<U256>,
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+}
+
+fn default_admin() -> Address {
+    Address::from_str(DEFAULT_ADMIN).unwrap()
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        GenesisConfig {
+            timestamp: None,
+            admin: default_admin(),
+            chain: ChainConfig::default(),
+            token: TokenConfig::default(),
+            validators: vec![Validator {
+                address: default_admin(),
+                stake: 0,
+            }],
+            quota: QuotaConfig::default(),
+            alloc: Vec::new(),
+        }
+    }
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig {
+            chain_id: 1,
+            chain_name: "test-chain".to_owned(),
+            operator: "test-operator".to_owned(),
+            website: "https://www.example.com".to_owned(),
+            block_interval: 3000,
+            delay_block_number: 1,
+            check_permission: false,
+            check_quota: false,
+            economical_model: 0,
+            parent_chain_id: 0,
+            parent_chain_authorities: Vec::new(),
+        }
+    }
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        TokenConfig {
+            name: "Nervos".to_owned(),
+            symbol: "NOS".to_owned(),
+            avatar: "https://avatars1.githubusercontent.com/u/35361817".to_owned(),
+            initial_balance: U256::from_str("ffffffffffffffffffffffffff").unwrap(),
+        }
+    }
+}
+
+impl GenesisConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("can not read {}: {}", path, err))?;
+        toml::from_str(&content).map_err(|err| format!("{} is malformed: {}", path, err))
+    }
+
+    /// Check the values which the system contracts would refuse or ignore silently.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        let chain = &self.chain;
+        if chain.chain_id == 0 {
+            errors.push("chain.chain_id: 0 is reserved for no parent chain".to_owned());
+        }
+        if chain.block_interval == 0 {
+            errors.push("chain.block_interval: must be greater than 0".to_owned());
+        }
+        if chain.economical_model > 1 {
+            errors.push(format!(
+                "chain.economical_model: {} is neither 0 (quota) nor 1 (charge)",
+                chain.economical_model
+            ));
+        }
+        if chain.parent_chain_id == 0 && !chain.parent_chain_authorities.is_empty() {
+            errors.push(
+                "chain.parent_chain_authorities: must be empty without a parent chain".to_owned(),
+            );
+        }
+        if chain.parent_chain_id != 0 && chain.parent_chain_authorities.is_empty() {
+            errors.push(format!(
+                "chain.parent_chain_authorities: parent chain {} has no authorities",
+                chain.parent_chain_id
+            ));
+        }
+        if self.admin.is_zero() {
+            errors.push("admin: must not be the zero address".to_owned());
+        }
+        if self.validators.is_empty() {
+            errors.push("validators: at least one validator is required".to_owned());
+        }
+        let mut validators = HashSet::new();
+        for validator in &self.validators {
+            if !validators.insert(validator.address) {
+                errors.push(format!(
+                    "validators: 0x{} is duplicated",
+                    validator.address.lower_hex()
+                ));
+            }
+        }
+        if let Some(limit) = self.quota.block_quota_limit {
+            if limit < MIN_BLOCK_QUOTA_LIMIT || limit > MAX_QUOTA_LIMIT {
+                errors.push(format!(
+                    "quota.block_quota_limit: {} is not in [{}, {}]",
+                    limit, MIN_BLOCK_QUOTA_LIMIT, MAX_QUOTA_LIMIT
+                ));
+            }
+        }
+        if let Some(limit) = self.quota.default_account_quota_limit {
+            if limit < MIN_QUOTA_LIMIT || limit > MAX_QUOTA_LIMIT {
+                errors.push(format!(
+                    "quota.default_account_quota_limit: {} is not in [{}, {}]",
+                    limit, MIN_QUOTA_LIMIT, MAX_QUOTA_LIMIT
+                ));
+            }
+        }
+        for account in &self.quota.accounts {
+            if account.quota < MIN_QUOTA_LIMIT || account.quota > MAX_QUOTA_LIMIT {
+                errors.push(format!(
+                    "quota.accounts: quota {} of 0x{} is not in [{}, {}]",
+                    account.quota,
+                    account.address.lower_hex(),
+                    MIN_QUOTA_LIMIT,
+                    MAX_QUOTA_LIMIT
+                ));
+            }
+        }
+        let mut accounts = HashSet::new();
+        for account in &self.alloc {
+            if !accounts.insert(account.address) {
+                errors.push(format!(
+                    "alloc: 0x{} is duplicated",
+                    account.address.lower_hex()
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GenesisConfig;
+    use cita_types::Address;
+    use std::str::FromStr;
+    use toml;
+
+    #[test]
+    fn default_values() {
+        let config: GenesisConfig = toml::from_str("").unwrap();
+        assert!(config.timestamp.is_none());
+        assert_eq!(config.admin, super::default_admin());
+        assert_eq!(config.chain.chain_id, 1);
+        assert_eq!(config.chain.block_interval, 3000);
+        assert_eq!(config.chain.delay_block_number, 1);
+        assert_eq!(config.token.symbol, "NOS");
+        assert_eq!(config.validators.len(), 1);
+        assert_eq!(config.validators[0].address, super::default_admin());
+        assert!(config.quota.block_quota_limit.is_none());
+        assert!(config.alloc.is_empty());
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn override_values() {
+        let config = r#"
+admin = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+
+[chain]
+chain_id = 2
+
+[[validators]]
+address = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+stake = 1
+"#;
+        let config: GenesisConfig = toml::from_str(config).unwrap();
+        let address = Address::from_str("2e988a386a799f506693793c6a5af6b54dfaabfb").unwrap();
+        assert_eq!(config.admin, address);
+        assert_eq!(config.chain.chain_id, 2);
+        assert_eq!(config.chain.chain_name, "test-chain");
+        assert_eq!(config.validators.len(), 1);
+        assert_eq!(config.validators[0].address, address);
+        assert_eq!(config.validators[0].stake, 1);
+    }
+
+    #[test]
+    fn validate() {
+        let config = r#"
+[chain]
+chain_id = 0
+parent_chain_id = 2
+
+[[validators]]
+address = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+
+[[validators]]
+address = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+
+[quota]
+block_quota_limit = 1024
+
+[[alloc]]
+address = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+
+[[alloc]]
+address = "0x2e988a386a799f506693793c6a5af6b54dfaabfb"
+"#;
+        let config: GenesisConfig = toml::from_str(config).unwrap();
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                "chain.chain_id: 0 is reserved for no parent chain".to_owned(),
+                "chain.parent_chain_authorities: parent chain 2 has no authorities".to_owned(),
+                "validators: 0x2e988a386a799f506693793c6a5af6b54dfaabfb is duplicated".to_owned(),
+                "quota.block_quota_limit: 1024 is not in [268435456, 9223372036854775807]"
+                    .to_owned(),
+                "alloc: 0x2e988a386a799f506693793c6a5af6b54dfaabfb is duplicated".to_owned(),
+            ])
+        );
+    }
+
+    #[test]
+    fn reject_unknown_fields() {
+        assert!(toml::from_str::<GenesisConfig>("[chain]\nchain_id = 1").is_ok());
+        assert!(toml::from_str::<GenesisConfig>("[chain]\nchainid = 1").is_err());
+    }
+}
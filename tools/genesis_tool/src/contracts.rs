@@ -0,0 +1,233 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The system contracts listed in `contracts.yml` of the contracts folder.
+
+use cita_types::{clean_0x, Address};
+use core_executor::contracts::encode_contract_name;
+use ethabi::{self, ParamType};
+use rustc_hex::FromHex;
+use serde_json;
+use serde_yaml;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+#[derive(Debug, Deserialize)]
+struct ContractsFile {
+    #[serde(rename = "NormalContracts")]
+    normal_contracts: Vec<BTreeMap<String, ContractInfo>>,
+    #[serde(rename = "PermissionContracts")]
+    permission_contracts: PermissionContractsInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContractInfo {
+    address: String,
+    file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PermissionContractsInfo {
+    file: String,
+    basic: Vec<BTreeMap<String, PermissionInfo>>,
+    contracts: Vec<BTreeMap<String, PermissionInfo>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PermissionInfo {
+    address: String,
+    #[serde(default)]
+    contracts: Vec<String>,
+    #[serde(default)]
+    functions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Compiled {
+    contracts: HashMap<String, CompiledContract>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompiledContract {
+    bin: String,
+    abi: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalContract {
+    pub name: String,
+    pub address: Address,
+    pub file: String,
+}
+
+/// A `Permission` contract, with the resources it grants.
+#[derive(Debug, Clone)]
+pub struct PermissionContract {
+    pub name: String,
+    pub address: Address,
+    pub contracts: Vec<Address>,
+    pub functions: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SystemContracts {
+    dir: PathBuf,
+    pub normal_contracts: Vec<NormalContract>,
+    pub permission_file: String,
+    pub permission_contracts: Vec<PermissionContract>,
+}
+
+impl SystemContracts {
+    pub fn load(dir: &str) -> Result<Self, String> {
+        let path = Path::new(dir).join("contracts.yml");
+        let file = File::open(&path)
+            .map_err(|err| format!("can not open {}: {}", path.display(), err))?;
+        let contracts_file: ContractsFile = serde_yaml::from_reader(file)
+            .map_err(|err| format!("{} is malformed: {}", path.display(), err))?;
+
+        let mut normal_contracts = Vec::new();
+        for (name, info) in contracts_file.normal_contracts.into_iter().flat_map(|m| m) {
+            normal_contracts.push(NormalContract {
+                address: parse_address(&info.address)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?,
+                name,
+                file: info.file,
+            });
+        }
+
+        let mut permission_contracts = Vec::new();
+        let permission_info = contracts_file.permission_contracts;
+        for (name, info) in permission_info.basic.into_iter().flat_map(|m| m) {
+            let address = parse_address(&info.address)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            // A basic permission grants the permission itself with an empty function.
+            permission_contracts.push(PermissionContract {
+                name,
+                address,
+                contracts: vec![address],
+                functions: vec![vec![0; 4]],
+            });
+        }
+        for (name, info) in permission_info.contracts.into_iter().flat_map(|m| m) {
+            let mut contracts = Vec::new();
+            for contract in &info.contracts {
+                let address = normal_contracts
+                    .iter()
+                    .find(|normal| &normal.name == contract)
+                    .map(|normal| normal.address)
+                    .ok_or_else(|| {
+                        format!(
+                            "{}: permission {} uses unknown contract {}",
+                            path.display(),
+                            name,
+                            contract
+                        )
+                    })?;
+                contracts.push(address);
+            }
+            permission_contracts.push(PermissionContract {
+                address: parse_address(&info.address)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?,
+                name,
+                contracts,
+                functions: info
+                    .functions
+                    .iter()
+                    .map(|function| encode_contract_name(function.as_bytes()))
+                    .collect(),
+            });
+        }
+
+        Ok(SystemContracts {
+            dir: PathBuf::from(dir),
+            normal_contracts,
+            permission_file: permission_info.file,
+            permission_contracts,
+        })
+    }
+
+    /// Names and addresses of all system contracts.
+    pub fn addresses(&self) -> Vec<(&str, Address)> {
+        self.normal_contracts
+            .iter()
+            .map(|contract| (contract.name.as_str(), contract.address))
+            .chain(
+                self.permission_contracts
+                    .iter()
+                    .map(|contract| (contract.name.as_str(), contract.address)),
+            )
+            .collect()
+    }
+
+    /// Compile a contract by `solc`, and return its deploy code and the types
+    /// of its constructor arguments.
+    pub fn compile(&self, name: &str, file: &str) -> Result<(Vec<u8>, Vec<ParamType>), String> {
+        let path = self.dir.join(file);
+        let output = Command::new("solc")
+            .arg(format!("common={}", self.dir.join("common").display()))
+            .arg("--combined-json")
+            .arg("bin,abi")
+            .arg(&path)
+            .output()
+            .map_err(|err| format!("failed to execute solc: {}", err))?;
+        if !output.status.success() {
+            return Err(format!(
+                "solc failed to compile {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let compiled: Compiled = serde_json::from_slice(&output.stdout)
+            .map_err(|err| format!("unknown output of solc: {}", err))?;
+        let suffix = format!(":{}", name);
+        compiled
+            .contracts
+            .iter()
+            .find(|&(key, _)| key.ends_with(&suffix))
+            .ok_or_else(|| format!("contract {} is not found in {}", name, path.display()))
+            .and_then(|(_, contract)| {
+                let code = contract
+                    .bin
+                    .from_hex()
+                    .map_err(|err| format!("bad code of {}: {}", name, err))?;
+                let abi = ethabi::Contract::load(contract.abi.as_bytes())
+                    .map_err(|err| format!("bad abi of {}: {:?}", name, err))?;
+                let types = abi
+                    .constructor
+                    .map(|constructor| {
+                        constructor
+                            .inputs
+                            .into_iter()
+                            .map(|param| param.kind)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok((code, types))
+            })
+    }
+}
+
+pub fn parse_address(s: &str) -> Result<Address, String> {
+    let hex = clean_0x(s);
+    if hex.len() != 40 {
+        return Err(format!("{} is not an address", s));
+    }
+    Address::from_str(hex).map_err(|_| format!("{} is not an address", s))
+}
@@ -0,0 +1,195 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Build, check and hash the resource of a `genesis.json`.
+//!
+//! - `build` deploys the system contracts with the values in a declarative
+//!   config, and writes the genesis with the hash of the resource folder next
+//!   to the output file.
+//! - `check` reports all problems of an existing genesis, which would make the
+//!   executor refuse to start.
+//! - `resource_hash` prints the hash of a resource folder, which is the
+//!   `prevhash` of the genesis.
+
+extern crate cita_types;
+#[macro_use]
+extern crate clap;
+extern crate core_executor;
+extern crate ethabi;
+extern crate evm;
+extern crate logger;
+extern crate rustc_hex;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_yaml;
+extern crate toml;
+extern crate util;
+
+mod builder;
+mod config;
+mod contracts;
+
+use cita_types::traits::LowerHex;
+use cita_types::{clean_0x, H256};
+use config::GenesisConfig;
+use contracts::SystemContracts;
+use core_executor::libexecutor::genesis::{resource_hash, Spec};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    logger::init();
+
+    let matches = clap_app!(genesis_tool =>
+        (version: "0.1")
+        (author: "Cryptape Technologies")
+        (about: "CITA Genesis Tool by Rust")
+        (@setting SubcommandRequiredElseHelp)
+        (@subcommand build =>
+            (about: "Build a genesis from a declarative config.")
+            (@arg Config: -c --config +takes_value "Input a toml config of the chain, the default chain if not set.")
+            (@arg ContractsDir: -d --contracts_dir +takes_value +required "Input the directory of the system contracts.")
+            (@arg Output: -o --output +takes_value +required "Input the path of the output genesis.")
+        )
+        (@subcommand check =>
+            (about: "Check a genesis.")
+            (@arg Genesis: -g --genesis +takes_value +required "Input the path of the genesis.")
+            (@arg ContractsDir: -d --contracts_dir +takes_value "Input the directory of the system contracts to check they are all in the genesis.")
+        )
+        (@subcommand resource_hash =>
+            (about: "Compute the hash of a resource directory.")
+            (@arg Resource: -r --resource +takes_value +required "Input the resource directory.")
+        )
+    ).get_matches();
+
+    let result = match matches.subcommand() {
+        ("build", Some(m)) => build(
+            m.value_of("Config"),
+            m.value_of("ContractsDir").unwrap(),
+            m.value_of("Output").unwrap(),
+        ),
+        ("check", Some(m)) => check(m.value_of("Genesis").unwrap(), m.value_of("ContractsDir")),
+        ("resource_hash", Some(m)) => resource_hash(Path::new(m.value_of("Resource").unwrap()))
+            .map(|hash| println!("{:?}", hash))
+            .map_err(|err| vec![err]),
+        _ => unreachable!(),
+    };
+    if let Err(errors) = result {
+        for error in errors {
+            eprintln!("error: {}", error);
+        }
+        ::std::process::exit(1);
+    }
+}
+
+fn build(config_path: Option<&str>, contracts_dir: &str, output: &str) -> Result<(), Vec<String>> {
+    let config = match config_path {
+        Some(path) => GenesisConfig::load(path).map_err(|err| vec![err])?,
+        None => GenesisConfig::default(),
+    };
+    config.validate()?;
+    let contracts = SystemContracts::load(contracts_dir).map_err(|err| vec![err])?;
+    let resource_path = resource_path(output);
+    let prevhash = if resource_path.exists() {
+        resource_hash(&resource_path).map_err(|err| vec![err])?
+    } else {
+        H256::zero()
+    };
+    let timestamp = config.timestamp.unwrap_or_else(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        now.as_secs() * 1000 + u64::from(now.subsec_nanos() / 1_000_000)
+    });
+    let spec = builder::build(&config, &contracts, prevhash, timestamp).map_err(|err| vec![err])?;
+    spec.validate()?;
+    check_system_contracts(&spec, &contracts)?;
+
+    let content = serde_json::to_string_pretty(&spec).unwrap();
+    fs::write(output, content).map_err(|err| vec![format!("can not write {}: {}", output, err)])?;
+    println!("{} is written, with prevhash {:?}", output, prevhash);
+    Ok(())
+}
+
+fn check(path: &str, contracts_dir: Option<&str>) -> Result<(), Vec<String>> {
+    let spec = Spec::load(path).map_err(|err| vec![err])?;
+    let mut errors = spec.validate().err().unwrap_or_default();
+    let resource_path = resource_path(path);
+    let hash = if resource_path.exists() {
+        resource_hash(&resource_path)
+    } else {
+        Ok(H256::zero())
+    };
+    match hash {
+        Ok(ref hash) if *hash != spec.prevhash => errors.push(format!(
+            "prevhash {:?} does not match the hash {:?} of {}",
+            spec.prevhash,
+            hash,
+            resource_path.display()
+        )),
+        Ok(_) => {}
+        Err(err) => errors.push(err),
+    }
+    if let Some(contracts_dir) = contracts_dir {
+        let contracts = SystemContracts::load(contracts_dir).map_err(|err| vec![err])?;
+        if let Err(mut contract_errors) = check_system_contracts(&spec, &contracts) {
+            errors.append(&mut contract_errors);
+        }
+    }
+    if errors.is_empty() {
+        println!("{} is valid", path);
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_system_contracts(spec: &Spec, contracts: &SystemContracts) -> Result<(), Vec<String>> {
+    let errors: Vec<String> = contracts
+        .addresses()
+        .into_iter()
+        .filter_map(|(name, address)| {
+            let key = format!("0x{}", address.lower_hex());
+            let code = spec
+                .alloc
+                .iter()
+                .find(|&(k, _)| k.to_lowercase() == key)
+                .map(|(_, contract)| contract.code.as_str());
+            match code {
+                None => Some(format!("system contract {} at {} is missing", name, key)),
+                Some(code) if clean_0x(code).is_empty() => {
+                    Some(format!("system contract {} at {} has no code", name, key))
+                }
+                Some(_) => None,
+            }
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The executor reads the resource folder next to the genesis.
+fn resource_path(genesis_path: &str) -> ::std::path::PathBuf {
+    Path::new(genesis_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("resource")
+}
@@ -22,24 +22,28 @@ use cita_types::H256;
 
 pub struct AppArgs {
     pub cfg_file: String,
-    pub chain_id: u32,
-    pub tx_hash: H256,
+    pub daemon: bool,
+    pub chain_id: Option<u32>,
+    pub tx_hash: Option<H256>,
 }
 
 impl<'a> From<&'a clap::ArgMatches<'a>> for AppArgs {
     fn from(matches: &'a clap::ArgMatches) -> Self {
         let cfg_file = matches.value_of("ConfigFile").unwrap();
-        let chain_id = value_t!(matches, "ChainId", u32).unwrap();
-        let tx_hash_str = matches.value_of("TxHash").unwrap();
-        let tx_hash_str = if tx_hash_str.starts_with("0x") {
-            &tx_hash_str[2..]
-        } else {
-            tx_hash_str
-        };
-        let tx_hash = H256::from_str(tx_hash_str).unwrap();
+        let daemon = matches.is_present("Daemon");
+        let chain_id = value_t!(matches, "ChainId", u32).ok();
+        let tx_hash = matches.value_of("TxHash").map(|tx_hash_str| {
+            let tx_hash_str = if tx_hash_str.starts_with("0x") {
+                &tx_hash_str[2..]
+            } else {
+                tx_hash_str
+            };
+            H256::from_str(tx_hash_str).unwrap()
+        });
         AppArgs {
             cfg_file: cfg_file.to_owned(),
-            chain_id: chain_id.to_owned(),
+            daemon: daemon,
+            chain_id: chain_id,
             tx_hash: tx_hash,
         }
     }
@@ -49,6 +53,7 @@ impl ::std::fmt::Display for AppArgs {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         f.debug_struct("AppArgs")
             .field("cfg_file", &self.cfg_file)
+            .field("daemon", &self.daemon)
             .field("chain_id", &self.chain_id)
             .field("tx_hash", &self.tx_hash)
             .finish()
@@ -61,8 +66,9 @@ pub fn build_commandline<'a>() -> clap::ArgMatches<'a> {
         (author: "Cryptape Technologies")
         (about: "CITA Relay Info Parser by Rust")
         (@arg ConfigFile: -f --config_file +takes_value +required "Input a toml configuration file.")
-        (@arg ChainId: -c --chain_id +takes_value +required "Input a chain id for the transaction hash.")
-        (@arg TxHash: -t --tx_hash +takes_value +required "Input a hex string of the transaction hash.")
+        (@arg Daemon: -d --daemon "Keep relaying the cross-chain messages of all chains in the configuration.")
+        (@arg ChainId: -c --chain_id +takes_value required_unless[Daemon] "Input a chain id for the transaction hash.")
+        (@arg TxHash: -t --tx_hash +takes_value required_unless[Daemon] "Input a hex string of the transaction hash.")
     ).get_matches();
     trace!("matches = {:?}", matches);
     matches
@@ -24,7 +24,7 @@ use serde_json;
 use std::convert::{Into, TryInto};
 use tokio_core::reactor::{Core, Timeout};
 
-use cita_types::{H256, U256};
+use cita_types::{H160, H256, U256};
 use configuration::UpStream;
 use jsonrpc_types::{request, rpctypes};
 use libproto::blockchain::UnverifiedTransaction;
@@ -174,3 +174,20 @@ pub fn cita_send_transaction(
         Err(Error::BadStatus)
     }
 }
+
+pub fn cita_get_logs(
+    upstream: &UpStream,
+    filter: rpctypes::Filter,
+) -> Result<Vec<rpctypes::Log>, Error> {
+    let req = request::GetLogsParams::new(filter).into_request(1);
+    let result = rpc_send_and_get_result_from_reply!(upstream, req, Vec<rpctypes::Log>);
+    Ok(result)
+}
+
+pub fn cita_call(upstream: &UpStream, to: H160, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let call_request = rpctypes::CallRequest::new(None, to.into(), Some(data.into()));
+    let height = rpctypes::BlockNumber::latest();
+    let req = request::CallParams::new(call_request, height).into_request(1);
+    let result = rpc_send_and_get_result_from_reply!(upstream, req, rpctypes::Data);
+    Ok(result.into())
+}
@@ -33,16 +33,56 @@ struct Chain {
     pub servers: Vec<UpStream>,
 }
 
+/// Settings of the daemon mode.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// File to save the progress, so a restarted relayer goes on from it.
+    pub state_file: String,
+    /// File to write the status after each round.
+    pub status_file: String,
+    /// Interval in seconds between two rounds of watching the chains.
+    pub poll_interval: u64,
+    /// Height to start watching a chain which has no progress saved.
+    pub start_height: u64,
+    /// Max number of blocks to get logs from in one request.
+    pub batch_size: u64,
+    /// Max number of attempts of a relay, before giving it up.
+    pub max_retries: u32,
+    /// Interval in seconds before the first retry, doubled by each retry after it.
+    pub retry_interval: u64,
+    /// Upper bound in seconds of the interval between two retries.
+    pub max_retry_interval: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        DaemonConfig {
+            state_file: "relayer-state.json".to_owned(),
+            status_file: "relayer-status.json".to_owned(),
+            poll_interval: 3,
+            start_height: 0,
+            batch_size: 1000,
+            max_retries: 10,
+            retry_interval: 5,
+            max_retry_interval: 300,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 struct FileConfig {
     pub private_key: PrivKey,
     pub chains: Vec<Chain>,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pkey: PrivKey,
     servers: HashMap<u32, Vec<UpStream>>,
+    daemon: DaemonConfig,
 }
 
 impl FileConfig {
@@ -62,6 +102,15 @@ impl Config {
     pub fn get_private_key(&self) -> &PrivKey {
         &self.pkey
     }
+    #[inline]
+    pub fn get_daemon_config(&self) -> &DaemonConfig {
+        &self.daemon
+    }
+    pub fn get_chain_ids(&self) -> Vec<u32> {
+        let mut chain_ids: Vec<u32> = self.servers.keys().cloned().collect();
+        chain_ids.sort();
+        chain_ids
+    }
 }
 
 pub fn parse_configfile(path: &str) -> Config {
@@ -72,5 +121,10 @@ pub fn parse_configfile(path: &str) -> Config {
         .into_iter()
         .map(|c| (c.id, c.servers))
         .collect();
-    Config { pkey, servers }
+    let daemon = config.daemon;
+    Config {
+        pkey,
+        servers,
+        daemon,
+    }
 }
@@ -0,0 +1,636 @@
+// CITA
+// Copyright 2016-2018 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The daemon mode relays the messages of the `CrossChainMessenger`.
+//!
+//! It watches the messenger logs on every chain in the config file. Each
+//! message is relayed to its destination chain, and each acknowledgement is
+//! relayed back to the source chain. A relay is done only after the target
+//! chain reports it as delivered. Until then it is retried with backoff,
+//! trying the servers one by one.
+//!
+//! The progress is saved to the state file after each round, so a restarted
+//! daemon goes on from where it stopped.
+
+use serde_json;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cita_crypto::PrivKey;
+use cita_types::{H160, H256, U256};
+use core::libchain::cross_chain::{
+    CrossChainAck, CrossChainMessage, ACK_TOPIC, MESSAGE_TOPIC, MESSENGER_ADDRESS,
+};
+use jsonrpc_types::rpctypes::{BlockNumber, Filter, Log, VariadicValue};
+
+use communication;
+use configuration::{Config, DaemonConfig, UpStream};
+use transaction;
+
+/// deliverMessage(bytes,uint256)
+const DELIVER_MESSAGE_HASHER: &str = "765e80f6";
+/// deliverAck(bytes,uint256)
+const DELIVER_ACK_HASHER: &str = "c8d2a40c";
+/// isDelivered(uint32,uint256)
+const IS_DELIVERED: [u8; 4] = [0xeb, 0x60, 0x7b, 0xb8];
+/// getAckStatus(uint32,uint256)
+const GET_ACK_STATUS: [u8; 4] = [0x07, 0x83, 0x7c, 0x84];
+/// The proof of a transaction can be got after two more blocks.
+const PROOF_DELAY: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobKind {
+    /// Relay a message to its destination chain.
+    Message,
+    /// Relay an acknowledgement back to the source chain of the message.
+    Ack,
+}
+
+/// A relay to do.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub kind: JobKind,
+    /// Source chain of the message.
+    pub from_chain_id: u32,
+    /// Destination chain of the message.
+    pub to_chain_id: u32,
+    pub nonce: U256,
+    /// Hash of the transaction which emits the log.
+    pub tx_hash: H256,
+    /// Index of the log in the receipt of the transaction.
+    pub log_index: usize,
+    pub attempts: u32,
+    /// Unix time in seconds of the next attempt.
+    pub next_attempt: u64,
+}
+
+impl Job {
+    /// Chain where the log is emitted.
+    fn source(&self) -> u32 {
+        match self.kind {
+            JobKind::Message => self.from_chain_id,
+            JobKind::Ack => self.to_chain_id,
+        }
+    }
+
+    /// Chain where the proof is relayed to.
+    fn target(&self) -> u32 {
+        match self.kind {
+            JobKind::Message => self.to_chain_id,
+            JobKind::Ack => self.from_chain_id,
+        }
+    }
+
+    fn hasher(&self) -> &'static str {
+        match self.kind {
+            JobKind::Message => DELIVER_MESSAGE_HASHER,
+            JobKind::Ack => DELIVER_ACK_HASHER,
+        }
+    }
+
+    /// Data to call the messenger on the target chain, to check whether the job is done.
+    fn query_data(&self) -> Vec<u8> {
+        let selector = match self.kind {
+            JobKind::Message => IS_DELIVERED,
+            JobKind::Ack => GET_ACK_STATUS,
+        };
+        let mut data = selector.to_vec();
+        data.extend_from_slice(&H256::from(self.source() as u64));
+        data.extend_from_slice(&H256::from(self.nonce));
+        data
+    }
+
+    fn is_same(&self, other: &Job) -> bool {
+        self.kind == other.kind
+            && self.from_chain_id == other.from_chain_id
+            && self.nonce == other.nonce
+    }
+}
+
+/// Progress of the daemon, saved in the state file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// Last height whose logs are processed, by chain id.
+    pub heights: BTreeMap<u32, u64>,
+    /// Number of the messages relayed.
+    ///
+    /// The relayed jobs are not kept. The target chain reports a relayed job as
+    /// delivered, so its log is harmless if it is seen again.
+    pub relayed_messages: u64,
+    /// Number of the acknowledgements relayed.
+    pub relayed_acks: u64,
+    /// Jobs waiting for the next attempt.
+    pub pending: Vec<Job>,
+    /// Jobs given up after too many attempts.
+    pub failed: Vec<Job>,
+}
+
+impl State {
+    fn load(path: &str) -> Result<Self, String> {
+        if !Path::new(path).exists() {
+            return Ok(State::default());
+        }
+        let file = fs::File::open(path).map_err(|err| format!("{:?}", err))?;
+        let reader = ::std::io::BufReader::new(file);
+        serde_json::from_reader(reader).map_err(|err| format!("{:?}", err))
+    }
+
+    /// Write to a temporary file first, so a crash never leaves a broken state file.
+    fn save(&self, path: &str) -> Result<(), String> {
+        let tmp_path = format!("{}.tmp", path);
+        let data = serde_json::to_string_pretty(self).map_err(|err| format!("{:?}", err))?;
+        fs::write(&tmp_path, data).map_err(|err| format!("{:?}", err))?;
+        fs::rename(&tmp_path, path).map_err(|err| format!("{:?}", err))
+    }
+
+    fn set_relayed(&mut self, job: &Job) {
+        match job.kind {
+            JobKind::Message => self.relayed_messages += 1,
+            JobKind::Ack => self.relayed_acks += 1,
+        }
+    }
+
+    fn is_known(&self, job: &Job) -> bool {
+        self.pending.iter().any(|pending| pending.is_same(job))
+            || self.failed.iter().any(|failed| failed.is_same(job))
+    }
+}
+
+/// Status of the daemon, written to the status file after each round.
+#[derive(Debug, Serialize)]
+struct Status {
+    heights: BTreeMap<u32, u64>,
+    relayed_messages: u64,
+    relayed_acks: u64,
+    pending: usize,
+    failed: usize,
+    last_error: Option<String>,
+    updated_at: u64,
+}
+
+struct Relayer<'a> {
+    cfg: &'a Config,
+    state: State,
+    last_error: Option<String>,
+}
+
+pub fn run(cfg: &Config) -> Result<(), String> {
+    let daemon_cfg = cfg.get_daemon_config();
+    let state = State::load(&daemon_cfg.state_file)
+        .map_err(|err| format!("load state file {} failed: {}", daemon_cfg.state_file, err))?;
+    let mut relayer = Relayer {
+        cfg,
+        state,
+        last_error: None,
+    };
+    info!(
+        "relayer daemon starts with {} pending jobs",
+        relayer.state.pending.len()
+    );
+    loop {
+        relayer.poll();
+        thread::sleep(Duration::from_secs(daemon_cfg.poll_interval));
+    }
+}
+
+impl<'a> Relayer<'a> {
+    fn daemon_cfg(&self) -> &'a DaemonConfig {
+        self.cfg.get_daemon_config()
+    }
+
+    fn servers(&self, chain_id: u32) -> Result<&'a Vec<UpStream>, String> {
+        self.cfg
+            .get_servers(chain_id)
+            .ok_or_else(|| format!("no servers for chain {}", chain_id))
+    }
+
+    fn report_error(&mut self, err: String) {
+        warn!("{}", err);
+        self.last_error = Some(err);
+    }
+
+    fn poll(&mut self) {
+        for chain_id in self.cfg.get_chain_ids() {
+            if let Err(err) = self.watch(chain_id) {
+                self.report_error(format!("watch chain {} failed: {}", chain_id, err));
+            }
+        }
+        self.process_jobs();
+        let state_file = &self.daemon_cfg().state_file;
+        if let Err(err) = self.state.save(state_file) {
+            self.report_error(format!("save state failed: {}", err));
+        }
+        self.write_status();
+    }
+
+    /// Get the new logs of the messenger on a chain, and add jobs for them.
+    fn watch(&mut self, chain_id: u32) -> Result<(), String> {
+        let servers = self.servers(chain_id)?;
+        let latest = first_ok(servers, communication::cita_block_number)?.low_u64();
+        if latest < PROOF_DELAY {
+            return Ok(());
+        }
+        let safe_height = latest - PROOF_DELAY;
+        let from = self
+            .state
+            .heights
+            .get(&chain_id)
+            .map_or(self.daemon_cfg().start_height, |height| height + 1);
+        if from > safe_height {
+            return Ok(());
+        }
+        let to = cmp::min(safe_height, from + cmp::max(self.daemon_cfg().batch_size, 1) - 1);
+        let logs = first_ok(servers, |upstream| {
+            communication::cita_get_logs(upstream, messenger_filter(from, to))
+        })?;
+        trace!("chain {} got {} logs in [{}, {}]", chain_id, logs.len(), from, to);
+        for log in logs {
+            if let Some(job) = self.job_from_log(chain_id, log) {
+                if !self.state.is_known(&job) {
+                    info!("new job {:?}", job);
+                    self.state.pending.push(job);
+                }
+            }
+        }
+        self.state.heights.insert(chain_id, to);
+        Ok(())
+    }
+
+    fn job_from_log(&self, chain_id: u32, log: Log) -> Option<Job> {
+        let tx_hash = log.transaction_hash?;
+        let log_index = log.transaction_log_index?.low_u64() as usize;
+        let topic = log.topics.first().cloned()?;
+        let data: Vec<u8> = log.data.into();
+        let job = if topic == *MESSAGE_TOPIC {
+            CrossChainMessage::decode(&data).map(|message| Job {
+                kind: JobKind::Message,
+                from_chain_id: message.from_chain_id,
+                to_chain_id: message.to_chain_id,
+                nonce: message.nonce,
+                tx_hash,
+                log_index,
+                attempts: 0,
+                next_attempt: 0,
+            })
+        } else if topic == *ACK_TOPIC {
+            CrossChainAck::decode(&data).map(|ack| Job {
+                kind: JobKind::Ack,
+                from_chain_id: ack.from_chain_id,
+                to_chain_id: ack.to_chain_id,
+                nonce: ack.nonce,
+                tx_hash,
+                log_index,
+                attempts: 0,
+                next_attempt: 0,
+            })
+        } else {
+            None
+        };
+        job.and_then(|job| {
+            if job.source() != chain_id {
+                warn!("chain {} emits a log of another chain: {:?}", chain_id, job);
+                None
+            } else if self.cfg.get_servers(job.target()).is_none() {
+                warn!("skip {:?}, chain {} is not in the config", job, job.target());
+                None
+            } else {
+                Some(job)
+            }
+        })
+    }
+
+    fn process_jobs(&mut self) {
+        let now = unix_now();
+        let jobs = mem::replace(&mut self.state.pending, Vec::new());
+        for mut job in jobs {
+            if job.next_attempt > now {
+                self.state.pending.push(job);
+                continue;
+            }
+            match self.relay(&job) {
+                Ok(true) => {
+                    info!("job done {:?}", job);
+                    self.state.set_relayed(&job);
+                    continue;
+                }
+                Ok(false) => {}
+                Err(err) => self.report_error(format!("relay {:?} failed: {}", job, err)),
+            }
+            job.attempts += 1;
+            if job.attempts > self.daemon_cfg().max_retries {
+                error!("give up {:?}", job);
+                self.state.failed.push(job);
+            } else {
+                job.next_attempt = now + backoff(self.daemon_cfg(), job.attempts);
+                self.state.pending.push(job);
+            }
+        }
+    }
+
+    /// Check whether the job is done, and relay the proof if not.
+    ///
+    /// A sent relay is confirmed by the next attempt, after the transaction is packaged.
+    fn relay(&self, job: &Job) -> Result<bool, String> {
+        let target_servers = self.servers(job.target())?;
+        let query_data = job.query_data();
+        let result = first_ok(target_servers, |upstream| {
+            communication::cita_call(upstream, *MESSENGER_ADDRESS, query_data.clone())
+        })?;
+        if result.iter().any(|byte| *byte != 0) {
+            return Ok(true);
+        }
+        let source_servers = self.servers(job.source())?;
+        let tx_proof_rlp = first_ok(source_servers, |upstream| {
+            communication::cita_get_transaction_proof(upstream, job.tx_hash)
+        })?;
+        let hash = send_proof(
+            target_servers,
+            self.cfg.get_private_key(),
+            tx_proof_rlp,
+            job.log_index,
+            job.hasher(),
+            job.target(),
+        )?;
+        info!("relay {:?} by transaction {:?}", job, hash);
+        Ok(false)
+    }
+
+    fn write_status(&self) {
+        let status = Status {
+            heights: self.state.heights.clone(),
+            relayed_messages: self.state.relayed_messages,
+            relayed_acks: self.state.relayed_acks,
+            pending: self.state.pending.len(),
+            failed: self.state.failed.len(),
+            last_error: self.last_error.clone(),
+            updated_at: unix_now(),
+        };
+        info!(
+            "status: heights {:?}, relayed {} messages and {} acks, {} pending, {} failed",
+            status.heights,
+            status.relayed_messages,
+            status.relayed_acks,
+            status.pending,
+            status.failed
+        );
+        let result = serde_json::to_string_pretty(&status)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|data| {
+                fs::write(&self.daemon_cfg().status_file, data).map_err(|err| format!("{:?}", err))
+            });
+        if let Err(err) = result {
+            warn!("write status failed: {}", err);
+        }
+    }
+}
+
+fn messenger_filter(from: u64, to: u64) -> Filter {
+    let address: H160 = *MESSENGER_ADDRESS;
+    let topics = vec![(*MESSAGE_TOPIC).into(), (*ACK_TOPIC).into()];
+    Filter::new(
+        BlockNumber::new(from.into()),
+        BlockNumber::new(to.into()),
+        Some(VariadicValue::Single(address.into())),
+        Some(vec![VariadicValue::Multiple(topics)]),
+    )
+}
+
+/// Try the servers one by one, until one of them succeeds.
+fn first_ok<T, F>(servers: &[UpStream], mut f: F) -> Result<T, String>
+where
+    F: FnMut(&UpStream) -> Result<T, communication::Error>,
+{
+    let mut last_err = "no servers".to_owned();
+    for upstream in servers.iter() {
+        match f(upstream) {
+            Ok(result) => return Ok(result),
+            Err(err) => last_err = format!("{} returns {:?}", upstream.url, err),
+        }
+    }
+    Err(last_err)
+}
+
+fn send_proof(
+    servers: &[UpStream],
+    pkey: &PrivKey,
+    tx_proof_rlp: Vec<u8>,
+    log_index: usize,
+    hasher: &str,
+    chain_id: u32,
+) -> Result<H256, String> {
+    first_ok(servers, |upstream| {
+        let metadata = communication::cita_get_metadata(upstream)?;
+        if metadata.chain_id != chain_id {
+            error!("chain id is not right {} != {}", metadata.chain_id, chain_id);
+            return Err(communication::Error::BadStatus);
+        }
+        let height = communication::cita_block_number(upstream)?;
+        let utx = transaction::construct_delivery_transaction(
+            pkey,
+            tx_proof_rlp.clone(),
+            log_index,
+            hasher,
+            *MESSENGER_ADDRESS,
+            chain_id,
+            height,
+        ).ok_or(communication::Error::Parse)?;
+        communication::cita_send_transaction(upstream, &utx)
+    })
+}
+
+/// The interval in seconds before the next attempt, doubled by each attempt.
+fn backoff(daemon_cfg: &DaemonConfig, attempts: u32) -> u64 {
+    let factor = 1u64 << cmp::min(attempts.saturating_sub(1), 16);
+    cmp::min(
+        daemon_cfg.retry_interval.saturating_mul(factor),
+        daemon_cfg.max_retry_interval,
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use configuration::parse_configfile;
+    use std::env;
+    use std::process;
+
+    fn demo_config() -> Config {
+        parse_configfile(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/res/relayer-parser-demo.json"
+        ))
+    }
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("{}-{}.json", name, process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn message(from_chain_id: u32, to_chain_id: u32) -> CrossChainMessage {
+        CrossChainMessage {
+            from_chain_id,
+            to_chain_id,
+            nonce: 7.into(),
+            sender: H160::from(4),
+            dest_contract: H160::from(5),
+            gas_limit: 100_000.into(),
+            payload: vec![1, 2, 3],
+        }
+    }
+
+    fn log(topic: H256, data: Vec<u8>, log_index: Option<u64>) -> Log {
+        Log {
+            address: *MESSENGER_ADDRESS,
+            topics: vec![topic],
+            data: data.into(),
+            block_hash: None,
+            block_number: None,
+            transaction_hash: Some(H256::from(9)),
+            transaction_index: None,
+            log_index: None,
+            transaction_log_index: log_index.map(U256::from),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let daemon_cfg = DaemonConfig::default();
+        assert_eq!(backoff(&daemon_cfg, 0), 5);
+        assert_eq!(backoff(&daemon_cfg, 1), 5);
+        assert_eq!(backoff(&daemon_cfg, 2), 10);
+        assert_eq!(backoff(&daemon_cfg, 4), 40);
+        assert_eq!(backoff(&daemon_cfg, 7), 300);
+        assert_eq!(backoff(&daemon_cfg, u32::max_value()), 300);
+    }
+
+    #[test]
+    fn job_from_messenger_logs() {
+        let cfg = demo_config();
+        let relayer = Relayer {
+            cfg: &cfg,
+            state: State::default(),
+            last_error: None,
+        };
+
+        let data = message(1, 2).encode();
+        let job = relayer
+            .job_from_log(1, log(*MESSAGE_TOPIC, data.clone(), Some(2)))
+            .unwrap();
+        assert_eq!(job.kind, JobKind::Message);
+        assert_eq!((job.from_chain_id, job.to_chain_id), (1, 2));
+        assert_eq!(job.nonce, 7.into());
+        assert_eq!(job.tx_hash, H256::from(9));
+        assert_eq!(job.log_index, 2);
+        assert_eq!(job.target(), 2);
+        assert_eq!(job.hasher(), DELIVER_MESSAGE_HASHER);
+
+        let ack = CrossChainAck {
+            from_chain_id: 1,
+            to_chain_id: 2,
+            nonce: 7.into(),
+            sender: H160::from(4),
+            success: true,
+        };
+        let job = relayer
+            .job_from_log(2, log(*ACK_TOPIC, ack.encode(), Some(0)))
+            .unwrap();
+        assert_eq!(job.kind, JobKind::Ack);
+        assert_eq!(job.target(), 1);
+        assert_eq!(job.hasher(), DELIVER_ACK_HASHER);
+
+        // Emitted by another chain.
+        assert!(
+            relayer
+                .job_from_log(2, log(*MESSAGE_TOPIC, data.clone(), Some(0)))
+                .is_none()
+        );
+        // Sent to a chain which is not in the config.
+        let data_to_unknown = message(1, 3).encode();
+        assert!(
+            relayer
+                .job_from_log(1, log(*MESSAGE_TOPIC, data_to_unknown, Some(0)))
+                .is_none()
+        );
+        assert!(
+            relayer
+                .job_from_log(1, log(*ACK_TOPIC, data.clone(), Some(0)))
+                .is_none()
+        );
+        assert!(
+            relayer
+                .job_from_log(1, log(*MESSAGE_TOPIC, data, None))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let path = temp_path("relayer-state-round-trip");
+        let _ = fs::remove_file(&path);
+        assert!(State::load(&path).unwrap().pending.is_empty());
+
+        let mut state = State::default();
+        state.heights.insert(1, 100);
+        let job = Job {
+            kind: JobKind::Message,
+            from_chain_id: 1,
+            to_chain_id: 2,
+            nonce: 7.into(),
+            tx_hash: H256::from(9),
+            log_index: 2,
+            attempts: 3,
+            next_attempt: 1000,
+        };
+        state.set_relayed(&job);
+        // A relayed job is counted only.
+        assert!(!state.is_known(&job));
+        state.pending.push(job.clone());
+        state.save(&path).unwrap();
+
+        let loaded = State::load(&path).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&state).unwrap()
+        );
+        assert_eq!(loaded.relayed_messages, 1);
+        assert!(loaded.is_known(&job));
+        assert_eq!(loaded.pending[0].log_index, 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reject_corrupt_state() {
+        let path = temp_path("relayer-state-corrupt");
+        fs::write(&path, "{\"heights\": ").unwrap();
+        assert!(State::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -40,6 +40,7 @@ extern crate logger;
 mod arguments;
 mod communication;
 mod configuration;
+mod daemon;
 mod transaction;
 
 use cita_crypto::PrivKey;
@@ -57,6 +58,16 @@ fn main() {
     let args = parse_arguments(&matches);
     let cfg = parse_configfile(&args.cfg_file);
 
+    if args.daemon {
+        if let Err(err) = daemon::run(&cfg) {
+            error!("relayer daemon stops: {}", err);
+            ::std::process::exit(1);
+        }
+        return;
+    }
+    let chain_id = args.chain_id.expect("chain id is required");
+    let tx_hash = args.tx_hash.expect("transaction hash is required");
+
     let mut retcode = 1;
     // Get servers list from the config file by the input chain id.
     // Try to get transaction proof from servers in server list.
@@ -64,8 +75,8 @@ fn main() {
     // and relay the transaction to the to-chain.
     // The chain id of to-chain is in the tx proof.
     // Relay the transaction to each server in to-chain servers list, until succeed.
-    cfg.get_servers(args.chain_id)
-        .and_then(|servers| fetch_txproof(servers, args.tx_hash))
+    cfg.get_servers(chain_id)
+        .and_then(|servers| fetch_txproof(servers, tx_hash))
         .and_then(|tx_proof_rlp| {
             deconstruct_txproof(&tx_proof_rlp).map(|relay_info| (tx_proof_rlp, relay_info))
         })
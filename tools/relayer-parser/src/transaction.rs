@@ -20,7 +20,7 @@ use rustc_hex::FromHex;
 
 use cita_crypto::PrivKey;
 use cita_types::traits::LowerHex;
-use cita_types::{H160, U256};
+use cita_types::{H160, H256, U256};
 use libproto::blockchain::{Transaction, UnverifiedTransaction};
 
 pub fn construct_transaction(
@@ -31,17 +31,34 @@ pub fn construct_transaction(
     chain_id: u32,
     height: U256,
 ) -> Option<UnverifiedTransaction> {
-    encode(dest_hasher, tx_proof_rlp).map(|code| sign(pkey, dest_contract, code, chain_id, height))
+    let args = [ethabi::Token::Bytes(tx_proof_rlp)];
+    encode(dest_hasher, &args).map(|code| sign(pkey, dest_contract, code, chain_id, height))
+}
+
+/// Construct the transaction which delivers the log at `log_index` of the proved receipt.
+pub fn construct_delivery_transaction(
+    pkey: &PrivKey,
+    tx_proof_rlp: Vec<u8>,
+    log_index: usize,
+    dest_hasher: &str,
+    dest_contract: H160,
+    chain_id: u32,
+    height: U256,
+) -> Option<UnverifiedTransaction> {
+    let args = [
+        ethabi::Token::Bytes(tx_proof_rlp),
+        ethabi::Token::Uint(H256::from(log_index as u64).0),
+    ];
+    encode(dest_hasher, &args).map(|code| sign(pkey, dest_contract, code, chain_id, height))
 }
 
 #[inline]
-fn encode(dest_hasher: &str, tx_proof_rlp: Vec<u8>) -> Option<Vec<u8>> {
+fn encode(dest_hasher: &str, args: &[ethabi::Token]) -> Option<Vec<u8>> {
     FromHex::from_hex(dest_hasher)
         .map(|hasher| {
             trace!("encode dest_hasher {:?}", hasher);
-            trace!("encode proof_len {:?}", tx_proof_rlp.len());
-            trace!("encode proof_data {:?}", tx_proof_rlp);
-            let encoded = ethabi::encode(&[ethabi::Token::Bytes(tx_proof_rlp)]);
+            trace!("encode args {:?}", args);
+            let encoded = ethabi::encode(args);
             let ret = hasher.into_iter().chain(encoded.into_iter()).collect();
             trace!("encode result {:?}", ret);
             ret